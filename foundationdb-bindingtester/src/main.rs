@@ -19,6 +19,9 @@ use futures::future;
 use futures::prelude::*;
 use num_bigint::BigInt;
 
+/// internal_error, reported by the unit tests when a check fails
+const INTERNAL_ERROR: i32 = 4100;
+
 static WAITED_FOR_EMPTY: Element = Element::Bytes(Bytes(Cow::Borrowed(b"WAITED_FOR_EMPTY")));
 static RESULT_NOT_PRESENT: Element = Element::Bytes(Bytes(Cow::Borrowed(b"RESULT_NOT_PRESENT")));
 static GOT_READ_VERSION: Element = Element::Bytes(Bytes(Cow::Borrowed(b"GOT_READ_VERSION")));
@@ -630,7 +633,7 @@ impl StackMachine {
         let opt = RangeOption::from(&Subspace::from(&self.prefix));
         debug!("opt = {:?}", opt);
         let instrs = Vec::new();
-        trx.get_ranges(opt, false)
+        trx.read_ranges(opt)
             .try_fold(instrs, |mut instrs, res| {
                 for kv in res.iter() {
                     let instr = Instr::from(kv.value());
@@ -1035,6 +1038,8 @@ impl StackMachine {
             Get => {
                 let key: Bytes = self.pop_bytes().await;
                 debug!("get {:?}", key);
+                // the snapshot flag of the instruction is given as is
+                #[allow(deprecated)]
                 let f = trx
                     .as_mut()
                     .get(&key, instr.pop_snapshot())
@@ -1059,6 +1064,8 @@ impl StackMachine {
                 let prefix: Bytes = self.pop_bytes().await;
                 debug!("get_key {:?}, prefix = {:?}", selector, prefix);
 
+                // the snapshot flag of the instruction is given as is
+                #[allow(deprecated)]
                 let f = trx
                     .as_mut()
                     .get_key(&selector, instr.pop_snapshot())
@@ -1161,7 +1168,7 @@ impl StackMachine {
 
                 let res = trx
                     .as_mut()
-                    .get_ranges(opt, snapshot)
+                    .get_ranges_batched(opt, snapshot)
                     .try_fold(Vec::new(), move |mut out, kvs| {
                         for kv in kvs.iter() {
                             let key = kv.key();
@@ -1336,7 +1343,8 @@ impl StackMachine {
                 self.check(
                     number,
                     trx.as_mut()
-                        .set_option(TransactionOption::NextWriteNoWriteConflictRange),
+                        .set_option(TransactionOption::NextWriteNoWriteConflictRange)
+                        .map_err(FdbError::from),
                 )?
             }
             // Commits the current transaction (with no retry behavior). May optionally
@@ -1665,9 +1673,7 @@ impl StackMachine {
                     begin: &[u8],
                     end: &[u8],
                 ) -> FdbResult<()> {
-                    let range = trx
-                        .get_range(&RangeOption::from((begin, end)), 1, false)
-                        .await?;
+                    let range = trx.read_range(&RangeOption::from((begin, end)), 1).await?;
 
                     debug!("wait_empty {:?} range {}", Bytes::from(begin), range.len());
                     if range.len() != 0 {
@@ -1719,22 +1725,52 @@ impl StackMachine {
                     tr.set_option(TransactionOption::ReadLockAware)?;
                     tr.set_option(TransactionOption::LockAware)?;
 
-                    tr.get(b"\xff", false).await?;
+                    tr.read(b"\xff").await?;
 
                     Ok(())
                 }
                 let r = unit_test(&db, trx.as_mut()).await;
                 self.check(number, r)?;
 
+                async fn test_retry_limits(db: &fdb::Database) -> FdbResult<()> {
+                    let trx = db.create_trx()?;
+                    trx.set_option(TransactionOption::RetryLimit(1))?;
+                    trx.set(b"foo", b"bar");
+                    // `transaction_too_old` is retryable, the first retry is allowed
+                    let trx = trx.on_error(FdbError::from_code(1007)).await?;
+                    trx.set(b"foo", b"bar");
+                    trx.set_option(TransactionOption::RetryLimit(1))?;
+                    match trx.on_error(FdbError::from_code(1007)).await {
+                        Err(err) if err.code() == 1007 => Ok(()),
+                        Err(err) => Err(err),
+                        Ok(_) => {
+                            error!("retry limit was not enforced");
+                            Err(FdbError::from_code(INTERNAL_ERROR))
+                        }
+                    }
+                }
+                let r = test_retry_limits(&db).await;
+                self.check(number, r)?;
+
+                fn test_predicates() -> FdbResult<()> {
+                    if FdbError::from_code(1020).is_retryable()
+                        && !FdbError::from_code(10).is_retryable()
+                    {
+                        Ok(())
+                    } else {
+                        error!("errors are not classified as retryable correctly");
+                        Err(FdbError::from_code(INTERNAL_ERROR))
+                    }
+                }
+                self.check(number, test_predicates())?;
+
                 // TODO
                 // test_cancellation(db)
-                // test_retry_limits(db)
                 // test_db_retry_limits(db)
                 // test_timeouts(db)
                 // test_db_timeouts(db)
                 // test_combinations(db)
                 // test_locality(db)
-                // test_predicates()
             }
 
             // Pop 1 tuple off the stack as [path]. Pop 1 additional item as [raw_prefix].
@@ -9,10 +9,64 @@ const TAB2: &str = "        ";
 const TAB3: &str = "            ";
 const TAB4: &str = "                ";
 
+/// The values accepted by `Int` options, by scope and option name, which `fdb.options` only
+/// documents in the option descriptions
+const INT_RANGES: &[(&str, &str, &str, &str)] = &[
+    (
+        "NetworkOption",
+        "buggify_section_activated_probability",
+        "0",
+        "100",
+    ),
+    (
+        "NetworkOption",
+        "buggify_section_fired_probability",
+        "0",
+        "100",
+    ),
+    (
+        "NetworkOption",
+        "client_buggify_section_activated_probability",
+        "0",
+        "100",
+    ),
+    (
+        "NetworkOption",
+        "client_buggify_section_fired_probability",
+        "0",
+        "100",
+    ),
+    ("DatabaseOption", "transaction_timeout", "0", "i32::MAX"),
+    (
+        "DatabaseOption",
+        "transaction_retry_limit",
+        "-1",
+        "i32::MAX",
+    ),
+    (
+        "DatabaseOption",
+        "transaction_max_retry_delay",
+        "0",
+        "i32::MAX",
+    ),
+    (
+        "DatabaseOption",
+        "transaction_size_limit",
+        "32",
+        "10_000_000",
+    ),
+    ("TransactionOption", "timeout", "0", "i32::MAX"),
+    ("TransactionOption", "retry_limit", "-1", "i32::MAX"),
+    ("TransactionOption", "max_retry_delay", "0", "i32::MAX"),
+    ("TransactionOption", "size_limit", "32", "10_000_000"),
+];
+
 #[derive(Debug)]
 struct FdbScope {
     name: String,
     options: Vec<FdbOption>,
+    // not generated, see `hidden_name`
+    hidden: Vec<FdbOption>,
 }
 impl FdbScope {
     fn gen_ty<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
@@ -36,6 +90,7 @@ impl FdbScope {
     fn gen_impl<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
         writeln!(w, "impl {name} {{", name = self.name)?;
         self.gen_code(w)?;
+        self.gen_validate(w)?;
         self.gen_apply(w)?;
         writeln!(w, "}}")
     }
@@ -73,6 +128,144 @@ impl FdbScope {
         writeln!(w, "{t}}}", t = TAB1)
     }
 
+    fn gen_validate<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        if !self.with_ty() {
+            return Ok(());
+        }
+
+        writeln!(
+            w,
+            "{t}/// The name of the option, i.e. `{scope}::Name`.",
+            t = TAB1,
+            scope = self.name
+        )?;
+        writeln!(w, "{t}pub fn name(&self) -> &'static str {{", t = TAB1)?;
+        writeln!(w, "{t}match *self {{", t = TAB2)?;
+        for option in self.options.iter() {
+            writeln!(
+                w,
+                "{t}{scope}::{name}{param} => \"{scope}::{name}\",",
+                t = TAB3,
+                scope = self.name,
+                name = option.name,
+                param = if option.get_ty().is_some() {
+                    "(..)"
+                } else {
+                    ""
+                },
+            )?;
+        }
+        writeln!(w, "{t}}}", t = TAB2)?;
+        writeln!(w, "{t}}}", t = TAB1)?;
+
+        writeln!(
+            w,
+            "{t}/// Checks the option parameter against the values accepted by the client.",
+            t = TAB1
+        )?;
+        writeln!(
+            w,
+            "{t}pub fn validate(&self) -> Result<(), InvalidOptionValue> {{",
+            t = TAB1
+        )?;
+        writeln!(w, "{t}match *self {{", t = TAB2)?;
+        for option in self.options.iter() {
+            if let Some((min, max)) = self.int_range(option) {
+                writeln!(
+                    w,
+                    "{t}{scope}::{name}(v) => InvalidOptionValue::check_range(self.name(), v, {min}, {max}),",
+                    t = TAB3,
+                    scope = self.name,
+                    name = option.name,
+                    min = min,
+                    max = max,
+                )?;
+            }
+        }
+        writeln!(w, "{t}_ => Ok(()),", t = TAB3)?;
+        writeln!(w, "{t}}}", t = TAB2)?;
+        writeln!(w, "{t}}}", t = TAB1)?;
+
+        writeln!(
+            w,
+            "{t}/// Returns `true` if the option is documented as deprecated in `fdb.options`.",
+            t = TAB1
+        )?;
+        writeln!(w, "{t}pub fn is_deprecated(&self) -> bool {{", t = TAB1)?;
+        let deprecated: Vec<String> = self
+            .options
+            .iter()
+            .filter(|option| option.is_deprecated())
+            .map(|option| {
+                format!(
+                    "{}::{}{}",
+                    self.name,
+                    option.name,
+                    if option.get_ty().is_some() {
+                        "(..)"
+                    } else {
+                        ""
+                    }
+                )
+            })
+            .collect();
+        if deprecated.is_empty() {
+            writeln!(w, "{t}false", t = TAB2)?;
+        } else {
+            writeln!(
+                w,
+                "{t}matches!(*self, {variants})",
+                t = TAB2,
+                variants = deprecated.join(" | ")
+            )?;
+        }
+        writeln!(w, "{t}}}", t = TAB1)?;
+
+        writeln!(
+            w,
+            "{t}/// The name of the option of `code` if it is hidden in `fdb.options`, such options are",
+            t = TAB1
+        )?;
+        writeln!(
+            w,
+            "{t}/// not part of this enum and are meant for the tests of FoundationDB itself.",
+            t = TAB1
+        )?;
+        writeln!(
+            w,
+            "{t}pub fn hidden_name(code: fdb_sys::FDB{name}) -> Option<&'static str> {{",
+            t = TAB1,
+            name = self.name,
+        )?;
+        writeln!(w, "{t}match code {{", t = TAB2)?;
+        for option in self.hidden.iter() {
+            writeln!(
+                w,
+                "{t}{code} => Some(\"{scope}::{name}\"),",
+                t = TAB3,
+                code = option.code,
+                scope = self.name,
+                name = option.name,
+            )?;
+        }
+        writeln!(w, "{t}_ => None,", t = TAB3)?;
+        writeln!(w, "{t}}}", t = TAB2)?;
+        writeln!(w, "{t}}}", t = TAB1)
+    }
+
+    /// The range of values accepted by an `Int` option, see [`INT_RANGES`]
+    fn int_range(&self, option: &FdbOption) -> Option<(&'static str, &'static str)> {
+        if !matches!(option.param_type, FdbOptionTy::Int) {
+            return None;
+        }
+        INT_RANGES
+            .iter()
+            .find(|(scope, name, _, _)| {
+                *scope == self.name && name.eq_ignore_ascii_case(&option.c_name)
+            })
+            .map(|(_, _, min, max)| (*min, *max))
+    }
+
     fn gen_apply<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
         let fn_name = match self.apply_fn_name() {
             Some(name) => name,
@@ -86,10 +279,19 @@ impl FdbScope {
 
         writeln!(
             w,
-            "{t}pub unsafe fn apply(&self{args}) -> FdbResult<()> {{",
+            "{t}pub unsafe fn apply(&self{args}) -> Result<(), OptionError> {{",
             t = TAB1,
             args = first_arg
         )?;
+        writeln!(w, "{t}self.validate()?;", t = TAB2)?;
+        writeln!(w, "{t}#[cfg(feature = \"tracing\")]", t = TAB2)?;
+        writeln!(w, "{t}if self.is_deprecated() {{", t = TAB2)?;
+        writeln!(
+            w,
+            "{t}tracing::warn!(option = self.name(), \"setting a deprecated option\");",
+            t = TAB3
+        )?;
+        writeln!(w, "{t}}}", t = TAB2)?;
         writeln!(w, "{t}let code = self.code();", t = TAB2)?;
         writeln!(w, "{t}let err = match *self {{", t = TAB2)?;
 
@@ -149,7 +351,7 @@ impl FdbScope {
         writeln!(w, "{t}}};", t = TAB2)?;
         writeln!(
             w,
-            "{t}if err != 0 {{ Err(FdbError::from_code(err)) }} else {{ Ok(()) }}",
+            "{t}if err != 0 {{ Err(FdbError::from_code(err).into()) }} else {{ Ok(()) }}",
             t = TAB2,
         )?;
         writeln!(w, "{t}}}", t = TAB1)
@@ -215,6 +417,7 @@ struct FdbOption {
     hidden: bool,
     default_for: Option<i32>,
     persistent: bool,
+    sensitive: bool,
 }
 
 impl FdbOption {
@@ -235,6 +438,10 @@ impl FdbOption {
         Ok(())
     }
 
+    fn is_deprecated(&self) -> bool {
+        self.description.starts_with("Deprecated")
+    }
+
     fn get_ty(&self) -> Option<&'static str> {
         match self.param_type {
             FdbOptionTy::Int => Some("i32"),
@@ -303,6 +510,11 @@ impl From<Vec<OwnedAttribute>> for FdbOption {
                     "false" => opt.persistent = false,
                     _ => panic!("unexpected boolean value in 'persistent': {}", v),
                 },
+                "sensitive" => match v.as_str() {
+                    "true" => opt.sensitive = true,
+                    "false" => opt.sensitive = false,
+                    _ => panic!("unexpected boolean value in 'sensitive': {}", v),
+                },
                 attr => {
                     panic!("unexpected option attribute: {}", attr);
                 }
@@ -312,11 +524,13 @@ impl From<Vec<OwnedAttribute>> for FdbOption {
     }
 }
 
-fn on_scope<I>(parser: &mut I) -> Vec<FdbOption>
+/// Returns the options of a scope, and its hidden ones
+fn on_scope<I>(parser: &mut I) -> (Vec<FdbOption>, Vec<FdbOption>)
 where
     I: Iterator<Item = xml::reader::Result<XmlEvent>>,
 {
     let mut options = Vec::new();
+    let mut hidden = Vec::new();
     for e in parser {
         let e = e.unwrap();
         match e {
@@ -326,13 +540,15 @@ where
                 assert_eq!(name.local_name, "Option", "unexpected token");
 
                 let option = FdbOption::from(attributes.clone());
-                if !option.hidden {
+                if option.hidden {
+                    hidden.push(option);
+                } else {
                     options.push(option);
                 }
             }
             XmlEvent::EndElement { name, .. } => {
                 if name.local_name == "Scope" {
-                    return options;
+                    return (options, hidden);
                 }
             }
             _ => {}
@@ -352,22 +568,29 @@ const OPTIONS_DATA: &[u8] = include_bytes!("/usr/local/include/foundationdb/fdb.
 const OPTIONS_DATA: &[u8] =
     include_bytes!("C:/Program Files/foundationdb/include/foundationdb/fdb.options");
 
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-5_1"))]
-const OPTIONS_DATA: &[u8] = include_bytes!("../include/510/fdb.options");
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-5_2"))]
-const OPTIONS_DATA: &[u8] = include_bytes!("../include/520/fdb.options");
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-6_0"))]
-const OPTIONS_DATA: &[u8] = include_bytes!("../include/600/fdb.options");
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-6_1"))]
-const OPTIONS_DATA: &[u8] = include_bytes!("../include/610/fdb.options");
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-6_2"))]
-const OPTIONS_DATA: &[u8] = include_bytes!("../include/620/fdb.options");
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-6_3"))]
-const OPTIONS_DATA: &[u8] = include_bytes!("../include/630/fdb.options");
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-7_0"))]
-const OPTIONS_DATA: &[u8] = include_bytes!("../include/700/fdb.options");
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-7_1"))]
-const OPTIONS_DATA: &[u8] = include_bytes!("../include/710/fdb.options");
+// The version features are additive, the highest one enabled selects the options.
+#[cfg(feature = "embedded-fdb-include")]
+const OPTIONS_DATA: &[u8] = if cfg!(feature = "fdb-7_3") {
+    include_bytes!("../include/730/fdb.options")
+} else if cfg!(feature = "fdb-7_1") {
+    include_bytes!("../include/710/fdb.options")
+} else if cfg!(feature = "fdb-7_0") {
+    include_bytes!("../include/700/fdb.options")
+} else if cfg!(feature = "fdb-6_3") {
+    include_bytes!("../include/630/fdb.options")
+} else if cfg!(feature = "fdb-6_2") {
+    include_bytes!("../include/620/fdb.options")
+} else if cfg!(feature = "fdb-6_1") {
+    include_bytes!("../include/610/fdb.options")
+} else if cfg!(feature = "fdb-6_0") {
+    include_bytes!("../include/600/fdb.options")
+} else if cfg!(feature = "fdb-5_2") {
+    include_bytes!("../include/520/fdb.options")
+} else if cfg!(feature = "fdb-5_1") {
+    include_bytes!("../include/510/fdb.options")
+} else {
+    panic!("one of the `fdb-X_Y` features must be enabled to select the FoundationDB API version")
+};
 
 pub fn emit(w: &mut impl fmt::Write) -> fmt::Result {
     let mut reader = OPTIONS_DATA;
@@ -386,10 +609,11 @@ pub fn emit(w: &mut impl fmt::Write) -> fmt::Result {
                         .find(|attr| attr.name.local_name == "name")
                         .unwrap();
 
-                    let options = on_scope(&mut iter);
+                    let (options, hidden) = on_scope(&mut iter);
                     scopes.push(FdbScope {
                         name: scope_name.value,
                         options,
+                        hidden,
                     });
                 }
             }
@@ -401,7 +625,7 @@ pub fn emit(w: &mut impl fmt::Write) -> fmt::Result {
     }
 
     writeln!(w, "use std::convert::TryFrom;")?;
-    writeln!(w, "use crate::{{FdbError, FdbResult}};")?;
+    writeln!(w, "use crate::FdbError;")?;
     writeln!(w, "use foundationdb_sys as fdb_sys;")?;
     for scope in scopes.iter() {
         scope.gen_ty(w)?;
@@ -7,6 +7,8 @@ use syn::parse::Parser;
 use syn::{Item, ItemFn, LitInt};
 use try_map::FallibleMapExt;
 
+mod tuple;
+
 /// Allow to compute the range of supported api versions for a functionality.
 ///
 /// This macro came out from the frustration of bumping fdb's version, where
@@ -17,8 +19,10 @@ use try_map::FallibleMapExt;
 /// Thanks to the macro, we can now specify a `minimum` and an optional `max` version, and
 /// generate the right list of any. Not specifying a `max` allow easy bump to a new version.
 ///
-/// `#[cfg_api_versions(min = 510, max = 600)]` will be translated to:
-/// `#[cfg(any(feature = "fdb-5_1", feature = "fdb-5_2", feature = "fdb-6_0"))]`
+/// The version features are additive, the highest one enabled selects the api version, so
+/// an item with a `max` is also disabled by any higher version feature:
+/// `#[cfg_api_versions(min = 600, max = 610)]` will be translated to:
+/// `#[cfg(all(any(feature = "fdb-6_0", feature = "fdb-6_1"), not(any(feature = "fdb-6_2", ...))))]`
 #[proc_macro_attribute]
 pub fn cfg_api_versions(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = proc_macro2::TokenStream::from(args);
@@ -63,16 +67,45 @@ fn generate_feature_range(
     minimum_version: i32,
     maximum_version: Option<i32>,
 ) -> proc_macro2::TokenStream {
-    let allowed_fdb_versions: Vec<TokenStream2> =
-        get_supported_feature_range(minimum_version, maximum_version)
+    let to_features = |versions: Vec<String>| -> Vec<TokenStream2> {
+        versions
             .iter()
             .map(|fdb_version| quote!(feature = #fdb_version))
-            .collect();
+            .collect()
+    };
+    let allowed_fdb_versions = to_features(get_supported_feature_range(
+        minimum_version,
+        maximum_version,
+    ));
+    let newer_fdb_versions = to_features(
+        maximum_version
+            .map(get_newer_feature_range)
+            .unwrap_or_default(),
+    );
+
+    if newer_fdb_versions.is_empty() {
+        quote!(
+            #[cfg(any(#(#allowed_fdb_versions),*))]
+            #input
+        )
+    } else {
+        quote!(
+            #[cfg(all(any(#(#allowed_fdb_versions),*), not(any(#(#newer_fdb_versions),*))))]
+            #input
+        )
+    }
+}
 
-    quote!(
-        #[cfg(any(#(#allowed_fdb_versions),*))]
-        #input
-    )
+/// The features selecting a version above `maximum_version`.
+fn get_newer_feature_range(maximum_version: i32) -> Vec<String> {
+    let mut values: Vec<String> = get_version_mapping()
+        .iter()
+        .filter(|(_, version)| **version > maximum_version)
+        .map(|(feature, _)| feature.to_owned())
+        .collect();
+    values.sort();
+
+    values
 }
 
 /// Given a range of version, this function will generate the appropriate macro text.
@@ -92,7 +125,8 @@ fn get_supported_feature_range(minimum_version: i32, maximum_version: Option<i32
 
 // TODO: Should we import something like lazy_static?
 fn get_version_mapping() -> HashMap<String, i32> {
-    let mut version_mapping = HashMap::with_capacity(8);
+    let mut version_mapping = HashMap::with_capacity(10);
+    version_mapping.insert("fdb-7_3".into(), 730);
     version_mapping.insert("fdb-7_1".into(), 710);
     version_mapping.insert("fdb-7_0".into(), 700);
     version_mapping.insert("fdb-6_3".into(), 630);
@@ -129,19 +163,155 @@ pub fn simulation_entrypoint(_attr: TokenStream, item: TokenStream) -> TokenStre
     .into()
 }
 
+/// Turns an `async fn main(db: Database)` into the `main` of a program using FoundationDB, see
+/// `foundationdb::main`.
+#[proc_macro_attribute]
+pub fn main(attr: TokenStream, item: TokenStream) -> TokenStream {
+    main_impl(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Turns an `async fn(Database, Subspace) -> Result<(), E>` into a test run with a subspace of
+/// its own, see `foundationdb::testing::test`.
+#[proc_macro_attribute]
+pub fn test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    test_impl(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `TuplePack` for a struct or an enum with unit variants, see
+/// `foundationdb::tuple::TuplePackFields`.
+#[proc_macro_derive(TuplePack, attributes(fdb))]
+pub fn derive_tuple_pack(input: TokenStream) -> TokenStream {
+    tuple::derive_pack(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `TupleUnpack` for a struct or an enum with unit variants, see
+/// `foundationdb::tuple::TuplePackFields`.
+#[proc_macro_derive(TupleUnpack, attributes(fdb))]
+pub fn derive_tuple_unpack(input: TokenStream) -> TokenStream {
+    tuple::derive_unpack(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// The executor running the body of `#[foundationdb::main]` or `#[foundationdb::testing::test]`
+fn executor(macro_name: &str, attr: TokenStream2) -> syn::Result<TokenStream2> {
+    if attr.is_empty() {
+        return Ok(quote!(::foundationdb::macro_support::block_on));
+    }
+    let flavor: syn::Ident = syn::parse2(attr)?;
+    if flavor == "tokio" {
+        Ok(quote!(|future| {
+            ::tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .expect("failed to build the tokio runtime")
+                .block_on(future)
+        }))
+    } else {
+        Err(syn::Error::new_spanned(
+            flavor,
+            format!(
+                "unsupported executor, use `#[{}]` or `#[{}(tokio)]`",
+                macro_name, macro_name
+            ),
+        ))
+    }
+}
+
+/// Checks that `function` is an `async fn` without generics taking `arguments` arguments
+fn check_signature(function: &ItemFn, expected: &str, arguments: usize) -> syn::Result<()> {
+    let sig = &function.sig;
+    if sig.asyncness.is_none() {
+        return Err(syn::Error::new_spanned(
+            sig.fn_token,
+            format!("the `async` keyword is missing, expected `{}`", expected),
+        ));
+    }
+    if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+        return Err(syn::Error::new_spanned(
+            &sig.generics,
+            format!("generics are not supported, expected `{}`", expected),
+        ));
+    }
+    if sig.inputs.len() != arguments {
+        return Err(syn::Error::new_spanned(
+            &sig.inputs,
+            format!("wrong number of arguments, expected `{}`", expected),
+        ));
+    }
+    Ok(())
+}
+
+fn main_impl(attr: TokenStream2, input: TokenStream2) -> syn::Result<TokenStream2> {
+    let executor = executor("foundationdb::main", attr)?;
+    let mut main: ItemFn = syn::parse2(input)?;
+    check_signature(&main, "async fn main(db: Database)", 1)?;
+    if main.sig.ident != "main" {
+        return Err(syn::Error::new_spanned(
+            &main.sig.ident,
+            "`#[foundationdb::main]` can only be used on the `main` function",
+        ));
+    }
+    let attrs = std::mem::take(&mut main.attrs);
+    let vis = &main.vis;
+    let output = &main.sig.output;
+
+    Ok(quote!(
+        #(#attrs)*
+        #vis fn main() #output {
+            #main
+            ::foundationdb::macro_support::run_main(main, #executor)
+        }
+    ))
+}
+
+fn test_impl(attr: TokenStream2, input: TokenStream2) -> syn::Result<TokenStream2> {
+    let executor = executor("foundationdb::testing::test", attr)?;
+    let mut test: ItemFn = syn::parse2(input)?;
+    check_signature(
+        &test,
+        "async fn(db: Database, subspace: Subspace) -> Result<(), E>",
+        2,
+    )?;
+    // attributes like `#[ignore]` apply to the generated test
+    let attrs = std::mem::take(&mut test.attrs);
+    let name = test.sig.ident.clone();
+
+    Ok(quote!(
+        #[test]
+        #(#attrs)*
+        fn #name() {
+            #test
+            ::foundationdb::macro_support::run_test(
+                concat!(module_path!(), "::", stringify!(#name)),
+                #name,
+                #executor,
+            )
+        }
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::cfg_api_versions_impl;
     use crate::get_supported_feature_range;
+    use crate::{main_impl, test_impl};
     use proc_macro2::TokenStream;
     use quote::quote;
 
     #[test]
     fn test_create_supported_list() {
         let v = get_supported_feature_range(700, None);
-        assert_eq!(v.len(), 2);
+        assert_eq!(v.len(), 3);
         assert!(v.contains(&String::from("fdb-7_0")));
         assert!(v.contains(&String::from("fdb-7_1")));
+        assert!(v.contains(&String::from("fdb-7_3")));
 
         let v = get_supported_feature_range(600, Some(700));
         assert_eq!(v.len(), 5);
@@ -160,7 +330,8 @@ mod tests {
         assert!(v.contains(&String::from("fdb-5_0")));
 
         let v = get_supported_feature_range(500, None);
-        assert_eq!(v.len(), 9);
+        assert_eq!(v.len(), 10);
+        assert!(v.contains(&String::from("fdb-7_3")));
         assert!(v.contains(&String::from("fdb-7_1")));
         assert!(v.contains(&String::from("fdb-7_0")));
         assert!(v.contains(&String::from("fdb-6_3")));
@@ -186,9 +357,31 @@ mod tests {
         assert_eq!(result.to_string(), expected.to_string())
     }
 
+    fn test_cfg_versions_below(
+        expected_versions: TokenStream,
+        newer_versions: TokenStream,
+        attrs: TokenStream,
+    ) {
+        let input = quote! {
+            fn ma_fonction() {}
+        };
+
+        let expected = quote! {
+            #[cfg(all(any(#expected_versions), not(any(#newer_versions))))]
+            fn ma_fonction() {}
+        };
+
+        let result = cfg_api_versions_impl(attrs, input);
+        assert_eq!(result.to_string(), expected.to_string())
+    }
+
     #[test]
     fn test_min_700_no_max_version() {
-        let data = quote!(feature = "fdb-7_0", feature = "fdb-7_1");
+        let data = quote!(
+            feature = "fdb-7_0",
+            feature = "fdb-7_1",
+            feature = "fdb-7_3"
+        );
 
         let attrs = quote!(min = 700);
 
@@ -205,9 +398,11 @@ mod tests {
             feature = "fdb-7_0"
         );
 
+        let newer_versions = quote!(feature = "fdb-7_1", feature = "fdb-7_3");
+
         let attrs = quote!(min = 600, max = 700);
 
-        test_cfg_versions(expected_versions, attrs)
+        test_cfg_versions_below(expected_versions, newer_versions, attrs)
     }
 
     #[test]
@@ -220,9 +415,17 @@ mod tests {
             feature = "fdb-6_1"
         );
 
+        let newer_versions = quote!(
+            feature = "fdb-6_2",
+            feature = "fdb-6_3",
+            feature = "fdb-7_0",
+            feature = "fdb-7_1",
+            feature = "fdb-7_3"
+        );
+
         let attrs = quote!(min = 500, max = 610);
 
-        test_cfg_versions(expected_versions, attrs)
+        test_cfg_versions_below(expected_versions, newer_versions, attrs)
     }
 
     #[test]
@@ -236,7 +439,8 @@ mod tests {
             feature = "fdb-6_2",
             feature = "fdb-6_3",
             feature = "fdb-7_0",
-            feature = "fdb-7_1"
+            feature = "fdb-7_1",
+            feature = "fdb-7_3"
         );
 
         let attrs = quote!(min = 500);
@@ -244,6 +448,27 @@ mod tests {
         test_cfg_versions(expected_versions, attrs)
     }
 
+    #[test]
+    fn test_min_500_max_710() {
+        let expected_versions = quote!(
+            feature = "fdb-5_0",
+            feature = "fdb-5_1",
+            feature = "fdb-5_2",
+            feature = "fdb-6_0",
+            feature = "fdb-6_1",
+            feature = "fdb-6_2",
+            feature = "fdb-6_3",
+            feature = "fdb-7_0",
+            feature = "fdb-7_1"
+        );
+
+        let newer_versions = quote!(feature = "fdb-7_3");
+
+        let attrs = quote!(min = 500, max = 710);
+
+        test_cfg_versions_below(expected_versions, newer_versions, attrs)
+    }
+
     #[test]
     #[should_panic]
     fn test_no_min_version() {
@@ -253,4 +478,139 @@ mod tests {
 
         test_cfg_versions(expected_versions, attrs)
     }
+
+    #[test]
+    fn test_main() {
+        let input = quote! {
+            async fn main(db: Database) -> FdbResult<()> {
+                Ok(())
+            }
+        };
+
+        let expected = quote! {
+            fn main() -> FdbResult<()> {
+                async fn main(db: Database) -> FdbResult<()> {
+                    Ok(())
+                }
+                ::foundationdb::macro_support::run_main(main, ::foundationdb::macro_support::block_on)
+            }
+        };
+
+        let output = main_impl(TokenStream::new(), input).expect("valid main");
+        assert_eq!(output.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_main_tokio() {
+        let input = quote! {
+            async fn main(db: Database) {}
+        };
+        let output = main_impl(quote!(tokio), input).expect("valid main");
+        assert!(output
+            .to_string()
+            .contains(":: tokio :: runtime :: Builder"));
+    }
+
+    #[test]
+    fn test_test() {
+        let input = quote! {
+            #[ignore]
+            async fn my_test(db: Database, subspace: Subspace) -> FdbResult<()> {
+                Ok(())
+            }
+        };
+
+        let expected = quote! {
+            #[test]
+            #[ignore]
+            fn my_test() {
+                async fn my_test(db: Database, subspace: Subspace) -> FdbResult<()> {
+                    Ok(())
+                }
+                ::foundationdb::macro_support::run_test(
+                    concat!(module_path!(), "::", stringify!(my_test)),
+                    my_test,
+                    ::foundationdb::macro_support::block_on,
+                )
+            }
+        };
+
+        let output = test_impl(TokenStream::new(), input).expect("valid test");
+        assert_eq!(output.to_string(), expected.to_string());
+    }
+
+    fn error(result: syn::Result<TokenStream>) -> String {
+        result.expect_err("the macro should fail").to_string()
+    }
+
+    #[test]
+    fn test_misuse() {
+        let not_async = error(main_impl(
+            TokenStream::new(),
+            quote!(
+                fn main(db: Database) {}
+            ),
+        ));
+        assert!(
+            not_async.contains("`async` keyword is missing"),
+            "{}",
+            not_async
+        );
+
+        let no_database = error(main_impl(
+            TokenStream::new(),
+            quote!(
+                async fn main() {}
+            ),
+        ));
+        assert!(
+            no_database.contains("wrong number of arguments"),
+            "{}",
+            no_database
+        );
+
+        let not_main = error(main_impl(
+            TokenStream::new(),
+            quote!(
+                async fn run(db: Database) {}
+            ),
+        ));
+        assert!(
+            not_main.contains("only be used on the `main` function"),
+            "{}",
+            not_main
+        );
+
+        let executor = error(main_impl(
+            quote!(smol),
+            quote!(
+                async fn main(db: Database) {}
+            ),
+        ));
+        assert!(executor.contains("unsupported executor"), "{}", executor);
+
+        let generic = error(test_impl(
+            TokenStream::new(),
+            quote!(
+                async fn my_test<T>(db: Database, subspace: Subspace) {}
+            ),
+        ));
+        assert!(
+            generic.contains("generics are not supported"),
+            "{}",
+            generic
+        );
+
+        let no_subspace = error(test_impl(
+            TokenStream::new(),
+            quote!(
+                async fn my_test(db: Database) {}
+            ),
+        ));
+        assert!(
+            no_subspace.contains("wrong number of arguments"),
+            "{}",
+            no_subspace
+        );
+    }
 }
@@ -0,0 +1,389 @@
+//! `#[derive(TuplePack, TupleUnpack)]`, see `foundationdb::tuple::TuplePackFields`
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    parse_quote, Data, DataEnum, DeriveInput, Field, Fields, GenericParam, Generics, Index,
+    Lifetime, LifetimeParam,
+};
+
+/// How a field is packed, from its `#[fdb(...)]` attributes
+enum FieldKind {
+    Element,
+    Flatten,
+    Skip,
+}
+
+fn field_kind(field: &Field) -> syn::Result<FieldKind> {
+    let mut kind = FieldKind::Element;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("fdb"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if !matches!(kind, FieldKind::Element) {
+                return Err(meta.error("`skip` and `flatten` can not be combined"));
+            }
+            if meta.path.is_ident("skip") {
+                kind = FieldKind::Skip;
+            } else if meta.path.is_ident("flatten") {
+                kind = FieldKind::Flatten;
+            } else {
+                return Err(meta.error("unknown attribute, expected `skip` or `flatten`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(kind)
+}
+
+/// The fields with how they are packed, and how they are named in the struct
+fn fields(fields: &Fields) -> syn::Result<Vec<(TokenStream, FieldKind)>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let member = match &field.ident {
+                Some(ident) => quote!(#ident),
+                None => {
+                    let index = Index::from(i);
+                    quote!(#index)
+                }
+            };
+            Ok((member, field_kind(field)?))
+        })
+        .collect()
+}
+
+fn add_bounds(mut generics: Generics, bound: TokenStream) -> Generics {
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+    generics
+}
+
+/// The enum, checked to only hold unit variants
+fn unit_enum<'a>(input: &DeriveInput, data: &'a DataEnum) -> syn::Result<&'a DataEnum> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "generic enums are not supported",
+        ));
+    }
+    if let Some(variant) = data.variants.iter().find(|v| !v.fields.is_empty()) {
+        return Err(syn::Error::new_spanned(
+            variant,
+            "only enums with unit variants are supported",
+        ));
+    }
+    Ok(data)
+}
+
+pub(crate) fn derive_pack(input: TokenStream) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse2(input)?;
+    let name = &input.ident;
+
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        Data::Enum(data) => {
+            let data = unit_enum(&input, data)?;
+            let variants: Vec<_> = data.variants.iter().map(|v| &v.ident).collect();
+            return Ok(quote!(
+                impl ::foundationdb::tuple::TuplePack for #name {
+                    fn pack<W: ::foundationdb::tuple::io::Write>(
+                        &self,
+                        w: &mut W,
+                        tuple_depth: ::foundationdb::tuple::TupleDepth,
+                    ) -> ::foundationdb::tuple::io::Result<::foundationdb::tuple::VersionstampOffset> {
+                        let value: i64 = match self {
+                            #(Self::#variants => Self::#variants as i64,)*
+                        };
+                        ::foundationdb::tuple::TuplePack::pack(&value, w, tuple_depth)
+                    }
+
+                    fn size_hint(&self, tuple_depth: ::foundationdb::tuple::TupleDepth) -> usize {
+                        let value: i64 = match self {
+                            #(Self::#variants => Self::#variants as i64,)*
+                        };
+                        ::foundationdb::tuple::TuplePack::size_hint(&value, tuple_depth)
+                    }
+                }
+            ));
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "unions are not supported",
+            ))
+        }
+    };
+
+    let fields = fields(&data.fields)?;
+    let pack_fields = fields.iter().map(|(member, kind)| match kind {
+            FieldKind::Element => quote!(
+                offset += ::foundationdb::tuple::TuplePack::pack(&self.#member, w, tuple_depth)?;
+            ),
+            FieldKind::Flatten => quote!(
+                offset += ::foundationdb::tuple::TuplePackFields::pack_fields(&self.#member, w, tuple_depth)?;
+            ),
+            FieldKind::Skip => quote!(),
+        });
+    let size_hints = fields.iter().map(|(member, kind)| match kind {
+        FieldKind::Element => quote!(
+            + ::foundationdb::tuple::TuplePack::size_hint(&self.#member, tuple_depth)
+        ),
+        FieldKind::Flatten => quote!(
+            + ::foundationdb::tuple::TuplePackFields::fields_size_hint(&self.#member, tuple_depth)
+        ),
+        FieldKind::Skip => quote!(),
+    });
+
+    let generics = add_bounds(
+        input.generics.clone(),
+        quote!(::foundationdb::tuple::TuplePack),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote!(
+        impl #impl_generics ::foundationdb::tuple::TuplePackFields for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn pack_fields<W: ::foundationdb::tuple::io::Write>(
+                &self,
+                w: &mut W,
+                tuple_depth: ::foundationdb::tuple::TupleDepth,
+            ) -> ::foundationdb::tuple::io::Result<::foundationdb::tuple::VersionstampOffset> {
+                #[allow(unused_mut)]
+                let mut offset = ::foundationdb::tuple::VersionstampOffset::None { size: 0 };
+                #(#pack_fields)*
+                Ok(offset)
+            }
+
+            #[allow(unused_variables)]
+            fn fields_size_hint(&self, tuple_depth: ::foundationdb::tuple::TupleDepth) -> usize {
+                0 #(#size_hints)*
+            }
+        }
+
+        impl #impl_generics ::foundationdb::tuple::TuplePack for #name #ty_generics #where_clause {
+            fn pack<W: ::foundationdb::tuple::io::Write>(
+                &self,
+                w: &mut W,
+                tuple_depth: ::foundationdb::tuple::TupleDepth,
+            ) -> ::foundationdb::tuple::io::Result<::foundationdb::tuple::VersionstampOffset> {
+                ::foundationdb::tuple::TuplePackFields::pack_tuple(self, w, tuple_depth)
+            }
+
+            fn size_hint(&self, tuple_depth: ::foundationdb::tuple::TupleDepth) -> usize {
+                ::foundationdb::tuple::TuplePackFields::tuple_size_hint(self, tuple_depth)
+            }
+        }
+    ))
+}
+
+/// The generics of the `TupleUnpack<'de>` impl, with the lifetime used as `'de`
+///
+/// The lifetime of the type is borrowed from the input, a type without one is unpacked for any
+/// `'de`.
+fn unpack_generics(input: &DeriveInput) -> syn::Result<(Generics, Lifetime)> {
+    let mut lifetimes = input.generics.lifetimes();
+    let (mut generics, lifetime) = match (lifetimes.next(), lifetimes.next()) {
+        (None, _) => {
+            let lifetime = Lifetime::new("'de", Span::call_site());
+            let mut generics = input.generics.clone();
+            generics.params.insert(
+                0,
+                GenericParam::Lifetime(LifetimeParam::new(lifetime.clone())),
+            );
+            (generics, lifetime)
+        }
+        (Some(param), None) => (input.generics.clone(), param.lifetime.clone()),
+        (Some(_), Some(param)) => {
+            return Err(syn::Error::new_spanned(
+                param,
+                "types with several lifetimes are not supported",
+            ))
+        }
+    };
+    for param in generics.type_params_mut() {
+        param
+            .bounds
+            .push(parse_quote!(::foundationdb::tuple::TupleUnpack<#lifetime>));
+    }
+    Ok((generics, lifetime))
+}
+
+pub(crate) fn derive_unpack(input: TokenStream) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse2(input)?;
+    let name = &input.ident;
+    let (generics, de) = unpack_generics(&input)?;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        Data::Enum(data) => {
+            let data = unit_enum(&input, data)?;
+            let variants: Vec<_> = data.variants.iter().map(|v| &v.ident).collect();
+            let message = format!("not a variant of `{}`", name);
+            return Ok(quote!(
+                impl #impl_generics ::foundationdb::tuple::TupleUnpack<#de> for #name {
+                    fn unpack(
+                        input: &#de [u8],
+                        tuple_depth: ::foundationdb::tuple::TupleDepth,
+                    ) -> ::foundationdb::tuple::PackResult<(&#de [u8], Self)> {
+                        let (input, value) =
+                            <i64 as ::foundationdb::tuple::TupleUnpack>::unpack(input, tuple_depth)?;
+                        #(
+                            if value == Self::#variants as i64 {
+                                return Ok((input, Self::#variants));
+                            }
+                        )*
+                        Err(::foundationdb::tuple::PackError::Message(#message.into()))
+                    }
+                }
+            ));
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "unions are not supported",
+            ))
+        }
+    };
+
+    let fields = fields(&data.fields)?;
+    let vars: Vec<_> = (0..fields.len())
+        .map(|i| format_ident!("field{}", i))
+        .collect();
+    let unpack_fields = fields.iter().zip(&vars).map(|((_, kind), var)| match kind {
+        FieldKind::Element => quote!(
+            let (input, #var) = ::foundationdb::tuple::TupleUnpack::unpack(input, tuple_depth)?;
+        ),
+        FieldKind::Flatten => quote!(
+            let (input, #var) = ::foundationdb::tuple::TupleUnpackFields::unpack_fields(input, tuple_depth)?;
+        ),
+        FieldKind::Skip => quote!(
+            let #var = ::core::default::Default::default();
+        ),
+    });
+    let members = fields.iter().map(|(member, _)| member);
+
+    Ok(quote!(
+        impl #impl_generics ::foundationdb::tuple::TupleUnpackFields<#de> for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn unpack_fields(
+                input: &#de [u8],
+                tuple_depth: ::foundationdb::tuple::TupleDepth,
+            ) -> ::foundationdb::tuple::PackResult<(&#de [u8], Self)> {
+                #(#unpack_fields)*
+                Ok((input, Self { #(#members: #vars),* }))
+            }
+        }
+
+        impl #impl_generics ::foundationdb::tuple::TupleUnpack<#de> for #name #ty_generics #where_clause {
+            fn unpack(
+                input: &#de [u8],
+                tuple_depth: ::foundationdb::tuple::TupleDepth,
+            ) -> ::foundationdb::tuple::PackResult<(&#de [u8], Self)> {
+                ::foundationdb::tuple::TupleUnpackFields::unpack_tuple(input, tuple_depth)
+            }
+        }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{derive_pack, derive_unpack};
+    use quote::quote;
+
+    fn error(result: syn::Result<proc_macro2::TokenStream>) -> String {
+        result.expect_err("the derive should fail").to_string()
+    }
+
+    #[test]
+    fn test_derive_struct() {
+        let input = quote!(
+            struct Key<'a, T> {
+                name: &'a str,
+                #[fdb(skip)]
+                cache: u32,
+                #[fdb(flatten)]
+                inner: T,
+            }
+        );
+        let pack = derive_pack(input.clone()).unwrap().to_string();
+        assert!(pack.contains("impl < 'a , T : :: foundationdb :: tuple :: TuplePack >"));
+        assert!(pack.contains("TuplePackFields :: pack_fields (& self . inner"));
+        assert!(pack.contains("TuplePackFields :: fields_size_hint (& self . inner"));
+        assert!(!pack.contains("self . cache"));
+
+        let unpack = derive_unpack(input).unwrap().to_string();
+        assert!(unpack.contains("TupleUnpack < 'a > for Key < 'a , T >"));
+        assert!(unpack.contains("let field1 = :: core :: default :: Default :: default ()"));
+        assert!(unpack.contains("Self { name : field0 , cache : field1 , inner : field2 }"));
+
+        let unpack = derive_unpack(quote!(
+            struct Pair(String, u64);
+        ))
+        .unwrap()
+        .to_string();
+        assert!(unpack
+            .contains("impl < 'de > :: foundationdb :: tuple :: TupleUnpack < 'de > for Pair"));
+        assert!(unpack.contains("Self { 0 : field0 , 1 : field1 }"));
+    }
+
+    #[test]
+    fn test_derive_enum() {
+        let input = quote!(
+            enum State {
+                Active = 1,
+                Deleted = 7,
+            }
+        );
+        let pack = derive_pack(input.clone()).unwrap().to_string();
+        assert!(pack.contains("Self :: Deleted => Self :: Deleted as i64"));
+        let unpack = derive_unpack(input).unwrap().to_string();
+        assert!(unpack.contains("\"not a variant of `State`\""));
+    }
+
+    #[test]
+    fn test_derive_misuse() {
+        let unknown = error(derive_pack(quote!(
+            struct Key {
+                #[fdb(rename = "id")]
+                id: u64,
+            }
+        )));
+        assert_eq!(unknown, "unknown attribute, expected `skip` or `flatten`");
+
+        let combined = error(derive_unpack(quote!(
+            struct Key {
+                #[fdb(skip, flatten)]
+                id: u64,
+            }
+        )));
+        assert_eq!(combined, "`skip` and `flatten` can not be combined");
+
+        let data = error(derive_pack(quote!(
+            enum Value {
+                Int(i64),
+            }
+        )));
+        assert_eq!(data, "only enums with unit variants are supported");
+
+        let lifetimes = error(derive_unpack(quote!(
+            struct Key<'a, 'b> {
+                a: &'a str,
+                b: &'b str,
+            }
+        )));
+        assert_eq!(lifetimes, "types with several lifetimes are not supported");
+
+        let union = error(derive_pack(quote!(
+            union Bits {
+                a: u32,
+            }
+        )));
+        assert_eq!(union, "unions are not supported");
+    }
+}
@@ -1,6 +1,8 @@
 use foundationdb_macros::simulation_entrypoint;
 use foundationdb_simulation::{RustWorkload, WorkloadContext};
 
+#[path = "../common/atomic.rs"]
+mod atomic;
 mod workload;
 
 use workload::AtomicWorkload;
@@ -0,0 +1,16 @@
+use foundationdb_macros::simulation_entrypoint;
+use foundationdb_simulation::{RustWorkload, WorkloadContext};
+
+#[path = "../common/atomic.rs"]
+mod atomic;
+mod workload;
+
+use workload::AsyncAtomicWorkload;
+
+#[simulation_entrypoint]
+pub fn simulated_main(name: &str, context: WorkloadContext) -> Box<dyn RustWorkload> {
+    match name {
+        "AsyncAtomicWorkload" => Box::new(AsyncAtomicWorkload::new(context)),
+        name => panic!("no workload with name: {:?}", name),
+    }
+}
@@ -0,0 +1,135 @@
+use foundationdb::{options, tuple::Subspace, FdbBindingError, RangeOption};
+use foundationdb_simulation::{
+    details, sim_log, AsyncRustWorkload, Metric, Severity, SimDatabase, WorkloadContext,
+    WorkloadFuture,
+};
+use futures::TryStreamExt;
+
+use crate::atomic::AtomicCounter;
+
+pub struct AsyncAtomicWorkload {
+    context: WorkloadContext,
+    client_id: usize,
+    counter: AtomicCounter,
+    // how many keys are written then scanned
+    range_count: usize,
+}
+
+impl AsyncAtomicWorkload {
+    pub fn new(context: WorkloadContext) -> Self {
+        Self {
+            client_id: context.client_id(),
+            counter: AtomicCounter::new(&context),
+            range_count: context.get_option("rangeCount").unwrap_or(1000),
+            context,
+        }
+    }
+
+    async fn write_range(&mut self, db: &SimDatabase) {
+        let subspace = Subspace::all().subspace(&RANGE_KEY);
+        for batch in (0..self.range_count).step_by(RANGE_BATCH) {
+            let end = self.range_count.min(batch + RANGE_BATCH);
+            db.run(|trx, _maybe_committed| {
+                let subspace = &subspace;
+                async move {
+                    for i in batch..end {
+                        trx.set(&subspace.pack(&i), &i.to_le_bytes());
+                    }
+                    Ok(())
+                }
+            })
+            .await
+            .expect("Could not write the range");
+        }
+    }
+
+    /// Scans the range written in `write_range` in small batches, so that it takes many fetches.
+    async fn read_range(
+        &self,
+        db: &SimDatabase,
+        reverse: bool,
+    ) -> Result<Vec<usize>, FdbBindingError> {
+        let subspace = Subspace::all().subspace(&RANGE_KEY);
+        db.run(|trx, _maybe_committed| {
+            let subspace = &subspace;
+            async move {
+                let mut opt = RangeOption::from(subspace).mode(options::StreamingMode::Small);
+                if reverse {
+                    opt = opt.rev();
+                }
+                let mut values = trx.get_ranges_keyvalues(opt, false);
+                let mut indices = Vec::new();
+                while let Some(value) = values.try_next().await? {
+                    indices.push(subspace.unpack::<usize>(value.key())?);
+                }
+                Ok(indices)
+            }
+        })
+        .await
+    }
+
+    async fn check_range(&mut self, db: &SimDatabase) {
+        for reverse in [false, true] {
+            let mut expected: Vec<usize> = (0..self.range_count).collect();
+            if reverse {
+                expected.reverse();
+            }
+            let (severity, message) = match self.read_range(db, reverse).await {
+                Ok(indices) if indices == expected => (Severity::Info, "Range scan match"),
+                Ok(_) => (Severity::Error, "Range scan doesn't match"),
+                Err(_) => (Severity::Error, "Could not scan the range"),
+            };
+            self.context.trace(
+                severity,
+                message,
+                details![
+                    "Layer" => "Rust",
+                    "Client" => self.client_id,
+                    "Expected" => self.range_count,
+                    "Reverse" => reverse,
+                ],
+            );
+        }
+    }
+}
+
+const RANGE_KEY: &[u8] = b"range";
+// how many keys of the range are written by each transaction
+const RANGE_BATCH: usize = 100;
+
+impl AsyncRustWorkload for AsyncAtomicWorkload {
+    fn description(&self) -> String {
+        "Async Atomic Rust Workload".into()
+    }
+    fn setup<'a>(&'a mut self, db: &'a SimDatabase) -> WorkloadFuture<'a> {
+        sim_log!(self.context, "rust_setup({})", self.client_id);
+        Box::pin(async move {
+            if self.client_id == 0 {
+                self.write_range(db).await;
+            }
+        })
+    }
+    fn start<'a>(&'a mut self, db: &'a SimDatabase) -> WorkloadFuture<'a> {
+        sim_log!(self.context, "rust_start({})", self.client_id);
+        Box::pin(async move {
+            self.counter.run(&self.context, db).await;
+        })
+    }
+    fn check<'a>(&'a mut self, db: &'a SimDatabase) -> WorkloadFuture<'a> {
+        sim_log!(self.context, "rust_check({})", self.client_id);
+        Box::pin(async move {
+            self.counter.check(&self.context, db).await;
+            if self.client_id == 0 {
+                self.check_range(db).await;
+            }
+        })
+    }
+    fn get_metrics(&self) -> Vec<Metric> {
+        sim_log!(self.context, "rust_get_metrics({})", self.client_id);
+        self.counter.metrics()
+    }
+    fn get_check_timeout(&self) -> f64 {
+        sim_log!(self.context, "rust_get_check_timeout({})", self.client_id);
+        5000.0
+    }
+}
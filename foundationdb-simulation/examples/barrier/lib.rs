@@ -0,0 +1,14 @@
+use foundationdb_macros::simulation_entrypoint;
+use foundationdb_simulation::{RustWorkload, WorkloadContext};
+
+mod workload;
+
+use workload::BarrierWorkload;
+
+#[simulation_entrypoint]
+pub fn simulated_main(name: &str, context: WorkloadContext) -> Box<dyn RustWorkload> {
+    match name {
+        "BarrierWorkload" => Box::new(BarrierWorkload::new(context)),
+        name => panic!("no workload with name: {:?}", name),
+    }
+}
@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use foundationdb::options::StreamingMode;
+use foundationdb::tuple::Subspace;
+use foundationdb::RangeOption;
+use foundationdb_simulation::{
+    details, AsyncRustWorkload, Metric, Severity, SimDatabase, WorkloadContext, WorkloadFuture,
+};
+
+/// Every client loads `count` keys, then waits for the others at a barrier before counting the
+/// keys of all the clients: past the barrier, every client must see all of them.
+pub struct BarrierWorkload {
+    context: WorkloadContext,
+    client_id: usize,
+    client_count: usize,
+    // how many keys each client loads
+    count: usize,
+    // how many keys this client found once all the clients loaded theirs
+    found_count: usize,
+}
+
+impl BarrierWorkload {
+    pub fn new(context: WorkloadContext) -> Self {
+        Self {
+            client_id: context.client_id(),
+            client_count: context.client_count(),
+            count: context.get_option("count").expect("Could not get count"),
+            context,
+            found_count: 0,
+        }
+    }
+
+    async fn load(&self, db: &SimDatabase) {
+        let subspace = Subspace::all().subspace(&(KEYS, self.client_id as u64));
+        for i in 0..self.count as u64 {
+            let mut trx = db.create_trx().expect("Could not create transaction");
+            loop {
+                trx.set(&subspace.pack(&i), &i.to_le_bytes());
+                match trx.commit().await {
+                    Ok(_) => break,
+                    Err(err) => trx = err.on_error().await.expect("Could not retry"),
+                }
+            }
+        }
+    }
+
+    async fn count_all(&self, db: &SimDatabase) -> usize {
+        let subspace = Subspace::all().subspace(&KEYS);
+        let mut trx = db.create_trx().expect("Could not create transaction");
+        'retry: loop {
+            let mut opt = RangeOption {
+                mode: StreamingMode::WantAll,
+                ..RangeOption::from(&subspace)
+            };
+            let mut count = 0;
+            loop {
+                let result = trx.snapshot().get_range(&opt, 1).await;
+                let values = match result {
+                    Ok(values) => values,
+                    Err(err) => {
+                        trx = trx.on_error(err).await.expect("Could not retry");
+                        continue 'retry;
+                    }
+                };
+                count += values.len();
+                let last = values.last().map(|value| value.key());
+                opt = match opt.next_range_after(values.more(), values.len(), last) {
+                    Some(next) => next,
+                    None => return count,
+                };
+            }
+        }
+    }
+}
+
+const KEYS: &str = "barrier_example";
+const BARRIER_TIMEOUT: Duration = Duration::from_secs(300);
+
+impl AsyncRustWorkload for BarrierWorkload {
+    fn description(&self) -> String {
+        "Barrier Rust Workload".into()
+    }
+    fn setup<'a>(&'a mut self, _db: &'a SimDatabase) -> WorkloadFuture<'a> {
+        Box::pin(async {})
+    }
+    fn start<'a>(&'a mut self, db: &'a SimDatabase) -> WorkloadFuture<'a> {
+        Box::pin(async move {
+            // first phase: every client loads its keys
+            self.load(db).await;
+
+            if let Err(err) = self.context.barrier(db, "loaded", BARRIER_TIMEOUT).await {
+                self.context.trace(
+                    Severity::Error,
+                    "Barrier failed",
+                    details![
+                        "Layer" => "Rust",
+                        "Client" => self.client_id,
+                        "Error" => err,
+                    ],
+                );
+                return;
+            }
+
+            // second phase: every client queries the keys of all of them
+            self.found_count = self.count_all(db).await;
+        })
+    }
+    fn check<'a>(&'a mut self, _db: &'a SimDatabase) -> WorkloadFuture<'a> {
+        Box::pin(async move {
+            let expected = self.count * self.client_count;
+            let (severity, message) = if self.found_count == expected {
+                (Severity::Info, "All the keys were loaded at the barrier")
+            } else {
+                (Severity::Error, "Keys were missing at the barrier")
+            };
+            self.context.trace(
+                severity,
+                message,
+                details![
+                    "Layer" => "Rust",
+                    "Client" => self.client_id,
+                    "Expected" => expected,
+                    "Found" => self.found_count,
+                ],
+            );
+        })
+    }
+    fn get_metrics(&self) -> Vec<Metric> {
+        vec![Metric::val("found_count", self.found_count as f64)]
+    }
+    fn get_check_timeout(&self) -> f64 {
+        5000.0
+    }
+}
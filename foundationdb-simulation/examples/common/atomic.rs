@@ -0,0 +1,118 @@
+//! The counter shared by the atomic workloads: a single client adds one to a key in `count`
+//! transactions, then checks the value against the commits it saw.
+
+use foundationdb::{options, tuple::Subspace};
+use foundationdb_simulation::{details, Metric, Severity, SimDatabase, WorkloadContext};
+
+const COUNT_KEY: &[u8] = b"count";
+
+pub struct AtomicCounter {
+    client_id: usize,
+    // how many transactions will be run
+    expected_count: usize,
+    // how many transactions succeeded
+    success_count: usize,
+    // how many transactions failed
+    error_count: usize,
+    // how many maybe_committed transactions we encountered
+    maybe_committed_count: usize,
+}
+
+impl AtomicCounter {
+    pub fn new(context: &WorkloadContext) -> Self {
+        Self {
+            client_id: context.client_id(),
+            expected_count: context.get_option("count").expect("Could not get count"),
+            success_count: 0,
+            error_count: 0,
+            maybe_committed_count: 0,
+        }
+    }
+
+    /// Runs all the increments, from the first client only.
+    pub async fn run(&mut self, context: &WorkloadContext, db: &SimDatabase) {
+        if self.client_id == 0 {
+            for _ in 0..self.expected_count {
+                self.increment(context, db).await;
+            }
+        }
+    }
+
+    async fn increment(&mut self, context: &WorkloadContext, db: &SimDatabase) {
+        let trx = db.create_trx().expect("Could not create transaction");
+        let buf: [u8; 8] = 1i64.to_le_bytes();
+        trx.atomic_op(
+            &Subspace::all().pack(&COUNT_KEY),
+            &buf,
+            options::MutationType::Add,
+        );
+
+        match trx.commit().await {
+            Ok(_) => self.success_count += 1,
+            Err(err) if err.is_maybe_committed() => {
+                context.trace(
+                    Severity::Info,
+                    "Detected an maybe_committed transactions",
+                    details![
+                        "Layer" => "Rust",
+                        "Client" => self.client_id
+                    ],
+                );
+                self.maybe_committed_count += 1;
+            }
+            Err(_) => self.error_count += 1,
+        }
+    }
+
+    /// Checks the count, from the first client only.
+    pub async fn check(&self, context: &WorkloadContext, db: &SimDatabase) {
+        if self.client_id != 0 {
+            return;
+        }
+        let trx = db.create_trx().expect("Could not create transaction");
+        let count = match trx.snapshot().get(&Subspace::all().pack(&COUNT_KEY)).await {
+            Ok(Some(fdb_slice)) => i64::from_le_bytes(fdb_slice[..8].try_into().unwrap()) as usize,
+            _ => {
+                context.trace(
+                    Severity::Error,
+                    "Could not get Atomic count",
+                    details![
+                        "Layer" => "Rust",
+                        "Client" => self.client_id
+                    ],
+                );
+                return;
+            }
+        };
+
+        // We don't know how much maybe_committed transactions has succeeded,
+        // so we are checking the possible range
+        let (severity, message) = if self.success_count <= count
+            && count <= self.expected_count + self.maybe_committed_count
+        {
+            (Severity::Info, "Atomic count match")
+        } else {
+            (Severity::Error, "Atomic count doesn't match")
+        };
+        context.trace(
+            severity,
+            message,
+            details![
+                "Layer" => "Rust",
+                "Client" => self.client_id,
+                "Expected" => self.expected_count,
+                "Found" => count,
+                "CommittedCount" => self.success_count,
+                "MaybeCommitted" => self.maybe_committed_count,
+            ],
+        );
+    }
+
+    pub fn metrics(&self) -> Vec<Metric> {
+        vec![
+            Metric::val("expected_count", self.expected_count as f64),
+            Metric::val("success_count", self.success_count as f64),
+            Metric::val("error_count", self.error_count as f64),
+        ]
+    }
+}
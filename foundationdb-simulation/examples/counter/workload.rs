@@ -0,0 +1,237 @@
+use std::ops::Range;
+
+use foundationdb::counter::Counter;
+use foundationdb::options::MutationType;
+use foundationdb::tuple::Subspace;
+use foundationdb::{FdbResult, RangeOption, Transaction};
+use foundationdb_simulation::{
+    details, AsyncRustWorkload, Metric, Severity, SimDatabase, WorkloadContext, WorkloadFuture,
+};
+
+/// How sequence numbers are assigned
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    /// read the counter and write it back
+    Naive,
+    /// `Counter::increment_and_get`
+    IncrementAndGet,
+    /// `Counter::allocate_block`
+    AllocateBlock,
+}
+
+const MODES: [Mode; 3] = [Mode::Naive, Mode::IncrementAndGet, Mode::AllocateBlock];
+
+impl Mode {
+    fn name(self) -> &'static str {
+        match self {
+            Mode::Naive => "naive",
+            Mode::IncrementAndGet => "increment_and_get",
+            Mode::AllocateBlock => "allocate_block",
+        }
+    }
+}
+
+/// Every client assigns `count` sequence numbers with each mode, and counts the `not_committed`
+/// errors. Each committed assignment also increments a claim key per number, so the check can
+/// find numbers assigned twice.
+pub struct CounterWorkload {
+    context: WorkloadContext,
+    client_id: usize,
+    client_count: usize,
+    // how many numbers each client assigns with each mode
+    count: usize,
+    // how many numbers are assigned at once with `Mode::AllocateBlock`
+    block_size: u32,
+    // not_committed errors, by mode
+    conflict_count: [usize; 3],
+    // maybe_committed errors, by mode
+    maybe_committed_count: [usize; 3],
+}
+
+impl CounterWorkload {
+    pub fn new(context: WorkloadContext) -> Self {
+        Self {
+            client_id: context.client_id(),
+            client_count: context.client_count(),
+            count: context.get_option("count").expect("Could not get count"),
+            block_size: context.get_option("blockSize").unwrap_or(10),
+            context,
+            conflict_count: [0; 3],
+            maybe_committed_count: [0; 3],
+        }
+    }
+
+    async fn assign_once(
+        &self,
+        trx: &Transaction,
+        counter: &Counter,
+        mode: Mode,
+        remaining: usize,
+    ) -> FdbResult<Range<i64>> {
+        match mode {
+            Mode::Naive => {
+                let next = counter.get(trx).await? + 1;
+                trx.set(counter.key(), &next.to_le_bytes());
+                Ok(next..next + 1)
+            }
+            Mode::IncrementAndGet => {
+                let next = counter.increment_and_get(trx, 1).await?;
+                Ok(next..next + 1)
+            }
+            Mode::AllocateBlock => {
+                let n = self.block_size.min(remaining as u32);
+                counter.allocate_block(trx, n).await
+            }
+        }
+    }
+
+    async fn assign(&mut self, db: &SimDatabase, mode: Mode) {
+        let subspace = subspace(mode);
+        let counter = Counter::new(subspace.pack(&VALUE_KEY));
+        let claims = subspace.subspace(&CLAIMS_KEY);
+
+        let mut assigned = 0;
+        while assigned < self.count {
+            let mut trx = db.create_trx().expect("Could not create transaction");
+            loop {
+                let values = match self
+                    .assign_once(&trx, &counter, mode, self.count - assigned)
+                    .await
+                {
+                    Ok(values) => values,
+                    Err(err) => {
+                        trx = trx.on_error(err).await.expect("Could not retry");
+                        continue;
+                    }
+                };
+                for value in values.clone() {
+                    trx.atomic_op(&claims.pack(&value), &1i64.to_le_bytes(), MutationType::Add);
+                }
+                match trx.commit().await {
+                    Ok(_) => {
+                        assigned += values.count();
+                        break;
+                    }
+                    Err(err) => {
+                        if err.code() == NOT_COMMITTED {
+                            self.conflict_count[mode as usize] += 1;
+                        } else if err.is_maybe_committed() {
+                            self.maybe_committed_count[mode as usize] += 1;
+                        }
+                        trx = err.on_error().await.expect("Could not retry");
+                    }
+                }
+            }
+        }
+    }
+
+    async fn check_claims(&self, db: &SimDatabase, mode: Mode) {
+        let claims = subspace(mode).subspace(&CLAIMS_KEY);
+        let trx = db.create_trx().expect("Could not create transaction");
+        let mut opt = RangeOption::from(&claims);
+        let mut iteration = 1;
+        let mut claimed = 0;
+        let mut duplicates = 0;
+        loop {
+            let values = trx
+                .snapshot()
+                .get_range(&opt, iteration)
+                .await
+                .expect("Could not read claims");
+            iteration += 1;
+            for value in values.iter() {
+                claimed += 1;
+                if value.value() != 1i64.to_le_bytes() {
+                    duplicates += 1;
+                }
+            }
+            let last = values.last().map(|value| value.key());
+            opt = match opt.next_range_after(values.more(), values.len(), last) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        let expected = self.count * self.client_count;
+        // a maybe committed transaction retried assigns other numbers, leaving holes
+        let (severity, message) = if duplicates == 0 && claimed >= expected {
+            (Severity::Info, "Counter assignments are unique")
+        } else {
+            (Severity::Error, "Counter assignments are not unique")
+        };
+        self.context.trace(
+            severity,
+            message,
+            details![
+                "Layer" => "Rust",
+                "Client" => self.client_id,
+                "Mode" => mode.name(),
+                "Expected" => expected,
+                "Claimed" => claimed,
+                "Duplicates" => duplicates,
+            ],
+        );
+    }
+}
+
+const VALUE_KEY: &str = "value";
+const CLAIMS_KEY: &str = "claims";
+const NOT_COMMITTED: i32 = 1020;
+
+fn subspace(mode: Mode) -> Subspace {
+    Subspace::all().subspace(&("counter", mode.name()))
+}
+
+impl AsyncRustWorkload for CounterWorkload {
+    fn description(&self) -> String {
+        "Counter Rust Workload".into()
+    }
+    fn setup<'a>(&'a mut self, _db: &'a SimDatabase) -> WorkloadFuture<'a> {
+        Box::pin(async {})
+    }
+    fn start<'a>(&'a mut self, db: &'a SimDatabase) -> WorkloadFuture<'a> {
+        Box::pin(async move {
+            for mode in MODES {
+                self.assign(db, mode).await;
+            }
+            // block allocations should conflict about `block_size` times less
+            self.context.trace(
+                Severity::Info,
+                "Counter conflicts",
+                details![
+                    "Layer" => "Rust",
+                    "Client" => self.client_id,
+                    "Naive" => self.conflict_count[Mode::Naive as usize],
+                    "IncrementAndGet" => self.conflict_count[Mode::IncrementAndGet as usize],
+                    "AllocateBlock" => self.conflict_count[Mode::AllocateBlock as usize],
+                ],
+            );
+        })
+    }
+    fn check<'a>(&'a mut self, db: &'a SimDatabase) -> WorkloadFuture<'a> {
+        Box::pin(async move {
+            if self.client_id == 0 {
+                for mode in MODES {
+                    self.check_claims(db, mode).await;
+                }
+            }
+        })
+    }
+    fn get_metrics(&self) -> Vec<Metric> {
+        let mut metrics = Vec::new();
+        for mode in MODES {
+            metrics.push(Metric::val(
+                format!("{}_conflicts", mode.name()),
+                self.conflict_count[mode as usize] as f64,
+            ));
+            metrics.push(Metric::val(
+                format!("{}_maybe_committed", mode.name()),
+                self.maybe_committed_count[mode as usize] as f64,
+            ));
+        }
+        metrics
+    }
+    fn get_check_timeout(&self) -> f64 {
+        5000.0
+    }
+}
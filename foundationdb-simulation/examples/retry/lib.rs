@@ -0,0 +1,14 @@
+use foundationdb_macros::simulation_entrypoint;
+use foundationdb_simulation::{RustWorkload, WorkloadContext};
+
+mod workload;
+
+use workload::RetryWorkload;
+
+#[simulation_entrypoint]
+pub fn simulated_main(name: &str, context: WorkloadContext) -> Box<dyn RustWorkload> {
+    match name {
+        "RetryWorkload" => Box::new(RetryWorkload::new(context)),
+        name => panic!("no workload with name: {:?}", name),
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use foundationdb::tuple::Subspace;
+use foundationdb::{FdbBindingError, RetryableTransaction};
+use foundationdb_simulation::{
+    details, AsyncRustWorkload, Metric, Severity, SimDatabase, WorkloadContext, WorkloadFuture,
+};
+
+/// Every client increments a single shared value `count` times with `Database::run`, read then
+/// written back so that the clients conflict with each other. Each increment also writes a claim
+/// key, so a retried increment whose first attempt was maybe committed is not applied twice, and
+/// the check can compare the value with the number of claims.
+pub struct RetryWorkload {
+    context: WorkloadContext,
+    client_id: usize,
+    client_count: usize,
+    // how many increments each client commits
+    count: usize,
+    // how many times the closure was called
+    attempts: usize,
+    // how many times the closure was told the previous attempt was maybe committed
+    maybe_committed_count: usize,
+}
+
+impl RetryWorkload {
+    pub fn new(context: WorkloadContext) -> Self {
+        Self {
+            client_id: context.client_id(),
+            client_count: context.client_count(),
+            count: context.get_option("count").expect("Could not get count"),
+            context,
+            attempts: 0,
+            maybe_committed_count: 0,
+        }
+    }
+
+    async fn increment(trx: RetryableTransaction, claim: Vec<u8>) -> Result<(), FdbBindingError> {
+        // the previous attempt was committed after all
+        if trx.read(&claim).await?.is_some() {
+            return Ok(());
+        }
+        let value = trx.read(&value_key()).await?;
+        let value = value.map_or(0, |value| decode(&value));
+        trx.set(&value_key(), &(value + 1).to_le_bytes());
+        trx.set(&claim, b"");
+        Ok(())
+    }
+}
+
+const VALUE_KEY: &str = "value";
+const CLAIMS_KEY: &str = "claims";
+
+fn subspace() -> Subspace {
+    Subspace::all().subspace(&"retry")
+}
+
+fn value_key() -> Vec<u8> {
+    subspace().pack(&VALUE_KEY)
+}
+
+fn decode(value: &[u8]) -> i64 {
+    i64::from_le_bytes(value.try_into().expect("the value to be an i64"))
+}
+
+impl AsyncRustWorkload for RetryWorkload {
+    fn description(&self) -> String {
+        "Retry Rust Workload".into()
+    }
+    fn setup<'a>(&'a mut self, _db: &'a SimDatabase) -> WorkloadFuture<'a> {
+        Box::pin(async {})
+    }
+    fn start<'a>(&'a mut self, db: &'a SimDatabase) -> WorkloadFuture<'a> {
+        Box::pin(async move {
+            let claims = subspace().subspace(&CLAIMS_KEY);
+            let attempts = AtomicUsize::new(0);
+            let maybe_committed_count = AtomicUsize::new(0);
+            for i in 0..self.count {
+                let claim = claims.pack(&(self.client_id, i));
+                db.run(|trx, maybe_committed| {
+                    attempts.fetch_add(1, Ordering::SeqCst);
+                    if maybe_committed {
+                        maybe_committed_count.fetch_add(1, Ordering::SeqCst);
+                    }
+                    Self::increment(trx, claim.clone())
+                })
+                .await
+                .expect("Could not increment");
+            }
+            self.attempts = attempts.into_inner();
+            self.maybe_committed_count = maybe_committed_count.into_inner();
+            self.context.trace(
+                Severity::Info,
+                "Retry attempts",
+                details![
+                    "Layer" => "Rust",
+                    "Client" => self.client_id,
+                    "Increments" => self.count,
+                    "Attempts" => self.attempts,
+                    "MaybeCommitted" => self.maybe_committed_count,
+                ],
+            );
+        })
+    }
+    fn check<'a>(&'a mut self, db: &'a SimDatabase) -> WorkloadFuture<'a> {
+        Box::pin(async move {
+            if self.client_id != 0 {
+                return;
+            }
+            let trx = db.create_trx().expect("Could not create transaction");
+            let value = trx
+                .read(&value_key())
+                .await
+                .expect("Could not read the value")
+                .map_or(0, |value| decode(&value));
+            let (begin, end) = subspace().subspace(&CLAIMS_KEY).range();
+            let claimed = trx
+                .get_range_all(&begin, &end, usize::MAX, None, false)
+                .await
+                .expect("Could not read claims")
+                .len();
+
+            let expected = self.count * self.client_count;
+            let (severity, message) = if value as usize == expected && claimed == expected {
+                (Severity::Info, "Every increment was committed once")
+            } else {
+                (Severity::Error, "Increments were lost or applied twice")
+            };
+            self.context.trace(
+                severity,
+                message,
+                details![
+                    "Layer" => "Rust",
+                    "Expected" => expected,
+                    "Value" => value,
+                    "Claimed" => claimed,
+                ],
+            );
+        })
+    }
+    fn get_metrics(&self) -> Vec<Metric> {
+        vec![
+            Metric::val("attempts", self.attempts as f64),
+            Metric::val("retries", (self.attempts - self.count) as f64),
+            Metric::val("maybe_committed", self.maybe_committed_count as f64),
+        ]
+    }
+    fn get_check_timeout(&self) -> f64 {
+        5000.0
+    }
+}
@@ -0,0 +1,170 @@
+//! Barrier module
+//!
+//! This module defines `WorkloadContext::barrier`, which makes the clients of a workload wait
+//! for each other within a phase.
+//!
+//! The barrier is a small protocol in the database itself, under the `("__rust_barrier", name)`
+//! subspace: each client writes a key of its own under `arrived` and bumps a `version` key, then
+//! watches `version` until every client has written its key. Writing the key is idempotent, so
+//! retrying a transaction that maybe committed does not count a client twice.
+
+use std::{error::Error, fmt, time::Duration};
+
+use foundationdb::{
+    options::{MutationType, StreamingMode, TransactionOption},
+    tuple::Subspace,
+    Database, FdbError, RangeOption,
+};
+
+use crate::WorkloadContext;
+
+/// `transaction_timed_out`, also raised by the watches of a timed out transaction
+const TRANSACTION_TIMED_OUT: i32 = 1031;
+
+/// The error returned when the clients of a workload did not all reach a barrier
+#[derive(Debug)]
+pub enum BarrierError {
+    /// Some clients did not reach the barrier in time, they may have failed before
+    Timeout {
+        /// The name of the barrier
+        name: String,
+        /// How many clients reached the barrier
+        arrived: usize,
+        /// How many clients run the workload
+        expected: usize,
+    },
+    /// A non retryable error of the database
+    Fdb(FdbError),
+}
+
+impl fmt::Display for BarrierError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BarrierError::Timeout {
+                name,
+                arrived,
+                expected,
+            } => write!(
+                f,
+                "only {} of the {} clients reached the barrier {:?} in time",
+                arrived, expected, name
+            ),
+            BarrierError::Fdb(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for BarrierError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BarrierError::Timeout { .. } => None,
+            BarrierError::Fdb(err) => Some(err),
+        }
+    }
+}
+
+impl From<FdbError> for BarrierError {
+    fn from(err: FdbError) -> Self {
+        BarrierError::Fdb(err)
+    }
+}
+
+impl WorkloadContext {
+    /// Waits until every client of the workload reached the barrier called `name`.
+    ///
+    /// Fails with [`BarrierError::Timeout`] if they did not within `timeout` of simulated time,
+    /// for instance because one of them failed before, so the run does not hang. A name must
+    /// only be used once per run.
+    pub async fn barrier(
+        &self,
+        db: &Database,
+        name: &str,
+        timeout: Duration,
+    ) -> Result<(), BarrierError> {
+        let subspace = Subspace::from(("__rust_barrier", name));
+        let arrived_subspace = subspace.subspace(&"arrived");
+        let own_key = arrived_subspace.pack(&(self.client_id() as u64));
+        let version_key = subspace.pack(&"version");
+        let expected = self.client_count();
+        let deadline = self.now() + timeout.as_secs_f64();
+
+        let mut arrived = 0;
+        let mut announced = false;
+        let mut trx = db.create_trx()?;
+        loop {
+            let remaining = deadline - self.now();
+            let timed_out = |arrived| BarrierError::Timeout {
+                name: name.to_string(),
+                arrived,
+                expected,
+            };
+            if remaining <= 0.0 {
+                return Err(timed_out(arrived));
+            }
+
+            let step = async {
+                // the watch is also cancelled once the transaction times out
+                trx.set_option(TransactionOption::Timeout(
+                    (remaining * 1000.0).ceil().min(i32::MAX as f64) as i32,
+                ))?;
+                if !announced {
+                    trx.set(&own_key, b"");
+                    trx.atomic_op(&version_key, &1i64.to_le_bytes(), MutationType::Add);
+                }
+                let range = RangeOption {
+                    mode: StreamingMode::WantAll,
+                    ..RangeOption::from(&arrived_subspace)
+                };
+                let values = trx.read_range(&range, 1).await?;
+                let count = values.len();
+                let watch = if count < expected {
+                    Some(trx.watch(&version_key))
+                } else {
+                    None
+                };
+                Ok::<_, FdbError>((count, watch))
+            }
+            .await;
+
+            let err = match step {
+                Ok((count, watch)) => {
+                    arrived = count;
+                    match trx.commit().await {
+                        Ok(committed) => {
+                            announced = true;
+                            match watch {
+                                None => return Ok(()),
+                                Some(watch) => match watch.await {
+                                    Ok(()) => {
+                                        trx = committed.reset();
+                                        continue;
+                                    }
+                                    Err(err) if err.code() == TRANSACTION_TIMED_OUT => {
+                                        return Err(timed_out(arrived))
+                                    }
+                                    Err(err) => {
+                                        trx = committed.reset();
+                                        trx = trx.on_error(err).await?;
+                                        continue;
+                                    }
+                                },
+                            }
+                        }
+                        Err(err) if err.code() == TRANSACTION_TIMED_OUT => {
+                            return Err(timed_out(arrived))
+                        }
+                        Err(err) => {
+                            trx = err.on_error().await?;
+                            continue;
+                        }
+                    }
+                }
+                Err(err) => err,
+            };
+            if err.code() == TRANSACTION_TIMED_OUT {
+                return Err(timed_out(arrived));
+            }
+            trx = trx.on_error(err).await?;
+        }
+    }
+}
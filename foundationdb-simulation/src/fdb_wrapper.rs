@@ -5,7 +5,9 @@
 
 use std::{
     ffi::{CStr, CString},
+    io,
     os::raw::c_char,
+    rc::Rc,
     str::FromStr,
 };
 
@@ -48,11 +50,19 @@ struct CPPStringPair {
 /// A wrapper around a FoundationDB promise
 pub struct WorkloadContext {
     inner: *const opaque::Context,
+    name: Rc<str>,
 }
 
 /// A wrapper around a FoundationDB promise
+///
+/// It must be resolved exactly once with [`send`](Promise::send). Dropping it unresolved traces a
+/// `SevError` event naming the phase and the client, which stops the simulation, and resolves it
+/// so the tester does not wait for it until the timeout.
 pub struct Promise {
     inner: *const opaque::Promise,
+    context: WorkloadContext,
+    phase: &'static str,
+    sent: bool,
 }
 
 /// A single metric entry
@@ -110,6 +120,32 @@ macro_rules! details {
     };
 }
 
+/// Macro tracing a formatted message, to use instead of `println!` in workloads.
+///
+/// The message is traced with [`WorkloadContext::log`], so it ends up in the trace files of the
+/// simulation, in order with the events of fdbserver, instead of being interleaved with its
+/// output on stdout.
+///
+/// ```rs
+/// sim_log!(self.context, "rust_setup({})", self.client_id);
+/// ```
+#[macro_export]
+macro_rules! sim_log {
+    ($context:expr, $($arg:tt)+) => {
+        $crate::WorkloadContext::log(&$context, ::std::format!($($arg)+))
+    };
+}
+
+/// An [`io::Write`] tracing each line written with [`WorkloadContext::log`]
+///
+/// A line not terminated yet is traced when the writer is flushed or dropped, including while
+/// unwinding. It holds no lock, so it can be written to from anywhere, even from the
+/// implementation of a value being traced.
+pub struct TraceWriter<'a> {
+    context: &'a WorkloadContext,
+    buffer: Vec<u8>,
+}
+
 // -----------------------------------------------------------------------------
 // Rust to C++ bindings
 
@@ -173,8 +209,8 @@ where
 // Wrappers to map C++ behavior to Rust structs
 
 impl WorkloadContext {
-    pub(crate) fn new(inner: *const opaque::Context) -> Self {
-        Self { inner }
+    pub(crate) fn new(inner: *const opaque::Context, name: Rc<str>) -> Self {
+        Self { inner, name }
     }
     /// Add a log entry in the FoundationDB logs
     pub fn trace<S>(&self, severity: Severity, name: S, details: Vec<(String, String)>)
@@ -207,6 +243,32 @@ impl WorkloadContext {
             );
         }
     }
+    /// Add a log entry with a message at [`Severity::Info`], with the client id attached
+    ///
+    /// See [`sim_log!`](crate::sim_log) to format the message.
+    pub fn log<S>(&self, message: S)
+    where
+        S: Into<String>,
+    {
+        // a nul byte would not fit in the C string, and panicking here could abort when unwinding
+        let message = message.into().replace('\0', "\\0");
+        self.trace(
+            Severity::Info,
+            "RustWorkloadLog",
+            details![
+                "Layer" => "Rust",
+                "Client" => self.client_id(),
+                "Message" => message,
+            ],
+        );
+    }
+    /// Returns an [`io::Write`] tracing each line written with [`log`](WorkloadContext::log)
+    pub fn trace_writer(&self) -> TraceWriter<'_> {
+        TraceWriter {
+            context: self,
+            buffer: Vec::new(),
+        }
+    }
     /// Get the process id of the workload
     pub fn get_process_id(&self) -> u64 {
         unsafe { FDBContext_getProcessID(self.inner) }
@@ -266,26 +328,93 @@ impl WorkloadContext {
     pub fn shared_random_number(&self) -> u64 {
         unsafe { FDBContext_sharedRandomNumber(self.inner) }
     }
+    /// Get the name the workload was instantiated with, its `workloadName` in the test file
+    pub fn workload_name(&self) -> &str {
+        &self.name
+    }
 }
 
 impl Promise {
-    pub(crate) fn new(inner: *const opaque::Promise) -> Self {
-        Self { inner }
+    pub(crate) fn new(
+        inner: *const opaque::Promise,
+        context: WorkloadContext,
+        phase: &'static str,
+    ) -> Self {
+        Self {
+            inner,
+            context,
+            phase,
+            sent: false,
+        }
     }
     /// Resolve a FoundationDB promise by setting its value to a boolean.
-    /// You can resolve a Promise only once.
+    /// You can resolve a Promise only once, it is consumed:
+    ///
+    /// ```compile_fail
+    /// # use foundationdb_simulation::Promise;
+    /// fn resolve(done: Promise) {
+    ///     done.send(true);
+    ///     done.send(true); // use of moved value: `done`
+    /// }
+    /// ```
     ///
     /// note: FoundationDB disregards the value sent, so sending `true` or `false` is equivalent
-    pub fn send(self, value: bool) {
+    pub fn send(mut self, value: bool) {
         unsafe { FDBPromise_send(self.inner, value) };
+        self.sent = true;
     }
 }
 impl Drop for Promise {
     fn drop(&mut self) {
+        if !self.sent {
+            self.context.trace(
+                Severity::Error,
+                "RustWorkloadPromiseDropped",
+                details![
+                    "Layer" => "Rust",
+                    "Phase" => self.phase,
+                    "Client" => self.context.client_id(),
+                ],
+            );
+            unsafe { FDBPromise_send(self.inner, false) };
+        }
         unsafe { FDBPromise_free(self.inner) };
     }
 }
 
+impl<'a> TraceWriter<'a> {
+    fn log_lines(&mut self, flush: bool) {
+        while let Some(end) = self.buffer.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            self.context
+                .log(String::from_utf8_lossy(&line[..end]).into_owned());
+        }
+        if flush && !self.buffer.is_empty() {
+            let rest = std::mem::take(&mut self.buffer);
+            self.context
+                .log(String::from_utf8_lossy(&rest).into_owned());
+        }
+    }
+}
+
+impl<'a> io::Write for TraceWriter<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        self.log_lines(false);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.log_lines(true);
+        Ok(())
+    }
+}
+
+impl<'a> Drop for TraceWriter<'a> {
+    fn drop(&mut self) {
+        self.log_lines(true);
+    }
+}
+
 impl Metric {
     /// Create a metric value entry
     pub fn val<S, V>(name: S, value: V) -> Self
@@ -300,6 +429,31 @@ impl Metric {
             format_code: None,
         }
     }
+    /// Create a metric value entry with the smallest of `values`, `NaN` if there are none
+    ///
+    /// note: the tester sums the value metrics of all clients, so a cluster-wide minimum should
+    /// be reported by a single client, see [`Histogram::merge_clients`](crate::Histogram::merge_clients)
+    pub fn min<S, I>(name: S, values: I) -> Self
+    where
+        S: Into<String>,
+        I: IntoIterator,
+        f64: From<I::Item>,
+    {
+        let value = values.into_iter().map(f64::from).reduce(f64::min);
+        Self::val::<S, f64>(name, value.unwrap_or(f64::NAN))
+    }
+    /// Create a metric value entry with the largest of `values`, `NaN` if there are none
+    ///
+    /// note: like [`Metric::min`], it should be reported by a single client
+    pub fn max<S, I>(name: S, values: I) -> Self
+    where
+        S: Into<String>,
+        I: IntoIterator,
+        f64: From<I::Item>,
+    {
+        let value = values.into_iter().map(f64::from).reduce(f64::max);
+        Self::val::<S, f64>(name, value.unwrap_or(f64::NAN))
+    }
     /// Create a metric average entry
     pub fn avg<S, V>(name: S, value: V) -> Self
     where
@@ -0,0 +1,326 @@
+//! Histogram module
+//!
+//! This module defines `Histogram`, a recorder of latencies, or of any positive values, reported
+//! as percentiles in the metrics of a workload.
+//!
+//! The tester sums the metrics of all clients, which makes no sense for percentiles. To report
+//! cluster-wide percentiles, every client calls `Histogram::merge_clients` in `check`, which
+//! exchanges the histograms through the database and gives the merged one to the first client.
+//! Only that client then returns `Histogram::metrics` from `get_metrics`.
+
+use std::time::Duration;
+
+use foundationdb::{
+    options::StreamingMode,
+    tuple::{pack, unpack, Subspace},
+    Database, RangeOption,
+};
+
+use crate::{BarrierError, Metric, WorkloadContext};
+
+/// How many buckets split each power of two, bounding the relative error to about 4.4%
+const SUB_BUCKETS: usize = 16;
+/// The upper bound of the first bucket
+const LOWEST: f64 = 1e-6;
+/// 32 powers of two, from 1µs to more than an hour for values in seconds
+const BUCKET_COUNT: usize = 32 * SUB_BUCKETS;
+
+/// A histogram of values with fixed buckets
+///
+/// Recording a value does not allocate. Values are stored in buckets growing geometrically from
+/// `1e-6` to more than `4000`, so percentiles are approximated by the upper bound of their
+/// bucket, within about 4.4%, and clamped to the smallest and largest values recorded. Values
+/// outside of that range fall in the first or last bucket.
+#[derive(Clone)]
+pub struct Histogram {
+    buckets: [u64; BUCKET_COUNT],
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: [0; BUCKET_COUNT],
+            count: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+}
+
+fn bucket_index(value: f64) -> usize {
+    if value <= LOWEST {
+        0
+    } else {
+        let index = ((value / LOWEST).log2() * SUB_BUCKETS as f64).ceil() as usize;
+        index.min(BUCKET_COUNT - 1)
+    }
+}
+
+fn bucket_upper_bound(index: usize) -> f64 {
+    LOWEST * (index as f64 / SUB_BUCKETS as f64).exp2()
+}
+
+impl Histogram {
+    /// Create an empty histogram
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Record a value, `NaN` is ignored
+    pub fn record(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        self.buckets[bucket_index(value)] += 1;
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+    /// Record a duration in seconds
+    pub fn record_duration(&mut self, duration: Duration) {
+        self.record(duration.as_secs_f64())
+    }
+    /// Get the number of values recorded
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+    /// Get the smallest value recorded
+    pub fn min(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.min)
+    }
+    /// Get the largest value recorded
+    pub fn max(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max)
+    }
+    /// Get the mean of the values recorded
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+    /// Get the value below which `percentile` percent of the values recorded are
+    pub fn percentile(&self, percentile: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((percentile / 100.0 * self.count as f64).ceil() as u64).clamp(1, self.count);
+        // the extreme ranks are known exactly
+        if rank == 1 {
+            return Some(self.min);
+        }
+        if rank == self.count {
+            return Some(self.max);
+        }
+        let mut seen = 0;
+        let index = self
+            .buckets
+            .iter()
+            .position(|&count| {
+                seen += count;
+                seen >= rank
+            })
+            .unwrap_or(BUCKET_COUNT - 1);
+        Some(bucket_upper_bound(index).clamp(self.min, self.max))
+    }
+    /// Add the values recorded by `other` to this histogram
+    pub fn merge(&mut self, other: &Histogram) {
+        for (bucket, count) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *bucket += count;
+        }
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+    /// Create the metrics `{name}_count`, `{name}_min`, `{name}_max`, `{name}_mean`,
+    /// `{name}_p50`, `{name}_p95` and `{name}_p99`
+    ///
+    /// The metrics are not averaged, they should only be returned by a single client, see the
+    /// [module documentation](self).
+    pub fn metrics(&self, name: &str) -> Vec<Metric> {
+        let value = |value: Option<f64>| value.unwrap_or(f64::NAN);
+        vec![
+            Metric::val(format!("{}_count", name), self.count as f64),
+            Metric::val(format!("{}_min", name), value(self.min())),
+            Metric::val(format!("{}_max", name), value(self.max())),
+            Metric::val(format!("{}_mean", name), value(self.mean())),
+            Metric::val(format!("{}_p50", name), value(self.percentile(50.0))),
+            Metric::val(format!("{}_p95", name), value(self.percentile(95.0))),
+            Metric::val(format!("{}_p99", name), value(self.percentile(99.0))),
+        ]
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let buckets: Vec<(u32, u64)> = self
+            .buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(index, &count)| (index as u32, count))
+            .collect();
+        pack(&(self.count, self.sum, self.min, self.max, buckets))
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let (count, sum, min, max, buckets): (u64, f64, f64, f64, Vec<(u32, u64)>) =
+            unpack(bytes).expect("Could not decode histogram");
+        let mut histogram = Self {
+            count,
+            sum,
+            min,
+            max,
+            ..Self::default()
+        };
+        for (index, count) in buckets {
+            histogram.buckets[index as usize] = count;
+        }
+        histogram
+    }
+
+    /// Merge the histograms called `name` of all the clients of the workload
+    ///
+    /// Every client must call it, typically in `check`. The first client gets the merged
+    /// histogram, the others get `None`. It fails like [`WorkloadContext::barrier`] if some
+    /// clients did not call it within `timeout`.
+    pub async fn merge_clients(
+        &self,
+        context: &WorkloadContext,
+        db: &Database,
+        name: &str,
+        timeout: Duration,
+    ) -> Result<Option<Histogram>, BarrierError> {
+        let subspace = Subspace::from(("__rust_histogram", name));
+        let own_key = subspace.pack(&(context.client_id() as u64));
+        let bytes = self.to_bytes();
+
+        let mut trx = db.create_trx()?;
+        loop {
+            trx.set(&own_key, &bytes);
+            match trx.commit().await {
+                Ok(_) => break,
+                Err(err) => trx = err.on_error().await?,
+            }
+        }
+
+        context
+            .barrier(db, &format!("__rust_histogram/{}", name), timeout)
+            .await?;
+        if context.client_id() != 0 {
+            return Ok(None);
+        }
+
+        let mut trx = db.create_trx()?;
+        'retry: loop {
+            let mut merged = Histogram::new();
+            let mut opt = RangeOption {
+                mode: StreamingMode::WantAll,
+                ..RangeOption::from(&subspace)
+            };
+            loop {
+                let result = trx.snapshot().get_range(&opt, 1).await;
+                let values = match result {
+                    Ok(values) => values,
+                    Err(err) => {
+                        trx = trx.on_error(err).await?;
+                        continue 'retry;
+                    }
+                };
+                for value in values.iter() {
+                    merged.merge(&Histogram::from_bytes(value.value()));
+                }
+                let last = values.last().map(|value| value.key());
+                opt = match opt.next_range_after(values.more(), values.len(), last) {
+                    Some(next) => next,
+                    None => return Ok(Some(merged)),
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("a value");
+        assert!(
+            (actual - expected).abs() <= expected * 0.045,
+            "{} is not within 4.5% of {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn percentiles() {
+        let mut histogram = Histogram::new();
+        assert_eq!(histogram.percentile(50.0), None);
+        // 1ms to 1s, uniformly
+        for ms in 1..=1000 {
+            histogram.record(ms as f64 / 1000.0);
+        }
+        histogram.record(f64::NAN);
+
+        assert_eq!(histogram.count(), 1000);
+        assert_eq!(histogram.min(), Some(0.001));
+        assert_eq!(histogram.max(), Some(1.0));
+        assert_close(histogram.mean(), 0.5005);
+        assert_close(histogram.percentile(50.0), 0.5);
+        assert_close(histogram.percentile(95.0), 0.95);
+        assert_close(histogram.percentile(99.0), 0.99);
+        assert_eq!(histogram.percentile(100.0), Some(1.0));
+        assert_eq!(histogram.percentile(0.0), Some(0.001));
+
+        let metrics = histogram.metrics("latency");
+        let p99 = metrics.iter().find(|m| m.name == "latency_p99").unwrap();
+        assert_close(Some(p99.value), 0.99);
+        assert!(!p99.averaged);
+    }
+
+    #[test]
+    fn skewed_distribution() {
+        let mut histogram = Histogram::new();
+        // 90% of fast requests, 10% of slow ones
+        for _ in 0..900 {
+            histogram.record_duration(Duration::from_millis(2));
+        }
+        for _ in 0..100 {
+            histogram.record_duration(Duration::from_millis(800));
+        }
+        assert_close(histogram.percentile(50.0), 0.002);
+        assert_close(histogram.percentile(90.0), 0.002);
+        assert_close(histogram.percentile(95.0), 0.8);
+
+        // out of range values are kept in the extreme buckets
+        histogram.record(0.0);
+        histogram.record(1e9);
+        assert_eq!(histogram.percentile(0.0), Some(0.0));
+        assert_eq!(histogram.percentile(100.0), Some(1e9));
+    }
+
+    #[test]
+    fn merge() {
+        let (mut even, mut odd, mut all) = (Histogram::new(), Histogram::new(), Histogram::new());
+        for ms in 1..=1000 {
+            let value = ms as f64 / 1000.0;
+            if ms % 2 == 0 { &mut even } else { &mut odd }.record(value);
+            all.record(value);
+        }
+        let mut merged = Histogram::from_bytes(&even.to_bytes());
+        merged.merge(&Histogram::from_bytes(&odd.to_bytes()));
+
+        assert_eq!(merged.buckets, all.buckets);
+        assert_eq!(merged.count(), all.count());
+        assert_eq!(merged.min(), all.min());
+        assert_eq!(merged.max(), all.max());
+        assert_eq!(merged.percentile(99.0), all.percentile(99.0));
+        assert_eq!(
+            Histogram::from_bytes(&Histogram::new().to_bytes()).min(),
+            None
+        );
+    }
+}
@@ -4,17 +4,25 @@
 //! under a Rust trait, as well as a way to register a Workload in the simulation.
 
 #![warn(missing_docs)]
-use std::{mem::ManuallyDrop, os::raw::c_char, ptr::NonNull};
+use std::{future::Future, mem::ManuallyDrop, os::raw::c_char, pin::Pin, ptr::NonNull, rc::Rc};
 
 use foundationdb::Database;
 use foundationdb_sys::FDBDatabase;
 
+mod barrier;
 mod fdb_rt;
 mod fdb_wrapper;
+mod histogram;
+mod sim_config;
 
+pub use barrier::BarrierError;
 pub use fdb_rt::fdb_spawn;
 use fdb_wrapper::{metrics_extend, opaque, str_for_c, str_from_c};
-pub use fdb_wrapper::{CPPWorkloadFactory, Details, Metric, Promise, Severity, WorkloadContext};
+pub use fdb_wrapper::{
+    CPPWorkloadFactory, Details, Metric, Promise, Severity, TraceWriter, WorkloadContext,
+};
+pub use histogram::Histogram;
+pub use sim_config::SimConfig;
 
 // -----------------------------------------------------------------------------
 // User friendly types
@@ -23,6 +31,8 @@ pub use fdb_wrapper::{CPPWorkloadFactory, Details, Metric, Promise, Severity, Wo
 pub type SimDatabase = ManuallyDrop<Database>;
 /// Rust representation of a FoundationDB workload
 pub type Workload = Box<dyn RustWorkload>;
+/// Future returned by the phases of an [`AsyncRustWorkload`]
+pub type WorkloadFuture<'a> = Pin<Box<dyn Future<Output = ()> + 'a>>;
 
 /// RustWorkload trait provides a one to one equivalent to the C++ abstract class `FDBWorkload`
 pub trait RustWorkload {
@@ -64,6 +74,77 @@ pub trait RustWorkload {
     fn get_check_timeout(&self) -> f64;
 }
 
+/// AsyncRustWorkload trait is an alternative to [`RustWorkload`] for workloads written with async code
+///
+/// Each phase returns a future borrowing the workload and the database, which is run with
+/// [`fdb_spawn`] and resolves the `done` promise on completion. Any type implementing this trait
+/// implements [`RustWorkload`], so it can be returned as is from the entrypoint.
+pub trait AsyncRustWorkload {
+    /// Return the name or description of the workload.
+    /// Primarily used for tracing.
+    fn description(&self) -> String;
+
+    /// This method is called by the tester during the setup phase.
+    /// It should be used to populate the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `db` - The simulated database.
+    fn setup<'a>(&'a mut self, db: &'a SimDatabase) -> WorkloadFuture<'a>;
+
+    /// This method should run the actual test.
+    ///
+    /// # Arguments
+    ///
+    /// * `db` - The simulated database.
+    fn start<'a>(&'a mut self, db: &'a SimDatabase) -> WorkloadFuture<'a>;
+
+    /// This method is called when the tester completes.
+    /// A workload should run any consistency/correctness tests during this phase.
+    ///
+    /// # Arguments
+    ///
+    /// * `db` - The simulated database.
+    fn check<'a>(&'a mut self, db: &'a SimDatabase) -> WorkloadFuture<'a>;
+
+    /// If a workload collects metrics (like latencies or throughput numbers), these should be reported back here.
+    /// The multitester (or test orchestrator) will collect all metrics from all test clients and it will aggregate them.
+    fn get_metrics(&self) -> Vec<Metric>;
+
+    /// Set the check timeout for this workload.
+    fn get_check_timeout(&self) -> f64;
+}
+
+impl<W: AsyncRustWorkload + 'static> RustWorkload for W {
+    fn description(&self) -> String {
+        AsyncRustWorkload::description(self)
+    }
+    fn setup(&'static mut self, db: SimDatabase, done: Promise) {
+        fdb_spawn(async move {
+            AsyncRustWorkload::setup(self, &db).await;
+            done.send(true);
+        });
+    }
+    fn start(&'static mut self, db: SimDatabase, done: Promise) {
+        fdb_spawn(async move {
+            AsyncRustWorkload::start(self, &db).await;
+            done.send(true);
+        });
+    }
+    fn check(&'static mut self, db: SimDatabase, done: Promise) {
+        fdb_spawn(async move {
+            AsyncRustWorkload::check(self, &db).await;
+            done.send(true);
+        });
+    }
+    fn get_metrics(&self) -> Vec<Metric> {
+        AsyncRustWorkload::get_metrics(self)
+    }
+    fn get_check_timeout(&self) -> f64 {
+        AsyncRustWorkload::get_check_timeout(self)
+    }
+}
+
 // -----------------------------------------------------------------------------
 // Hook the user has to define (through `#[simulation_entrypoint])
 
@@ -74,24 +155,36 @@ extern "Rust" {
 // -----------------------------------------------------------------------------
 // C++ to Rust bindings
 
+/// The workload with the context it was instantiated with, which its promises use to report
+/// the client they belong to.
+struct WorkloadHandle {
+    workload: Workload,
+    context: *const opaque::Context,
+    name: Rc<str>,
+}
+
 #[no_mangle]
 extern "C" fn workload_instantiate(
     raw_name: *const c_char,
     raw_context: *mut opaque::Context,
-) -> *mut Workload {
-    let name = str_from_c(raw_name);
-    let context = WorkloadContext::new(raw_context);
+) -> *mut WorkloadHandle {
+    let name: Rc<str> = Rc::from(str_from_c(raw_name));
+    let context = WorkloadContext::new(raw_context, name.clone());
     let workload = unsafe { workload_instantiate_hook(&name, context) };
-    // the `Box<dyn RustWorkload>` is put on the heap with another `Box::new`
+    // the `Box<dyn RustWorkload>` is put on the heap with the context in another `Box::new`
     // `Box::into_raw` turns that `Box` into a thin pointer
     // it is this pointer that will be stored in the C++ `WorkloadTranslater`
-    // and that is passed to the other `workload_*` functions as `&'static Workload` or `&Workload`
+    // and that is passed to the other `workload_*` functions as `&'static WorkloadHandle` or `&WorkloadHandle`
     // `Box::from_raw` will be called by `workload_drop` to clean up everything
-    Box::into_raw(Box::new(workload))
+    Box::into_raw(Box::new(WorkloadHandle {
+        workload,
+        context: raw_context,
+        name,
+    }))
 }
 #[no_mangle]
-extern "C" fn workload_description(workload: &Workload) -> *const c_char {
-    let description = str_for_c(workload.description());
+extern "C" fn workload_description(handle: &WorkloadHandle) -> *const c_char {
+    let description = str_for_c(handle.workload.description());
     // FIXME: the CString will be dropped by Rust before it is read by the C++ side
     // but if Rust doesn't drop it now it's a memory leak...
     // note that that C++ instantly makes a copy so the pointer doesn't stay dangling too long
@@ -99,44 +192,56 @@ extern "C" fn workload_description(workload: &Workload) -> *const c_char {
 }
 #[no_mangle]
 extern "C" fn workload_setup(
-    workload: &'static mut Workload,
+    handle: &'static mut WorkloadHandle,
     raw_database: NonNull<FDBDatabase>,
     raw_promise: *const opaque::Promise,
 ) {
     let db = ManuallyDrop::new(Database::new_from_pointer(raw_database));
-    let done = Promise::new(raw_promise);
-    workload.setup(db, done);
+    let done = Promise::new(
+        raw_promise,
+        WorkloadContext::new(handle.context, handle.name.clone()),
+        "setup",
+    );
+    handle.workload.setup(db, done);
 }
 #[no_mangle]
 extern "C" fn workload_start(
-    workload: &'static mut Workload,
+    handle: &'static mut WorkloadHandle,
     raw_database: NonNull<FDBDatabase>,
     raw_promise: *const opaque::Promise,
 ) {
     let db = ManuallyDrop::new(Database::new_from_pointer(raw_database));
-    let done = Promise::new(raw_promise);
-    workload.start(db, done)
+    let done = Promise::new(
+        raw_promise,
+        WorkloadContext::new(handle.context, handle.name.clone()),
+        "start",
+    );
+    handle.workload.start(db, done)
 }
 #[no_mangle]
 extern "C" fn workload_check(
-    workload: &'static mut Workload,
+    handle: &'static mut WorkloadHandle,
     raw_database: NonNull<FDBDatabase>,
     raw_promise: *const opaque::Promise,
 ) {
     let db = ManuallyDrop::new(Database::new_from_pointer(raw_database));
-    let done = Promise::new(raw_promise);
-    workload.check(db, done)
+    let done = Promise::new(
+        raw_promise,
+        WorkloadContext::new(handle.context, handle.name.clone()),
+        "check",
+    );
+    handle.workload.check(db, done)
 }
 #[no_mangle]
-extern "C" fn workload_get_metrics(workload: &Workload, out: *const opaque::Metrics) {
-    let metrics = workload.get_metrics();
+extern "C" fn workload_get_metrics(handle: &WorkloadHandle, out: *const opaque::Metrics) {
+    let metrics = handle.workload.get_metrics();
     metrics_extend(out, metrics)
 }
 #[no_mangle]
-extern "C" fn workload_get_check_timeout(workload: &Workload) -> f64 {
-    workload.get_check_timeout()
+extern "C" fn workload_get_check_timeout(handle: &WorkloadHandle) -> f64 {
+    handle.workload.get_check_timeout()
 }
 #[no_mangle]
-extern "C" fn workload_drop(workload: *mut Workload) {
-    unsafe { drop(Box::from_raw(workload)) };
+extern "C" fn workload_drop(handle: *mut WorkloadHandle) {
+    unsafe { drop(Box::from_raw(handle)) };
 }
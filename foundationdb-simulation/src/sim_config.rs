@@ -0,0 +1,312 @@
+//! Simulator configuration module
+//!
+//! This module defines `WorkloadContext::simulator_config`, which describes the test a workload
+//! runs in, and `WorkloadContext::get_knob`.
+//!
+//! The `FDBWorkloadContext` given to external workloads only exposes the name, client id and
+//! options of the workload. The rest is read from the TOML test file given to `fdbserver` with
+//! `-f`, the only argument looked at. The configuration of the database itself is read from its
+//! system keys by `WorkloadContext::database_configuration`.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use foundationdb::{
+    options::{StreamingMode, TransactionOption},
+    Database, FdbError, RangeOption,
+};
+use toml::{Table, Value};
+
+use crate::WorkloadContext;
+
+/// What the workload knows about the test it runs in
+#[derive(Debug, Clone, Default)]
+pub struct SimConfig {
+    /// The name the workload was instantiated with, its `workloadName` in the test file
+    pub workload_name: String,
+    /// The client id of the workload
+    pub client_id: usize,
+    /// How many clients run the workload
+    pub client_count: usize,
+    /// The test file given to `fdbserver`
+    pub test_file: Option<PathBuf>,
+    /// The `testTitle` of the test running the workload
+    pub test_title: Option<String>,
+    /// The longest `testDuration` of the workloads of the test, roughly how long it lasts
+    pub duration: Option<f64>,
+    /// The `[configuration]` section of the test file, e.g. `storageEngineType` or `extraDB`
+    pub configuration: BTreeMap<String, String>,
+    knobs: BTreeMap<String, String>,
+}
+
+impl SimConfig {
+    /// Get the value of a knob overridden in the `[[knobs]]` section of the test file
+    ///
+    /// Knobs keeping their default value, or overridden on the command line of `fdbserver`, are
+    /// not visible to the workload and give `None`.
+    pub fn knob(&self, name: &str) -> Option<&str> {
+        self.knobs.get(&knob_name(name)).map(String::as_str)
+    }
+}
+
+impl WorkloadContext {
+    /// Describe the test the workload runs in
+    ///
+    /// The test file is read again on each call, so it is best called once, when instantiating
+    /// the workload. Fields are left empty when the information can't be reached, e.g. with a
+    /// test file in the old `.txt` format.
+    pub fn simulator_config(&self) -> SimConfig {
+        let test_file_path = test_file_path(std::env::args());
+        let test_file = test_file_path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|contents| TestFile::parse(&contents, self.workload_name()))
+            .unwrap_or_default();
+        SimConfig {
+            workload_name: self.workload_name().to_string(),
+            client_id: self.client_id(),
+            client_count: self.client_count(),
+            test_file: test_file_path,
+            test_title: test_file.test_title,
+            duration: test_file.duration,
+            configuration: test_file.configuration,
+            knobs: test_file.knobs,
+        }
+    }
+    /// Get the value of a knob overridden for this test, see [`SimConfig::knob`]
+    pub fn get_knob(&self, name: &str) -> Option<String> {
+        self.simulator_config().knob(name).map(str::to_string)
+    }
+    /// Read the configuration of the database from its `\xff/conf/` system keys
+    ///
+    /// It holds for instance `storage_replicas` and `log_replicas`, the replication factors, and
+    /// `storage_engine`, as the decimal strings set by `configure`.
+    pub async fn database_configuration(
+        &self,
+        db: &Database,
+    ) -> Result<BTreeMap<String, String>, FdbError> {
+        const PREFIX: &[u8] = b"\xff/conf/";
+        let end: &[u8] = b"\xff/conf0";
+        let mut trx = db.create_trx()?;
+        'retry: loop {
+            trx.set_option(TransactionOption::ReadSystemKeys)?;
+            let mut configuration = BTreeMap::new();
+            let mut opt = RangeOption {
+                mode: StreamingMode::WantAll,
+                ..RangeOption::from((PREFIX, end))
+            };
+            loop {
+                let result = trx.snapshot().get_range(&opt, 1).await;
+                let values = match result {
+                    Ok(values) => values,
+                    Err(err) => {
+                        trx = trx.on_error(err).await?;
+                        continue 'retry;
+                    }
+                };
+                for value in values.iter() {
+                    configuration.insert(
+                        String::from_utf8_lossy(&value.key()[PREFIX.len()..]).into_owned(),
+                        String::from_utf8_lossy(value.value()).into_owned(),
+                    );
+                }
+                let last = values.last().map(|value| value.key());
+                opt = match opt.next_range_after(values.more(), values.len(), last) {
+                    Some(next) => next,
+                    None => return Ok(configuration),
+                };
+            }
+        }
+    }
+}
+
+/// Knobs are case insensitive and accept `-` for `_`
+fn knob_name(name: &str) -> String {
+    name.to_ascii_lowercase().replace('-', "_")
+}
+
+/// Find the test file in the arguments of `fdbserver`, given as `-f`, `--testfile` or
+/// `--testfile=`
+fn test_file_path<I: IntoIterator<Item = String>>(args: I) -> Option<PathBuf> {
+    let mut args = args.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        if let Some(path) = arg.strip_prefix("--testfile=") {
+            return Some(PathBuf::from(path));
+        }
+        if arg == "-f" || arg == "--testfile" {
+            return args.next().map(PathBuf::from);
+        }
+    }
+    None
+}
+
+/// The parts of a TOML test file used by [`SimConfig`]
+#[derive(Debug, Default)]
+struct TestFile {
+    test_title: Option<String>,
+    duration: Option<f64>,
+    configuration: BTreeMap<String, String>,
+    knobs: BTreeMap<String, String>,
+}
+
+impl TestFile {
+    /// Parse a test file, left empty if it is not valid TOML
+    fn parse(contents: &str, workload_name: &str) -> Self {
+        let mut test_file = Self::default();
+        let file: Table = match contents.parse() {
+            Ok(file) => file,
+            Err(_) => return test_file,
+        };
+        if let Some(configuration) = file.get("configuration").and_then(Value::as_table) {
+            test_file.configuration = configuration
+                .iter()
+                .map(|(key, value)| (key.clone(), to_string(value)))
+                .collect();
+        }
+        for knobs in tables(&file, "knobs") {
+            let knobs = knobs
+                .iter()
+                .map(|(key, value)| (knob_name(key), to_string(value)));
+            test_file.knobs.extend(knobs);
+        }
+
+        let runs_workload = |workload: &Table| {
+            workload.get("workloadName").and_then(Value::as_str) == Some(workload_name)
+        };
+        let test = tables(&file, "test").find(|test| tables(test, "workload").any(runs_workload));
+        if let Some(test) = test {
+            test_file.test_title = test
+                .get("testTitle")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            test_file.duration = tables(test, "workload")
+                .filter_map(|workload| {
+                    let duration = workload.get("testDuration")?;
+                    duration
+                        .as_float()
+                        .or_else(|| duration.as_integer().map(|duration| duration as f64))
+                })
+                .reduce(f64::max);
+        }
+        test_file
+    }
+}
+
+/// The tables of the array of tables `key`, like `[[test]]`
+fn tables<'a>(table: &'a Table, key: &str) -> impl Iterator<Item = &'a Table> {
+    table
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_table)
+}
+
+/// Strings are given unquoted, other values as written in TOML
+fn to_string(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_FILE: &str = r#"
+[configuration]
+storageEngineType = 0 # ssd
+extraDB = 1
+
+[[knobs]]
+Enable_Encryption = true
+
+[[test]]
+testTitle = 'SetupTest'
+
+  [[test.workload]]
+    testName = 'External'
+    workloadName = 'OtherWorkload'
+    testDuration = 5.0
+
+[[test]]
+testTitle = "MainTest"
+
+  [[test.workload]]
+    testName = 'External'
+    libraryName = 'atomic'
+    workloadName = 'AtomicWorkload' # the one we look for
+
+  [[test.workload]]
+    testName = 'RandomClogging'
+    testDuration = 30.0
+
+  [[test.workload]]
+    testName = 'Attrition'
+    testDuration = 20.0
+"#;
+
+    #[test]
+    fn test_file() {
+        let test_file = TestFile::parse(TEST_FILE, "AtomicWorkload");
+        assert_eq!(test_file.test_title.as_deref(), Some("MainTest"));
+        assert_eq!(test_file.duration, Some(30.0));
+        assert_eq!(test_file.configuration["storageEngineType"], "0");
+        assert_eq!(test_file.configuration["extraDB"], "1");
+        assert_eq!(test_file.knobs["enable_encryption"], "true");
+
+        let test_file = TestFile::parse(TEST_FILE, "OtherWorkload");
+        assert_eq!(test_file.test_title.as_deref(), Some("SetupTest"));
+        assert_eq!(test_file.duration, Some(5.0));
+
+        let test_file = TestFile::parse(TEST_FILE, "MissingWorkload");
+        assert_eq!(test_file.test_title, None);
+        assert_eq!(test_file.duration, None);
+    }
+
+    #[test]
+    fn command_line() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let path = Some(PathBuf::from("tests/atomic.toml"));
+        assert_eq!(
+            test_file_path(args(&[
+                "fdbserver",
+                "-r",
+                "simulation",
+                "-f",
+                "tests/atomic.toml"
+            ])),
+            path
+        );
+        assert_eq!(
+            test_file_path(args(&[
+                "fdbserver",
+                "--testfile=tests/atomic.toml",
+                "-b",
+                "on"
+            ])),
+            path
+        );
+        assert_eq!(
+            test_file_path(args(&["fdbserver", "-r", "simulation"])),
+            None
+        );
+    }
+
+    #[test]
+    fn knobs() {
+        let config = SimConfig {
+            knobs: TestFile::parse(TEST_FILE, "AtomicWorkload").knobs,
+            ..SimConfig::default()
+        };
+        assert_eq!(config.knob("Enable-Encryption"), Some("true"));
+        assert_eq!(config.knob("unknown"), None);
+    }
+
+    #[test]
+    fn old_test_file() {
+        let test_file = TestFile::parse("testTitle=Atomic\ntestName=External\n", "AtomicWorkload");
+        assert_eq!(test_file.test_title, None);
+        assert!(test_file.configuration.is_empty());
+    }
+}
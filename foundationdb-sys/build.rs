@@ -14,35 +14,310 @@ const INCLUDE_PATH: &str = "-I/usr/local/include/foundationdb/";
 #[cfg(all(not(feature = "embedded-fdb-include"), target_os = "windows"))]
 const INCLUDE_PATH: &str = "-IC:/Program Files/foundationdb/include/foundationdb";
 
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-5_1"))]
-const INCLUDE_PATH: &str = "-I./include/510";
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-5_2"))]
-const INCLUDE_PATH: &str = "-I./include/520";
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-6_0"))]
-const INCLUDE_PATH: &str = "-I./include/600";
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-6_1"))]
-const INCLUDE_PATH: &str = "-I./include/610";
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-6_2"))]
-const INCLUDE_PATH: &str = "-I./include/620";
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-6_3"))]
-const INCLUDE_PATH: &str = "-I./include/630";
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-7_0"))]
-const INCLUDE_PATH: &str = "-I./include/700";
-#[cfg(all(feature = "embedded-fdb-include", feature = "fdb-7_1"))]
-const INCLUDE_PATH: &str = "-I./include/710";
+#[cfg(feature = "embedded-fdb-include")]
+fn include_path(api_version: i32) -> String {
+    format!("-I./include/{}", api_version)
+}
+
+#[cfg(not(feature = "embedded-fdb-include"))]
+fn include_path(_api_version: i32) -> String {
+    println!("cargo:rerun-if-env-changed=FDB_INCLUDE_PATH");
+    match env::var("FDB_INCLUDE_PATH") {
+        Ok(path) => format!("-I{}", path),
+        Err(_) => INCLUDE_PATH.to_string(),
+    }
+}
+
+// Where `libfdb_c.dylib` is looked for when `FDB_LIB_PATH` is not set, after
+// `FDB_CLIENT_LIB_PATH`: the client package installs it in `/usr/local/lib` on both Intel and
+// Apple Silicon.
+#[cfg(not(any(feature = "dlopen", feature = "static")))]
+const MACOS_LIB_PATHS: &[&str] = &[
+    "/usr/local/lib",
+    "/opt/homebrew/lib",
+    "/opt/foundationdb/lib",
+];
+
+/// Finds `libfdb_c.dylib` and checks that it was built for the target architecture.
+///
+/// Linking a library of another architecture, typically an x86_64 client with an arm64
+/// toolchain or the other way around under Rosetta, only fails at runtime with an obscure dyld
+/// error. A missing library is only a warning, `cargo check` and `cargo doc` do not link.
+#[cfg(not(any(feature = "dlopen", feature = "static")))]
+fn link_search_macos() {
+    println!("cargo:rerun-if-env-changed=FDB_LIB_PATH");
+    let searched: Vec<PathBuf> = match env::var_os("FDB_LIB_PATH") {
+        // an explicit path is the only one searched
+        Some(path) => vec![PathBuf::from(path)],
+        None => env::var_os("FDB_CLIENT_LIB_PATH")
+            .map(PathBuf::from)
+            .into_iter()
+            .chain(MACOS_LIB_PATHS.iter().map(PathBuf::from))
+            .collect(),
+    };
+    let dir = match searched
+        .iter()
+        .find(|dir| dir.join("libfdb_c.dylib").is_file())
+    {
+        Some(dir) => dir,
+        None => {
+            let searched: Vec<String> = searched
+                .iter()
+                .map(|dir| dir.display().to_string())
+                .collect();
+            println!(
+                "cargo:warning=libfdb_c.dylib was not found in {}, install the FoundationDB \
+                 client package, or set FDB_LIB_PATH to the directory containing libfdb_c.dylib",
+                searched.join(", ")
+            );
+            return;
+        }
+    };
+
+    let library = dir.join("libfdb_c.dylib");
+    let target_arch =
+        env::var("CARGO_CFG_TARGET_ARCH").expect("CARGO_CFG_TARGET_ARCH is not defined!");
+    let architectures = macho_architectures(&library).unwrap_or_else(|e| {
+        panic!(
+            "couldn't read the architectures of {}: {}",
+            library.display(),
+            e
+        )
+    });
+    if !architectures.contains(&target_arch.as_str()) {
+        panic!(
+            "{} is built for {}, but the target architecture is {}\n\
+             help: install the FoundationDB client package for {}, or set FDB_LIB_PATH to the \
+             directory of a matching libfdb_c.dylib. A toolchain running under Rosetta builds \
+             for x86_64, check `rustc -vV`",
+            library.display(),
+            architectures.join(", "),
+            target_arch,
+            target_arch,
+        );
+    }
+    println!("cargo:rerun-if-changed={}", library.display());
+    println!("cargo:rustc-link-search=native={}", dir.display());
+}
+
+/// Returns the architectures of a Mach-O file, as `target_arch` names, several for a universal
+/// binary.
+#[cfg(not(any(feature = "dlopen", feature = "static")))]
+fn macho_architectures(path: &std::path::Path) -> std::io::Result<Vec<&'static str>> {
+    let mut header = Vec::new();
+    File::open(path)?.take(4096).read_to_end(&mut header)?;
+    let u32_at = |offset: usize, big_endian: bool| -> Option<u32> {
+        let bytes: [u8; 4] = header.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+    let arch = |cpu_type: u32| match cpu_type {
+        0x0100_0007 => "x86_64",
+        0x0100_000c => "aarch64",
+        _ => "an unknown architecture",
+    };
+    let invalid = || std::io::Error::new(std::io::ErrorKind::InvalidData, "not a Mach-O file");
+
+    match u32_at(0, true).ok_or_else(invalid)? {
+        // universal binary: a big endian header followed by 20 bytes per architecture
+        0xcafe_babe => {
+            let count = u32_at(4, true).ok_or_else(invalid)? as usize;
+            (0..count)
+                .map(|i| u32_at(8 + 20 * i, true).map(arch).ok_or_else(invalid))
+                .collect()
+        }
+        // 64 bits little endian binary
+        0xcffa_edfe => Ok(vec![arch(u32_at(4, false).ok_or_else(invalid)?)]),
+        _ => Err(invalid()),
+    }
+}
+
+// Where `fdb_c.lib` is looked for when `FDB_LIB_PATH` is not set, after `FDB_CLIENT_LIB_PATH`.
+#[cfg(not(any(feature = "dlopen", feature = "static")))]
+const WINDOWS_LIB_PATH: &str = "C:/Program Files/foundationdb/lib/foundationdb";
+
+/// Finds the `fdb_c.lib` import library installed with the Windows client.
+///
+/// The client only exports undecorated `extern "C"` symbols for x86_64, which the MSVC linker
+/// resolves through the import library. At runtime, `fdb_c.dll` is loaded from the `PATH`, to
+/// which the installer adds `C:\Program Files\foundationdb\bin`. As for macOS, problems are
+/// only warnings, and left to the linker.
+#[cfg(not(any(feature = "dlopen", feature = "static")))]
+fn link_search_windows() {
+    println!("cargo:rerun-if-env-changed=FDB_LIB_PATH");
+    if env::var("CARGO_CFG_TARGET_ENV").as_deref() != Ok("msvc") {
+        println!(
+            "cargo:warning=the FoundationDB client for Windows can only be linked by the MSVC \
+             toolchain, build for the `x86_64-pc-windows-msvc` target"
+        );
+    }
+
+    let searched: Vec<PathBuf> = match env::var_os("FDB_LIB_PATH") {
+        // an explicit path is the only one searched
+        Some(path) => vec![PathBuf::from(path)],
+        None => env::var_os("FDB_CLIENT_LIB_PATH")
+            .map(PathBuf::from)
+            .into_iter()
+            .chain([PathBuf::from(WINDOWS_LIB_PATH)])
+            .collect(),
+    };
+    match searched.iter().find(|dir| dir.join("fdb_c.lib").is_file()) {
+        Some(dir) => println!("cargo:rustc-link-search=native={}", dir.display()),
+        None => {
+            let searched: Vec<String> = searched
+                .iter()
+                .map(|dir| dir.display().to_string())
+                .collect();
+            println!(
+                "cargo:warning=fdb_c.lib was not found in {}, install the FoundationDB client \
+                 with the official installer, or set FDB_LIB_PATH to the directory containing \
+                 fdb_c.lib",
+                searched.join(", ")
+            );
+        }
+    }
+}
+
+// Where `libfdb_c.a` is looked for when `FDB_LIB_PATH` is not set, after `FDB_CLIENT_LIB_PATH`
+// and `LIBRARY_PATH`.
+#[cfg(all(feature = "static", target_os = "linux"))]
+const STATIC_SEARCH_PATHS: &[&str] = &[
+    "/usr/lib",
+    "/usr/lib64",
+    "/usr/lib/x86_64-linux-gnu",
+    "/usr/lib/aarch64-linux-gnu",
+    "/usr/local/lib",
+    "/usr/local/lib64",
+];
+
+#[cfg(all(feature = "static", target_os = "macos"))]
+const STATIC_SEARCH_PATHS: &[&str] = &["/usr/local/lib", "/opt/homebrew/lib"];
+
+// The C++ runtime and system libraries `libfdb_c.a` needs, linked after it. The C++ runtime
+// can be overridden with a comma separated list in `FDB_STATIC_CXX_LIBS`.
+#[cfg(all(feature = "static", target_os = "linux"))]
+const STATIC_CXX_LIBS: &[&str] = &["stdc++"];
+#[cfg(all(feature = "static", target_os = "linux"))]
+const STATIC_SYSTEM_LIBS: &[&str] = &["m", "pthread", "dl", "rt"];
+
+#[cfg(all(feature = "static", target_os = "macos"))]
+const STATIC_CXX_LIBS: &[&str] = &["c++", "c++abi"];
+#[cfg(all(feature = "static", target_os = "macos"))]
+const STATIC_SYSTEM_LIBS: &[&str] = &[
+    "m",
+    "pthread",
+    "framework=CoreFoundation",
+    "framework=IOKit",
+];
+
+// Surfaced with the errors of the static link, as they are only discovered at link or run time.
+#[cfg(feature = "static")]
+const STATIC_CAVEATS: &str = "\
+note: libfdb_c.a is built against a specific C library and C++ runtime, which the final binary
+      must be compatible with:
+      - the binary needs a glibc at least as recent as the one libfdb_c.a was built with, so
+        build the library on the oldest distribution the binary must run on, and it cannot be
+        linked in a musl binary;
+      - libfdb_c.a is linked with libstdc++ on Linux and libc++ on macOS, set
+        FDB_STATIC_CXX_LIBS (for instance to `c++,c++abi`) when it was built with another C++
+        runtime, and avoid linking two C++ runtimes in the same binary.";
+
+/// Finds `libfdb_c.a` and links it with the libraries it depends on.
+#[cfg(all(feature = "static", any(target_os = "linux", target_os = "macos")))]
+fn link_static() {
+    println!("cargo:rerun-if-env-changed=FDB_LIB_PATH");
+    println!("cargo:rerun-if-env-changed=FDB_STATIC_CXX_LIBS");
+
+    let mut searched = Vec::new();
+    match env::var_os("FDB_LIB_PATH") {
+        // an explicit path is the only one searched
+        Some(path) => searched.push(PathBuf::from(path)),
+        None => {
+            searched.extend(env::var_os("FDB_CLIENT_LIB_PATH").map(PathBuf::from));
+            if let Some(paths) = env::var_os("LIBRARY_PATH") {
+                searched.extend(env::split_paths(&paths));
+            }
+            searched.extend(STATIC_SEARCH_PATHS.iter().map(PathBuf::from));
+        }
+    }
+
+    let dir = searched
+        .iter()
+        .find(|dir| dir.join("libfdb_c.a").is_file())
+        .unwrap_or_else(|| {
+            let searched: Vec<String> = searched
+                .iter()
+                .map(|dir| format!("      - {}", dir.display()))
+                .collect();
+            panic!(
+                "the `static` feature requires libfdb_c.a, which was not found in:\n{}\n\
+                 help: set FDB_LIB_PATH to the directory containing libfdb_c.a\n{}",
+                searched.join("\n"),
+                STATIC_CAVEATS,
+            )
+        });
+    println!(
+        "cargo:rerun-if-changed={}",
+        dir.join("libfdb_c.a").display()
+    );
+    println!("cargo:rustc-link-search=native={}", dir.display());
+    println!("cargo:rustc-link-lib=static=fdb_c");
+
+    let cxx_libs: Vec<String> = match env::var("FDB_STATIC_CXX_LIBS") {
+        Ok(libs) => libs
+            .split(',')
+            .map(str::trim)
+            .filter(|lib| !lib.is_empty())
+            .map(String::from)
+            .collect(),
+        Err(_) => STATIC_CXX_LIBS.iter().map(|lib| lib.to_string()).collect(),
+    };
+    for lib in cxx_libs
+        .iter()
+        .map(String::as_str)
+        .chain(STATIC_SYSTEM_LIBS.iter().copied())
+    {
+        println!("cargo:rustc-link-lib={}", lib);
+    }
+}
+
+#[cfg(all(feature = "static", not(any(target_os = "linux", target_os = "macos"))))]
+fn link_static() {
+    panic!(
+        "the `static` feature is only supported on Linux and macOS, \
+         FoundationDB does not distribute a static libfdb_c for this platform\n{}",
+        STATIC_CAVEATS
+    );
+}
 
 fn main() {
-    // Link against fdb_c.
+    // Link against fdb_c, unless it is loaded at runtime or linked statically.
+    #[cfg(not(any(feature = "dlopen", feature = "static")))]
     println!("cargo:rustc-link-lib=fdb_c");
+    #[cfg(all(feature = "static", not(feature = "dlopen")))]
+    link_static();
 
     if let Ok(lib_path) = env::var("FDB_CLIENT_LIB_PATH") {
         println!("cargo:rustc-link-search=native={}", lib_path);
     }
-
-    // Include the link directory for the .lib file on windows (which will resolve to
-    // the shared library, at runtime)
-    #[cfg(target_os = "windows")]
-    println!("cargo:rustc-link-search=C:/Program Files/foundationdb/lib/foundationdb");
+    // the build script runs on the host, `cfg(target_os)` would be the host OS
+    #[cfg(not(any(feature = "dlopen", feature = "static")))]
+    match env::var("CARGO_CFG_TARGET_OS").as_deref() {
+        Ok("linux") => {
+            if let Ok(lib_path) = env::var("FDB_LIB_PATH") {
+                println!("cargo:rerun-if-env-changed=FDB_LIB_PATH");
+                println!("cargo:rustc-link-search=native={}", lib_path);
+            }
+        }
+        Ok("macos") => link_search_macos(),
+        // Include the link directory for the .lib file on windows (which will resolve to
+        // the shared library, at runtime)
+        Ok("windows") => link_search_windows(),
+        _ => {}
+    }
 
     let out_path = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is not defined!"));
 
@@ -51,39 +326,52 @@ fn main() {
     // to the driver and then '#define FDB_API_VERSION FDB_TRICKY_VERSION', but
     // bindgen isn't smart enough to resolve that from the arguments. Instead, write
     // out a src/wrapper.h file with the chosen version instead.
-    let api_version;
+    //
+    // The version features are additive: the highest one enabled selects the API version, so
+    // that crates depending on different versions can be built together.
+    let api_version = if cfg!(feature = "fdb-7_3") {
+        730
+    } else if cfg!(feature = "fdb-7_1") {
+        710
+    } else if cfg!(feature = "fdb-7_0") {
+        700
+    } else if cfg!(feature = "fdb-6_3") {
+        630
+    } else if cfg!(feature = "fdb-6_2") {
+        620
+    } else if cfg!(feature = "fdb-6_1") {
+        610
+    } else if cfg!(feature = "fdb-6_0") {
+        600
+    } else if cfg!(feature = "fdb-5_2") {
+        520
+    } else if cfg!(feature = "fdb-5_1") {
+        510
+    } else {
+        panic!(
+            "one of the `fdb-X_Y` features must be enabled to select the FoundationDB API version"
+        );
+    };
 
-    #[cfg(feature = "fdb-5_1")]
-    {
-        api_version = 510;
-    }
-    #[cfg(feature = "fdb-5_2")]
-    {
-        api_version = 520;
-    }
-    #[cfg(feature = "fdb-6_0")]
-    {
-        api_version = 600;
-    }
-    #[cfg(feature = "fdb-6_1")]
-    {
-        api_version = 610;
-    }
-    #[cfg(feature = "fdb-6_2")]
-    {
-        api_version = 620;
-    }
-    #[cfg(feature = "fdb-6_3")]
-    {
-        api_version = 630;
-    }
-    #[cfg(feature = "fdb-7_0")]
+    // The vendored headers must describe the API version selected by the feature,
+    // otherwise the generated bindings would silently mismatch the requested version.
+    #[cfg(feature = "embedded-fdb-include")]
     {
-        api_version = 700;
-    }
-    #[cfg(feature = "fdb-7_1")]
-    {
-        api_version = 710;
+        let version_path = format!("./include/{}/version.txt", api_version);
+        let header_version = std::fs::read_to_string(&version_path)
+            .unwrap_or_else(|e| panic!("couldn't read {}: {}", version_path, e));
+        let header_version = header_version.trim();
+        let mut parts = header_version.split('.');
+        let major: i32 = parts.next().and_then(|v| v.parse().ok()).unwrap_or(0);
+        let minor: i32 = parts.next().and_then(|v| v.parse().ok()).unwrap_or(0);
+        assert_eq!(
+            major * 100 + minor * 10,
+            api_version,
+            "the vendored header {} is for FoundationDB {}, which does not match the selected API version {}",
+            version_path,
+            header_version,
+            api_version,
+        );
     }
 
     // Sigh, bindgen only takes a String for its header path, but that's UTF-8 while
@@ -105,13 +393,122 @@ fn main() {
     // Finish up by writing the actual bindings
     let bindings = bindgen::Builder::default()
         // TODO: there must be a way to get foundationdb from pkg-config...
-        .clang_arg(INCLUDE_PATH)
+        .clang_arg(include_path(api_version))
         .header(wrapper_path)
         .generate_comments(true)
         .layout_tests(false)
         .generate()
         .expect("Unable to generate FoundationDB bindings");
+
+    #[cfg(not(feature = "dlopen"))]
     bindings
         .write_to_file(out_path.join("bindings.rs"))
         .expect("Couldn't write bindings!");
+
+    #[cfg(feature = "dlopen")]
+    std::fs::write(
+        out_path.join("bindings.rs"),
+        dlopen::dynamic_bindings(&bindings.to_string()),
+    )
+    .expect("Couldn't write bindings!");
+}
+
+#[cfg(feature = "dlopen")]
+mod dlopen {
+    //! Turns the `extern "C"` functions of the bindings into functions calling the library loaded
+    //! at runtime, see `src/dlopen.rs`.
+
+    use proc_macro2::TokenStream;
+    use quote::{format_ident, quote};
+    use syn::{FnArg, ForeignItem, Item, Pat};
+
+    pub fn dynamic_bindings(bindings: &str) -> String {
+        let file = syn::parse_file(bindings).expect("Unable to parse the generated bindings");
+
+        let mut items = Vec::new();
+        let mut functions = Vec::new();
+        for item in file.items {
+            match item {
+                Item::ForeignMod(foreign) => {
+                    for foreign_item in foreign.items {
+                        match foreign_item {
+                            ForeignItem::Fn(function) => functions.push(function),
+                            other => panic!("unsupported extern item {}", quote!(#other)),
+                        }
+                    }
+                }
+                other => items.push(other),
+            }
+        }
+
+        let mut fields = Vec::new();
+        let mut loads = Vec::new();
+        let mut wrappers = Vec::new();
+        for function in &functions {
+            // the documentation, the wrappers are not `extern` functions
+            let attrs: Vec<_> = function
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("doc"))
+                .collect();
+            let sig = &function.sig;
+            let name = &sig.ident;
+            let inputs = &sig.inputs;
+            let output = &sig.output;
+            let symbol = name.to_string();
+            let symbol_nul = format!("{}\0", symbol);
+            let args: Vec<TokenStream> = inputs
+                .iter()
+                .map(|input| match input {
+                    FnArg::Typed(arg) => match &*arg.pat {
+                        Pat::Ident(ident) => {
+                            let ident = &ident.ident;
+                            quote!(#ident)
+                        }
+                        other => panic!("unsupported argument {} of {}", quote!(#other), symbol),
+                    },
+                    FnArg::Receiver(_) => panic!("unexpected receiver in {}", symbol),
+                })
+                .collect();
+            let field = format_ident!("{}", name);
+
+            fields.push(quote!(#field: unsafe extern "C" fn(#inputs) #output,));
+            loads.push(quote! {
+                #field: *library
+                    .get::<unsafe extern "C" fn(#inputs) #output>(#symbol_nul.as_bytes())
+                    .map_err(|source| crate::LoadError::MissingSymbol {
+                        symbol: #symbol,
+                        source,
+                    })?,
+            });
+            wrappers.push(quote! {
+                #(#attrs)*
+                pub unsafe fn #name(#inputs) #output {
+                    (crate::dlopen::api().#field)(#(#args),*)
+                }
+            });
+        }
+
+        quote! {
+            #(#items)*
+
+            /// The functions of the C API, resolved in the loaded library.
+            pub(crate) struct FdbCApi {
+                #(#fields)*
+            }
+
+            impl FdbCApi {
+                pub(crate) unsafe fn load(
+                    library: &::libloading::Library,
+                ) -> Result<Self, crate::LoadError> {
+                    Ok(FdbCApi {
+                        #(#loads)*
+                    })
+                }
+            }
+
+            #(#wrappers)*
+        }
+        .to_string()
+    }
 }
@@ -0,0 +1,87 @@
+//! Loading of libfdb_c at runtime, with the `dlopen` feature.
+//!
+//! The functions of the bindings call the library loaded by [`load_library`]. If none was loaded
+//! when the first of them is called, the library is looked up by its platform specific name,
+//! `libfdb_c.so` on Linux, in the default locations of the dynamic linker.
+
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fmt;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use crate::FdbCApi;
+
+static API: AtomicPtr<FdbCApi> = AtomicPtr::new(ptr::null_mut());
+// serializes the loads, the functions are only resolved once
+static LOAD: Mutex<()> = Mutex::new(());
+
+/// The error returned when libfdb_c could not be loaded
+#[derive(Debug)]
+pub enum LoadError {
+    /// The library could not be opened
+    Library(libloading::Error),
+    /// A function of the C API is missing from the library, which is likely older than the
+    /// headers the bindings were generated from
+    MissingSymbol {
+        /// The name of the function
+        symbol: &'static str,
+        /// The error of the dynamic linker
+        source: libloading::Error,
+    },
+    /// A library was already loaded, it cannot be replaced
+    AlreadyLoaded,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Library(err) => write!(f, "could not open libfdb_c: {}", err),
+            LoadError::MissingSymbol { symbol, source } => {
+                write!(f, "libfdb_c has no function {}: {}", symbol, source)
+            }
+            LoadError::AlreadyLoaded => f.write_str("libfdb_c is already loaded"),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Library(err) | LoadError::MissingSymbol { source: err, .. } => Some(err),
+            LoadError::AlreadyLoaded => None,
+        }
+    }
+}
+
+/// Loads libfdb_c from `path`, and resolves all the functions of the C API.
+///
+/// It must be called before any other function of the bindings, which would otherwise load the
+/// library from its default location. The library stays loaded until the process exits.
+pub fn load_library<P: AsRef<OsStr>>(path: P) -> Result<(), LoadError> {
+    let _load = LOAD.lock().unwrap_or_else(PoisonError::into_inner);
+    if !API.load(Ordering::Acquire).is_null() {
+        return Err(LoadError::AlreadyLoaded);
+    }
+    // the library runs the network thread and cannot be safely unloaded, it is leaked
+    let library = unsafe { libloading::Library::new(path) }.map_err(LoadError::Library)?;
+    let api = unsafe { FdbCApi::load(&library) }?;
+    std::mem::forget(library);
+    API.store(Box::into_raw(Box::new(api)), Ordering::Release);
+    Ok(())
+}
+
+/// The functions of the loaded library, loading it from its default location if needed.
+pub(crate) fn api() -> &'static FdbCApi {
+    let mut api = API.load(Ordering::Acquire);
+    if api.is_null() {
+        match load_library(libloading::library_filename("fdb_c")) {
+            Ok(()) | Err(LoadError::AlreadyLoaded) => {}
+            Err(err) => panic!("{}, see foundationdb_sys::load_library", err),
+        }
+        api = API.load(Ordering::Acquire);
+    }
+    // never freed once stored
+    unsafe { &*api }
+}
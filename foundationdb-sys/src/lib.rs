@@ -2,4 +2,61 @@
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 #![allow(clippy::unreadable_literal)]
+#![cfg_attr(
+    feature = "dlopen",
+    allow(clippy::missing_safety_doc, clippy::too_many_arguments)
+)]
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+#[cfg(feature = "dlopen")]
+mod dlopen;
+mod library;
+
+#[cfg(feature = "dlopen")]
+pub use dlopen::{load_library, LoadError};
+pub use library::library_path;
+
+#[cfg(all(feature = "static", feature = "dlopen"))]
+compile_error!("the `static` and `dlopen` features cannot be enabled together");
+
+#[cfg(not(any(
+    feature = "fdb-5_1",
+    feature = "fdb-5_2",
+    feature = "fdb-6_0",
+    feature = "fdb-6_1",
+    feature = "fdb-6_2",
+    feature = "fdb-6_3",
+    feature = "fdb-7_0",
+    feature = "fdb-7_1",
+    feature = "fdb-7_3"
+)))]
+compile_error!(
+    "one of the `fdb-X_Y` features must be enabled to select the FoundationDB API version"
+);
+
+// The version features are additive, the highest one enabled selects the API version.
+const SELECTED_API_VERSION: u32 = if cfg!(feature = "fdb-7_3") {
+    730
+} else if cfg!(feature = "fdb-7_1") {
+    710
+} else if cfg!(feature = "fdb-7_0") {
+    700
+} else if cfg!(feature = "fdb-6_3") {
+    630
+} else if cfg!(feature = "fdb-6_2") {
+    620
+} else if cfg!(feature = "fdb-6_1") {
+    610
+} else if cfg!(feature = "fdb-6_0") {
+    600
+} else if cfg!(feature = "fdb-5_2") {
+    520
+} else {
+    510
+};
+
+// The generated bindings must match the API version requested by the enabled features.
+const _: () = assert!(
+    FDB_API_VERSION == SELECTED_API_VERSION,
+    "bindings were not generated for the API version selected by the `fdb-X_Y` features"
+);
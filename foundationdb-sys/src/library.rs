@@ -0,0 +1,63 @@
+//! Location of the libfdb_c loaded in the process, to diagnose which client is actually used
+//! when several are installed, or when the dynamic linker picked a library of another
+//! architecture.
+
+use std::path::PathBuf;
+
+/// Returns the path of the loaded libfdb_c, as reported by `dladdr` for one of its functions.
+///
+/// Returns `None` on platforms without `dladdr`, or when the library is linked statically.
+#[cfg(unix)]
+pub fn library_path() -> Option<PathBuf> {
+    use std::ffi::{c_char, c_int, c_void, CStr, OsStr};
+    use std::os::unix::ffi::OsStrExt;
+
+    #[repr(C)]
+    struct DlInfo {
+        dli_fname: *const c_char,
+        dli_fbase: *mut c_void,
+        dli_sname: *const c_char,
+        dli_saddr: *mut c_void,
+    }
+
+    // part of libc since glibc 2.34, and of libdl before that
+    #[cfg_attr(target_os = "linux", link(name = "dl"))]
+    extern "C" {
+        fn dladdr(addr: *const c_void, info: *mut DlInfo) -> c_int;
+    }
+
+    #[cfg(feature = "dlopen")]
+    let symbol = crate::dlopen::api().fdb_get_max_api_version as *const c_void;
+    #[cfg(not(feature = "dlopen"))]
+    let symbol = crate::fdb_get_max_api_version as *const c_void;
+
+    let mut info = DlInfo {
+        dli_fname: std::ptr::null(),
+        dli_fbase: std::ptr::null_mut(),
+        dli_sname: std::ptr::null(),
+        dli_saddr: std::ptr::null_mut(),
+    };
+    if unsafe { dladdr(symbol, &mut info) } == 0 || info.dli_fname.is_null() {
+        return None;
+    }
+    let path = PathBuf::from(OsStr::from_bytes(
+        unsafe { CStr::from_ptr(info.dli_fname) }.to_bytes(),
+    ));
+    // a statically linked library reports the executable itself
+    let is_executable = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.canonicalize().ok())
+        .zip(path.canonicalize().ok())
+        .map_or(false, |(exe, path)| exe == path);
+    if is_executable {
+        None
+    } else {
+        Some(path)
+    }
+}
+
+/// Returns the path of the loaded libfdb_c, which is not known on this platform.
+#[cfg(not(unix))]
+pub fn library_path() -> Option<PathBuf> {
+    None
+}
@@ -0,0 +1,54 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Packs and unpacks keys with the tuple layer of the `foundationdb` crate, using only `alloc`.
+//!
+//! Building this crate for a target without `std`, like `thumbv7em-none-eabihf`, checks that the
+//! tuple layer does not depend on the standard library.
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use foundationdb::tuple::{PackResult, Subspace};
+
+/// Returns the key of the user `id` of `team`, under `subspace`.
+pub fn user_key(subspace: &Subspace, team: &str, id: i64) -> Vec<u8> {
+    subspace.pack(&(team, id))
+}
+
+/// Decodes a key returned by [`user_key`].
+pub fn parse_user_key(subspace: &Subspace, key: &[u8]) -> PackResult<(String, i64)> {
+    subspace.unpack(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use foundationdb::tuple::{pack, unpack, Element, PackError};
+
+    #[test]
+    fn round_trip() {
+        let subspace = Subspace::from("users");
+        let key = user_key(&subspace, "ops", 42);
+        assert_eq!(key, b"\x02users\x00\x02ops\x00\x15\x2a");
+        assert_eq!(
+            parse_user_key(&subspace, &key).unwrap(),
+            ("ops".to_string(), 42)
+        );
+        assert!(matches!(
+            parse_user_key(&Subspace::from("teams"), &key),
+            Err(PackError::BadPrefix)
+        ));
+
+        let element = Element::Tuple(alloc::vec![Element::Int(-1), Element::Bool(true)]);
+        let packed = pack(&element);
+        assert_eq!(unpack::<Element>(&packed).unwrap(), element);
+    }
+}
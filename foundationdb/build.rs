@@ -1,11 +1,13 @@
+#[cfg(feature = "client")]
 extern crate foundationdb_gen;
 
-use std::env;
-use std::fs::File;
-use std::io::prelude::*;
-use std::path::PathBuf;
-
+#[cfg(feature = "client")]
 fn main() {
+    use std::env;
+    use std::fs::File;
+    use std::io::prelude::*;
+    use std::path::PathBuf;
+
     let out_path = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is undefined!"));
     let options_file = out_path.join("options.rs");
     let mut options = String::new();
@@ -16,3 +18,7 @@ fn main() {
         .write_all(options.as_bytes())
         .expect("couldn't write options.rs!");
 }
+
+// the options are only needed by the client
+#[cfg(not(feature = "client"))]
+fn main() {}
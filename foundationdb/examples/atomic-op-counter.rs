@@ -2,13 +2,8 @@ use byteorder::ByteOrder;
 use foundationdb::tuple::Subspace;
 use foundationdb::{options, Database, FdbError, Transaction};
 
-#[tokio::main]
-async fn main() {
-    let _guard = unsafe { foundationdb::boot() };
-    let db = Database::new_compat(None)
-        .await
-        .expect("failed to get database");
-
+#[foundationdb::main(tokio)]
+async fn main(db: Database) {
     let counter_key = Subspace::all().subspace(&"stats").pack(&"my_counter");
 
     // write initial value
@@ -47,7 +42,8 @@ fn increment(trx: &Transaction, key: &[u8], incr: i64) {
 
 async fn read_counter(trx: &Transaction, key: &[u8]) -> Result<i64, FdbError> {
     let raw_counter = trx
-        .get(key, true)
+        .snapshot()
+        .get(key)
         .await
         .expect("could not read key")
         .expect("no value found");
@@ -209,7 +209,7 @@ async fn write_blob(
 async fn read_data(db: &Database, subspace: &Subspace) -> Option<Vec<u8>> {
     let transaction = db.create_trx().expect("Unable to create transaction");
 
-    let get_result = transaction.get(subspace.bytes(), false).await;
+    let get_result = transaction.read(subspace.bytes()).await;
 
     if let Ok(Some(data)) = get_result {
         return Some(data.to_vec());
@@ -224,7 +224,7 @@ async fn read_blob(db: &Database, subspace: &Subspace) -> Option<Vec<u8>> {
 
     let range = RangeOption::from(subspace.range());
 
-    let get_result = transaction.get_range(&range, 1_024, false).await;
+    let get_result = transaction.read_range(&range, 1_024).await;
 
     if let Ok(results) = get_result {
         let mut data: Vec<u8> = vec![];
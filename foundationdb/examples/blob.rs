@@ -40,7 +40,7 @@ async fn read_blob(db: &Database, subspace: &Subspace) -> Option<Vec<u8>> {
 
     let range = RangeOption::from(subspace.range());
 
-    let get_result = transaction.get_range(&range, 1_024, false).await;
+    let get_result = transaction.read_range(&range, 1_024).await;
 
     if let Ok(results) = get_result {
         let mut data: Vec<u8> = vec![];
@@ -108,7 +108,7 @@ async fn get_available_classes(db: &Database) -> Vec<String> {
     let range = RangeOption::from(&Subspace::from("class"));
 
     let got_range = trx
-        .get_range(&range, 1_024, false)
+        .read_range(&range, 1_024)
         .await
         .expect("failed to get classes");
     let mut available_classes = Vec::<String>::new();
@@ -130,7 +130,8 @@ async fn ditch_trx(trx: &Transaction, student: &str, class: &str) {
 
     // TODO: should get take an &Encode? current impl does encourage &[u8] reuse...
     if trx
-        .get(&attends_key, true)
+        .snapshot()
+        .get(&attends_key)
         .await
         .expect("get failed")
         .is_none()
@@ -140,7 +141,8 @@ async fn ditch_trx(trx: &Transaction, student: &str, class: &str) {
 
     let class_key = pack(&("class", class));
     let available_seats = trx
-        .get(&class_key, true)
+        .snapshot()
+        .get(&class_key)
         .await
         .expect("get failed")
         .expect("class seats were not initialized");
@@ -164,7 +166,8 @@ async fn ditch(db: &Database, student: String, class: String) -> Result<()> {
 async fn signup_trx(trx: &Transaction, student: &str, class: &str) -> Result<()> {
     let attends_key = pack(&("attends", student, class));
     if trx
-        .get(&attends_key, true)
+        .snapshot()
+        .get(&attends_key)
         .await
         .expect("get failed")
         .is_some()
@@ -175,7 +178,8 @@ async fn signup_trx(trx: &Transaction, student: &str, class: &str) -> Result<()>
 
     let class_key = pack(&("class", class));
     let available_seats: i64 = unpack(
-        &trx.get(&class_key, true)
+        &trx.snapshot()
+            .get(&class_key)
             .await
             .expect("get failed")
             .expect("class seats were not initialized"),
@@ -188,7 +192,7 @@ async fn signup_trx(trx: &Transaction, student: &str, class: &str) -> Result<()>
 
     let attends_range = RangeOption::from(&("attends", &student).into());
     if trx
-        .get_range(&attends_range, 1_024, false)
+        .read_range(&attends_range, 1_024)
         .await
         .expect("get_range failed")
         .len()
@@ -348,7 +352,7 @@ async fn run_sim(db: &Database, students: usize, ops_per_student: usize) {
         for key_value in db
             .create_trx()
             .unwrap()
-            .get_range(&attends_range, 1_024, false)
+            .read_range(&attends_range, 1_024)
             .await
             .expect("get_range failed")
             .iter()
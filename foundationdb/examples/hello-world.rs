@@ -1,18 +1,6 @@
-#[tokio::main]
-async fn main() {
-    // Safe because drop is called before the program exits
-    let network = unsafe { foundationdb::boot() };
-
-    // Have fun with the FDB API
-    hello_world().await.expect("could not run the hello world");
-
-    // shutdown the client
-    drop(network);
-}
-
-async fn hello_world() -> foundationdb::FdbResult<()> {
-    let db = foundationdb::Database::default()?;
-
+// boots the network, opens the database and shuts the client down once done
+#[foundationdb::main(tokio)]
+async fn main(db: foundationdb::Database) -> foundationdb::FdbResult<()> {
     // write a value in a retryable closure
     match db
         .run(|trx, _maybe_committed| async move {
@@ -27,7 +15,7 @@ async fn hello_world() -> foundationdb::FdbResult<()> {
 
     // read a value
     match db
-        .run(|trx, _maybe_committed| async move { Ok(trx.get(b"hello", false).await.unwrap()) })
+        .run(|trx, _maybe_committed| async move { Ok(trx.read(b"hello").await.unwrap()) })
         .await
     {
         Ok(slice) => assert_eq!(b"world", slice.unwrap().as_ref()),
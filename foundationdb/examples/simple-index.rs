@@ -58,7 +58,7 @@ async fn get_user(
     let key = user_subspace.pack(&(user_id, zipcode));
 
     let user = transaction
-        .get(&key, false)
+        .read(&key)
         .await?
         .expect("Could not found a row");
 
@@ -87,7 +87,7 @@ async fn search_user_by_zipcode(
 
     let range = RangeOption::from((begin, end));
 
-    let result_get_index = &transaction.get_range(&range, 1, false).await;
+    let result_get_index = &transaction.read_range(&range, 1).await;
 
     let mut users = vec![];
 
@@ -13,20 +13,148 @@
 //! - [API versioning](https://apple.github.io/foundationdb/api-c.html#api-versioning)
 //! - [Network](https://apple.github.io/foundationdb/api-c.html#network)
 
+use std::cell::Cell;
+use std::fmt;
+use std::os::raw::c_int;
 use std::panic;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Condvar, Mutex};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Once, PoisonError, TryLockError};
 use std::thread;
 
-use crate::options::NetworkOption;
-use crate::{error, FdbResult};
+use crate::options::{NetworkOption, OptionError};
+use crate::{error, FdbError, FdbResult};
 use foundationdb_sys as fdb_sys;
 
+#[cfg(feature = "dlopen")]
+pub use foundationdb_sys::LoadError;
+
+/// Loads libfdb_c from `path` at runtime, with the `dlopen` feature.
+///
+/// It must be called before any other function of this crate, which would otherwise load the
+/// library from the default locations of the dynamic linker and panic if it is not found. Fails
+/// if the library cannot be opened, misses a function of the C API, or was already loaded.
+///
+/// ```no_run
+/// foundationdb::api::load_library("/opt/foundationdb/lib/libfdb_c.so")
+///     .expect("could not load libfdb_c");
+/// let network = unsafe { foundationdb::boot() };
+/// ```
+#[cfg(feature = "dlopen")]
+pub fn load_library<P: AsRef<std::ffi::OsStr>>(path: P) -> Result<(), LoadError> {
+    fdb_sys::load_library(path)
+}
+
+/// Returns the path of the loaded libfdb_c, to check which client is used when several are
+/// installed.
+///
+/// Returns `None` when it is not known, on Windows or when the library is linked statically.
+pub fn client_library_path() -> Option<std::path::PathBuf> {
+    fdb_sys::library_path()
+}
+
 /// Returns the max api version of the underlying Fdb C API Client
 pub fn get_max_api_version() -> i32 {
     unsafe { fdb_sys::fdb_get_max_api_version() }
 }
 
+/// The API versions involved when selecting the version of the Fdb C API
+///
+/// Returned by [`check_compatibility`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApiVersionReport {
+    /// The version of the `fdb_c.h` header this crate was compiled against, `foundationdb_sys::FDB_API_VERSION`.
+    pub header_version: i32,
+    /// The version of run-time behavior requested, see [`FdbApiBuilder::set_runtime_version`].
+    pub runtime_version: i32,
+    /// The max api version supported by the loaded libfdb_c, see [`get_max_api_version`].
+    pub max_api_version: i32,
+}
+
+impl ApiVersionReport {
+    /// Checks that the Fdb C API can be initialized with those versions.
+    ///
+    /// The runtime version must not be greater than the header version, which itself must not be
+    /// greater than the max api version supported by the loaded client library.
+    pub fn check(self) -> Result<Self, ApiVersionMismatch> {
+        if self.runtime_version > self.header_version {
+            Err(ApiVersionMismatch {
+                report: self,
+                kind: ApiVersionMismatchKind::RuntimeNewerThanHeader,
+            })
+        } else if self.header_version > self.max_api_version {
+            Err(ApiVersionMismatch {
+                report: self,
+                kind: ApiVersionMismatchKind::HeaderNewerThanClient,
+            })
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+/// Why the versions of an [`ApiVersionReport`] are not compatible
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiVersionMismatchKind {
+    /// The requested runtime version is greater than the compiled header version
+    RuntimeNewerThanHeader,
+    /// The compiled header version is greater than what the loaded libfdb_c supports
+    HeaderNewerThanClient,
+}
+
+/// The error returned when the Fdb C API cannot be initialized with the compiled and loaded versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApiVersionMismatch {
+    pub report: ApiVersionReport,
+    pub kind: ApiVersionMismatchKind,
+}
+
+impl fmt::Display for ApiVersionMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let report = &self.report;
+        write!(
+            f,
+            "incompatible FoundationDB API versions: header version {}, runtime version {}, \
+             libfdb_c max api version {}. ",
+            report.header_version, report.runtime_version, report.max_api_version
+        )?;
+        match self.kind {
+            ApiVersionMismatchKind::RuntimeNewerThanHeader => write!(
+                f,
+                "Request a runtime version lower than or equal to {} with \
+                 `FdbApiBuilder::set_runtime_version`, or enable the `fdb-X_Y` feature \
+                 matching version {}",
+                report.header_version, report.runtime_version
+            ),
+            ApiVersionMismatchKind::HeaderNewerThanClient => write!(
+                f,
+                "Install a libfdb_c supporting api version {}, or enable the `fdb-X_Y` feature \
+                 matching version {} or lower",
+                report.header_version, report.max_api_version
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ApiVersionMismatch {}
+
+/// Compares the max api version of the loaded Fdb C API Client against the compiled header
+/// version and the given runtime version.
+///
+/// ```
+/// let report = foundationdb::api::FdbApiBuilder::default()
+///     .check_compatibility()
+///     .expect("compatible fdb client");
+/// assert!(report.max_api_version >= report.header_version);
+/// ```
+pub fn check_compatibility(runtime_version: i32) -> Result<ApiVersionReport, ApiVersionMismatch> {
+    ApiVersionReport {
+        header_version: fdb_sys::FDB_API_VERSION as i32,
+        runtime_version,
+        max_api_version: get_max_api_version(),
+    }
+    .check()
+}
+
 static VERSION_SELECTED: AtomicBool = AtomicBool::new(false);
 
 /// A Builder with which different versions of the Fdb C API can be initialized
@@ -55,6 +183,12 @@ impl FdbApiBuilder {
         self
     }
 
+    /// Checks that the Fdb C API can be initialized with the requested runtime version,
+    /// see [`check_compatibility`].
+    pub fn check_compatibility(&self) -> Result<ApiVersionReport, ApiVersionMismatch> {
+        check_compatibility(self.runtime_version)
+    }
+
     /// Initialize the foundationDB API and returns a `NetworkBuilder`
     ///
     /// # Panics
@@ -93,9 +227,9 @@ impl Default for FdbApiBuilder {
 /// use foundationdb::api::FdbApiBuilder;
 ///
 /// let network_builder = FdbApiBuilder::default().build().expect("fdb api initialized");
-/// let guard = unsafe { network_builder.boot() };
+/// // Safe because the network is stopped when the process exits
+/// let _guard = unsafe { network_builder.boot() };
 /// // do some work with foundationDB
-/// drop(guard);
 /// ```
 pub struct NetworkBuilder {
     _private: (),
@@ -103,7 +237,7 @@ pub struct NetworkBuilder {
 
 impl NetworkBuilder {
     /// Set network options.
-    pub fn set_option(self, option: NetworkOption) -> FdbResult<Self> {
+    pub fn set_option(self, option: NetworkOption) -> Result<Self, OptionError> {
         unsafe { option.apply()? };
         Ok(self)
     }
@@ -156,15 +290,16 @@ impl NetworkBuilder {
     ///
     /// # Returns
     ///
-    /// A `NetworkAutoStop` handle which must be dropped before the program exits.
+    /// A `NetworkAutoStop` handle keeping the network running, see [`boot`](crate::boot).
     ///
     /// # Safety
     ///
-    /// You *MUST* ensure `drop` is called on the returned object before the program exits.
-    /// This is not required if the program is aborted.
+    /// The network *MUST* be stopped before the program exits. It is stopped by an exit handler
+    /// when the process exits normally, or by [`shutdown_forever`]. This is not required if the
+    /// program is aborted.
     ///
-    /// This method used to be safe in version `0.4`. But because `drop` on the returned object
-    /// might not be called before the program exits, it was found unsafe.
+    /// This method used to be safe in version `0.4`. But because the network might not be
+    /// stopped before the program exits, it was found unsafe.
     ///
     /// # Panics
     ///
@@ -177,9 +312,9 @@ impl NetworkBuilder {
     /// use foundationdb::api::FdbApiBuilder;
     ///
     /// let network_builder = FdbApiBuilder::default().build().expect("fdb api initialized");
-    /// let network = unsafe { network_builder.boot() };
+    /// // Safe because the network is stopped when the process exits
+    /// let _network = unsafe { network_builder.boot() };
     /// // do some interesting things with the API...
-    /// drop(network);
     /// ```
     ///
     /// ```rust
@@ -188,22 +323,219 @@ impl NetworkBuilder {
     /// #[tokio::main]
     /// async fn main() {
     ///     let network_builder = FdbApiBuilder::default().build().expect("fdb api initialized");
-    ///     let network = unsafe { network_builder.boot() };
+    ///     // Safe because the network is stopped when the process exits
+    ///     let _network = unsafe { network_builder.boot() };
     ///     // do some interesting things with the API...
-    ///     drop(network);
     /// }
     /// ```
     pub unsafe fn boot(self) -> FdbResult<NetworkAutoStop> {
+        self.start(&mut lock_network())?;
+        Ok(NetworkAutoStop { _private: () })
+    }
+
+    unsafe fn start(self, state: &mut NetworkState) -> FdbResult<()> {
         let (runner, cond) = self.build()?;
 
-        let net_thread = runner.spawn();
+        let handle = runner.spawn();
 
         let network = cond.wait();
 
-        Ok(NetworkAutoStop {
-            handle: Some(net_thread),
-            network: Some(network),
-        })
+        *state = NetworkState::Running { network, handle };
+        STOP_AT_EXIT.call_once(|| at_exit(stop_at_exit));
+        Ok(())
+    }
+}
+
+/// The network of the process, shared by the [`NetworkAutoStop`] guards
+enum NetworkState {
+    NotStarted,
+    Running {
+        network: NetworkStop,
+        handle: thread::JoinHandle<()>,
+    },
+    /// `fdb_stop_network` was called, the network cannot be restarted
+    Stopped,
+}
+
+static NETWORK: Mutex<NetworkState> = Mutex::new(NetworkState::NotStarted);
+/// Registers [`stop_at_exit`] the first time the network starts
+static STOP_AT_EXIT: Once = Once::new();
+thread_local! {
+    /// Set on the thread running the network by [`NetworkRunner::run`]
+    static IS_NETWORK_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
+fn lock_network() -> MutexGuard<'static, NetworkState> {
+    NETWORK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Returns another guard if the network is running, or boots it with the given builder if it
+/// was never started.
+pub(crate) unsafe fn share_or_boot(
+    builder: impl FnOnce() -> NetworkBuilder,
+) -> Result<NetworkAutoStop, BootError> {
+    let mut state = lock_network();
+    match &mut *state {
+        NetworkState::NotStarted => builder().start(&mut state)?,
+        NetworkState::Running { .. } => {}
+        NetworkState::Stopped => return Err(NetworkPermanentlyStopped.into()),
+    }
+    Ok(NetworkAutoStop { _private: () })
+}
+
+/// Returns if the network was stopped, after which the client must not be called anymore.
+pub(crate) fn is_network_stopped() -> bool {
+    matches!(*lock_network(), NetworkState::Stopped)
+}
+
+/// Stops the network and joins its thread, it can never be restarted in this process.
+fn stop(state: &mut NetworkState) {
+    if let NetworkState::Running {
+        network, handle, ..
+    } = std::mem::replace(state, NetworkState::Stopped)
+    {
+        if let Err(err) = network.stop() {
+            eprintln!("failed to stop network: {}", err);
+            // Not aborting can probably cause undefined behavior
+            std::process::abort();
+        }
+        if let Err(err) = check_not_network_thread() {
+            panic!("cannot join the fdb network thread: {}", err);
+        }
+        handle.join().expect("failed to join fdb thread");
+    }
+}
+
+extern "C" {
+    fn atexit(callback: extern "C" fn()) -> c_int;
+}
+
+/// Calls `callback` when the process exits normally, before the callbacks registered earlier.
+pub(crate) fn at_exit(callback: extern "C" fn()) {
+    if unsafe { atexit(callback) } != 0 {
+        eprintln!("failed to register an exit handler");
+    }
+}
+
+/// Stops the network, if it is still running, when the process exits.
+///
+/// Exit handlers must not panic, so the network is left alone if it cannot be stopped here: when
+/// its lock is held, or when exiting from the network thread itself, which cannot be joined.
+extern "C" fn stop_at_exit() {
+    let mut state = match NETWORK.try_lock() {
+        Ok(state) => state,
+        Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+        Err(TryLockError::WouldBlock) => return,
+    };
+    if matches!(*state, NetworkState::Running { .. }) && !is_network_thread() {
+        stop(&mut state);
+    }
+}
+
+/// Returns `true` if called from the thread running the network, e.g. in the callback of a
+/// future or in a waker it calls.
+pub fn is_network_thread() -> bool {
+    IS_NETWORK_THREAD.with(Cell::get)
+}
+
+/// Fails with [`WouldDeadlock`] if called from the thread running the network.
+///
+/// The network thread resolves every future, so blocking it until one is ready, or until the
+/// network stops, deadlocks the whole client. Code which blocks, like `block_on` helpers, can
+/// call this first to fail instead of hanging.
+pub fn check_not_network_thread() -> Result<(), WouldDeadlock> {
+    if is_network_thread() {
+        Err(WouldDeadlock)
+    } else {
+        Ok(())
+    }
+}
+
+/// Blocking was attempted on the thread running the network, which would never wake up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WouldDeadlock;
+
+impl fmt::Display for WouldDeadlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "blocking on the fdb network thread would deadlock the client"
+        )
+    }
+}
+
+impl std::error::Error for WouldDeadlock {}
+
+/// Stops the network for good, even if guards returned by [`boot`](crate::boot) are still
+/// alive.
+///
+/// The network is otherwise stopped when the process exits, this is meant for processes which
+/// need it stopped earlier, e.g. before unloading libfdb_c. Once stopped, the network cannot be
+/// restarted and [`try_boot`](crate::try_boot) fails with [`BootError::Stopped`].
+///
+/// # Panics
+///
+/// Panics if the network thread cannot be joined, or if called from the network thread itself.
+pub fn shutdown_forever() {
+    let mut state = lock_network();
+    match &*state {
+        NetworkState::NotStarted => *state = NetworkState::Stopped,
+        NetworkState::Running { .. } => stop(&mut state),
+        NetworkState::Stopped => {}
+    }
+}
+
+/// The network of this process was stopped, and the FoundationDB client cannot restart it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkPermanentlyStopped;
+
+impl fmt::Display for NetworkPermanentlyStopped {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the fdb network was stopped and cannot be restarted in this process"
+        )
+    }
+}
+
+impl std::error::Error for NetworkPermanentlyStopped {}
+
+/// The network could not be booted by [`try_boot`](crate::try_boot)
+#[derive(Debug, Clone, Copy)]
+pub enum BootError {
+    /// The network was stopped and cannot be restarted in this process
+    Stopped(NetworkPermanentlyStopped),
+    /// The client failed to start the network
+    Fdb(FdbError),
+}
+
+impl From<NetworkPermanentlyStopped> for BootError {
+    fn from(err: NetworkPermanentlyStopped) -> Self {
+        BootError::Stopped(err)
+    }
+}
+
+impl From<FdbError> for BootError {
+    fn from(err: FdbError) -> Self {
+        BootError::Fdb(err)
+    }
+}
+
+impl fmt::Display for BootError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BootError::Stopped(err) => err.fmt(f),
+            BootError::Fdb(err) => write!(f, "failed to start the fdb network: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BootError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BootError::Stopped(err) => Some(err),
+            BootError::Fdb(err) => Some(err),
+        }
     }
 }
 
@@ -229,6 +561,7 @@ impl NetworkRunner {
     }
 
     fn _run(self) -> FdbResult<()> {
+        IS_NETWORK_THREAD.with(|is_network_thread| is_network_thread.set(true));
         {
             let (lock, cvar) = &*self.cond;
             let mut started = lock.lock().unwrap();
@@ -288,31 +621,24 @@ impl NetworkStop {
     }
 }
 
-/// Stop the associated `NetworkRunner` and thread if dropped
+/// A handle on the running network
+///
+/// Every call to [`boot`](crate::boot) while the network is running returns another handle
+/// sharing the same network. Dropping them does not stop it: FoundationDB cannot restart a
+/// stopped network in a process, so code booting then dropping handles one after another, like
+/// tests, keeps using the same network. It runs until [`shutdown_forever`] is called or the
+/// process exits.
 ///
 /// If trying to stop the FoundationDB run loop results in an error.
 /// The error is printed in `stderr` and the process aborts.
-///
-/// # Panics
-///
-/// Panics if the network thread cannot be joined.
 pub struct NetworkAutoStop {
-    network: Option<NetworkStop>,
-    handle: Option<std::thread::JoinHandle<()>>,
+    _private: (),
 }
+
+// Does nothing, but code written when dropping the last handle stopped the network still calls
+// `drop` on them, which is linted against for types without a `Drop` implementation.
 impl Drop for NetworkAutoStop {
-    fn drop(&mut self) {
-        if let Err(err) = self.network.take().unwrap().stop() {
-            eprintln!("failed to stop network: {}", err);
-            // Not aborting can probably cause undefined behavior
-            std::process::abort();
-        }
-        self.handle
-            .take()
-            .unwrap()
-            .join()
-            .expect("failed to join fdb thread");
-    }
+    fn drop(&mut self) {}
 }
 
 #[cfg(test)]
@@ -323,4 +649,61 @@ mod tests {
     fn test_max_api() {
         assert!(get_max_api_version() > 0);
     }
+
+    #[test]
+    #[cfg(all(unix, not(feature = "static")))]
+    fn test_client_library_path() {
+        let path = client_library_path().expect("libfdb_c is dynamically linked");
+        assert!(path.exists());
+        let name = path.file_name().unwrap().to_string_lossy();
+        assert!(name.contains("fdb_c"), "{}", path.display());
+    }
+
+    #[test]
+    fn test_not_network_thread() {
+        // the network of the unit tests never runs
+        assert!(!is_network_thread());
+        assert_eq!(check_not_network_thread(), Ok(()));
+        assert!(WouldDeadlock.to_string().contains("network thread"));
+    }
+
+    #[test]
+    fn test_check_compatibility() {
+        let header_version = fdb_sys::FDB_API_VERSION as i32;
+
+        let report = check_compatibility(header_version - 10).expect("compatible versions");
+        assert_eq!(report.header_version, header_version);
+        assert_eq!(report.runtime_version, header_version - 10);
+        assert_eq!(report.max_api_version, get_max_api_version());
+
+        let mismatch = check_compatibility(header_version + 10).expect_err("runtime too recent");
+        assert_eq!(
+            mismatch.kind,
+            ApiVersionMismatchKind::RuntimeNewerThanHeader
+        );
+        assert_eq!(mismatch.report.runtime_version, header_version + 10);
+    }
+
+    #[test]
+    fn test_api_version_mismatch_message() {
+        let mismatch = ApiVersionReport {
+            header_version: 710,
+            runtime_version: 710,
+            max_api_version: 630,
+        }
+        .check()
+        .expect_err("client too old");
+        assert_eq!(mismatch.kind, ApiVersionMismatchKind::HeaderNewerThanClient);
+        let message = mismatch.to_string();
+        assert!(message.contains("header version 710"));
+        assert!(message.contains("runtime version 710"));
+        assert!(message.contains("max api version 630"));
+
+        let report = ApiVersionReport {
+            header_version: 710,
+            runtime_version: 630,
+            max_api_version: 710,
+        };
+        assert_eq!(report.check(), Ok(report));
+    }
 }
@@ -0,0 +1,164 @@
+// Copyright 2023 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+// Copyright 2013-2018 Apple, Inc and the FoundationDB project authors.
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The view of the client on its connection to the cluster, see
+//! [`crate::Database::client_status`].
+//!
+//! The report is a JSON document whose fields change between client versions: the fields that
+//! are parsed are all optional, and the whole document is kept in [`ClientStatus::raw`].
+
+use serde::Deserialize;
+
+/// The state of a database once the client was able to connect to its cluster
+const INITIALIZED: &str = "initialized";
+
+/// The client report of a database, as returned by `fdb_database_get_client_status`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct ClientStatus {
+    /// Whether the client considers the database healthy
+    pub healthy: Option<bool>,
+    /// Where the client is in connecting to the cluster, `initialized` once it is connected
+    pub initialization_state: Option<String>,
+    /// The code of the error that prevented the client from connecting, if any
+    pub initialization_error: Option<i32>,
+    /// The protocol version of the cluster, once known
+    pub protocol_version: Option<String>,
+    /// The client libraries loaded by the process, the local one and the external ones
+    pub available_clients: Vec<AvailableClient>,
+    /// The report of the client library actually talking to the cluster
+    pub database_status: Option<DatabaseStatus>,
+    /// The whole report, including the fields that are not parsed
+    #[serde(skip)]
+    pub raw: serde_json::Value,
+}
+
+impl ClientStatus {
+    /// Parses the report returned by the client.
+    pub(crate) fn parse(report: &[u8]) -> serde_json::Result<Self> {
+        let raw: serde_json::Value = serde_json::from_slice(report)?;
+        let mut status = ClientStatus::deserialize(&raw)?;
+        status.raw = raw;
+        Ok(status)
+    }
+
+    /// Whether the client is connected to the cluster and reports the database healthy.
+    ///
+    /// The database is considered healthy when it is initialized and every health flag of the
+    /// report, either on the database or on the client library talking to the cluster, is set.
+    /// A report without any health flag is not healthy.
+    pub fn healthy(&self) -> bool {
+        let initialized = self
+            .initialization_state
+            .as_deref()
+            .map_or(true, |state| state == INITIALIZED);
+        let flags = [
+            self.healthy,
+            self.database_status
+                .as_ref()
+                .and_then(|status| status.healthy),
+        ];
+        initialized && flags.iter().any(Option::is_some) && flags.iter().flatten().all(|&h| h)
+    }
+}
+
+/// A client library loaded by the process.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct AvailableClient {
+    /// The protocol version of the library
+    pub protocol_version: Option<String>,
+    /// The release version of the library, like `7.3.27`
+    pub release_version: Option<String>,
+    /// The index of the thread running the library
+    pub thread_index: Option<u64>,
+}
+
+/// The report of the client library talking to the cluster.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct DatabaseStatus {
+    /// Whether the library considers the database healthy
+    pub healthy: Option<bool>,
+    /// The coordinators of the cluster file
+    pub coordinators: Vec<String>,
+    /// The coordinator the client is connected to
+    pub current_coordinator: Option<String>,
+    /// The connections of the client to the processes of the cluster
+    pub connections: Vec<Connection>,
+    /// How many connections failed
+    pub num_connections_failed: Option<u64>,
+}
+
+/// A connection of the client to a process of the cluster.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Connection {
+    /// The address of the process
+    pub address: String,
+    /// The state of the connection, like `connected` or `failed`
+    pub status: String,
+    /// Whether the process speaks the protocol of the client
+    pub compatible: Option<bool>,
+    /// How many times connecting to the process failed
+    pub connect_failed_count: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"{
+        "Healthy": true,
+        "InitializationState": "initialized",
+        "ProtocolVersion": "fdb00b073000000",
+        "ConnectionRecord": "test:test@127.0.0.1:4500",
+        "AvailableClients": [
+            {"ProtocolVersion": "fdb00b073000000", "ReleaseVersion": "7.3.27", "ThreadIndex": 0}
+        ],
+        "DatabaseStatus": {
+            "Healthy": true,
+            "ClusterID": "a1b2",
+            "Coordinators": ["127.0.0.1:4500"],
+            "CurrentCoordinator": "127.0.0.1:4500",
+            "Connections": [
+                {"Address": "127.0.0.1:4500", "Status": "connected", "Compatible": true,
+                 "ConnectFailedCount": 0}
+            ],
+            "NumConnectionsFailed": 0
+        }
+    }"#;
+
+    #[test]
+    fn parse() {
+        let status = ClientStatus::parse(REPORT.as_bytes()).unwrap();
+        assert!(status.healthy());
+        assert_eq!(status.available_clients.len(), 1);
+        assert_eq!(
+            status.available_clients[0].release_version.as_deref(),
+            Some("7.3.27")
+        );
+        let database = status.database_status.as_ref().unwrap();
+        assert_eq!(database.coordinators, vec!["127.0.0.1:4500"]);
+        assert_eq!(database.connections[0].status, "connected");
+        assert_eq!(status.raw["ConnectionRecord"], "test:test@127.0.0.1:4500");
+        assert_eq!(status.raw["DatabaseStatus"]["ClusterID"], "a1b2");
+    }
+
+    #[test]
+    fn unhealthy() {
+        let initializing = r#"{"Healthy": true, "InitializationState": "initializing"}"#;
+        assert!(!ClientStatus::parse(initializing.as_bytes())
+            .unwrap()
+            .healthy());
+        let database = r#"{"Healthy": true, "DatabaseStatus": {"Healthy": false}}"#;
+        assert!(!ClientStatus::parse(database.as_bytes()).unwrap().healthy());
+        assert!(!ClientStatus::parse(b"{}").unwrap().healthy());
+        assert!(ClientStatus::parse(b"not json").is_err());
+    }
+}
@@ -95,6 +95,8 @@ impl TryFrom<FdbFutureHandle> for Database {
         Ok(Database {
             inner: NonNull::new(v)
                 .expect("fdb_future_get_database to not return null if there is no error"),
+            cluster_file_path: None,
+            default_options: Default::default(),
         })
     }
 }
@@ -0,0 +1,255 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! A counter stored in a single key, updated with atomic adds.
+//!
+//! Reading a counter then writing it back makes every transaction incrementing it conflict with
+//! each other. When the new value is not needed, [`Counter::add`] blindly adds to it and never
+//! conflicts. When it is, like to assign unique sequence numbers, [`Counter::increment_and_get`]
+//! reads it as a snapshot, adds to it atomically and adds a read conflict on its key by hand:
+//! without that conflict, two concurrent transactions could read the same value and both
+//! return it. Transactions assigning numbers then still conflict with each other, so
+//! [`Counter::allocate_block`] assigns a whole block of numbers at once, to be handed out
+//! without touching the database.
+//!
+//! The value is a little-endian signed 64 bits integer, as expected by
+//! [`MutationType::Add`](crate::options::MutationType::Add).
+
+use std::ops::Range;
+
+use crate::options::ConflictRangeType;
+use crate::{FdbResult, MutateTransaction, ReadTransaction, Snapshot};
+
+/// A counter stored in a single key, see the [module documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Counter {
+    key: Vec<u8>,
+}
+
+impl Counter {
+    /// Creates a counter stored in `key`, which should not be written outside of this counter.
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Counter { key: key.into() }
+    }
+
+    /// Returns the key storing this counter.
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Adds `delta` to the counter without reading it, this never conflicts.
+    pub fn add(&self, trx: &dyn MutateTransaction, delta: i64) {
+        trx.atomic_add_i64(&self.key, delta);
+    }
+
+    /// Reads the value of the counter, 0 if it was never written.
+    ///
+    /// Pass a [`Snapshot`] of the transaction to read it as a snapshot.
+    pub async fn get(&self, trx: &dyn ReadTransaction) -> FdbResult<i64> {
+        read_i64(trx, &self.key).await
+    }
+
+    /// Adds `delta` to the counter and returns its new value.
+    ///
+    /// The value returned is unique among the committed transactions calling this method on
+    /// the same counter: it is read as a snapshot, but a read conflict on the key makes the
+    /// commit fail with `not_committed` if another transaction changed it in the meantime.
+    /// Transactions only calling [`add`](Counter::add) do not conflict with each other, but do
+    /// conflict with this one.
+    pub async fn increment_and_get(
+        &self,
+        trx: &dyn MutateTransaction,
+        delta: i64,
+    ) -> FdbResult<i64> {
+        let current = self.get(&Snapshot::new(trx)).await?;
+        self.add(trx, delta);
+        let mut end = self.key.clone();
+        end.push(0x00);
+        trx.add_conflict_range(&self.key, &end, ConflictRangeType::Read)?;
+        // atomic adds wrap around on overflow
+        Ok(current.wrapping_add(delta))
+    }
+
+    /// Increments the counter by `n` and returns the `n` values assigned to this transaction.
+    ///
+    /// Like [`increment_and_get`](Counter::increment_and_get), the values are unique among
+    /// committed transactions, but a single transaction, and a single potential conflict,
+    /// assigns all of them.
+    pub async fn allocate_block(
+        &self,
+        trx: &dyn MutateTransaction,
+        n: u32,
+    ) -> FdbResult<Range<i64>> {
+        let last = self.increment_and_get(trx, i64::from(n)).await?;
+        Ok(last - i64::from(n) + 1..last + 1)
+    }
+}
+
+/// Reads the little-endian `i64` stored in `key`, like the ones written by
+/// [`MutateTransaction::atomic_add_i64`], 0 if the key is missing.
+///
+/// Shorter values are zero extended and longer ones truncated, like atomic adds see them. Pass
+/// a [`Snapshot`] of the transaction to read it as a snapshot.
+pub async fn read_i64(trx: &dyn ReadTransaction, key: &[u8]) -> FdbResult<i64> {
+    let value = trx.read(key).await?;
+    Ok(decode_i64(value.as_deref().unwrap_or_default()))
+}
+
+/// Decodes a little-endian integer, shorter values being zero extended like atomic adds do.
+pub fn decode_i64(value: &[u8]) -> i64 {
+    let mut bytes = [0; 8];
+    let len = value.len().min(8);
+    bytes[..len].copy_from_slice(&value[..len]);
+    i64::from_le_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockDatabase, MockTransaction};
+    use futures::executor::block_on;
+    use std::collections::BTreeSet;
+    use std::future::Future;
+
+    const WORKERS: usize = 8;
+    const VALUES_PER_WORKER: usize = 16;
+
+    /// Runs `WORKERS` workers in lockstep: each round, every worker still running opens a
+    /// transaction and calls `step`, then they all commit. Returns the values assigned by the
+    /// committed transactions and the number of `not_committed` errors.
+    fn run_concurrently<F, Fut>(db: &MockDatabase, step: F) -> (Vec<i64>, usize)
+    where
+        F: Fn(MockTransaction, usize) -> Fut,
+        Fut: Future<Output = FdbResult<Vec<i64>>>,
+    {
+        block_on(async {
+            let mut assigned = vec![Vec::new(); WORKERS];
+            let mut conflicts = 0;
+            while assigned
+                .iter()
+                .any(|values| values.len() < VALUES_PER_WORKER)
+            {
+                let mut round = Vec::new();
+                for (worker, values) in assigned.iter().enumerate() {
+                    if values.len() < VALUES_PER_WORKER {
+                        let trx = db.create_trx().unwrap();
+                        let remaining = VALUES_PER_WORKER - values.len();
+                        let values = step(trx.clone(), remaining).await.unwrap();
+                        round.push((worker, trx, values));
+                    }
+                }
+                for (worker, trx, values) in round {
+                    match trx.commit().await {
+                        Ok(()) => assigned[worker].extend(values),
+                        Err(err) if err.code() == 1020 => conflicts += 1,
+                        Err(err) => panic!("unexpected error {:?}", err),
+                    }
+                }
+            }
+            (assigned.into_iter().flatten().collect(), conflicts)
+        })
+    }
+
+    fn assert_unique(values: &[i64]) {
+        let unique: BTreeSet<i64> = values.iter().copied().collect();
+        assert_eq!(unique.len(), values.len(), "duplicate values assigned");
+        assert_eq!(unique, (1..=values.len() as i64).collect());
+    }
+
+    #[test]
+    fn add_and_get() {
+        let db = MockDatabase::new();
+        let counter = Counter::new(b"counter".to_vec());
+        block_on(async {
+            let trx = db.create_trx().unwrap();
+            assert_eq!(counter.get(&trx).await.unwrap(), 0);
+            counter.add(&trx, 5);
+            counter.add(&trx, -2);
+            assert_eq!(counter.get(&trx).await.unwrap(), 3);
+            trx.commit().await.unwrap();
+
+            // blind adds never conflict
+            let (first, second) = (db.create_trx().unwrap(), db.create_trx().unwrap());
+            first.get_read_version().await.unwrap();
+            second.get_read_version().await.unwrap();
+            counter.add(&first, 1);
+            counter.add(&second, 1);
+            first.commit().await.unwrap();
+            second.commit().await.unwrap();
+
+            let trx = db.create_trx().unwrap();
+            assert_eq!(counter.increment_and_get(&trx, 10).await.unwrap(), 15);
+            assert_eq!(counter.allocate_block(&trx, 3).await.unwrap(), 16..19);
+            assert_eq!(counter.get(&trx.snapshot()).await.unwrap(), 18);
+        });
+        assert_eq!(decode_i64(&[1]), 1);
+        assert_eq!(decode_i64(&[0xff; 9]), -1);
+    }
+
+    #[test]
+    fn increment_and_get_is_unique() {
+        let db = MockDatabase::new();
+        let counter = Counter::new(b"counter".to_vec());
+        let (values, conflicts) = run_concurrently(&db, |trx, _| {
+            let counter = counter.clone();
+            async move { Ok(vec![counter.increment_and_get(&trx, 1).await?]) }
+        });
+        assert_unique(&values);
+        assert!(conflicts > 0);
+    }
+
+    #[test]
+    fn snapshot_read_without_conflict_is_not_unique() {
+        let db = MockDatabase::new();
+        let counter = Counter::new(b"counter".to_vec());
+        let (values, _) = run_concurrently(&db, |trx, _| {
+            let counter = counter.clone();
+            async move {
+                let current = counter.get(&trx.snapshot()).await?;
+                counter.add(&trx, 1);
+                Ok(vec![current + 1])
+            }
+        });
+        let unique: BTreeSet<i64> = values.iter().copied().collect();
+        assert!(unique.len() < values.len());
+    }
+
+    #[test]
+    fn allocate_block_conflicts_less() {
+        let counter = Counter::new(b"counter".to_vec());
+
+        let db = MockDatabase::new();
+        let (naive, naive_conflicts) = run_concurrently(&db, |trx, _| {
+            let counter = counter.clone();
+            async move {
+                let next = counter.get(&trx).await? + 1;
+                trx.set(counter.key(), &next.to_le_bytes());
+                Ok(vec![next])
+            }
+        });
+        assert_unique(&naive);
+
+        let db = MockDatabase::new();
+        let (blocks, block_conflicts) = run_concurrently(&db, |trx, remaining| {
+            let counter = counter.clone();
+            async move {
+                Ok(counter
+                    .allocate_block(&trx, remaining as u32)
+                    .await?
+                    .collect())
+            }
+        });
+        assert_unique(&blocks);
+
+        // every round, all the workers but one conflict
+        assert_eq!(
+            naive_conflicts,
+            VALUES_PER_WORKER * WORKERS * (WORKERS - 1) / 2
+        );
+        assert_eq!(block_conflicts, WORKERS * (WORKERS - 1) / 2);
+    }
+}
@@ -14,6 +14,7 @@ use std::convert::TryInto;
 use std::marker::PhantomData;
 use std::pin::Pin;
 use std::ptr::NonNull;
+use std::sync::{Arc, PoisonError, RwLock};
 use std::time::{Duration, Instant};
 
 use foundationdb_macros::cfg_api_versions;
@@ -23,10 +24,20 @@ use crate::options;
 use crate::transaction::*;
 use crate::{error, FdbError, FdbResult};
 
-use crate::error::FdbBindingError;
+use crate::error::{FdbBindingError, TagThrottled};
+#[cfg_api_versions(min = 630)]
+use crate::special_keys::{self, TagThrottleReason, TagThrottleType, TransactionPriority};
+#[cfg_api_versions(min = 700)]
+use crate::tuple::Subspace;
+#[cfg_api_versions(min = 730)]
+use crate::{
+    client_status::ClientStatus,
+    future::{FdbFuture, FdbSlice},
+};
+use futures::future::BoxFuture;
 use futures::prelude::*;
 
-#[cfg(feature = "fdb-7_1")]
+#[cfg(any(feature = "fdb-7_1", feature = "fdb-7_3"))]
 #[cfg(feature = "tenant-experimental")]
 use crate::tenant::FdbTenant;
 
@@ -35,13 +46,22 @@ use crate::tenant::FdbTenant;
 /// A mutable, lexicographically ordered mapping from binary keys to binary values.
 ///
 /// Modifications to a database are performed via transactions.
+///
+/// Several databases, connected to the same or to different clusters, can be used at the same
+/// time: they share the network of the process, and their options only apply to themselves.
 pub struct Database {
     pub(crate) inner: NonNull<fdb_sys::FDBDatabase>,
+    pub(crate) cluster_file_path: Option<String>,
+    pub(crate) default_options: RwLock<TransactionOptions>,
 }
 unsafe impl Send for Database {}
 unsafe impl Sync for Database {}
 impl Drop for Database {
     fn drop(&mut self) {
+        // once the network is stopped, the client must not be called anymore
+        if crate::api::is_network_stopped() {
+            return;
+        }
         unsafe {
             fdb_sys::fdb_database_destroy(self.inner.as_ptr());
         }
@@ -64,12 +84,20 @@ impl Database {
         error::eval(err)?;
         let ptr =
             NonNull::new(v).expect("fdb_create_database to not return null if there is no error");
-        Ok(Self::new_from_pointer(ptr))
+        Ok(Database {
+            inner: ptr,
+            cluster_file_path: path.map(String::from),
+            default_options: RwLock::default(),
+        })
     }
 
     /// Create a new FDBDatabase from a raw pointer. Users are expected to use the `new` method.
     pub fn new_from_pointer(ptr: NonNull<fdb_sys::FDBDatabase>) -> Self {
-        Self { inner: ptr }
+        Self {
+            inner: ptr,
+            cluster_file_path: None,
+            default_options: RwLock::default(),
+        }
     }
 
     /// Create a database for the given configuration path
@@ -106,39 +134,334 @@ impl Database {
     }
 }
 
+#[cfg_api_versions(min = 730)]
+impl Database {
+    /// Returns the view of the client on its connection to the cluster: the coordinators it
+    /// knows of, its connections, the client libraries loaded and the errors met while
+    /// connecting.
+    ///
+    /// The report describes the client as it is when it is called. It is not healthy until the
+    /// client has connected to the cluster, which happens on the first transaction of the
+    /// database.
+    pub async fn client_status(&self) -> Result<ClientStatus, FdbBindingError> {
+        let report = FdbFuture::<FdbSlice>::new(unsafe {
+            fdb_sys::fdb_database_get_client_status(self.inner.as_ptr())
+        })
+        .await?;
+        ClientStatus::parse(&report).map_err(|err| FdbBindingError::CustomError(Box::new(err)))
+    }
+}
+
+#[cfg_api_versions(min = 630)]
+impl Database {
+    /// Manually throttle the transactions tagged with `tag` to `rate` transactions per second
+    /// for `duration`, as `fdbcli`'s `throttle on tag` would do.
+    ///
+    /// The throttle applies to default priority transactions and is visible through
+    /// [`crate::special_keys::SpecialKeys::throttled_tags`] once committed. Throttled transactions
+    /// will fail with a `tag_throttled` error, see [`FdbError::is_tag_throttled`].
+    pub async fn set_manual_throttle(
+        &self,
+        tag: &[u8],
+        rate: f64,
+        duration: Duration,
+    ) -> FdbResult<()> {
+        let key = special_keys::tag_throttle_key(
+            TagThrottleType::Manual,
+            TransactionPriority::Default,
+            tag,
+        )?;
+        let value = special_keys::tag_throttle_value(
+            rate,
+            0.0,
+            duration.as_secs_f64(),
+            TagThrottleReason::Manual,
+        );
+        let key_ref = &key;
+        let value_ref = &value;
+
+        self.run(|trx, _maybe_committed| async move {
+            trx.set_option(options::TransactionOption::AccessSystemKeys)?;
+            trx.set_option(options::TransactionOption::PrioritySystemImmediate)?;
+
+            let previous = trx.read(key_ref).await?;
+            trx.set(key_ref, value_ref);
+            if previous.is_none() {
+                special_keys::update_throttle_count(&trx, 1).await?;
+            }
+            special_keys::signal_throttle_change(&trx);
+            Ok(())
+        })
+        .await
+        // error can only be an fdb_error
+        .map_err(|e| e.get_fdb_error().unwrap())
+    }
+
+    /// List the tags currently throttled by the cluster, in a transaction of its own, see
+    /// [`crate::special_keys::SpecialKeys::throttled_tags`].
+    pub async fn throttled_tags(&self) -> FdbResult<Vec<special_keys::ThrottledTag>> {
+        self.run(|trx, _maybe_committed| async move {
+            trx.set_option(options::TransactionOption::AccessSystemKeys)?;
+            Ok(trx.special_keys().throttled_tags().await?)
+        })
+        .await
+        // error can only be an fdb_error
+        .map_err(|e| e.get_fdb_error().unwrap())
+    }
+
+    /// Remove the manual throttles set on `tag`, whatever their priority.
+    ///
+    /// Returns `false` if the tag was not manually throttled.
+    pub async fn remove_throttle(&self, tag: &[u8]) -> FdbResult<bool> {
+        let keys: Vec<Vec<u8>> = [
+            TransactionPriority::Batch,
+            TransactionPriority::Default,
+            TransactionPriority::Immediate,
+        ]
+        .into_iter()
+        .map(|priority| special_keys::tag_throttle_key(TagThrottleType::Manual, priority, tag))
+        .collect::<FdbResult<_>>()?;
+        let keys_ref = &keys;
+
+        self.run(|trx, _maybe_committed| async move {
+            trx.set_option(options::TransactionOption::AccessSystemKeys)?;
+            trx.set_option(options::TransactionOption::PrioritySystemImmediate)?;
+
+            let mut removed = 0;
+            for key in keys_ref {
+                if trx.read(key).await?.is_some() {
+                    trx.clear(key);
+                    removed += 1;
+                }
+            }
+            if removed > 0 {
+                special_keys::update_throttle_count(&trx, -removed).await?;
+                special_keys::signal_throttle_change(&trx);
+            }
+            Ok(removed > 0)
+        })
+        .await
+        // error can only be an fdb_error
+        .map_err(|e| e.get_fdb_error().unwrap())
+    }
+}
+
+/// Keys read from the beginning of a subspace before extrapolating its key count
+#[cfg_api_versions(min = 700)]
+const SAMPLE_KEYS: usize = 1000;
+/// Chunks of a subspace whose first keys are read when extrapolating its key count
+#[cfg_api_versions(min = 700)]
+const SAMPLE_CHUNKS: usize = 8;
+/// How many subspaces [`Database::sample_subspace_sizes`] samples at the same time
+#[cfg_api_versions(min = 700)]
+const SAMPLE_CONCURRENCY: usize = 4;
+
+/// Size estimates of a subspace, given by [`Database::sample_subspace_sizes`]
+#[cfg_api_versions(min = 700)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubspaceStats {
+    /// The size of the subspace estimated by the byte sample of the cluster, see
+    /// [`Transaction::get_estimated_range_size_bytes`]
+    pub estimated_bytes: i64,
+    /// The number of keys of the subspace, counted if `exact`, extrapolated otherwise
+    pub estimated_keys: u64,
+    /// The number of keys read to estimate `estimated_keys`
+    pub keys_seen: u64,
+    /// The size of the keys and values read
+    pub bytes_seen: u64,
+    /// Whether every key of the subspace was read, making `estimated_keys` exact
+    pub exact: bool,
+    /// A rough relative standard error of `estimated_keys`, `1 / sqrt(keys_seen)`, or `0` if
+    /// exact
+    ///
+    /// It only accounts for the keys sampled being more or less representative, not for the
+    /// error of the byte sample itself, which is larger on small subspaces.
+    pub relative_error: f64,
+}
+
+#[cfg_api_versions(min = 700)]
+impl Database {
+    /// Estimate the size and the number of keys of each subspace, in the same order.
+    ///
+    /// The size comes from the byte sample of the cluster. The keys are counted by reading the
+    /// first 1000 keys of the subspace: when it holds more, the first keys of up to 8 chunks of
+    /// the subspace, cut by [`Transaction::get_range_split_points`], are also read, and the key
+    /// count is extrapolated from the size of the keys and values read.
+    ///
+    /// Each subspace is sampled by a retried transaction of its own, reading a bounded number of
+    /// keys, so large subspaces do not hit the 5 seconds limit of transactions. At most 4
+    /// subspaces are sampled at the same time.
+    pub async fn sample_subspace_sizes(
+        &self,
+        subspaces: &[Subspace],
+    ) -> FdbResult<Vec<SubspaceStats>> {
+        stream::iter(subspaces)
+            .map(|subspace| self.sample_subspace_size(subspace))
+            .buffered(SAMPLE_CONCURRENCY)
+            .try_collect()
+            .await
+    }
+
+    async fn sample_subspace_size(&self, subspace: &Subspace) -> FdbResult<SubspaceStats> {
+        let (begin, end) = subspace.range();
+        let (begin, end) = (&begin, &end);
+
+        self.run(|trx, _maybe_committed| async move {
+            let estimated_bytes = trx.get_estimated_range_size_bytes(begin, end).await?;
+
+            let head = trx
+                .snapshot()
+                .get_range(&sample_range(begin, end, SAMPLE_KEYS), 1)
+                .await?;
+            let mut keys_seen = head.len() as u64;
+            let mut bytes_seen: u64 = head.iter().map(key_value_size).sum();
+            if head.len() < SAMPLE_KEYS {
+                return Ok(SubspaceStats {
+                    estimated_bytes,
+                    estimated_keys: keys_seen,
+                    keys_seen,
+                    bytes_seen,
+                    exact: true,
+                    relative_error: 0.0,
+                });
+            }
+
+            // the first keys of each chunk, skipping the chunks already read
+            let last_seen = head.last().map(|kv| kv.key().to_vec()).unwrap_or_default();
+            let chunk_size = (estimated_bytes / SAMPLE_CHUNKS as i64).max(1);
+            let split_points = trx.get_range_split_points(begin, end, chunk_size).await?;
+            let chunks = split_points
+                .iter()
+                .map(|split_point| split_point.key())
+                .filter(|&key| key > last_seen.as_slice() && key < end.as_slice())
+                .take(SAMPLE_CHUNKS);
+            let samples = future::try_join_all(chunks.map(|chunk_begin| {
+                trx.snapshot().get_range(
+                    &sample_range(chunk_begin, end, SAMPLE_KEYS / SAMPLE_CHUNKS),
+                    1,
+                )
+            }))
+            .await?;
+            for sample in samples {
+                keys_seen += sample.len() as u64;
+                bytes_seen += sample.iter().map(key_value_size).sum::<u64>();
+            }
+
+            let estimated_keys = (estimated_bytes.max(0) as f64 * keys_seen as f64
+                / bytes_seen.max(1) as f64) as u64;
+            Ok(SubspaceStats {
+                estimated_bytes,
+                estimated_keys: estimated_keys.max(keys_seen),
+                keys_seen,
+                bytes_seen,
+                exact: false,
+                relative_error: 1.0 / (keys_seen as f64).sqrt(),
+            })
+        })
+        .await
+        // error can only be an fdb_error
+        .map_err(|e| e.get_fdb_error().unwrap())
+    }
+}
+
+#[cfg_api_versions(min = 700)]
+fn sample_range<'a>(begin: &'a [u8], end: &'a [u8], limit: usize) -> RangeOption<'a> {
+    RangeOption {
+        limit: Some(limit),
+        mode: options::StreamingMode::Exact,
+        ..RangeOption::from((begin, end))
+    }
+}
+
+#[cfg_api_versions(min = 700)]
+fn key_value_size(key_value: &crate::KeyValue) -> u64 {
+    (key_value.key().len() + key_value.value().len()) as u64
+}
+
 impl Database {
     /// Create a database for the given configuration path
     ///
     /// This is a compatibility api. If you only use API version ≥ 610 you should
     /// use `Database::new`, `Database::from_path` or  `Database::default`.
     pub async fn new_compat(path: Option<&str>) -> FdbResult<Database> {
-        #[cfg(any(feature = "fdb-5_1", feature = "fdb-5_2", feature = "fdb-6_0"))]
+        #[cfg(not(any(
+            feature = "fdb-6_1",
+            feature = "fdb-6_2",
+            feature = "fdb-6_3",
+            feature = "fdb-7_0",
+            feature = "fdb-7_1",
+            feature = "fdb-7_3"
+        )))]
         {
             let cluster = crate::cluster::Cluster::new(path).await?;
-            let database = cluster.create_database().await?;
+            let mut database = cluster.create_database().await?;
+            database.cluster_file_path = path.map(String::from);
             Ok(database)
         }
 
-        #[cfg(not(any(feature = "fdb-5_1", feature = "fdb-5_2", feature = "fdb-6_0")))]
+        #[cfg(any(
+            feature = "fdb-6_1",
+            feature = "fdb-6_2",
+            feature = "fdb-6_3",
+            feature = "fdb-7_0",
+            feature = "fdb-7_1",
+            feature = "fdb-7_3"
+        ))]
         {
             Database::new(path)
         }
     }
 
+    /// Returns the path of the cluster file this database was created with, `None` if it was
+    /// created with the default cluster file or from a raw pointer.
+    ///
+    /// This identifies the database when several are used in the same process, it is not
+    /// resolved: the default cluster file is looked up by the client, see
+    /// [`default_config_path`](crate::default_config_path).
+    pub fn cluster_file_path(&self) -> Option<&str> {
+        self.cluster_file_path.as_deref()
+    }
+
     /// Called to set an option an on `Database`.
-    pub fn set_option(&self, opt: options::DatabaseOption) -> FdbResult<()> {
+    pub fn set_option(&self, opt: options::DatabaseOption) -> Result<(), options::OptionError> {
         unsafe { opt.apply(self.inner.as_ptr()) }
     }
 
+    /// Sets the default options of the transactions created by this database.
+    ///
+    /// The options with a database level equivalent, like
+    /// [`DatabaseOption::TransactionTimeout`](options::DatabaseOption::TransactionTimeout), are
+    /// set on the database when they are `Some`, the unset ones keep the value given by a
+    /// previous call or by [`Database::set_option`]. The others are set on each transaction when
+    /// it is created, and again when it is reset, replacing the ones of a previous call. Either
+    /// way, an option set on a transaction overrides its default.
+    pub fn set_default_transaction_options(&self, defaults: TransactionOptions) -> FdbResult<()> {
+        for opt in defaults.database_options() {
+            self.set_option(opt)?;
+        }
+        *self
+            .default_options
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = defaults;
+        Ok(())
+    }
+
     /// Creates a new transaction on the given database.
     pub fn create_trx(&self) -> FdbResult<Transaction> {
         let mut trx: *mut fdb_sys::FDBTransaction = std::ptr::null_mut();
         let err =
             unsafe { fdb_sys::fdb_database_create_transaction(self.inner.as_ptr(), &mut trx) };
         error::eval(err)?;
-        Ok(Transaction::new(NonNull::new(trx).expect(
-            "fdb_database_create_transaction to not return null if there is no error",
-        )))
+        let trx = Transaction::new(
+            NonNull::new(trx)
+                .expect("fdb_database_create_transaction to not return null if there is no error"),
+        );
+        let default_options = self
+            .default_options
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .transaction_options();
+        trx.with_default_options(default_options)
     }
 
     fn create_retryable_trx(&self) -> FdbResult<RetryableTransaction> {
@@ -159,46 +482,137 @@ impl Database {
     /// Once [Generic Associated Types](https://github.com/rust-lang/rfcs/blob/master/text/1598-generic_associated_types.md)
     /// lands in stable rust, the returned future of f won't need to be boxed anymore, also the
     /// lifetime limitations around f might be lowered.
-    pub async fn transact<F>(&self, mut f: F, options: TransactOption) -> Result<F::Item, F::Error>
+    pub async fn transact<F>(&self, f: F, options: TransactOption) -> Result<F::Item, F::Error>
     where
         F: DatabaseTransact,
     {
-        let is_idempotent = options.is_idempotent;
-        let time_out = options.time_out.map(|d| Instant::now() + d);
-        let retry_limit = options.retry_limit;
-        let mut tries: u32 = 0;
-        let mut trx = self.create_trx()?;
-        let mut can_retry = move || {
-            tries += 1;
-            retry_limit.map(|limit| tries < limit).unwrap_or(true)
-                && time_out.map(|t| Instant::now() < t).unwrap_or(true)
+        self.retry_loop(f, options.into())
+            .await
+            .map_err(|failure| failure.error)
+    }
+
+    /// Like [`transact_boxed`](Self::transact_boxed), but retries as told by `policy`, and
+    /// reports how the last attempt failed, see [`TransactFailure`].
+    ///
+    /// ```no_run
+    /// use foundationdb::{Database, FdbError, MutateTransaction, RetryPolicy};
+    /// use futures::FutureExt;
+    /// use std::time::Duration;
+    ///
+    /// async fn increment(db: &Database) {
+    ///     let policy = RetryPolicy::default()
+    ///         .max_attempts(5)
+    ///         .max_total_time(Duration::from_secs(10))
+    ///         .backoff(|attempt, err: &FdbError| {
+    ///             eprintln!("attempt {} failed: {}", attempt, err);
+    ///             // a runtime sleep can be awaited here
+    ///             futures::future::ready(())
+    ///         });
+    ///     let result = db
+    ///         .transact_with_policy(
+    ///             (),
+    ///             |trx, _| async move {
+    ///                 trx.atomic_add_i64(b"counter", 1);
+    ///                 Ok::<_, FdbError>(())
+    ///             }
+    ///             .boxed(),
+    ///             policy,
+    ///         )
+    ///         .await;
+    ///     if let Err(failure) = result {
+    ///         if failure.maybe_committed {
+    ///             eprintln!("the counter may have been incremented: {}", failure.error);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn transact_with_policy<'trx, F, D, T, E>(
+        &'trx self,
+        data: D,
+        f: F,
+        policy: RetryPolicy,
+    ) -> impl Future<Output = Result<T, TransactFailure<E>>> + Send + 'trx
+    where
+        for<'a> F: FnMut(
+            &'a Transaction,
+            &'a mut D,
+        ) -> Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'a>>,
+        E: TransactError,
+        F: Send + 'trx,
+        T: Send + 'trx,
+        E: Send + 'trx,
+        D: Send + 'trx,
+    {
+        self.retry_loop(
+            boxed::FnMutBoxed {
+                f,
+                d: data,
+                m: PhantomData,
+            },
+            policy,
+        )
+    }
+
+    async fn retry_loop<F>(
+        &self,
+        mut f: F,
+        policy: RetryPolicy,
+    ) -> Result<F::Item, TransactFailure<F::Error>>
+    where
+        F: DatabaseTransact,
+    {
+        let deadline = policy.max_total_time.map(|d| Instant::now() + d);
+        let mut attempts: u32 = 0;
+        let can_retry = |err: &FdbError, attempts: u32| {
+            (policy.is_idempotent || !err.is_maybe_committed())
+                && policy.max_attempts.map_or(true, |max| attempts < max)
+                && deadline.map_or(true, |t| Instant::now() < t)
+        };
+        let failure = |err: FdbError, attempts: u32| TransactFailure {
+            error: F::Error::from(err),
+            maybe_committed: err.is_maybe_committed(),
+            attempts,
         };
+        let mut trx = self.create_trx().map_err(|err| failure(err, 0))?;
         loop {
+            attempts += 1;
             let r = f.transact(trx).await;
             f = r.0;
-            trx = r.1;
-            trx = match r.2 {
-                Ok(item) => match trx.commit().await {
+            let err = match r.2 {
+                Ok(item) => match r.1.commit().await {
                     Ok(_) => break Ok(item),
                     Err(e) => {
-                        if (is_idempotent || !e.is_maybe_committed()) && can_retry() {
-                            e.on_error().await?
-                        } else {
-                            break Err(F::Error::from(e.into()));
+                        let err = *e;
+                        if !can_retry(&err, attempts) {
+                            break Err(failure(err, attempts));
                         }
+                        trx = e.on_error().await.map_err(|err| failure(err, attempts))?;
+                        err
                     }
                 },
                 Err(user_err) => match user_err.try_into_fdb_error() {
-                    Ok(e) => {
-                        if (is_idempotent || !e.is_maybe_committed()) && can_retry() {
-                            trx.on_error(e).await?
-                        } else {
-                            break Err(F::Error::from(e));
+                    Ok(err) => {
+                        if !can_retry(&err, attempts) {
+                            break Err(failure(err, attempts));
                         }
+                        trx =
+                            r.1.on_error(err)
+                                .await
+                                .map_err(|err| failure(err, attempts))?;
+                        err
+                    }
+                    Err(user_err) => {
+                        break Err(TransactFailure {
+                            error: user_err,
+                            maybe_committed: false,
+                            attempts,
+                        })
                     }
-                    Err(user_err) => break Err(user_err),
                 },
             };
+            if let Some(backoff) = &policy.backoff {
+                backoff(attempts, &err).await;
+            }
         }
     }
 
@@ -293,6 +707,7 @@ impl Database {
                 // checks if it is an FdbError
                 if let Some(e) = e.get_fdb_error() {
                     maybe_committed_transaction = e.is_maybe_committed();
+                    let tags = transaction.tags();
                     // The closure returned an Error,
                     match transaction.on_error(e).await {
                         // we can retry the error
@@ -301,7 +716,7 @@ impl Database {
                             continue;
                         }
                         Ok(Err(non_retryable_error)) => {
-                            return Err(FdbBindingError::from(non_retryable_error))
+                            return Err(run_error(non_retryable_error, tags))
                         }
                         // The only FdbBindingError that can be thrown here is `ReferenceToTransactionKept`
                         Err(non_retryable_error) => return Err(non_retryable_error),
@@ -319,6 +734,7 @@ impl Database {
                 Ok(Ok(_)) => return result_closure,
                 Ok(Err(transaction_commit_error)) => {
                     maybe_committed_transaction = transaction_commit_error.is_maybe_committed();
+                    let tags = transaction_commit_error.tags();
                     // we have an error during commit, checking if it is a retryable error
                     match transaction_commit_error.on_error().await {
                         Ok(t) => {
@@ -326,7 +742,7 @@ impl Database {
                             continue;
                         }
                         Err(non_retryable_error) => {
-                            return Err(FdbBindingError::from(non_retryable_error))
+                            return Err(run_error(non_retryable_error, tags))
                         }
                     }
                 }
@@ -351,6 +767,16 @@ impl Database {
         Ok(())
     }
 }
+/// The error of `db.run` once its transaction cannot be retried, naming the tags of the
+/// transaction when it was throttled
+fn run_error(err: FdbError, tags: Vec<String>) -> FdbBindingError {
+    if err.is_tag_throttled() {
+        FdbBindingError::TagThrottled(TagThrottled { tags })
+    } else {
+        FdbBindingError::from(err)
+    }
+}
+
 pub trait DatabaseTransact: Sized {
     type Item;
     type Error: TransactError;
@@ -495,3 +921,279 @@ impl TransactOption {
         }
     }
 }
+
+/// How [`Database::transact_with_policy`] retries its transaction
+///
+/// By default, it retries until the transaction commits or fails with an error which is not
+/// retryable, or maybe committed.
+#[derive(Default, Clone)]
+pub struct RetryPolicy {
+    max_attempts: Option<u32>,
+    max_total_time: Option<Duration>,
+    is_idempotent: bool,
+    backoff: Option<Backoff>,
+}
+
+/// The hook of [`RetryPolicy::backoff`]
+type Backoff = Arc<dyn Fn(u32, &FdbError) -> BoxFuture<'static, ()> + Send + Sync>;
+
+impl RetryPolicy {
+    /// Gives up after `max_attempts` attempts, the first one included.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Does not retry anymore once `max_total_time` elapsed since the first attempt.
+    pub fn max_total_time(mut self, max_total_time: Duration) -> Self {
+        self.max_total_time = Some(max_total_time);
+        self
+    }
+
+    /// Also retries the attempts which may have been committed, the closure being idempotent.
+    pub fn idempotent(mut self) -> Self {
+        self.is_idempotent = true;
+        self
+    }
+
+    /// Awaits `backoff` before each retry, after the backoff of `on_error`.
+    ///
+    /// It is called with the number of attempts made so far and the error being retried. The
+    /// crate does not depend on an async runtime, the sleep of the runtime used can be awaited in
+    /// the returned future.
+    pub fn backoff<B, Fut>(mut self, backoff: B) -> Self
+    where
+        B: Fn(u32, &FdbError) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.backoff = Some(Arc::new(move |attempts: u32, err: &FdbError| {
+            backoff(attempts, err).boxed()
+        }));
+        self
+    }
+}
+
+impl From<TransactOption> for RetryPolicy {
+    fn from(options: TransactOption) -> Self {
+        RetryPolicy {
+            max_attempts: options.retry_limit,
+            max_total_time: options.time_out,
+            is_idempotent: options.is_idempotent,
+            backoff: None,
+        }
+    }
+}
+
+/// The error of [`Database::transact_with_policy`], once its transaction cannot be retried
+#[derive(Debug)]
+pub struct TransactFailure<E> {
+    /// The error of the last attempt, returned by the closure or by the commit
+    pub error: E,
+    /// `true` if the last attempt failed with an error leaving it maybe committed, see
+    /// [`FdbError::is_maybe_committed`]
+    pub maybe_committed: bool,
+    /// The number of attempts made, `0` if the transaction could not be created
+    pub attempts: u32,
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for TransactFailure<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} after {} attempts", self.error, self.attempts)?;
+        if self.maybe_committed {
+            write!(f, ", the last one maybe committed")?;
+        }
+        Ok(())
+    }
+}
+
+impl<E: std::error::Error> std::error::Error for TransactFailure<E> {}
+
+/// The default options of the transactions created by a database, see
+/// [`Database::set_default_transaction_options`].
+///
+/// Unset options are left as they are, to the defaults of the client or to the values set on the
+/// database.
+#[derive(Debug, Default, Clone)]
+pub struct TransactionOptions {
+    /// Cancels the transactions after this duration, retries included, see
+    /// [`TransactionOption::Timeout`](options::TransactionOption::Timeout). A zero duration
+    /// disables the timeout, others are rounded up to the millisecond.
+    pub timeout: Option<Duration>,
+    /// Maximum number of retries, see
+    /// [`TransactionOption::RetryLimit`](options::TransactionOption::RetryLimit).
+    pub retry_limit: Option<u32>,
+    /// Maximum delay between retries, see
+    /// [`TransactionOption::MaxRetryDelay`](options::TransactionOption::MaxRetryDelay), rounded
+    /// up to the millisecond.
+    pub max_retry_delay: Option<Duration>,
+    /// Maximum size in bytes of the transactions, see
+    /// [`TransactionOption::SizeLimit`](options::TransactionOption::SizeLimit).
+    #[cfg(any(
+        feature = "fdb-6_2",
+        feature = "fdb-6_3",
+        feature = "fdb-7_0",
+        feature = "fdb-7_1",
+        feature = "fdb-7_3"
+    ))]
+    pub size_limit: Option<u32>,
+    /// See [`TransactionOption::CausalReadRisky`](options::TransactionOption::CausalReadRisky).
+    pub causal_read_risky: bool,
+    /// See
+    /// [`TransactionOption::ReportConflictingKeys`](options::TransactionOption::ReportConflictingKeys).
+    #[cfg(any(
+        feature = "fdb-6_3",
+        feature = "fdb-7_0",
+        feature = "fdb-7_1",
+        feature = "fdb-7_3"
+    ))]
+    pub report_conflicting_keys: bool,
+    /// Identifier set, along with
+    /// [`TransactionOption::LogTransaction`](options::TransactionOption::LogTransaction), on a
+    /// sample of the transactions so they can be found in the client trace files.
+    #[cfg(any(
+        feature = "fdb-6_1",
+        feature = "fdb-6_2",
+        feature = "fdb-6_3",
+        feature = "fdb-7_0",
+        feature = "fdb-7_1",
+        feature = "fdb-7_3"
+    ))]
+    pub debug_transaction_identifier: Option<String>,
+    /// Probability of a transaction being given the `debug_transaction_identifier`, all of them
+    /// are if unset.
+    #[cfg(any(
+        feature = "fdb-6_1",
+        feature = "fdb-6_2",
+        feature = "fdb-6_3",
+        feature = "fdb-7_0",
+        feature = "fdb-7_1",
+        feature = "fdb-7_3"
+    ))]
+    pub debug_sample_rate: Option<f64>,
+}
+
+/// Rounded up, a sub-millisecond timeout truncated to 0 would disable it.
+pub(crate) fn millis(duration: Duration) -> i32 {
+    let rounded = duration.as_millis() + u128::from(duration.subsec_nanos() % 1_000_000 != 0);
+    rounded.try_into().unwrap_or(i32::MAX)
+}
+
+pub(crate) fn saturating_i32(v: u32) -> i32 {
+    v.try_into().unwrap_or(i32::MAX)
+}
+
+impl TransactionOptions {
+    /// The database options of the set fields.
+    #[cfg_api_versions(min = 610)]
+    fn database_options(&self) -> Vec<options::DatabaseOption> {
+        let mut opts = Vec::new();
+        if let Some(timeout) = self.timeout {
+            // 0 disables the timeout
+            opts.push(options::DatabaseOption::TransactionTimeout(millis(timeout)));
+        }
+        if let Some(retry_limit) = self.retry_limit {
+            opts.push(options::DatabaseOption::TransactionRetryLimit(
+                saturating_i32(retry_limit),
+            ));
+        }
+        if let Some(max_retry_delay) = self.max_retry_delay {
+            opts.push(options::DatabaseOption::TransactionMaxRetryDelay(millis(
+                max_retry_delay,
+            )));
+        }
+        #[cfg(any(
+            feature = "fdb-6_2",
+            feature = "fdb-6_3",
+            feature = "fdb-7_0",
+            feature = "fdb-7_1",
+            feature = "fdb-7_3"
+        ))]
+        if let Some(size_limit) = self.size_limit {
+            opts.push(options::DatabaseOption::TransactionSizeLimit(
+                saturating_i32(size_limit),
+            ));
+        }
+        opts
+    }
+
+    /// There are no database level transaction options before 6.1.
+    #[cfg_api_versions(min = 510, max = 600)]
+    fn database_options(&self) -> Vec<options::DatabaseOption> {
+        Vec::new()
+    }
+
+    /// The options set on each transaction, the debug identifier being sampled.
+    fn transaction_options(&self) -> Vec<options::TransactionOption> {
+        let mut opts = Vec::new();
+        #[cfg(not(any(
+            feature = "fdb-6_1",
+            feature = "fdb-6_2",
+            feature = "fdb-6_3",
+            feature = "fdb-7_0",
+            feature = "fdb-7_1",
+            feature = "fdb-7_3"
+        )))]
+        {
+            if let Some(timeout) = self.timeout {
+                opts.push(options::TransactionOption::Timeout(millis(timeout)));
+            }
+            if let Some(retry_limit) = self.retry_limit {
+                opts.push(options::TransactionOption::RetryLimit(saturating_i32(
+                    retry_limit,
+                )));
+            }
+            if let Some(max_retry_delay) = self.max_retry_delay {
+                opts.push(options::TransactionOption::MaxRetryDelay(millis(
+                    max_retry_delay,
+                )));
+            }
+        }
+        if self.causal_read_risky {
+            opts.push(options::TransactionOption::CausalReadRisky);
+        }
+        #[cfg(any(
+            feature = "fdb-6_3",
+            feature = "fdb-7_0",
+            feature = "fdb-7_1",
+            feature = "fdb-7_3"
+        ))]
+        if self.report_conflicting_keys {
+            opts.push(options::TransactionOption::ReportConflictingKeys);
+        }
+        #[cfg(any(
+            feature = "fdb-6_1",
+            feature = "fdb-6_2",
+            feature = "fdb-6_3",
+            feature = "fdb-7_0",
+            feature = "fdb-7_1",
+            feature = "fdb-7_3"
+        ))]
+        if let Some(identifier) = &self.debug_transaction_identifier {
+            if self
+                .debug_sample_rate
+                .map_or(true, |rate| rand::random::<f64>() < rate)
+            {
+                opts.push(options::TransactionOption::DebugTransactionIdentifier(
+                    identifier.clone(),
+                ));
+                opts.push(options::TransactionOption::LogTransaction);
+            }
+        }
+        opts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_millis_rounds_up() {
+        assert_eq!(millis(Duration::ZERO), 0);
+        assert_eq!(millis(Duration::from_nanos(1)), 1);
+        assert_eq!(millis(Duration::from_micros(500)), 1);
+        assert_eq!(millis(Duration::from_millis(1)), 1);
+        assert_eq!(millis(Duration::from_micros(1500)), 2);
+        assert_eq!(millis(Duration::from_secs(u64::MAX)), i32::MAX);
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright 2018 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Directory operations running in their own retry loop.
+
+use crate::directory::directory_layer::DirectoryLayer;
+use crate::directory::error::DirectoryError;
+use crate::directory::{Directory, DirectoryOutput};
+use crate::{Database, FdbBindingError, MutateTransaction};
+
+impl Database {
+    /// Run directory operations of the default [`DirectoryLayer`] in their own transactions,
+    /// retried with [`Database::run`].
+    pub fn directory(&self) -> DatabaseDirectory<'_> {
+        self.directory_with(DirectoryLayer::default())
+    }
+
+    /// Run directory operations of `directory_layer` in their own transactions, retried with
+    /// [`Database::run`].
+    pub fn directory_with(&self, directory_layer: DirectoryLayer) -> DatabaseDirectory<'_> {
+        DatabaseDirectory {
+            db: self,
+            directory_layer,
+        }
+    }
+}
+
+/// The operations of a [`DirectoryLayer`], each one running in a transaction of its own.
+///
+/// Directories opened or created give a [`DirectoryHandle`], to be validated by the later
+/// transactions using them.
+///
+/// # Warning: maybe committed transactions
+///
+/// Like any closure given to [`Database::run`], an operation may be retried after it has been
+/// committed. `create_or_open` and `open` are idempotent, but a retried `create`, `move_to` or
+/// `remove` may fail as if the directory was created, moved or removed by someone else.
+pub struct DatabaseDirectory<'a> {
+    db: &'a Database,
+    directory_layer: DirectoryLayer,
+}
+
+impl<'a> DatabaseDirectory<'a> {
+    /// Creates or opens the directory located at path (creating parent directories, if necessary).
+    pub async fn create_or_open(
+        &self,
+        path: &[String],
+    ) -> Result<DirectoryHandle, FdbBindingError> {
+        self.db
+            .run(|trx, _maybe_committed| async move {
+                let directory = self
+                    .directory_layer
+                    .create_or_open(&trx, path, None, None)
+                    .await?;
+                Ok(self.handle(&trx, path, directory).await?)
+            })
+            .await
+    }
+
+    /// Creates the directory located at path (creating parent directories if necessary).
+    pub async fn create(&self, path: &[String]) -> Result<DirectoryHandle, FdbBindingError> {
+        self.db
+            .run(|trx, _maybe_committed| async move {
+                let directory = self.directory_layer.create(&trx, path, None, None).await?;
+                Ok(self.handle(&trx, path, directory).await?)
+            })
+            .await
+    }
+
+    /// Opens the directory located at path.
+    pub async fn open(&self, path: &[String]) -> Result<DirectoryHandle, FdbBindingError> {
+        self.db
+            .run(|trx, _maybe_committed| async move {
+                let directory = self.directory_layer.open(&trx, path, None).await?;
+                Ok(self.handle(&trx, path, directory).await?)
+            })
+            .await
+    }
+
+    /// Checks if the directory located at path exists.
+    pub async fn exists(&self, path: &[String]) -> Result<bool, FdbBindingError> {
+        self.db
+            .run(|trx, _maybe_committed| async move {
+                Ok(self.directory_layer.exists(&trx, path).await?)
+            })
+            .await
+    }
+
+    /// Moves the directory located at old_path to new_path.
+    ///
+    /// The handles of the directory at old_path are no longer valid.
+    pub async fn move_to(
+        &self,
+        old_path: &[String],
+        new_path: &[String],
+    ) -> Result<DirectoryHandle, FdbBindingError> {
+        self.db
+            .run(|trx, _maybe_committed| async move {
+                let directory = self
+                    .directory_layer
+                    .move_to(&trx, old_path, new_path)
+                    .await?;
+                Ok(self.handle(&trx, new_path, directory).await?)
+            })
+            .await
+    }
+
+    /// Removes the directory located at path and all of its subdirectories, as well as all of
+    /// their contents.
+    pub async fn remove(&self, path: &[String]) -> Result<bool, FdbBindingError> {
+        self.db
+            .run(|trx, _maybe_committed| async move {
+                Ok(self.directory_layer.remove(&trx, path).await?)
+            })
+            .await
+    }
+
+    /// Removes the directory located at path (if the path exists) and all of its subdirectories,
+    /// as well as all of their contents.
+    pub async fn remove_if_exists(&self, path: &[String]) -> Result<bool, FdbBindingError> {
+        self.db
+            .run(|trx, _maybe_committed| async move {
+                Ok(self.directory_layer.remove_if_exists(&trx, path).await?)
+            })
+            .await
+    }
+
+    /// List the subdirectories of the directory located at path.
+    pub async fn list(&self, path: &[String]) -> Result<Vec<String>, FdbBindingError> {
+        self.db
+            .run(|trx, _maybe_committed| async move {
+                Ok(self.directory_layer.list(&trx, path).await?)
+            })
+            .await
+    }
+
+    async fn handle(
+        &self,
+        trx: &dyn MutateTransaction,
+        path: &[String],
+        directory: DirectoryOutput,
+    ) -> Result<DirectoryHandle, DirectoryError> {
+        let (entry_key, prefix) = self
+            .directory_layer
+            .node_entry(trx, path)
+            .await?
+            .ok_or(DirectoryError::DirectoryDoesNotExists)?;
+        Ok(DirectoryHandle {
+            directory,
+            entry_key,
+            prefix,
+        })
+    }
+}
+
+/// A directory opened in a previous transaction.
+///
+/// A `DirectorySubspace` is only valid within the transaction which opened it: once committed,
+/// the directory may be moved or removed, and its prefix given to another directory. The handle
+/// records where its prefix is stored in the node of its parent, so later transactions can check
+/// it still holds the same prefix with a single read, see [`DirectoryHandle::validate`].
+#[derive(Clone, Debug)]
+pub struct DirectoryHandle {
+    directory: DirectoryOutput,
+    entry_key: Vec<u8>,
+    prefix: Vec<u8>,
+}
+
+impl DirectoryHandle {
+    /// Checks that the directory is still located at its path, and gives it to be used by `trx`.
+    ///
+    /// The read conflicts with a concurrent move or removal of the directory, so `trx` can not be
+    /// committed if the directory stops being valid meanwhile. Moving one of the parents of the
+    /// directory is not detected.
+    ///
+    /// It fails with [`DirectoryError::DirectoryMovedOrRemoved`] if the directory was moved or
+    /// removed, even if another one has been created at the same path since, unless it was given
+    /// the very same prefix.
+    pub async fn validate(
+        &self,
+        trx: &dyn MutateTransaction,
+    ) -> Result<&DirectoryOutput, DirectoryError> {
+        match trx.read(&self.entry_key).await? {
+            Some(prefix) if prefix == self.prefix => Ok(&self.directory),
+            _ => Err(DirectoryError::DirectoryMovedOrRemoved),
+        }
+    }
+
+    /// The directory, as it was when the handle was created.
+    pub fn directory_unchecked(&self) -> &DirectoryOutput {
+        &self.directory
+    }
+
+    /// The path of the directory.
+    pub fn get_path(&self) -> &[String] {
+        self.directory.get_path()
+    }
+
+    /// The layer of the directory.
+    pub fn get_layer(&self) -> &[u8] {
+        self.directory.get_layer()
+    }
+}
@@ -13,11 +13,11 @@ use crate::directory::directory_subspace::DirectorySubspace;
 use crate::directory::error::DirectoryError;
 use crate::directory::node::Node;
 use crate::directory::{compare_slice, strinc, Directory, DirectoryOutput};
-use crate::future::FdbSlice;
+use crate::traits::read_range_as;
 use crate::tuple::hca::HighContentionAllocator;
 use crate::tuple::{Element, Subspace, TuplePack};
 use crate::RangeOption;
-use crate::{FdbResult, Transaction};
+use crate::{FdbResult, MutateTransaction};
 use async_recursion::async_recursion;
 use async_trait::async_trait;
 use std::cmp::Ordering;
@@ -128,7 +128,7 @@ impl DirectoryLayer {
         self.path.as_slice()
     }
 
-    fn node_with_optional_prefix(&self, prefix: Option<FdbSlice>) -> Option<Subspace> {
+    fn node_with_optional_prefix(&self, prefix: Option<Vec<u8>>) -> Option<Subspace> {
         prefix.map(|fdb_slice| self.node_with_prefix(&fdb_slice.deref()))
     }
 
@@ -138,7 +138,7 @@ impl DirectoryLayer {
 
     async fn find(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
     ) -> Result<Option<Node>, DirectoryError> {
         let mut current_path = vec![];
@@ -152,7 +152,7 @@ impl DirectoryLayer {
             let key = node_subspace.subspace(&(DEFAULT_SUB_DIRS, path_name.to_owned()));
 
             // finding the next node
-            let fdb_slice_value = trx.get(key.bytes(), false).await?;
+            let fdb_slice_value = trx.read(key.bytes()).await?;
 
             loaded = true;
             node_subspace = match self.node_with_optional_prefix(fdb_slice_value) {
@@ -179,6 +179,40 @@ impl DirectoryLayer {
         }))
     }
 
+    /// `node_entry` returns the key holding the prefix of the directory at `path` in the node of
+    /// its parent, along with that prefix, entering partitions if needed.
+    #[async_recursion]
+    pub(crate) async fn node_entry(
+        &self,
+        trx: &dyn MutateTransaction,
+        path: &'async_recursion [String],
+    ) -> Result<Option<(Vec<u8>, Vec<u8>)>, DirectoryError> {
+        let (path_last, parent_path) = path.split_last().ok_or(DirectoryError::NoPathProvided)?;
+
+        let parent = match self.find(trx, parent_path).await? {
+            None => return Ok(None),
+            Some(parent) => parent,
+        };
+
+        if parent.is_in_partition(true) {
+            let mut sub_path = parent.get_partition_subpath();
+            sub_path.push(path_last.to_owned());
+            return match parent.get_contents()? {
+                DirectoryOutput::DirectorySubspace(_) => unreachable!("already in partition"),
+                DirectoryOutput::DirectoryPartition(directory_partition) => {
+                    directory_partition
+                        .directory_subspace
+                        .directory_layer
+                        .node_entry(trx, &sub_path)
+                        .await
+                }
+            };
+        }
+
+        let key = parent.subspace.pack(&(DEFAULT_SUB_DIRS, path_last));
+        Ok(trx.read(&key).await?.map(|prefix| (key, prefix)))
+    }
+
     fn to_absolute_path(&self, sub_path: &[String]) -> Vec<String> {
         let mut path: Vec<String> = Vec::with_capacity(self.path.len() + sub_path.len());
 
@@ -214,7 +248,7 @@ impl DirectoryLayer {
     #[async_recursion]
     async fn create_or_open_internal(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
         prefix: Option<&'async_recursion [u8]>,
         layer: Option<&'async_recursion [u8]>,
@@ -294,7 +328,7 @@ impl DirectoryLayer {
 
     async fn create_internal(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
         layer: Option<&[u8]>,
         prefix: Option<&[u8]>,
@@ -333,7 +367,7 @@ impl DirectoryLayer {
 
     async fn get_parent_node(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
     ) -> Result<Subspace, DirectoryError> {
         return match path.split_last() {
@@ -353,7 +387,7 @@ impl DirectoryLayer {
 
     async fn is_prefix_free(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         prefix: &[u8],
         snapshot: bool,
     ) -> Result<bool, DirectoryError> {
@@ -372,14 +406,14 @@ impl DirectoryLayer {
             self.node_subspace.pack(&strinc(prefix.to_vec())),
         ));
 
-        let result = trx.get_range(&range_option, 1, snapshot).await?;
+        let result = read_range_as(trx, &range_option, 1, snapshot).await?;
 
         Ok(result.is_empty())
     }
 
     async fn node_containing_key(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         key: &[u8],
         snapshot: bool,
     ) -> Result<Option<Subspace>, DirectoryError> {
@@ -398,7 +432,7 @@ impl DirectoryLayer {
         range_option.limit = Some(1);
 
         // checking range
-        let fdb_values = trx.get_range(&range_option, 1, snapshot).await?;
+        let fdb_values = read_range_as(trx, &range_option, 1, snapshot).await?;
 
         match fdb_values.get(0) {
             None => {}
@@ -418,7 +452,7 @@ impl DirectoryLayer {
 
     async fn get_prefix(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         prefix: Option<&[u8]>,
     ) -> Result<Vec<u8>, DirectoryError> {
         match prefix {
@@ -429,7 +463,7 @@ impl DirectoryLayer {
 
                 // checking range
                 let result = trx
-                    .get_range(&RangeOption::from(subspace.range()), 1, false)
+                    .read_range(&RangeOption::from(subspace.range()), 1)
                     .await?;
 
                 if !result.is_empty() {
@@ -445,7 +479,7 @@ impl DirectoryLayer {
     /// `check_version` is checking the Directory's version in FDB.
     async fn check_version(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         allow_creation: bool,
     ) -> Result<(), DirectoryError> {
         let version = self.get_version_value(trx).await?;
@@ -489,7 +523,10 @@ impl DirectoryLayer {
     }
 
     /// `initialize_directory` is initializing the directory
-    async fn initialize_directory(&self, trx: &Transaction) -> Result<(), DirectoryError> {
+    async fn initialize_directory(
+        &self,
+        trx: &dyn MutateTransaction,
+    ) -> Result<(), DirectoryError> {
         let mut value = vec![];
         value.extend(&MAJOR_VERSION.to_le_bytes());
         value.extend(&MINOR_VERSION.to_le_bytes());
@@ -501,16 +538,16 @@ impl DirectoryLayer {
         Ok(())
     }
 
-    async fn get_version_value(&self, trx: &Transaction) -> FdbResult<Option<FdbSlice>> {
+    async fn get_version_value(&self, trx: &dyn MutateTransaction) -> FdbResult<Option<Vec<u8>>> {
         let version_subspace: &[u8] = b"version";
         let version_key = self.root_node.subspace(&version_subspace);
 
-        trx.get(version_key.bytes(), false).await
+        trx.read(version_key.bytes()).await
     }
 
     async fn exists_internal(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
     ) -> Result<bool, DirectoryError> {
         self.check_version(trx, false).await?;
@@ -528,7 +565,7 @@ impl DirectoryLayer {
 
     async fn list_internal(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
     ) -> Result<Vec<String>, DirectoryError> {
         self.check_version(trx, false).await?;
@@ -555,7 +592,7 @@ impl DirectoryLayer {
 
     async fn move_to_internal(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         old_path: &[String],
         new_path: &[String],
     ) -> Result<DirectoryOutput, DirectoryError> {
@@ -627,7 +664,7 @@ impl DirectoryLayer {
 
     async fn remove_from_parent(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
     ) -> Result<(), DirectoryError> {
         let (last_element, parent_path) = path
@@ -648,7 +685,7 @@ impl DirectoryLayer {
     #[async_recursion]
     async fn remove_internal(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
         fail_on_nonexistent: bool,
     ) -> Result<bool, DirectoryError> {
@@ -688,7 +725,7 @@ impl DirectoryLayer {
     #[async_recursion]
     async fn remove_recursive(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         node_sub: &Subspace,
     ) -> Result<(), DirectoryError> {
         let sub_dir = node_sub.subspace(&DEFAULT_SUB_DIRS);
@@ -697,7 +734,7 @@ impl DirectoryLayer {
         loop {
             let range_option = RangeOption::from((begin.as_slice(), end.as_slice()));
 
-            let range = trx.get_range(&range_option, 1024, false).await?;
+            let range = trx.read_range(&range_option, 1024).await?;
             let has_more = range.more();
 
             for row_key in range {
@@ -714,7 +751,8 @@ impl DirectoryLayer {
         let node_prefix: Vec<u8> = self.node_subspace.unpack(node_sub.bytes())?;
 
         trx.clear_range(&node_prefix, &strinc(node_prefix.to_owned()));
-        trx.clear_subspace_range(node_sub);
+        let (begin, end) = node_sub.range();
+        trx.clear_range(&begin, &end);
 
         Ok(())
     }
@@ -724,7 +762,7 @@ impl DirectoryLayer {
 impl Directory for DirectoryLayer {
     async fn create_or_open(
         &self,
-        txn: &Transaction,
+        txn: &dyn MutateTransaction,
         path: &[String],
         prefix: Option<&[u8]>,
         layer: Option<&[u8]>,
@@ -735,7 +773,7 @@ impl Directory for DirectoryLayer {
 
     async fn create(
         &self,
-        txn: &Transaction,
+        txn: &dyn MutateTransaction,
         path: &[String],
         prefix: Option<&[u8]>,
         layer: Option<&[u8]>,
@@ -746,7 +784,7 @@ impl Directory for DirectoryLayer {
 
     async fn open(
         &self,
-        txn: &Transaction,
+        txn: &dyn MutateTransaction,
         path: &[String],
         layer: Option<&[u8]>,
     ) -> Result<DirectoryOutput, DirectoryError> {
@@ -754,13 +792,17 @@ impl Directory for DirectoryLayer {
             .await
     }
 
-    async fn exists(&self, trx: &Transaction, path: &[String]) -> Result<bool, DirectoryError> {
+    async fn exists(
+        &self,
+        trx: &dyn MutateTransaction,
+        path: &[String],
+    ) -> Result<bool, DirectoryError> {
         self.exists_internal(trx, path).await
     }
 
     async fn move_directory(
         &self,
-        _trx: &Transaction,
+        _trx: &dyn MutateTransaction,
         _new_path: &[String],
     ) -> Result<DirectoryOutput, DirectoryError> {
         Err(DirectoryError::CannotMoveRootDirectory)
@@ -773,20 +815,24 @@ impl Directory for DirectoryLayer {
     /// parent directory of newPath does not exist.
     async fn move_to(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         old_path: &[String],
         new_path: &[String],
     ) -> Result<DirectoryOutput, DirectoryError> {
         self.move_to_internal(trx, old_path, new_path).await
     }
 
-    async fn remove(&self, trx: &Transaction, path: &[String]) -> Result<bool, DirectoryError> {
+    async fn remove(
+        &self,
+        trx: &dyn MutateTransaction,
+        path: &[String],
+    ) -> Result<bool, DirectoryError> {
         self.remove_internal(trx, path, true).await
     }
 
     async fn remove_if_exists(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
     ) -> Result<bool, DirectoryError> {
         self.remove_internal(trx, path, false).await
@@ -794,7 +840,7 @@ impl Directory for DirectoryLayer {
 
     async fn list(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
     ) -> Result<Vec<String>, DirectoryError> {
         self.list_internal(trx, path).await
@@ -13,7 +13,7 @@ use crate::directory::directory_subspace::DirectorySubspace;
 use crate::directory::error::DirectoryError;
 use crate::directory::{Directory, DirectoryOutput};
 use crate::tuple::Subspace;
-use crate::Transaction;
+use crate::MutateTransaction;
 use async_trait::async_trait;
 use std::ops::Deref;
 use std::sync::Arc;
@@ -122,7 +122,7 @@ impl DirectoryPartition {
 impl Directory for DirectoryPartition {
     async fn create_or_open(
         &self,
-        txn: &Transaction,
+        txn: &dyn MutateTransaction,
         path: &[String],
         prefix: Option<&[u8]>,
         layer: Option<&[u8]>,
@@ -135,7 +135,7 @@ impl Directory for DirectoryPartition {
 
     async fn create(
         &self,
-        txn: &Transaction,
+        txn: &dyn MutateTransaction,
         path: &[String],
         prefix: Option<&[u8]>,
         layer: Option<&[u8]>,
@@ -148,14 +148,18 @@ impl Directory for DirectoryPartition {
 
     async fn open(
         &self,
-        txn: &Transaction,
+        txn: &dyn MutateTransaction,
         path: &[String],
         layer: Option<&[u8]>,
     ) -> Result<DirectoryOutput, DirectoryError> {
         self.inner.directory_subspace.open(txn, path, layer).await
     }
 
-    async fn exists(&self, trx: &Transaction, path: &[String]) -> Result<bool, DirectoryError> {
+    async fn exists(
+        &self,
+        trx: &dyn MutateTransaction,
+        path: &[String],
+    ) -> Result<bool, DirectoryError> {
         let directory_layer = self.get_directory_layer_for_path(path);
 
         directory_layer
@@ -168,7 +172,7 @@ impl Directory for DirectoryPartition {
 
     async fn move_directory(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         new_path: &[String],
     ) -> Result<DirectoryOutput, DirectoryError> {
         let directory_layer = self.get_directory_layer_for_path(&[]);
@@ -203,7 +207,7 @@ impl Directory for DirectoryPartition {
 
     async fn move_to(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         old_path: &[String],
         new_path: &[String],
     ) -> Result<DirectoryOutput, DirectoryError> {
@@ -213,7 +217,11 @@ impl Directory for DirectoryPartition {
             .await
     }
 
-    async fn remove(&self, trx: &Transaction, path: &[String]) -> Result<bool, DirectoryError> {
+    async fn remove(
+        &self,
+        trx: &dyn MutateTransaction,
+        path: &[String],
+    ) -> Result<bool, DirectoryError> {
         let directory_layer = self.get_directory_layer_for_path(path);
         directory_layer
             .remove(
@@ -225,7 +233,7 @@ impl Directory for DirectoryPartition {
 
     async fn remove_if_exists(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
     ) -> Result<bool, DirectoryError> {
         let directory_layer = self.get_directory_layer_for_path(path);
@@ -239,7 +247,7 @@ impl Directory for DirectoryPartition {
 
     async fn list(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
     ) -> Result<Vec<String>, DirectoryError> {
         self.inner.directory_subspace.list(trx, path).await
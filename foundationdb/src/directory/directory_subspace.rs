@@ -12,7 +12,7 @@ use crate::directory::directory_layer::DirectoryLayer;
 use crate::directory::error::DirectoryError;
 use crate::directory::{Directory, DirectoryOutput};
 use crate::tuple::{PackResult, Subspace, TuplePack, TupleUnpack};
-use crate::Transaction;
+use crate::MutateTransaction;
 use async_trait::async_trait;
 
 /// A `DirectorySubspace` represents the contents of a directory, but it also remembers
@@ -112,7 +112,7 @@ impl DirectorySubspace {
 impl Directory for DirectorySubspace {
     async fn create_or_open(
         &self,
-        txn: &Transaction,
+        txn: &dyn MutateTransaction,
         path: &[String],
         prefix: Option<&[u8]>,
         layer: Option<&[u8]>,
@@ -124,7 +124,7 @@ impl Directory for DirectorySubspace {
 
     async fn create(
         &self,
-        txn: &Transaction,
+        txn: &dyn MutateTransaction,
         path: &[String],
         prefix: Option<&[u8]>,
         layer: Option<&[u8]>,
@@ -136,7 +136,7 @@ impl Directory for DirectorySubspace {
 
     async fn open(
         &self,
-        txn: &Transaction,
+        txn: &dyn MutateTransaction,
         path: &[String],
         layer: Option<&[u8]>,
     ) -> Result<DirectoryOutput, DirectoryError> {
@@ -145,7 +145,11 @@ impl Directory for DirectorySubspace {
             .await
     }
 
-    async fn exists(&self, trx: &Transaction, path: &[String]) -> Result<bool, DirectoryError> {
+    async fn exists(
+        &self,
+        trx: &dyn MutateTransaction,
+        path: &[String],
+    ) -> Result<bool, DirectoryError> {
         let directory_layer = self.get_directory_layer_for_path(path);
 
         directory_layer
@@ -158,7 +162,7 @@ impl Directory for DirectorySubspace {
 
     async fn move_directory(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         new_path: &[String],
     ) -> Result<DirectoryOutput, DirectoryError> {
         let directory_layer = self.get_directory_layer_for_path(&[]);
@@ -193,7 +197,7 @@ impl Directory for DirectorySubspace {
 
     async fn move_to(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         old_path: &[String],
         new_path: &[String],
     ) -> Result<DirectoryOutput, DirectoryError> {
@@ -206,7 +210,11 @@ impl Directory for DirectorySubspace {
             .await
     }
 
-    async fn remove(&self, trx: &Transaction, path: &[String]) -> Result<bool, DirectoryError> {
+    async fn remove(
+        &self,
+        trx: &dyn MutateTransaction,
+        path: &[String],
+    ) -> Result<bool, DirectoryError> {
         let directory_layer = self.get_directory_layer_for_path(path);
         directory_layer
             .remove(
@@ -218,7 +226,7 @@ impl Directory for DirectorySubspace {
 
     async fn remove_if_exists(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
     ) -> Result<bool, DirectoryError> {
         let directory_layer = self.get_directory_layer_for_path(path);
@@ -232,7 +240,7 @@ impl Directory for DirectorySubspace {
 
     async fn list(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
     ) -> Result<Vec<String>, DirectoryError> {
         self.directory_layer
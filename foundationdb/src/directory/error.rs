@@ -59,6 +59,8 @@ pub enum DirectoryError {
     CannotUnpackDirectoryPartition,
     /// cannot get range for the root of a directory partition
     CannotRangeDirectoryPartition,
+    /// the directory of a `DirectoryHandle` was moved or removed since it was opened
+    DirectoryMovedOrRemoved,
     IoError(io::Error),
     FdbError(error::FdbError),
     HcaError(HcaError),
@@ -53,12 +53,12 @@
 //!     Ok(())
 //! }
 //!
-//! // Safe because drop is called before the program exits
-//! let network = unsafe { foundationdb::boot() };
+//! // Safe because the network is stopped when the process exits
+//! let _network = unsafe { foundationdb::boot() };
 //! futures::executor::block_on(async_main()).expect("failed to run");
-//! drop(network);
 //! ```
 
+mod directory_handle;
 mod directory_layer;
 mod directory_partition;
 mod directory_subspace;
@@ -66,9 +66,10 @@ mod error;
 mod node;
 
 use crate::tuple::{PackResult, Subspace, TuplePack, TupleUnpack};
-use crate::Transaction;
+use crate::MutateTransaction;
 use async_trait::async_trait;
 use core::cmp;
+pub use directory_handle::{DatabaseDirectory, DirectoryHandle};
 pub use directory_layer::DirectoryLayer;
 pub use directory_partition::DirectoryPartition;
 pub use directory_subspace::DirectorySubspace;
@@ -76,6 +77,10 @@ pub use error::DirectoryError;
 use std::cmp::Ordering;
 
 /// `Directory` represents a subspace of keys in a FoundationDB database, identified by a hierarchical path.
+///
+/// The methods take a `&dyn MutateTransaction` rather than being generic over the transaction,
+/// so the trait stays object safe: they accept a [`Transaction`](crate::Transaction) as well as
+/// a [`MockTransaction`](crate::mock::MockTransaction).
 #[async_trait]
 pub trait Directory {
     /// Creates or opens the subdirectory of this Directory located at path (creating parent directories, if necessary).
@@ -85,7 +90,7 @@ pub trait Directory {
     /// If you need to create several paths with the same prefix, you **must** use several transactions(See [this link](https://github.com/apple/foundationdb/issues/895#issuecomment-436704180) for context)
     async fn create_or_open(
         &self,
-        txn: &Transaction,
+        txn: &dyn MutateTransaction,
         path: &[String],
         prefix: Option<&[u8]>,
         layer: Option<&[u8]>,
@@ -98,7 +103,7 @@ pub trait Directory {
     /// If you need to create several paths with the same prefix, you **must** use several transactions(See [this link](https://github.com/apple/foundationdb/issues/895#issuecomment-436704180) for context)
     async fn create(
         &self,
-        txn: &Transaction,
+        txn: &dyn MutateTransaction,
         path: &[String],
         prefix: Option<&[u8]>,
         layer: Option<&[u8]>,
@@ -107,42 +112,53 @@ pub trait Directory {
     /// Opens the subdirectory of this Directory located at path.
     async fn open(
         &self,
-        txn: &Transaction,
+        txn: &dyn MutateTransaction,
         path: &[String],
         layer: Option<&[u8]>,
     ) -> Result<DirectoryOutput, DirectoryError>;
 
     /// Checks if the subdirectory of this Directory located at path exists.
-    async fn exists(&self, trx: &Transaction, path: &[String]) -> Result<bool, DirectoryError>;
+    async fn exists(
+        &self,
+        trx: &dyn MutateTransaction,
+        path: &[String],
+    ) -> Result<bool, DirectoryError>;
 
     /// Moves this Directory to the specified newAbsolutePath.
     async fn move_directory(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         new_path: &[String],
     ) -> Result<DirectoryOutput, DirectoryError>;
 
     /// Moves the subdirectory of this Directory located at oldpath to newpath.
     async fn move_to(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         old_path: &[String],
         new_path: &[String],
     ) -> Result<DirectoryOutput, DirectoryError>;
 
     /// Removes the subdirectory of this Directory located at path and all of its subdirectories, as well as all of their contents.
-    async fn remove(&self, trx: &Transaction, path: &[String]) -> Result<bool, DirectoryError>;
+    async fn remove(
+        &self,
+        trx: &dyn MutateTransaction,
+        path: &[String],
+    ) -> Result<bool, DirectoryError>;
 
     /// Removes the subdirectory of this Directory located at path (if the path exists) and all of its subdirectories, as well as all of their contents.
     async fn remove_if_exists(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
     ) -> Result<bool, DirectoryError>;
 
     /// List the subdirectories of this directory at a given subpath.
-    async fn list(&self, trx: &Transaction, path: &[String])
-        -> Result<Vec<String>, DirectoryError>;
+    async fn list(
+        &self,
+        trx: &dyn MutateTransaction,
+        path: &[String],
+    ) -> Result<Vec<String>, DirectoryError>;
 }
 
 pub(crate) fn compare_slice<T: Ord>(a: &[T], b: &[T]) -> cmp::Ordering {
@@ -234,7 +250,7 @@ impl DirectoryOutput {
 impl Directory for DirectoryOutput {
     async fn create_or_open(
         &self,
-        txn: &Transaction,
+        txn: &dyn MutateTransaction,
         path: &[String],
         prefix: Option<&[u8]>,
         layer: Option<&[u8]>,
@@ -251,7 +267,7 @@ impl Directory for DirectoryOutput {
 
     async fn create(
         &self,
-        txn: &Transaction,
+        txn: &dyn MutateTransaction,
         path: &[String],
         prefix: Option<&[u8]>,
         layer: Option<&[u8]>,
@@ -264,7 +280,7 @@ impl Directory for DirectoryOutput {
 
     async fn open(
         &self,
-        txn: &Transaction,
+        txn: &dyn MutateTransaction,
         path: &[String],
         layer: Option<&[u8]>,
     ) -> Result<DirectoryOutput, DirectoryError> {
@@ -274,7 +290,11 @@ impl Directory for DirectoryOutput {
         }
     }
 
-    async fn exists(&self, trx: &Transaction, path: &[String]) -> Result<bool, DirectoryError> {
+    async fn exists(
+        &self,
+        trx: &dyn MutateTransaction,
+        path: &[String],
+    ) -> Result<bool, DirectoryError> {
         match self {
             DirectoryOutput::DirectorySubspace(d) => d.exists(trx, path).await,
             DirectoryOutput::DirectoryPartition(d) => d.exists(trx, path).await,
@@ -283,7 +303,7 @@ impl Directory for DirectoryOutput {
 
     async fn move_directory(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         new_path: &[String],
     ) -> Result<DirectoryOutput, DirectoryError> {
         match self {
@@ -294,7 +314,7 @@ impl Directory for DirectoryOutput {
 
     async fn move_to(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         old_path: &[String],
         new_path: &[String],
     ) -> Result<DirectoryOutput, DirectoryError> {
@@ -304,7 +324,11 @@ impl Directory for DirectoryOutput {
         }
     }
 
-    async fn remove(&self, trx: &Transaction, path: &[String]) -> Result<bool, DirectoryError> {
+    async fn remove(
+        &self,
+        trx: &dyn MutateTransaction,
+        path: &[String],
+    ) -> Result<bool, DirectoryError> {
         match self {
             DirectoryOutput::DirectorySubspace(d) => d.remove(trx, path).await,
             DirectoryOutput::DirectoryPartition(d) => d.remove(trx, path).await,
@@ -313,7 +337,7 @@ impl Directory for DirectoryOutput {
 
     async fn remove_if_exists(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
     ) -> Result<bool, DirectoryError> {
         match self {
@@ -324,7 +348,7 @@ impl Directory for DirectoryOutput {
 
     async fn list(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         path: &[String],
     ) -> Result<Vec<String>, DirectoryError> {
         match self {
@@ -4,8 +4,8 @@ use crate::directory::directory_layer::{
 use crate::directory::error::DirectoryError;
 use crate::directory::DirectoryOutput;
 use crate::tuple::Subspace;
+use crate::MutateTransaction;
 use crate::RangeOption;
-use crate::Transaction;
 
 #[derive(Debug, Clone)]
 pub(super) struct Node {
@@ -19,11 +19,11 @@ pub(super) struct Node {
 impl Node {
     // `load_metadata` is loading extra information for the node, like the layer
     pub(crate) async fn load_metadata(
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
         subspace: &Subspace,
     ) -> Result<Vec<u8>, DirectoryError> {
         let key = subspace.pack(&LAYER_SUFFIX);
-        let layer = match trx.get(&key, false).await {
+        let layer = match trx.read(&key).await {
             Err(err) => return Err(DirectoryError::FdbError(err)),
             Ok(fdb_slice) => fdb_slice.as_deref().unwrap_or_default().to_vec(),
         };
@@ -37,13 +37,13 @@ impl Node {
     /// list sub-folders for a node
     pub(crate) async fn list_sub_folders(
         &self,
-        trx: &Transaction,
+        trx: &dyn MutateTransaction,
     ) -> Result<Vec<String>, DirectoryError> {
         let mut results = vec![];
 
         let range_option = RangeOption::from(&self.subspace.subspace(&DEFAULT_SUB_DIRS));
 
-        let fdb_values = trx.get_range(&range_option, 1_024, false).await?;
+        let fdb_values = trx.read_range(&range_option, 1_024).await?;
 
         for fdb_value in fdb_values {
             let subspace = Subspace::from_bytes(fdb_value.key());
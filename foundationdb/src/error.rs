@@ -76,6 +76,21 @@ impl FdbError {
         self.is_error_predicate(options::ErrorPredicate::RetryableNotCommitted)
     }
 
+    /// Indicates the transaction was rejected because one of its tags is throttled.
+    ///
+    /// `db.run` reports it as a [`TagThrottled`] error holding the tags of the transaction. The
+    /// tags that are currently throttled can be listed with
+    /// [`crate::special_keys::SpecialKeys::throttled_tags`].
+    pub fn is_tag_throttled(self) -> bool {
+        self.error_code == TAG_THROTTLED
+    }
+
+    /// Indicates the operation is not supported by the client library talking to the cluster,
+    /// like `Transaction::get_total_cost` through a client older than 7.3.
+    pub fn is_unsupported_operation(self) -> bool {
+        self.error_code == UNSUPPORTED_OPERATION
+    }
+
     /// Raw foundationdb error code
     pub fn code(self) -> i32 {
         self.error_code
@@ -90,6 +105,12 @@ impl fmt::Display for FdbError {
 
 impl std::error::Error for FdbError {}
 
+/// `tag_throttled` error
+const TAG_THROTTLED: i32 = 1213;
+
+/// `unsupported_operation` error
+const UNSUPPORTED_OPERATION: i32 = 2108;
+
 /// Alias for `Result<..., FdbError>`
 pub type FdbResult<T> = Result<T, FdbError>;
 
@@ -102,6 +123,14 @@ pub enum FdbBindingError {
     PackError(PackError),
     /// A reference to the `RetryableTransaction` has been kept
     ReferenceToTransactionKept,
+    /// A range read with `get_range_all` holds more bytes than allowed
+    RangeTooLarge(RangeTooLarge),
+    /// A key addressed through a `ScopedTransaction` is outside of its subspace
+    OutOfScope(OutOfScope),
+    /// An option parameter was rejected before reaching the client
+    InvalidOptionValue(options::InvalidOptionValue),
+    /// The transaction of `db.run` was still throttled when it ran out of retries
+    TagThrottled(TagThrottled),
     /// A custom error that layer developers can use
     CustomError(Box<dyn std::error::Error + Send + Sync>),
 }
@@ -125,6 +154,7 @@ impl FdbBindingError {
                     None
                 }
             }
+            FdbBindingError::TagThrottled(_) => Some(FdbError::new(TAG_THROTTLED)),
             _ => None,
         }
     }
@@ -148,6 +178,39 @@ impl From<DirectoryError> for FdbBindingError {
     }
 }
 
+impl From<PackError> for FdbBindingError {
+    fn from(e: PackError) -> Self {
+        Self::PackError(e)
+    }
+}
+
+impl From<RangeTooLarge> for FdbBindingError {
+    fn from(e: RangeTooLarge) -> Self {
+        Self::RangeTooLarge(e)
+    }
+}
+
+impl From<TagThrottled> for FdbBindingError {
+    fn from(e: TagThrottled) -> Self {
+        Self::TagThrottled(e)
+    }
+}
+
+impl From<OutOfScope> for FdbBindingError {
+    fn from(e: OutOfScope) -> Self {
+        Self::OutOfScope(e)
+    }
+}
+
+impl From<options::OptionError> for FdbBindingError {
+    fn from(e: options::OptionError) -> Self {
+        match e {
+            options::OptionError::InvalidValue(e) => Self::InvalidOptionValue(e),
+            options::OptionError::Fdb(e) => Self::NonRetryableFdbError(e),
+        }
+    }
+}
+
 impl FdbBindingError {
     /// create a new custom error
     pub fn new_custom_error(e: Box<dyn std::error::Error + Send + Sync>) -> Self {
@@ -165,6 +228,10 @@ impl Debug for FdbBindingError {
             FdbBindingError::ReferenceToTransactionKept => {
                 write!(f, "Reference to transaction kept")
             }
+            FdbBindingError::RangeTooLarge(err) => write!(f, "{:?}", err),
+            FdbBindingError::OutOfScope(err) => write!(f, "{:?}", err),
+            FdbBindingError::InvalidOptionValue(err) => write!(f, "{:?}", err),
+            FdbBindingError::TagThrottled(err) => write!(f, "{:?}", err),
             FdbBindingError::CustomError(err) => write!(f, "{:?}", err),
         }
     }
@@ -177,3 +244,62 @@ impl Display for FdbBindingError {
 }
 
 impl std::error::Error for FdbBindingError {}
+
+/// The error returned by [`ReadTransaction::get_range_all`](crate::ReadTransaction::get_range_all)
+/// when the keys and values of the range exceed its byte cap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeTooLarge {
+    /// The byte cap
+    pub max_bytes: usize,
+    /// The bytes of keys and values read when the cap was exceeded
+    pub read_bytes: usize,
+}
+
+impl Display for RangeTooLarge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "range larger than {} bytes, {} bytes read",
+            self.max_bytes, self.read_bytes
+        )
+    }
+}
+
+impl std::error::Error for RangeTooLarge {}
+
+/// The error returned by a [`ScopedTransaction`](crate::ScopedTransaction) when a key addressed
+/// is outside of its subspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutOfScope {
+    /// The key addressed, in the underlying transaction
+    pub key: Vec<u8>,
+}
+
+impl Display for OutOfScope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "key {:?} is outside of the subspace",
+            crate::Key::from(self.key.as_slice())
+        )
+    }
+}
+
+impl std::error::Error for OutOfScope {}
+
+/// The error returned by [`Database::run`](crate::Database::run) when its transaction is still
+/// rejected with a `tag_throttled` error once it runs out of retries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagThrottled {
+    /// The tags of the transaction, one of them being throttled, see
+    /// [`Transaction::tags`](crate::Transaction::tags)
+    pub tags: Vec<String>,
+}
+
+impl Display for TagThrottled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "transaction throttled, its tags are {:?}", self.tags)
+    }
+}
+
+impl std::error::Error for TagThrottled {}
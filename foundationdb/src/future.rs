@@ -401,6 +401,79 @@ impl DoubleEndedIterator for FdbValuesIter {
     }
 }
 
+/// A batch of keyvalues read by
+/// [`Transaction::get_ranges_batched`](crate::Transaction::get_ranges_batched)
+///
+/// The keyvalues are not copied, they stay in the buffer of the future that read them.
+pub struct RangeBatch {
+    values: FdbValues,
+    index: usize,
+}
+
+impl RangeBatch {
+    pub(crate) fn new(values: FdbValues, index: usize) -> Self {
+        Self { values, index }
+    }
+
+    /// The position of the batch in the stream, starting at 0
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// `true` if there is another batch after this one
+    pub fn more(&self) -> bool {
+        self.values.more()
+    }
+
+    /// The first key of the batch, in the order of the read, `None` if it is empty
+    pub fn first_key(&self) -> Option<&[u8]> {
+        self.values.first().map(FdbKeyValue::key)
+    }
+
+    /// The last key of the batch, in the order of the read, `None` if it is empty
+    pub fn last_key(&self) -> Option<&[u8]> {
+        self.values.last().map(FdbKeyValue::key)
+    }
+
+    /// The keyvalues of the batch
+    pub fn values(&self) -> &FdbValues {
+        &self.values
+    }
+
+    /// Gives back the keyvalues of the batch
+    pub fn into_values(self) -> FdbValues {
+        self.values
+    }
+}
+
+impl Deref for RangeBatch {
+    type Target = [FdbKeyValue];
+    fn deref(&self) -> &Self::Target {
+        self.values.deref()
+    }
+}
+impl AsRef<[FdbKeyValue]> for RangeBatch {
+    fn as_ref(&self) -> &[FdbKeyValue] {
+        self.deref()
+    }
+}
+impl<'a> IntoIterator for &'a RangeBatch {
+    type Item = &'a FdbKeyValue;
+    type IntoIter = std::slice::Iter<'a, FdbKeyValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.deref().iter()
+    }
+}
+impl IntoIterator for RangeBatch {
+    type Item = FdbValue;
+    type IntoIter = FdbValuesIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter()
+    }
+}
+
 /// A keyvalue you can own
 ///
 /// Until dropped, this might prevent multiple key/values from beeing freed.
@@ -493,7 +566,8 @@ impl TryFrom<FdbFutureHandle> for i64 {
                 feature = "fdb-6_2",
                 feature = "fdb-6_3",
                 feature = "fdb-7_0",
-                feature = "fdb-7_1"
+                feature = "fdb-7_1",
+                feature = "fdb-7_3"
             ))]
             {
                 fdb_sys::fdb_future_get_int64(f.as_ptr(), &mut version)
@@ -502,7 +576,8 @@ impl TryFrom<FdbFutureHandle> for i64 {
                 feature = "fdb-6_2",
                 feature = "fdb-6_3",
                 feature = "fdb-7_0",
-                feature = "fdb-7_1"
+                feature = "fdb-7_1",
+                feature = "fdb-7_3"
             )))]
             {
                 fdb_sys::fdb_future_get_version(f.as_ptr(), &mut version)
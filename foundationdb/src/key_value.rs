@@ -0,0 +1,213 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Owned keys and values, printed readably in logs and assert failures.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::ops::Deref;
+
+use crate::tuple::printable::Printable;
+use crate::tuple::{pack, unpack, Element};
+
+/// An owned key
+///
+/// It derefs to `[u8]` and converts from and into `Vec<u8>`. It is displayed as a tuple when it
+/// is the canonical encoding of one, like `("app", 1)`, and in the printable escape format of
+/// `fdbcli`, like `\x02app\x00\xff`, otherwise.
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Key(Vec<u8>);
+
+/// An owned value
+///
+/// It derefs to `[u8]` and converts from and into `Vec<u8>`. It is displayed in the printable
+/// escape format of `fdbcli`, like `\x15\x01`.
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Value(Vec<u8>);
+
+impl Key {
+    /// Returns the tuple this key is the canonical encoding of, if any.
+    fn as_tuple(&self) -> Option<Element<'static>> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let elements: Vec<Element> = unpack(&self.0).ok()?;
+        if pack(&elements) != self.0 {
+            return None;
+        }
+        Some(Element::Tuple(
+            elements.into_iter().map(Element::into_owned).collect(),
+        ))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.as_tuple() {
+            Some(tuple) => fmt::Display::fmt(&tuple, f),
+            None => fmt::Display::fmt(&Printable(&self.0), f),
+        }
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.as_tuple() {
+            Some(tuple) => write!(f, "Key({})", tuple),
+            None => write!(f, "Key(\"{}\")", Printable(&self.0)),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&Printable(&self.0), f)
+    }
+}
+
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Value(\"{}\")", Printable(&self.0))
+    }
+}
+
+macro_rules! impl_bytes {
+    ($name: ident) => {
+        impl $name {
+            /// Returns the bytes.
+            pub fn as_bytes(&self) -> &[u8] {
+                &self.0
+            }
+
+            /// Converts into the bytes.
+            pub fn into_bytes(self) -> Vec<u8> {
+                self.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = [u8];
+
+            fn deref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl Borrow<[u8]> for $name {
+            fn borrow(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl From<Vec<u8>> for $name {
+            fn from(bytes: Vec<u8>) -> Self {
+                $name(bytes)
+            }
+        }
+
+        impl From<&[u8]> for $name {
+            fn from(bytes: &[u8]) -> Self {
+                $name(bytes.to_vec())
+            }
+        }
+
+        impl<const N: usize> From<&[u8; N]> for $name {
+            fn from(bytes: &[u8; N]) -> Self {
+                $name(bytes.to_vec())
+            }
+        }
+
+        impl From<$name> for Vec<u8> {
+            fn from(bytes: $name) -> Self {
+                bytes.0
+            }
+        }
+
+        impl PartialEq<[u8]> for $name {
+            fn eq(&self, other: &[u8]) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&[u8]> for $name {
+            fn eq(&self, other: &&[u8]) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl<const N: usize> PartialEq<&[u8; N]> for $name {
+            fn eq(&self, other: &&[u8; N]) -> bool {
+                self.0 == other[..]
+            }
+        }
+
+        impl<const N: usize> PartialEq<[u8; N]> for $name {
+            fn eq(&self, other: &[u8; N]) -> bool {
+                self.0 == other[..]
+            }
+        }
+
+        impl PartialEq<Vec<u8>> for $name {
+            fn eq(&self, other: &Vec<u8>) -> bool {
+                &self.0 == other
+            }
+        }
+    };
+}
+
+impl_bytes!(Key);
+impl_bytes!(Value);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug() {
+        let key = Key::from(pack(&("app", 1, b"\x00\xff".to_vec(), -1.5)));
+        assert_eq!(
+            format!("{:?}", key),
+            r#"Key(("app", 1, b"\x00\xff", -1.5))"#
+        );
+        assert_eq!(key.to_string(), r#"("app", 1, b"\x00\xff", -1.5)"#);
+
+        let value = Value::from(b"\x00\x01hi\\\xff");
+        assert_eq!(format!("{:?}", value), r#"Value("\x00\x01hi\\\xff")"#);
+        assert_eq!(value.to_string(), r"\x00\x01hi\\\xff");
+
+        // not tuples, or not their canonical encoding
+        for (bytes, debug) in [
+            (&b""[..], r#"Key("")"#),
+            (b"\xff/metadata", r#"Key("\xff/metadata")"#),
+            (b"\x15\x00", r#"Key("\x15\x00")"#),
+        ] {
+            assert_eq!(format!("{:?}", Key::from(bytes)), debug);
+        }
+    }
+
+    #[test]
+    fn conversions() {
+        let key = Key::from(vec![1, 2]);
+        assert_eq!(key, &[1u8, 2]);
+        assert_eq!(key.len(), 2);
+        assert_eq!(key.as_ref(), &[1, 2]);
+        assert_eq!(Vec::from(key.clone()), vec![1, 2]);
+        let lower = Key::from(vec![1]);
+        assert!(lower < key);
+
+        let set: std::collections::HashSet<Key> = [key].into_iter().collect();
+        assert!(set.contains(&[1u8, 2][..]));
+    }
+}
@@ -9,7 +9,8 @@
 //! A `KeySelector` identifies a particular key in the database.
 
 use crate::tuple::Bytes;
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use core::ops::{Add, Sub};
 
 /// A `KeySelector` identifies a particular key in the database.
 ///
@@ -29,6 +30,10 @@ use std::borrow::Cow;
 /// - `last_less_or_equal`
 /// - `first_greater_than`
 /// - `first_greater_or_equal`
+///
+/// A selector can then be moved by a number of keys, by adding or subtracting an offset:
+/// `KeySelector::first_greater_or_equal(key) + 2` picks the third key greater than or equal to
+/// `key`.
 #[derive(Clone, Debug)]
 pub struct KeySelector<'a> {
     key: Bytes<'a>,
@@ -81,6 +86,7 @@ impl<'a> KeySelector<'a> {
         Self::new(key.into(), false, 1)
     }
 
+    #[cfg(feature = "client")]
     fn make_key(&mut self, key: &[u8]) {
         match &mut self.key {
             Bytes(Cow::Borrowed(..)) => self.key = Bytes::from(key.to_owned()),
@@ -91,15 +97,54 @@ impl<'a> KeySelector<'a> {
         };
     }
 
+    #[cfg(feature = "client")]
     pub(crate) fn make_first_greater_or_equal(&mut self, key: &[u8]) {
         self.make_key(key);
         self.or_equal = false;
         self.offset = 1;
     }
 
+    #[cfg(feature = "client")]
     pub(crate) fn make_first_greater_than(&mut self, key: &[u8]) {
         self.make_key(key);
         self.or_equal = true;
         self.offset = 1;
     }
 }
+
+impl<'a> Add<i32> for KeySelector<'a> {
+    type Output = Self;
+
+    /// Moves the selector `offset` keys forward, the offset saturating at `i32::MAX`
+    fn add(mut self, offset: i32) -> Self {
+        self.offset = self.offset.saturating_add(offset);
+        self
+    }
+}
+
+impl<'a> Sub<i32> for KeySelector<'a> {
+    type Output = Self;
+
+    /// Moves the selector `offset` keys backward, the offset saturating at `i32::MIN`
+    fn sub(mut self, offset: i32) -> Self {
+        self.offset = self.offset.saturating_sub(offset);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_arithmetic() {
+        let selector = KeySelector::first_greater_than(&b"key"[..]) + 3 - 1;
+        assert_eq!(
+            (selector.key(), selector.or_equal(), selector.offset()),
+            (&b"key"[..], true, 3)
+        );
+        // the offset saturates instead of overflowing
+        assert_eq!((selector.clone() + i32::MAX).offset(), i32::MAX);
+        assert_eq!((selector - i32::MAX - i32::MAX).offset(), i32::MIN);
+    }
+}
@@ -5,77 +5,251 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 #![doc = include_str!("../README.md")]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+extern crate alloc;
+
+#[cfg(all(
+    feature = "tenant-experimental",
+    not(any(feature = "fdb-7_1", feature = "fdb-7_3"))
+))]
+compile_error!("the `tenant-experimental` feature requires the `fdb-7_1` or `fdb-7_3` feature");
+
+#[cfg(feature = "client")]
 #[macro_use]
 extern crate static_assertions;
 
+#[cfg(feature = "client")]
 pub mod api;
-#[cfg(any(feature = "fdb-5_1", feature = "fdb-5_2", feature = "fdb-6_0"))]
+#[cfg(feature = "fdb-7_3")]
+#[deny(missing_docs)]
+pub mod client_status;
+#[cfg(all(
+    feature = "client",
+    not(any(
+        feature = "fdb-6_1",
+        feature = "fdb-6_2",
+        feature = "fdb-6_3",
+        feature = "fdb-7_0",
+        feature = "fdb-7_1",
+        feature = "fdb-7_3"
+    ))
+))]
 pub mod cluster;
+#[cfg(feature = "client")]
+pub mod counter;
+#[cfg(feature = "client")]
 mod database;
+#[cfg(feature = "client")]
 pub mod directory;
+#[cfg(feature = "client")]
 mod error;
-#[cfg(any(feature = "fdb-7_0", feature = "fdb-7_1"))]
+#[cfg(any(feature = "fdb-7_0", feature = "fdb-7_1", feature = "fdb-7_3"))]
 #[deny(missing_docs)]
 pub mod fdb_keys;
+#[cfg(feature = "client")]
 pub mod future;
+mod key_value;
 mod keyselector;
-#[cfg(feature = "fdb-7_1")]
+#[cfg(feature = "client")]
+pub mod locality;
+#[cfg(feature = "client")]
+#[doc(hidden)]
+pub mod macro_support;
+#[cfg(any(feature = "fdb-7_1", feature = "fdb-7_3"))]
 #[deny(missing_docs)]
 pub mod mapped_key_values;
+#[cfg(feature = "client")]
+#[deny(missing_docs)]
+pub mod mock;
 /// Generated configuration types for use with the various `set_option` functions
+#[cfg(feature = "client")]
 #[allow(clippy::all)]
 pub mod options;
-#[cfg(any(feature = "fdb-7_1", feature = "tenant-experimental"))]
+#[cfg(feature = "client")]
+mod scoped;
+#[cfg(any(
+    feature = "fdb-6_3",
+    feature = "fdb-7_0",
+    feature = "fdb-7_1",
+    feature = "fdb-7_3"
+))]
+pub mod special_keys;
+#[cfg(any(
+    feature = "fdb-7_1",
+    feature = "fdb-7_3",
+    feature = "tenant-experimental"
+))]
 pub mod tenant;
+#[cfg(feature = "client")]
+#[deny(missing_docs)]
+pub mod testing;
+#[cfg(feature = "client")]
+mod traits;
+#[cfg(feature = "client")]
 mod transaction;
 pub mod tuple;
+#[cfg(feature = "client")]
+pub mod watch;
 
-#[cfg(any(feature = "fdb-5_1", feature = "fdb-5_2", feature = "fdb-6_0"))]
+#[cfg(all(
+    feature = "client",
+    not(any(
+        feature = "fdb-6_1",
+        feature = "fdb-6_2",
+        feature = "fdb-6_3",
+        feature = "fdb-7_0",
+        feature = "fdb-7_1",
+        feature = "fdb-7_3"
+    ))
+))]
 pub use crate::cluster::Cluster;
 
+#[cfg(feature = "client")]
 pub use crate::database::*;
+#[cfg(feature = "client")]
 pub use crate::error::FdbBindingError;
+#[cfg(feature = "client")]
 pub use crate::error::FdbError;
+#[cfg(feature = "client")]
 pub use crate::error::FdbResult;
+#[cfg(feature = "client")]
+pub use crate::error::OutOfScope;
+#[cfg(feature = "client")]
+pub use crate::error::RangeTooLarge;
+#[cfg(feature = "client")]
+pub use crate::error::TagThrottled;
+pub use crate::key_value::{Key, Value};
 pub use crate::keyselector::*;
+#[cfg(feature = "client")]
+pub use crate::scoped::ScopedTransaction;
+#[cfg(feature = "client")]
+pub use crate::traits::*;
+#[cfg(feature = "client")]
 pub use crate::transaction::*;
 
-/// Initialize the FoundationDB Client API, this can only be called once per process.
+/// Turns an `async fn main(db: Database)` into the `main` of a program, with the `macros`
+/// feature.
+///
+/// The generated `main` boots the network with the API version selected by the `fdb-*`
+/// features, opens the database of the default cluster file, or of `FDB_CLUSTER_FILE`, runs the
+/// body on a minimal built-in executor and stops the network once it returns, even if it
+/// panics. `#[foundationdb::main(tokio)]` runs it on a multi-threaded tokio runtime instead,
+/// which requires a dependency on `tokio` with the `rt-multi-thread` feature. The function can
+/// return anything `main` can.
+///
+/// ```no_run
+/// use foundationdb::{Database, FdbResult};
+///
+/// #[foundationdb::main]
+/// async fn main(db: Database) -> FdbResult<()> {
+///     let trx = db.create_trx()?;
+///     trx.set(b"hello", b"world");
+///     trx.commit().await?;
+///     Ok(())
+/// }
+/// ```
+///
+/// Misuses are reported at compile time:
+///
+/// ```compile_fail
+/// #[foundationdb::main]
+/// fn main(db: foundationdb::Database) {} // the `async` keyword is missing
+/// ```
+///
+/// ```compile_fail
+/// #[foundationdb::main]
+/// async fn main() {} // the database argument is missing
+/// ```
+#[cfg(feature = "macros")]
+pub use foundationdb_macros::main;
+
+/// Initialize the FoundationDB Client API, or share its network if it is already running.
 ///
 /// # Returns
 ///
-/// A `NetworkAutoStop` handle which must be dropped before the program exits.
+/// A `NetworkAutoStop` handle on the network. Calling `boot` again returns another one sharing
+/// the same network, which keeps running when they are dropped, until the process exits or
+/// [`api::shutdown_forever`] is called. See [`try_boot`] to boot after that without panicking.
 ///
 /// # Safety
 ///
-/// You *MUST* ensure drop is called on the returned object before the program exits.
+/// The network *MUST* be stopped before the program exits. It is stopped by an exit handler
+/// when the process exits normally, e.g. when `main` returns, or by [`api::shutdown_forever`].
 /// This is not required if the program is aborted.
 ///
-/// This method used to be safe in version `0.4`. But because `drop` on the returned object
-/// might not be called before the program exits, it was found unsafe.
+/// This method used to be safe in version `0.4`. But because the network might not be stopped
+/// before the program exits, it was found unsafe.
+///
+/// # Panics
+///
+/// Panics if the loaded libfdb_c is not compatible with the compiled API version,
+/// see [`api::check_compatibility`], if the network fails to start, or if it was already
+/// stopped, which cannot be undone in a process.
 ///
 /// # Examples
 ///
 /// ```rust
-/// let network = unsafe { foundationdb::boot() };
+/// // Safe because the network is stopped when the process exits
+/// let _network = unsafe { foundationdb::boot() };
 /// // do some interesting things with the API...
-/// drop(network);
 /// ```
 ///
 /// ```rust
 /// #[tokio::main]
 /// async fn main() {
-///     let network = unsafe { foundationdb::boot() };
+///     // Safe because the network is stopped when the process exits
+///     let _network = unsafe { foundationdb::boot() };
 ///     // do some interesting things with the API...
-///     drop(network);
 /// }
 /// ```
+#[cfg(feature = "client")]
 pub unsafe fn boot() -> api::NetworkAutoStop {
-    let network_builder = api::FdbApiBuilder::default()
-        .build()
-        .expect("foundationdb API to be initialized");
-    network_builder.boot().expect("fdb network running")
+    try_boot().unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Like [`boot`], but fails if the network fails to start, or was already stopped in this
+/// process.
+///
+/// # Safety
+///
+/// See [`boot`].
+///
+/// # Examples
+///
+/// ```rust
+/// use foundationdb::api::BootError;
+///
+/// let network = unsafe { foundationdb::try_boot() }.unwrap();
+/// drop(network);
+/// // the network is still running
+/// let _network = unsafe { foundationdb::try_boot() }.unwrap();
+/// foundationdb::api::shutdown_forever();
+/// assert!(matches!(
+///     unsafe { foundationdb::try_boot() },
+///     Err(BootError::Stopped(_))
+/// ));
+/// ```
+#[cfg(feature = "client")]
+pub unsafe fn try_boot() -> Result<api::NetworkAutoStop, api::BootError> {
+    api::share_or_boot(|| {
+        // reported on failure, several clients may be installed
+        let loaded_from = || match api::client_library_path() {
+            Some(path) => format!(" (libfdb_c loaded from {})", path.display()),
+            None => String::new(),
+        };
+        let api_builder = api::FdbApiBuilder::default();
+        if let Err(mismatch) = api_builder.check_compatibility() {
+            panic!("{}{}", mismatch, loaded_from());
+        }
+        api_builder.build().unwrap_or_else(|err| {
+            panic!(
+                "foundationdb API to be initialized: {}{}",
+                err,
+                loaded_from()
+            )
+        })
+    })
 }
 
 /// Returns the default Fdb cluster configuration file path
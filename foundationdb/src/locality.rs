@@ -0,0 +1,87 @@
+// Copyright 2018 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+// Copyright 2013-2018 Apple, Inc and the FoundationDB project authors.
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Where keys are stored in the cluster, to split work along shard boundaries.
+//!
+//! Like the locality API of the other bindings, those helpers are meant to plan work, like a
+//! parallel export, and give no transactional guarantee: shards move while they are read.
+
+use futures::TryStreamExt;
+
+use crate::options::TransactionOption;
+use crate::transaction::key_after;
+use crate::{Database, FdbResult, RangeOption, Transaction};
+
+/// Prefix of the system keys mapping each shard, by its first key, to its storage servers
+const KEY_SERVERS_PREFIX: &[u8] = b"\xff/keyServers/";
+
+/// transaction_too_old
+const TRANSACTION_TOO_OLD: i32 = 1007;
+
+fn key_servers_key(key: &[u8]) -> Vec<u8> {
+    let mut system_key = Vec::with_capacity(KEY_SERVERS_PREFIX.len() + key.len());
+    system_key.extend_from_slice(KEY_SERVERS_PREFIX);
+    system_key.extend_from_slice(key);
+    system_key
+}
+
+/// Returns the public network addresses, as `host:port` strings possibly followed by `:tls`, of
+/// the storage servers responsible for `key`, see [`Transaction::get_addresses_for_key`].
+pub async fn get_addresses_for_key(trx: &Transaction, key: &[u8]) -> FdbResult<Vec<String>> {
+    let addresses = trx.get_addresses_for_key(key).await?;
+    Ok(addresses
+        .iter()
+        .map(|address| address.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Returns the first keys of the shards starting in `begin..end`, in order.
+///
+/// The first boundary is `begin` itself only if a shard starts there. Together with `begin` and
+/// `end`, the boundaries split the range into pieces each stored by a single team of storage
+/// servers. The system keys are read with snapshot reads, in as many transactions as needed:
+/// when a long scan gets too old, it carries on in a new transaction from the last boundary
+/// read, so the boundaries may not all come from the same version.
+pub async fn get_boundary_keys(db: &Database, begin: &[u8], end: &[u8]) -> FdbResult<Vec<Vec<u8>>> {
+    let mut boundaries = Vec::new();
+    let mut begin = begin.to_vec();
+    let mut trx = db.create_trx()?;
+    loop {
+        let last_begin = begin.clone();
+        match read_boundary_keys(&trx, &mut begin, end, &mut boundaries).await {
+            Ok(()) => return Ok(boundaries),
+            // the boundaries read so far are kept, the scan goes on at a newer version
+            Err(err) if err.code() == TRANSACTION_TOO_OLD && begin != last_begin => {
+                trx = db.create_trx()?;
+            }
+            Err(err) => trx = trx.on_error(err).await?,
+        }
+    }
+}
+
+/// Reads the boundaries from `begin` to `end`, moving `begin` past each boundary read
+async fn read_boundary_keys(
+    trx: &Transaction,
+    begin: &mut Vec<u8>,
+    end: &[u8],
+    boundaries: &mut Vec<Vec<u8>>,
+) -> FdbResult<()> {
+    if begin.as_slice() >= end {
+        return Ok(());
+    }
+    trx.set_option(TransactionOption::AccessSystemKeys)?;
+    trx.set_option(TransactionOption::ReadLockAware)?;
+    let opt = RangeOption::from((key_servers_key(begin), key_servers_key(end)));
+    let mut values = trx.get_ranges_keyvalues(opt, true);
+    while let Some(value) = values.try_next().await? {
+        let boundary = &value.key()[KEY_SERVERS_PREFIX.len()..];
+        boundaries.push(boundary.to_vec());
+        *begin = key_after(boundary);
+    }
+    Ok(())
+}
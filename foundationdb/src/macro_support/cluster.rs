@@ -0,0 +1,209 @@
+// Copyright 2018 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Provisioning of a throwaway single process cluster.
+//!
+//! When `FDB_BINARY_PATH` is set to the directory holding the `fdbserver` binary, tests run
+//! against a cluster started in a temporary directory instead of the one described by the
+//! default cluster file. `fdbcli` is looked up in the same directory, then in the `PATH`. The
+//! cluster is started by the first test needing it, shared by all the tests of the test binary,
+//! and torn down when the binary exits, even if tests panicked.
+//!
+//! When no cluster is available at all, the tests written with `#[foundationdb::testing::test]`
+//! fail, unless they run on Windows, where often only the client is installed, or
+//! `FDB_TEST_SKIP_UNAVAILABLE` is set: they are skipped instead, see [`may_skip`].
+
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use std::{env, fs, thread};
+
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+
+const BINARY_PATH_VAR: &str = "FDB_BINARY_PATH";
+const SKIP_UNAVAILABLE_VAR: &str = "FDB_TEST_SKIP_UNAVAILABLE";
+const AVAILABILITY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The cluster shared by the tests, never dropped but stopped by [`stop_shared`]
+static SHARED: Mutex<Option<&'static TestCluster>> = Mutex::new(None);
+
+/// A running `fdbserver`, killed and wiped when dropped.
+pub struct TestCluster {
+    fdbcli: PathBuf,
+    dir: PathBuf,
+    cluster_file: PathBuf,
+    server: Mutex<Child>,
+}
+
+impl TestCluster {
+    /// Starts a new cluster with the binaries found in `bin_dir`, and waits for it to be
+    /// available.
+    pub fn start(bin_dir: &Path) -> Self {
+        let id: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect();
+        let dir = env::temp_dir().join(format!("foundationdb-rs-test-{}", id));
+        let data_dir = dir.join("data");
+        let log_dir = dir.join("logs");
+        fs::create_dir_all(&data_dir).expect("could not create the data directory");
+        fs::create_dir_all(&log_dir).expect("could not create the log directory");
+
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("could not find a free port")
+            .port();
+        let address = format!("127.0.0.1:{}", port);
+        let cluster_file = dir.join("fdb.cluster");
+        fs::write(&cluster_file, format!("test{}:{}@{}\n", id, id, address))
+            .expect("could not write the cluster file");
+
+        let server = Command::new(bin_dir.join(binary("fdbserver")))
+            .arg("--public_address")
+            .arg(&address)
+            .arg("--listen_address")
+            .arg(&address)
+            .arg("--cluster_file")
+            .arg(&cluster_file)
+            .arg("--datadir")
+            .arg(&data_dir)
+            .arg("--logdir")
+            .arg(&log_dir)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("could not start fdbserver");
+        // from now on, the server is stopped and the directory removed even if provisioning fails
+        let fdbcli = bin_dir.join(binary("fdbcli"));
+        let cluster = TestCluster {
+            fdbcli: if fdbcli.exists() {
+                fdbcli
+            } else {
+                PathBuf::from("fdbcli")
+            },
+            dir,
+            cluster_file,
+            server: Mutex::new(server),
+        };
+
+        #[allow(unused_mut)]
+        let mut configure = String::from("configure new single memory");
+        #[cfg(feature = "tenant-experimental")]
+        configure.push_str(" tenant_mode=optional_experimental");
+        // a timed out attempt may still have created the database
+        cluster.wait_for(&configure, |success, output| {
+            success || output.contains("already exists")
+        });
+        cluster.wait_for("status minimal", |success, output| {
+            success && output.contains("The database is available")
+        });
+        cluster
+    }
+
+    /// Returns the path of the cluster file describing this cluster.
+    pub fn cluster_file(&self) -> &Path {
+        &self.cluster_file
+    }
+
+    /// Runs `command` with fdbcli until its exit status and output are accepted.
+    fn wait_for(&self, command: &str, accept: impl Fn(bool, &str) -> bool) {
+        let deadline = Instant::now() + AVAILABILITY_TIMEOUT;
+        loop {
+            let output = Command::new(&self.fdbcli)
+                .arg("-C")
+                .arg(&self.cluster_file)
+                .arg("--timeout")
+                .arg("5")
+                .arg("--exec")
+                .arg(command)
+                .output()
+                .expect("could not run fdbcli");
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            if accept(output.status.success(), &stdout) {
+                return;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "`{}` did not succeed within {:?}: {}",
+                command,
+                AVAILABILITY_TIMEOUT,
+                stdout
+            );
+            thread::sleep(Duration::from_millis(200));
+        }
+    }
+
+    /// Kills the server and removes its directory, ignoring failures.
+    fn stop(&self) {
+        let mut server = self.server.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = server.kill();
+        let _ = server.wait();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+impl Drop for TestCluster {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// The file name of a binary, `fdbserver.exe` on Windows.
+fn binary(name: &str) -> String {
+    format!("{}{}", name, env::consts::EXE_SUFFIX)
+}
+
+/// Returns whether a cluster is available to the tests: one can be started from
+/// `FDB_BINARY_PATH`, or the cluster file in `FDB_CLUSTER_FILE` or at its default location exists.
+pub fn available() -> bool {
+    env::var_os(BINARY_PATH_VAR).is_some()
+        || env::var_os("FDB_CLUSTER_FILE")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(crate::default_config_path()))
+            .exists()
+}
+
+/// Returns whether the tests are skipped rather than failed when no cluster is [`available`]: on
+/// Windows, or when `FDB_TEST_SKIP_UNAVAILABLE` is set.
+pub fn may_skip() -> bool {
+    cfg!(windows) || env::var_os(SKIP_UNAVAILABLE_VAR).is_some()
+}
+
+/// Returns the cluster shared by the tests, starting it if needed.
+///
+/// Returns `None` when `FDB_BINARY_PATH` is not set, tests are then expected to run against the
+/// cluster of the default cluster file.
+pub fn shared() -> Option<&'static TestCluster> {
+    let bin_dir = env::var_os(BINARY_PATH_VAR)?;
+    let mut shared = SHARED.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(cluster) = *shared {
+        return Some(cluster);
+    }
+    let cluster = Box::leak(Box::new(TestCluster::start(Path::new(&bin_dir))));
+    *shared = Some(cluster);
+    crate::api::at_exit(stop_shared);
+    Some(cluster)
+}
+
+/// Stops the shared cluster when the test binary exits, after the network registered later.
+extern "C" fn stop_shared() {
+    if let Ok(shared) = SHARED.try_lock() {
+        if let Some(cluster) = *shared {
+            cluster.stop();
+        }
+    }
+}
+
+/// Returns the cluster file of the shared cluster if it is running.
+pub fn cluster_file() -> Option<String> {
+    let shared = SHARED.lock().unwrap_or_else(PoisonError::into_inner);
+    shared.map(|cluster| cluster.cluster_file().to_string_lossy().into_owned())
+}
@@ -0,0 +1,168 @@
+// Copyright 2018 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Functions called by the code generated by `#[foundationdb::main]` and
+//! `#[foundationdb::testing::test]`, not meant to be called directly.
+//!
+//! The integration tests of this repository use them too, to run against the same cluster.
+
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+
+use crate::api::{check_not_network_thread, NetworkAutoStop};
+use crate::tuple::Subspace;
+use crate::{Database, FdbResult};
+
+pub mod cluster;
+
+/// A future run by the executor given to the generated code
+pub type BoxedFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// The built-in executor, which runs the future on the current thread
+///
+/// # Panics
+///
+/// Panics with [`WouldDeadlock`](crate::api::WouldDeadlock) on the network thread.
+pub fn block_on<T>(future: BoxedFuture<'_, T>) -> T {
+    if let Err(err) = check_not_network_thread() {
+        panic!("cannot run the future: {}", err);
+    }
+    futures::executor::block_on(future)
+}
+
+/// Boots the network, opens the database of the default cluster file and runs `main` with it.
+///
+/// The network is stopped when the process exits, once `main` returns or panics.
+pub fn run_main<F, Fut, T, E>(main: F, executor: E) -> T
+where
+    F: FnOnce(Database) -> Fut,
+    Fut: Future<Output = T>,
+    E: FnOnce(BoxedFuture<'_, T>) -> T,
+{
+    // Safe because the network is stopped when the process exits
+    let _network = unsafe { crate::boot() };
+    executor(Box::pin(async move {
+        let db = Database::new_compat(None)
+            .await
+            .expect("failed to open the database");
+        main(db).await
+    }))
+}
+
+/// Opens the database the tests run against: the one of the shared test cluster if one is
+/// provisioned, see [`cluster`], or the one of the default cluster file.
+pub async fn database() -> FdbResult<Database> {
+    Database::new_compat(cluster::cluster_file().as_deref()).await
+}
+
+/// Starts the shared test cluster if one is provisioned, then boots the network.
+///
+/// Both keep running until the test binary exits.
+pub fn boot() -> NetworkAutoStop {
+    cluster::shared();
+    // Safe because the network is stopped when the process exits
+    unsafe { crate::boot() }
+}
+
+/// Runs the test `name` with the database the tests run against, see [`database`], and a
+/// subspace of its own, which is cleared before and after it runs, even if it panics.
+///
+/// When no cluster is available, the test fails, or is skipped if [`cluster::may_skip`].
+pub fn run_test<F, Fut, R, E>(name: &str, test: F, executor: E)
+where
+    F: FnOnce(Database, Subspace) -> Fut,
+    Fut: Future<Output = R>,
+    R: TestResult,
+    E: FnOnce(BoxedFuture<'_, ()>),
+{
+    if !cluster::available() {
+        assert!(
+            cluster::may_skip(),
+            "no FoundationDB cluster is available for {}: set FDB_BINARY_PATH or FDB_CLUSTER_FILE, \
+             or FDB_TEST_SKIP_UNAVAILABLE to skip the tests",
+            name
+        );
+        eprintln!("skipping {}: no FoundationDB cluster is available", name);
+        return;
+    }
+    let _network = boot();
+
+    if let Err(err) = check_not_network_thread() {
+        panic!("cannot run {}: {}", name, err);
+    }
+    // created outside of the test executor, so it can be cleared while unwinding
+    let keyspace =
+        futures::executor::block_on(Keyspace::new(name)).expect("failed to reserve a keyspace");
+    let subspace = keyspace.subspace.clone();
+    executor(Box::pin(async move {
+        let db = database().await.expect("failed to open the database");
+        test(db, subspace).await.check();
+    }));
+}
+
+/// What a test function may return
+pub trait TestResult {
+    /// Panics if the test failed
+    fn check(self);
+}
+
+impl TestResult for () {
+    fn check(self) {}
+}
+
+impl<E: Debug> TestResult for Result<(), E> {
+    fn check(self) {
+        if let Err(err) = self {
+            panic!("the test failed: {:?}", err);
+        }
+    }
+}
+
+/// The subspace reserved for a single test, cleared once it is done
+struct Keyspace {
+    db: Database,
+    subspace: Subspace,
+}
+
+impl Keyspace {
+    async fn new(name: &str) -> FdbResult<Self> {
+        let id: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(8)
+            .map(char::from)
+            .collect();
+        let keyspace = Keyspace {
+            db: database().await?,
+            subspace: Subspace::from(("foundationdb_test", name, id)),
+        };
+        keyspace.clear().await?;
+        Ok(keyspace)
+    }
+
+    async fn clear(&self) -> FdbResult<()> {
+        let trx = self.db.create_trx()?;
+        trx.clear_subspace_range(&self.subspace);
+        trx.commit().await?;
+        Ok(())
+    }
+}
+
+impl Drop for Keyspace {
+    fn drop(&mut self) {
+        if let Err(err) = check_not_network_thread() {
+            eprintln!("failed to clear {:?}: {}", self.subspace, err);
+            return;
+        }
+        if let Err(err) = futures::executor::block_on(self.clear()) {
+            eprintln!("failed to clear {:?}: {}", self.subspace, err);
+        }
+    }
+}
@@ -0,0 +1,1257 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! An in-memory database to unit test transactional code without any fdbserver.
+//!
+//! [`MockDatabase`] mimics the subset of [`Database`](crate::Database) and
+//! [`Transaction`](crate::Transaction) used by transaction closures: `run`, `create_trx`,
+//! `commit`, and the [`ReadTransaction`] and [`MutateTransaction`] traits. Transactions read a
+//! consistent snapshot with their own writes on top, and commits are checked for conflicts
+//! against the transactions committed since their read version, failing with `not_committed`
+//! (1020) like the real database would, while read-only transactions always commit. Commits can also be made to fail with
+//! `commit_unknown_result` (1021), having been applied or not, to test the idempotency of
+//! closures.
+//!
+//! ```
+//! use foundationdb::mock::MockDatabase;
+//! use foundationdb::{MutateTransaction, ReadTransaction};
+//! # futures::executor::block_on(async {
+//! let db = MockDatabase::new();
+//! db.run(|trx, _maybe_committed| async move {
+//!     trx.set(b"hello", b"world");
+//!     Ok(())
+//! })
+//! .await?;
+//!
+//! let value = db
+//!     .run(|trx, _maybe_committed| async move { Ok(trx.read(b"hello").await?) })
+//!     .await?;
+//! assert_eq!(value.as_deref(), Some(&b"world"[..]));
+//! # Ok::<(), foundationdb::FdbBindingError>(())
+//! # }).unwrap();
+//! ```
+//!
+//! # Divergences
+//!
+//! The mock only aims to behave like FoundationDB for the operations above, in particular:
+//!
+//! - `read_range` returns the whole range in a single batch, ignoring `mode`, `target_bytes` and
+//!   `iteration`, `more` is only set if `limit` was reached.
+//! - the retryable errors are a fixed list: `transaction_too_old` (1007), `future_version`
+//!   (1009), `not_committed` (1020), `commit_unknown_result` (1021), `process_behind` (1037) and
+//!   `tag_throttled` (1213).
+//! - versions are a counter incremented by each commit. Old versions are kept as long as a
+//!   transaction reads them, however long, so transactions never fail with
+//!   `transaction_too_old`.
+//! - key, value and transaction size limits, and the system keyspace, are not enforced.
+//! - `run` does not detect references to the transaction kept past the closure.
+//! - `get_key` and `get_range` conflict ranges are a superset of the real ones.
+//! - `get_estimated_range_size_bytes` is exact.
+//! - options other than `NextWriteNoWriteConflictRange` are ignored.
+//! - watches, versionstamped mutations and `AppendIfFits` are not supported, `atomic_op`
+//!   panics on unsupported mutation types.
+
+use std::cmp::Ordering;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::ops::{Bound, RangeBounds};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use foundationdb_macros::cfg_api_versions;
+use futures::future::{self, BoxFuture, FutureExt};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::options::{ConflictRangeType, MutationType, OptionError, TransactionOption};
+use crate::transaction::key_after;
+use crate::{
+    FdbBindingError, FdbError, FdbResult, KeySelector, KeyValue, KeyValues, MutateTransaction,
+    RangeOption, ReadTransaction,
+};
+
+/// A range of keys, from its first key included to its last key excluded
+type KeyRange = (Vec<u8>, Vec<u8>);
+/// The successive values of a key, `None` once cleared, by version
+type Versions = Vec<(i64, Option<Vec<u8>>)>;
+
+const TRANSACTION_TOO_OLD: i32 = 1007;
+const FUTURE_VERSION: i32 = 1009;
+const NOT_COMMITTED: i32 = 1020;
+const COMMIT_UNKNOWN_RESULT: i32 = 1021;
+const PROCESS_BEHIND: i32 = 1037;
+const TAG_THROTTLED: i32 = 1213;
+
+/// Returns if the mock retries a transaction failing with this error.
+fn is_retryable(err: FdbError) -> bool {
+    matches!(
+        err.code(),
+        TRANSACTION_TOO_OLD
+            | FUTURE_VERSION
+            | NOT_COMMITTED
+            | COMMIT_UNKNOWN_RESULT
+            | PROCESS_BEHIND
+            | TAG_THROTTLED
+    )
+}
+
+fn intersects(a: &KeyRange, b: &KeyRange) -> bool {
+    a.0 < b.1 && b.0 < a.1
+}
+
+#[derive(Debug, Clone)]
+enum Mutation {
+    Set(Vec<u8>, Vec<u8>),
+    Clear(Vec<u8>),
+    ClearRange(Vec<u8>, Vec<u8>),
+    Atomic(Vec<u8>, Vec<u8>, MutationType),
+}
+
+impl Mutation {
+    fn conflict_range(&self) -> KeyRange {
+        match self {
+            Mutation::Set(key, _) | Mutation::Clear(key) | Mutation::Atomic(key, _, _) => {
+                (key.clone(), key_after(key))
+            }
+            Mutation::ClearRange(begin, end) => (begin.clone(), end.clone()),
+        }
+    }
+
+    /// Returns the key this mutation may give a value to.
+    fn written_key(&self) -> Option<&[u8]> {
+        match self {
+            Mutation::Set(key, _) | Mutation::Atomic(key, _, _) => Some(key),
+            Mutation::Clear(_) | Mutation::ClearRange(_, _) => None,
+        }
+    }
+
+    /// Applies this mutation to the value of `key`.
+    fn apply_to(&self, key: &[u8], value: Option<Vec<u8>>) -> Option<Vec<u8>> {
+        match self {
+            Mutation::Set(k, v) if k == key => Some(v.clone()),
+            Mutation::Clear(k) if k == key => None,
+            Mutation::ClearRange(begin, end) if begin.as_slice() <= key && key < end.as_slice() => {
+                None
+            }
+            Mutation::Atomic(k, param, op_type) if k == key => {
+                atomic(value.as_deref(), param, *op_type)
+            }
+            _ => value,
+        }
+    }
+}
+
+/// Computes the result of an atomic operation, `None` if the key is cleared.
+fn atomic(existing: Option<&[u8]>, param: &[u8], op_type: MutationType) -> Option<Vec<u8>> {
+    // most operations see the existing value truncated or zero extended to the param length
+    let resized = |existing: &[u8]| {
+        let mut value = existing[..existing.len().min(param.len())].to_vec();
+        value.resize(param.len(), 0);
+        value
+    };
+    let bitwise = |f: fn(u8, u8) -> u8| -> Vec<u8> {
+        let existing = resized(existing.unwrap_or_default());
+        existing.iter().zip(param).map(|(&a, &b)| f(a, b)).collect()
+    };
+    let little_endian_cmp = |a: &[u8], b: &[u8]| a.iter().rev().cmp(b.iter().rev());
+
+    Some(match op_type {
+        MutationType::Add => {
+            let existing = resized(existing.unwrap_or_default());
+            let mut carry = 0u16;
+            existing
+                .iter()
+                .zip(param)
+                .map(|(&a, &b)| {
+                    let sum = u16::from(a) + u16::from(b) + carry;
+                    carry = sum >> 8;
+                    sum as u8
+                })
+                .collect()
+        }
+        MutationType::And | MutationType::BitAnd => match existing {
+            Some(_) => bitwise(|a, b| a & b),
+            None => param.to_vec(),
+        },
+        MutationType::Or | MutationType::BitOr => bitwise(|a, b| a | b),
+        MutationType::Xor | MutationType::BitXor => bitwise(|a, b| a ^ b),
+        MutationType::Max => {
+            let existing = resized(existing.unwrap_or_default());
+            std::cmp::max_by(existing, param.to_vec(), |a, b| little_endian_cmp(a, b))
+        }
+        MutationType::Min => match existing {
+            Some(existing) => {
+                let existing = resized(existing);
+                std::cmp::min_by(existing, param.to_vec(), |a, b| little_endian_cmp(a, b))
+            }
+            None => param.to_vec(),
+        },
+        MutationType::ByteMax => match existing {
+            Some(existing) => existing.max(param).to_vec(),
+            None => param.to_vec(),
+        },
+        MutationType::ByteMin => match existing {
+            Some(existing) => existing.min(param).to_vec(),
+            None => param.to_vec(),
+        },
+        #[cfg(any(
+            feature = "fdb-6_1",
+            feature = "fdb-6_2",
+            feature = "fdb-6_3",
+            feature = "fdb-7_0",
+            feature = "fdb-7_1",
+            feature = "fdb-7_3"
+        ))]
+        MutationType::CompareAndClear => match existing {
+            Some(existing) if existing == param => return None,
+            existing => return existing.map(<[u8]>::to_vec),
+        },
+        op_type => panic!("{:?} is not supported by the mock database", op_type),
+    })
+}
+
+/// A committed transaction
+struct Commit {
+    version: i64,
+    write_conflicts: Vec<KeyRange>,
+    // the keys whose value changed, to prune their old versions
+    keys: Vec<Vec<u8>>,
+}
+
+struct Store {
+    history: BTreeMap<Vec<u8>, Versions>,
+    // the commits newer than the oldest read version, by version
+    commits: Vec<Commit>,
+    // how many live transactions read at each version
+    readers: BTreeMap<i64, usize>,
+    version: i64,
+    maybe_committed_probability: f64,
+    rng: SmallRng,
+}
+
+impl Store {
+    fn value_at(&self, key: &[u8], version: i64) -> Option<Vec<u8>> {
+        Self::value_of(self.history.get(key)?, version).map(<[u8]>::to_vec)
+    }
+
+    fn value_of(versions: &[(i64, Option<Vec<u8>>)], version: i64) -> Option<&[u8]> {
+        versions
+            .iter()
+            .rev()
+            .find(|(v, _)| *v <= version)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    /// Returns the current version, kept until [`release`](Self::release) is called.
+    fn acquire(&mut self) -> i64 {
+        *self.readers.entry(self.version).or_default() += 1;
+        self.version
+    }
+
+    /// Releases a version returned by [`acquire`](Self::acquire).
+    fn release(&mut self, version: i64) {
+        if let Entry::Occupied(mut readers) = self.readers.entry(version) {
+            *readers.get_mut() -= 1;
+            if *readers.get() == 0 {
+                readers.remove();
+            }
+        }
+        self.prune();
+    }
+
+    /// Discards the versions no transaction can read anymore, and the commits no transaction
+    /// can conflict with.
+    fn prune(&mut self) {
+        let oldest = self.readers.keys().next().copied().unwrap_or(self.version);
+        let stale = self
+            .commits
+            .partition_point(|commit| commit.version <= oldest);
+        for commit in self.commits.drain(..stale) {
+            for key in commit.keys {
+                if let Entry::Occupied(mut entry) = self.history.entry(key) {
+                    let versions = entry.get_mut();
+                    if let Some(visible) = versions.iter().rposition(|(v, _)| *v <= oldest) {
+                        versions.drain(..visible);
+                    }
+                    if matches!(versions.first(), Some((_, None))) {
+                        versions.remove(0);
+                    }
+                    if versions.is_empty() {
+                        entry.remove();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Commits a transaction, see [`MockTransaction::commit`].
+    fn commit(&mut self, state: TransactionState) -> FdbResult<()> {
+        // like FoundationDB, read-only transactions are not checked for conflicts
+        if state.mutations.is_empty() && state.write_conflicts.is_empty() {
+            return Ok(());
+        }
+        if let Some(read_version) = state.read_version {
+            let conflicting = self
+                .commits
+                .iter()
+                .filter(|commit| commit.version > read_version)
+                .flat_map(|commit| &commit.write_conflicts)
+                .any(|write| {
+                    state
+                        .read_conflicts
+                        .iter()
+                        .any(|read| intersects(read, write))
+                });
+            if conflicting {
+                return Err(FdbError::from_code(NOT_COMMITTED));
+            }
+        }
+
+        let probability = self.maybe_committed_probability;
+        if probability > 0.0 && self.rng.gen_bool(probability) {
+            if self.rng.gen_bool(0.5) {
+                self.apply(&state.mutations, state.write_conflicts);
+            }
+            return Err(FdbError::from_code(COMMIT_UNKNOWN_RESULT));
+        }
+        self.apply(&state.mutations, state.write_conflicts);
+        Ok(())
+    }
+
+    /// Writes `mutations` at a new version.
+    fn apply(&mut self, mutations: &[Mutation], write_conflicts: Vec<KeyRange>) {
+        let version = self.version + 1;
+        // only the keys written, and the existing keys of the cleared ranges, may change
+        let mut keys = BTreeSet::new();
+        for mutation in mutations {
+            match mutation {
+                Mutation::Set(key, _) | Mutation::Clear(key) | Mutation::Atomic(key, _, _) => {
+                    keys.insert(key.clone());
+                }
+                Mutation::ClearRange(begin, end) if begin < end => {
+                    let cleared = self.history.range::<[u8], _>((
+                        Bound::Included(begin.as_slice()),
+                        Bound::Excluded(end.as_slice()),
+                    ));
+                    keys.extend(cleared.map(|(key, _)| key.clone()));
+                }
+                Mutation::ClearRange(_, _) => {}
+            }
+        }
+
+        let view = View {
+            store: self,
+            version: self.version,
+            mutations,
+        };
+        let changes: Vec<_> = keys
+            .into_iter()
+            .filter_map(|key| {
+                let value = view.get(&key);
+                (value != self.value_at(&key, self.version)).then_some((key, value))
+            })
+            .collect();
+        let mut keys = Vec::with_capacity(changes.len());
+        for (key, value) in changes {
+            self.history
+                .entry(key.clone())
+                .or_default()
+                .push((version, value));
+            keys.push(key);
+        }
+
+        self.commits.push(Commit {
+            version,
+            write_conflicts,
+            keys,
+        });
+        self.version = version;
+        self.prune();
+    }
+}
+
+/// The values visible to a transaction: the store at a version, with its writes on top.
+struct View<'a> {
+    store: &'a Store,
+    version: i64,
+    mutations: &'a [Mutation],
+}
+
+impl<'a> View<'a> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.mutations
+            .iter()
+            .fold(self.store.value_at(key, self.version), |value, mutation| {
+                mutation.apply_to(key, value)
+            })
+    }
+
+    /// Iterates over the keys and values within `bounds`, in order or in reverse.
+    fn range(
+        &self,
+        bounds: (Bound<&[u8]>, Bound<&[u8]>),
+        reverse: bool,
+    ) -> impl Iterator<Item = (&'a [u8], Vec<u8>)> + '_ {
+        let stored = self
+            .store
+            .history
+            .range::<[u8], _>(bounds)
+            .map(|(key, _)| key.as_slice());
+        let written: BTreeSet<&[u8]> = self
+            .mutations
+            .iter()
+            .filter_map(Mutation::written_key)
+            .filter(|key| bounds.contains(*key))
+            .collect();
+        let keys: Box<dyn Iterator<Item = &[u8]>> = if reverse {
+            Box::new(merge(stored.rev(), written.into_iter().rev(), true))
+        } else {
+            Box::new(merge(stored, written.into_iter(), false))
+        };
+        keys.filter_map(move |key| Some((key, self.get(key)?)))
+    }
+
+    /// Resolves a key selector.
+    fn resolve(&self, selector: &KeySelector) -> Vec<u8> {
+        let key = selector.key();
+        let offset = selector.offset();
+        // the selected key is `offset` keys after the last key less than (or equal to) `key`
+        if offset > 0 {
+            let lower = if selector.or_equal() {
+                Bound::Excluded(key)
+            } else {
+                Bound::Included(key)
+            };
+            self.range((lower, Bound::Unbounded), false)
+                .nth(offset as usize - 1)
+                .map_or_else(|| vec![0xff], |(key, _)| key.to_vec())
+        } else {
+            let upper = if selector.or_equal() {
+                Bound::Included(key)
+            } else {
+                Bound::Excluded(key)
+            };
+            self.range((Bound::Unbounded, upper), true)
+                .nth(offset.unsigned_abs() as usize)
+                .map_or_else(Vec::new, |(key, _)| key.to_vec())
+        }
+    }
+}
+
+/// Merges two iterators over sorted keys, in reverse order if `reverse`, keeping the keys found
+/// in both once.
+fn merge<'k>(
+    a: impl Iterator<Item = &'k [u8]>,
+    b: impl Iterator<Item = &'k [u8]>,
+    reverse: bool,
+) -> impl Iterator<Item = &'k [u8]> {
+    let (mut a, mut b) = (a.peekable(), b.peekable());
+    std::iter::from_fn(move || {
+        let order = match (a.peek(), b.peek()) {
+            (Some(x), Some(y)) if reverse => y.cmp(x),
+            (Some(x), Some(y)) => x.cmp(y),
+            (Some(_), None) => Ordering::Less,
+            (None, _) => Ordering::Greater,
+        };
+        match order {
+            Ordering::Less => a.next(),
+            Ordering::Equal => {
+                b.next();
+                a.next()
+            }
+            Ordering::Greater => b.next(),
+        }
+    })
+}
+
+/// An in-memory database behaving like FoundationDB for a subset of its API.
+///
+/// Clones share the same data. See the [module documentation](self) for what is supported.
+#[derive(Clone)]
+pub struct MockDatabase {
+    store: Arc<Mutex<Store>>,
+}
+
+impl Default for MockDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockDatabase {
+    /// Creates an empty database.
+    pub fn new() -> Self {
+        Self::with_rng(SmallRng::from_entropy())
+    }
+
+    /// Creates an empty database, whose injected failures are drawn from the given seed.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(SmallRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rng: SmallRng) -> Self {
+        MockDatabase {
+            store: Arc::new(Mutex::new(Store {
+                history: BTreeMap::new(),
+                commits: Vec::new(),
+                readers: BTreeMap::new(),
+                version: 0,
+                maybe_committed_probability: 0.0,
+                rng,
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Store> {
+        self.store.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Makes commits fail with `commit_unknown_result` with the given probability.
+    ///
+    /// Such commits are applied or not, with an equal probability.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `probability` is not within `0.0..=1.0`.
+    pub fn set_maybe_committed_probability(&self, probability: f64) {
+        assert!(
+            (0.0..=1.0).contains(&probability),
+            "probability must be within 0.0..=1.0"
+        );
+        self.lock().maybe_committed_probability = probability;
+    }
+
+    /// Returns the version of the last commit.
+    pub fn version(&self) -> i64 {
+        self.lock().version
+    }
+
+    /// Creates a new transaction on this database.
+    pub fn create_trx(&self) -> FdbResult<MockTransaction> {
+        Ok(MockTransaction {
+            inner: Arc::new(TransactionInner {
+                db: self.clone(),
+                state: Mutex::new(TransactionState::default()),
+            }),
+        })
+    }
+
+    /// Runs a transactional function against this database with retry logic, like
+    /// [`Database::run`](crate::Database::run).
+    ///
+    /// The closure receives a new transaction on each attempt, and whether the previous attempt
+    /// failed with a maybe committed error.
+    pub async fn run<F, Fut, T>(&self, closure: F) -> Result<T, FdbBindingError>
+    where
+        F: Fn(MockTransaction, bool) -> Fut,
+        Fut: Future<Output = Result<T, FdbBindingError>>,
+    {
+        let mut maybe_committed_transaction = false;
+        loop {
+            let transaction = self.create_trx()?;
+            let err = match closure(transaction.clone(), maybe_committed_transaction).await {
+                Ok(result) => match transaction.commit().await {
+                    Ok(()) => return Ok(result),
+                    Err(err) => err,
+                },
+                Err(err) => match err.get_fdb_error() {
+                    Some(err) => err,
+                    None => return Err(err),
+                },
+            };
+            if !is_retryable(err) {
+                return Err(FdbBindingError::from(err));
+            }
+            maybe_committed_transaction = err.code() == COMMIT_UNKNOWN_RESULT;
+        }
+    }
+}
+
+#[derive(Default)]
+struct TransactionState {
+    read_version: Option<i64>,
+    mutations: Vec<Mutation>,
+    read_conflicts: Vec<KeyRange>,
+    write_conflicts: Vec<KeyRange>,
+    next_write_no_write_conflict_range: bool,
+}
+
+/// The state shared by the clones of a transaction
+struct TransactionInner {
+    db: MockDatabase,
+    state: Mutex<TransactionState>,
+}
+
+impl Drop for TransactionInner {
+    fn drop(&mut self) {
+        let state = self.state.get_mut().unwrap_or_else(PoisonError::into_inner);
+        if let Some(read_version) = state.read_version.take() {
+            self.db.lock().release(read_version);
+        }
+    }
+}
+
+/// A transaction on a [`MockDatabase`].
+///
+/// Reads and writes are provided by the [`ReadTransaction`] and [`MutateTransaction`] traits.
+/// Clones refer to the same transaction.
+#[derive(Clone)]
+pub struct MockTransaction {
+    inner: Arc<TransactionInner>,
+}
+
+impl MockTransaction {
+    fn lock(&self) -> MutexGuard<'_, TransactionState> {
+        self.inner
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn push(&self, mutation: Mutation) {
+        let mut state = self.lock();
+        if !std::mem::take(&mut state.next_write_no_write_conflict_range) {
+            state.write_conflicts.push(mutation.conflict_range());
+        }
+        state.mutations.push(mutation);
+    }
+
+    /// Returns the read version, fixed by the first read of the transaction.
+    fn read_version(state: &mut TransactionState, store: &mut Store) -> i64 {
+        *state.read_version.get_or_insert_with(|| store.acquire())
+    }
+
+    /// Returns the values visible to this transaction, its writes included.
+    fn view<'a>(state: &'a mut TransactionState, store: &'a mut Store) -> View<'a> {
+        let version = Self::read_version(state, store);
+        View {
+            store,
+            version,
+            mutations: &state.mutations,
+        }
+    }
+
+    fn get_now(&self, key: &[u8], snapshot: bool) -> Option<Vec<u8>> {
+        let mut store = self.inner.db.lock();
+        let mut state = self.lock();
+        let value = Self::view(&mut state, &mut store).get(key);
+        if !snapshot {
+            state.read_conflicts.push((key.to_vec(), key_after(key)));
+        }
+        value
+    }
+
+    fn get_key_now(&self, selector: &KeySelector, snapshot: bool) -> Vec<u8> {
+        let mut store = self.inner.db.lock();
+        let mut state = self.lock();
+        let key = Self::view(&mut state, &mut store).resolve(selector);
+        if !snapshot {
+            // any key between the selector and the selected key changes the result
+            let (begin, end) = if key.as_slice() < selector.key() {
+                (key.clone(), selector.key())
+            } else {
+                (selector.key().to_vec(), key.as_slice())
+            };
+            state.read_conflicts.push((begin, key_after(end)));
+        }
+        key
+    }
+
+    fn get_range_now(&self, opt: &RangeOption, snapshot: bool) -> KeyValues {
+        let mut store = self.inner.db.lock();
+        let mut state = self.lock();
+        let view = Self::view(&mut state, &mut store);
+
+        let begin = view.resolve(&opt.begin);
+        let end = view.resolve(&opt.end);
+        let mut values = Vec::new();
+        let mut more = false;
+        if begin < end {
+            let bounds = (
+                Bound::Included(begin.as_slice()),
+                Bound::Excluded(end.as_slice()),
+            );
+            for (key, value) in view.range(bounds, opt.reverse) {
+                if matches!(opt.limit, Some(limit) if values.len() >= limit) {
+                    more = true;
+                    break;
+                }
+                values.push(KeyValue::new(key.to_vec(), value));
+            }
+        }
+
+        if !snapshot && begin < end {
+            // a range cut by the limit only conflicts up to the last key read
+            let conflict = match values.last() {
+                Some(last) if more && opt.reverse => (last.key().to_vec(), end),
+                Some(last) if more => (begin, key_after(last.key())),
+                _ => (begin, end),
+            };
+            state.read_conflicts.push(conflict);
+        }
+        KeyValues::new(values, more)
+    }
+
+    /// Commits the transaction, see [`Transaction::commit`](crate::Transaction::commit).
+    ///
+    /// Fails with `not_committed` if a key or range read by this transaction, outside of a
+    /// snapshot read, was written by a transaction committed since its read version. Like in
+    /// FoundationDB, transactions without writes nor write conflict ranges always commit.
+    pub async fn commit(self) -> FdbResult<()> {
+        let mut store = self.inner.db.lock();
+        let state = std::mem::take(&mut *self.lock());
+        let read_version = state.read_version;
+        let result = store.commit(state);
+        if let Some(read_version) = read_version {
+            store.release(read_version);
+        }
+        result
+    }
+
+    /// Discards the reads and writes of this transaction, to start over.
+    pub fn reset(&self) {
+        let mut store = self.inner.db.lock();
+        let state = std::mem::take(&mut *self.lock());
+        if let Some(read_version) = state.read_version {
+            store.release(read_version);
+        }
+    }
+}
+
+impl ReadTransaction for MockTransaction {
+    fn read(&self, key: &[u8]) -> BoxFuture<'_, FdbResult<Option<Vec<u8>>>> {
+        future::ok(self.get_now(key, false)).boxed()
+    }
+
+    fn read_key(&self, selector: &KeySelector) -> BoxFuture<'_, FdbResult<Vec<u8>>> {
+        future::ok(self.get_key_now(selector, false)).boxed()
+    }
+
+    /// The whole range is returned at once, `iteration`, `opt.mode` and `opt.target_bytes` are
+    /// ignored.
+    fn read_range(
+        &self,
+        opt: &RangeOption,
+        _iteration: usize,
+    ) -> BoxFuture<'_, FdbResult<KeyValues>> {
+        future::ok(self.get_range_now(opt, false)).boxed()
+    }
+
+    fn snapshot_read(&self, key: &[u8]) -> BoxFuture<'_, FdbResult<Option<Vec<u8>>>> {
+        future::ok(self.get_now(key, true)).boxed()
+    }
+
+    fn snapshot_read_key(&self, selector: &KeySelector) -> BoxFuture<'_, FdbResult<Vec<u8>>> {
+        future::ok(self.get_key_now(selector, true)).boxed()
+    }
+
+    /// The whole range is returned at once, like [`read_range`](Self::read_range).
+    fn snapshot_read_range(
+        &self,
+        opt: &RangeOption,
+        _iteration: usize,
+    ) -> BoxFuture<'_, FdbResult<KeyValues>> {
+        future::ok(self.get_range_now(opt, true)).boxed()
+    }
+
+    fn get_read_version(&self) -> BoxFuture<'_, FdbResult<i64>> {
+        let mut store = self.inner.db.lock();
+        let version = Self::read_version(&mut self.lock(), &mut store);
+        future::ok(version).boxed()
+    }
+
+    /// The exact size of the keys and values of the range, at the read version.
+    #[cfg_api_versions(min = 630)]
+    fn get_estimated_range_size_bytes(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+    ) -> BoxFuture<'_, FdbResult<i64>> {
+        let mut store = self.inner.db.lock();
+        let version = Self::read_version(&mut self.lock(), &mut store);
+        let view = View {
+            store: &store,
+            version,
+            mutations: &[],
+        };
+        let size = if begin < end {
+            view.range((Bound::Included(begin), Bound::Excluded(end)), false)
+                .map(|(key, value)| (key.len() + value.len()) as i64)
+                .sum()
+        } else {
+            0
+        };
+        future::ok(size).boxed()
+    }
+}
+
+impl MutateTransaction for MockTransaction {
+    fn set(&self, key: &[u8], value: &[u8]) {
+        self.push(Mutation::Set(key.to_vec(), value.to_vec()));
+    }
+
+    fn clear(&self, key: &[u8]) {
+        self.push(Mutation::Clear(key.to_vec()));
+    }
+
+    fn clear_range(&self, begin: &[u8], end: &[u8]) {
+        self.push(Mutation::ClearRange(begin.to_vec(), end.to_vec()));
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `op_type` is a versionstamped mutation or `AppendIfFits`.
+    fn atomic_op(&self, key: &[u8], param: &[u8], op_type: MutationType) {
+        // fail at the call site rather than at commit
+        atomic(None, param, op_type);
+        self.push(Mutation::Atomic(key.to_vec(), param.to_vec(), op_type));
+    }
+
+    fn add_conflict_range(&self, begin: &[u8], end: &[u8], ty: ConflictRangeType) -> FdbResult<()> {
+        let range = (begin.to_vec(), end.to_vec());
+        let mut state = self.lock();
+        match ty {
+            ConflictRangeType::Read => state.read_conflicts.push(range),
+            ConflictRangeType::Write => state.write_conflicts.push(range),
+        }
+        Ok(())
+    }
+
+    /// Only `NextWriteNoWriteConflictRange` is honored, other options are ignored.
+    fn set_option(&self, opt: TransactionOption) -> Result<(), OptionError> {
+        opt.validate()?;
+        if let TransactionOption::NextWriteNoWriteConflictRange = opt {
+            self.lock().next_write_no_write_conflict_range = true;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    fn keys(values: &KeyValues) -> Vec<&[u8]> {
+        values.iter().map(KeyValue::key).collect()
+    }
+
+    #[test]
+    fn read_your_writes() {
+        block_on(async {
+            let db = MockDatabase::new();
+            let trx = db.create_trx()?;
+            trx.set(b"a", b"1");
+            trx.set(b"b", b"2");
+            trx.set(b"c", b"3");
+            trx.clear(b"b");
+            assert_eq!(trx.read(b"a").await?.as_deref(), Some(&b"1"[..]));
+            assert_eq!(trx.read(b"b").await?, None);
+
+            let values = trx.read_range(&(&b"a"[..]..&b"z"[..]).into(), 1).await?;
+            assert_eq!(keys(&values), vec![&b"a"[..], b"c"]);
+
+            // nothing is visible to others until committed
+            assert_eq!(db.create_trx()?.read(b"a").await?, None);
+            trx.commit().await?;
+            assert_eq!(
+                db.create_trx()?.read(b"c").await?.as_deref(),
+                Some(&b"3"[..])
+            );
+            assert_eq!(db.version(), 1);
+            Ok::<_, FdbError>(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn snapshot_isolation() {
+        block_on(async {
+            let db = MockDatabase::new();
+            let trx = db.create_trx()?;
+            trx.set(b"key", b"old");
+            trx.commit().await?;
+
+            let reader = db.create_trx()?;
+            assert_eq!(
+                reader.snapshot().get(b"key").await?.as_deref(),
+                Some(&b"old"[..])
+            );
+
+            let writer = db.create_trx()?;
+            writer.set(b"key", b"new");
+            writer.clear_range(b"", b"\xff");
+            writer.commit().await?;
+
+            // the reader keeps reading at its read version
+            assert_eq!(
+                reader.snapshot().get(b"key").await?.as_deref(),
+                Some(&b"old"[..])
+            );
+            reader.commit().await?;
+            assert_eq!(db.create_trx()?.read(b"key").await?, None);
+            Ok::<_, FdbError>(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn conflicts() {
+        block_on(async {
+            let db = MockDatabase::new();
+
+            // read then written by another transaction
+            let trx1 = db.create_trx()?;
+            let trx2 = db.create_trx()?;
+            trx1.read(b"a").await?;
+            trx1.set(b"b", b"1");
+            trx2.read(b"c").await?;
+            trx2.set(b"a", b"2");
+            trx2.commit().await?;
+            assert_eq!(
+                trx1.commit().await.map_err(FdbError::code),
+                Err(NOT_COMMITTED)
+            );
+
+            // snapshot reads and disjoint writes do not conflict
+            let trx1 = db.create_trx()?;
+            let trx2 = db.create_trx()?;
+            trx1.snapshot().get(b"a").await?;
+            trx1.read_range(&(&b"m"[..]..&b"n"[..]).into(), 1).await?;
+            trx1.set(b"b", b"1");
+            trx2.set(b"a", b"3");
+            trx2.set(b"n", b"3");
+            trx2.commit().await?;
+            trx1.commit().await?;
+
+            // ranges cut by their limit only conflict up to the last key read
+            let trx = db.create_trx()?;
+            for key in [b"r1", b"r2", b"r3"] {
+                trx.set(key, b"");
+            }
+            trx.commit().await?;
+            let trx1 = db.create_trx()?;
+            let trx2 = db.create_trx()?;
+            let opt = RangeOption {
+                limit: Some(1),
+                ..(&b"r"[..]..&b"s"[..]).into()
+            };
+            let values = trx1.read_range(&opt, 1).await?;
+            assert_eq!(keys(&values), vec![&b"r1"[..]]);
+            assert!(values.more());
+            trx1.set(b"x", b"");
+            trx2.clear(b"r3");
+            trx2.commit().await?;
+            trx1.commit().await?;
+            Ok::<_, FdbError>(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn read_only_commits() {
+        block_on(async {
+            let db = MockDatabase::new();
+            let reader = db.create_trx()?;
+            let writer = db.create_trx()?;
+            reader.read(b"a").await?;
+            writer.set(b"a", b"1");
+            writer.commit().await?;
+            reader.commit().await?;
+            Ok::<_, FdbError>(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn history_pruned() {
+        block_on(async {
+            let db = MockDatabase::new();
+            let versions = |key: &[u8]| db.lock().history.get(key).map_or(0, Vec::len);
+            for value in [b"1", b"2", b"3"] {
+                db.run(|trx, _maybe_committed| async move {
+                    trx.set(b"a", value);
+                    trx.set(b"b", value);
+                    Ok(())
+                })
+                .await
+                .unwrap();
+            }
+            assert_eq!(versions(b"a"), 1);
+            assert!(db.lock().commits.is_empty());
+
+            // the versions read by a live transaction are kept
+            let reader = db.create_trx()?;
+            reader.get_read_version().await?;
+            let writer = db.create_trx()?;
+            writer.set(b"a", b"4");
+            writer.clear(b"b");
+            writer.commit().await?;
+            assert_eq!(versions(b"a"), 2);
+            assert_eq!(versions(b"b"), 2);
+            assert_eq!(reader.read(b"b").await?.as_deref(), Some(&b"3"[..]));
+
+            drop(reader);
+            assert_eq!(versions(b"a"), 1);
+            assert_eq!(versions(b"b"), 0);
+            assert!(db.lock().readers.is_empty());
+            assert!(db.lock().commits.is_empty());
+            Ok::<_, FdbError>(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn explicit_conflict_ranges() {
+        block_on(async {
+            let db = MockDatabase::new();
+
+            // writes without a conflict range are invisible to readers
+            let trx1 = db.create_trx()?;
+            let trx2 = db.create_trx()?;
+            trx1.read(b"a").await?;
+            trx1.set(b"b", b"1");
+            trx2.set_option(TransactionOption::NextWriteNoWriteConflictRange)?;
+            trx2.set(b"a", b"2");
+            trx2.commit().await?;
+            trx1.commit().await?;
+
+            // explicit conflict ranges are checked like the implicit ones
+            let trx1 = db.create_trx()?;
+            let trx2 = db.create_trx()?;
+            trx1.get_read_version().await?;
+            trx1.add_conflict_range(b"k", b"l", ConflictRangeType::Read)?;
+            trx1.set(b"b", b"1");
+            trx2.add_conflict_range(b"k1", b"k2", ConflictRangeType::Write)?;
+            trx2.commit().await?;
+            assert_eq!(
+                trx1.commit().await.map_err(FdbError::code),
+                Err(NOT_COMMITTED)
+            );
+
+            // single key conflict ranges cover the key only
+            let trx1 = db.create_trx()?;
+            let trx2 = db.create_trx()?;
+            trx1.snapshot().get(b"k").await?;
+            trx1.add_read_conflict_key(b"k")?;
+            trx1.set(b"b", b"1");
+            trx2.add_write_conflict_key(b"k\x00")?;
+            trx2.commit().await?;
+            trx1.commit().await?;
+
+            let trx1 = db.create_trx()?;
+            let trx2 = db.create_trx()?;
+            trx1.snapshot().get(b"k").await?;
+            trx1.add_read_conflict_key(b"k")?;
+            trx1.set(b"b", b"1");
+            trx2.add_write_conflict_key(b"k")?;
+            trx2.commit().await?;
+            assert_eq!(
+                trx1.commit().await.map_err(FdbError::code),
+                Err(NOT_COMMITTED)
+            );
+            Ok::<_, FdbError>(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn key_selectors() {
+        block_on(async {
+            let db = MockDatabase::new();
+            let trx = db.create_trx()?;
+            for key in [b"a", b"b", b"c", b"d"] {
+                trx.set(key, b"");
+            }
+            trx.commit().await?;
+
+            let trx = db.create_trx()?;
+            let range = |begin, end| RangeOption::from((begin, end));
+            let values = trx
+                .read_range(
+                    &range(
+                        KeySelector::first_greater_than(&b"a"[..]),
+                        KeySelector::last_less_or_equal(&b"c"[..]),
+                    ),
+                    1,
+                )
+                .await?;
+            assert_eq!(keys(&values), vec![&b"b"[..]]);
+
+            let values = trx
+                .read_range(
+                    &range(
+                        KeySelector::last_less_than(&b"a"[..]),
+                        KeySelector::first_greater_or_equal(&b"\xff"[..]),
+                    ),
+                    1,
+                )
+                .await?;
+            assert_eq!(values.len(), 4);
+
+            let opt = RangeOption {
+                reverse: true,
+                limit: Some(2),
+                ..(&b""[..]..&b"\xff"[..]).into()
+            };
+            let values = trx.read_range(&opt, 1).await?;
+            assert_eq!(keys(&values), vec![&b"d"[..], b"c"]);
+
+            let key = |selector| trx.read_key(&selector);
+            assert_eq!(key(KeySelector::first_greater_than(&b"b"[..])).await?, b"c");
+            assert_eq!(key(KeySelector::last_less_than(&b"b"[..])).await?, b"a");
+            assert_eq!(key(KeySelector::last_less_than(&b"a"[..])).await?, b"");
+            assert_eq!(
+                key(KeySelector::first_greater_than(&b"d"[..])).await?,
+                b"\xff"
+            );
+            Ok::<_, FdbError>(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn atomic_ops() {
+        assert_eq!(
+            atomic(Some(&[0xff, 0x00]), &[0x01, 0x00], MutationType::Add),
+            Some(vec![0x00, 0x01])
+        );
+        assert_eq!(
+            atomic(None, &5i64.to_le_bytes(), MutationType::Add),
+            Some(5i64.to_le_bytes().to_vec())
+        );
+        // the existing value is truncated to the param length
+        assert_eq!(
+            atomic(Some(&[1, 2, 3]), &[1], MutationType::Add),
+            Some(vec![2])
+        );
+        assert_eq!(
+            atomic(Some(&[0b1100]), &[0b1010], MutationType::BitAnd),
+            Some(vec![0b1000])
+        );
+        assert_eq!(
+            atomic(None, &[0b1010], MutationType::BitAnd),
+            Some(vec![0b1010])
+        );
+        assert_eq!(
+            atomic(Some(&[0b1100]), &[0b1010], MutationType::BitOr),
+            Some(vec![0b1110])
+        );
+        assert_eq!(
+            atomic(Some(&[0b1100]), &[0b1010], MutationType::BitXor),
+            Some(vec![0b0110])
+        );
+        assert_eq!(
+            atomic(Some(&[0x00, 0x02]), &[0xff, 0x01], MutationType::Max),
+            Some(vec![0x00, 0x02])
+        );
+        assert_eq!(
+            atomic(Some(&[0x00, 0x02]), &[0xff, 0x01], MutationType::Min),
+            Some(vec![0xff, 0x01])
+        );
+        assert_eq!(
+            atomic(Some(b"abc"), b"abd", MutationType::ByteMax),
+            Some(b"abd".to_vec())
+        );
+        assert_eq!(
+            atomic(Some(b"abc"), b"abd", MutationType::ByteMin),
+            Some(b"abc".to_vec())
+        );
+    }
+
+    #[cfg_api_versions(min = 610)]
+    #[test]
+    fn compare_and_clear() {
+        assert_eq!(
+            atomic(Some(b"x"), b"x", MutationType::CompareAndClear),
+            None
+        );
+        assert_eq!(
+            atomic(Some(b"x"), b"y", MutationType::CompareAndClear),
+            Some(b"x".to_vec())
+        );
+        assert_eq!(atomic(None, b"x", MutationType::CompareAndClear), None);
+    }
+
+    #[test]
+    fn run_retries_conflicts() {
+        block_on(async {
+            let db = MockDatabase::new();
+            let attempts = Mutex::new(0);
+            let count = db
+                .run(|trx, _maybe_committed| {
+                    let db = db.clone();
+                    let attempts = &attempts;
+                    async move {
+                        let count = match trx.read(b"count").await? {
+                            Some(count) => count[0],
+                            None => 0,
+                        };
+                        let attempt = {
+                            let mut attempts = attempts.lock().unwrap();
+                            *attempts += 1;
+                            *attempts
+                        };
+                        if attempt == 1 {
+                            // a concurrent increment makes the first attempt conflict
+                            let other = db.create_trx()?;
+                            other.set(b"count", &[10]);
+                            other.commit().await?;
+                        }
+                        trx.set(b"count", &[count + 1]);
+                        Ok(count + 1)
+                    }
+                })
+                .await?;
+            assert_eq!(count, 11);
+            assert_eq!(*attempts.lock().unwrap(), 2);
+            Ok::<_, FdbBindingError>(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn run_with_maybe_committed() {
+        block_on(async {
+            let db = MockDatabase::with_seed(42);
+            db.set_maybe_committed_probability(0.5);
+            let mut seen_maybe_committed = false;
+            for i in 0..20u8 {
+                let maybe_committed = db
+                    .run(|trx, maybe_committed| async move {
+                        // idempotent: only written if absent
+                        if trx.read(&[i]).await?.is_none() {
+                            trx.set(&[i], &[i]);
+                        }
+                        Ok(maybe_committed)
+                    })
+                    .await?;
+                seen_maybe_committed |= maybe_committed;
+            }
+            assert!(seen_maybe_committed);
+
+            db.set_maybe_committed_probability(0.0);
+            let trx = db.create_trx()?;
+            let values = trx.read_range(&(&b""[..]..&b"\xff"[..]).into(), 1).await?;
+            assert_eq!(values.len(), 20);
+            Ok::<_, FdbBindingError>(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn run_returns_non_retryable_errors() {
+        let db = MockDatabase::new();
+        let result: Result<(), _> = block_on(
+            db.run(|_trx, _maybe_committed| async { Err(FdbError::from_code(2000).into()) }),
+        );
+        match result {
+            Err(FdbBindingError::NonRetryableFdbError(err)) => assert_eq!(err.code(), 2000),
+            _ => panic!("expected a non retryable error"),
+        }
+    }
+}
@@ -1 +1,169 @@
 include!(concat!(env!("OUT_DIR"), "/options.rs"));
+
+/// The error returned when an option parameter is outside of the values accepted by the client
+///
+/// Options are validated by their `apply` method before being handed to the C API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidOptionValue {
+    /// The rejected option, i.e. `TransactionOption::Timeout`
+    pub option: &'static str,
+    /// Why the option parameter was rejected
+    pub reason: String,
+}
+
+impl InvalidOptionValue {
+    fn check_range(option: &'static str, v: i32, min: i32, max: i32) -> Result<(), Self> {
+        if v < min || v > max {
+            Err(InvalidOptionValue {
+                option,
+                reason: format!("{} is not in the range [{}, {}]", v, min, max),
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidOptionValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "invalid value for {}: {}", self.option, self.reason)
+    }
+}
+
+impl std::error::Error for InvalidOptionValue {}
+
+/// The error returned when setting an option fails
+#[derive(Debug, Clone)]
+pub enum OptionError {
+    /// The option parameter was rejected before reaching the client
+    InvalidValue(InvalidOptionValue),
+    /// The client failed to set the option
+    Fdb(FdbError),
+}
+
+impl From<InvalidOptionValue> for OptionError {
+    fn from(err: InvalidOptionValue) -> Self {
+        OptionError::InvalidValue(err)
+    }
+}
+
+impl From<FdbError> for OptionError {
+    fn from(err: FdbError) -> Self {
+        OptionError::Fdb(err)
+    }
+}
+
+/// Reported as an `invalid_option_value` error when the option parameter was rejected, for the
+/// callers returning an [`FdbResult`](crate::FdbResult).
+///
+/// The code is all that is left: the `option` and `reason` of the [`InvalidOptionValue`] are
+/// dropped. Converting into a [`FdbBindingError`](crate::FdbBindingError) keeps them, as its
+/// `InvalidOptionValue` variant.
+impl From<OptionError> for FdbError {
+    fn from(err: OptionError) -> Self {
+        match err {
+            // `invalid_option_value` error
+            OptionError::InvalidValue(_) => FdbError::from_code(2006),
+            OptionError::Fdb(err) => err,
+        }
+    }
+}
+
+impl std::fmt::Display for OptionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OptionError::InvalidValue(err) => err.fmt(f),
+            OptionError::Fdb(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for OptionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OptionError::InvalidValue(err) => Some(err),
+            OptionError::Fdb(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_int_range() {
+        assert_eq!(TransactionOption::Timeout(0).validate(), Ok(()));
+        assert_eq!(TransactionOption::Timeout(i32::MAX).validate(), Ok(()));
+        assert_eq!(
+            TransactionOption::Timeout(-1).validate(),
+            Err(InvalidOptionValue {
+                option: "TransactionOption::Timeout",
+                reason: "-1 is not in the range [0, 2147483647]".to_string(),
+            })
+        );
+
+        // -1 disables the retry limit
+        assert_eq!(TransactionOption::RetryLimit(-1).validate(), Ok(()));
+        assert!(TransactionOption::RetryLimit(-2).validate().is_err());
+        assert!(TransactionOption::MaxRetryDelay(-1).validate().is_err());
+
+        assert!(TransactionOption::SizeLimit(31).validate().is_err());
+        assert!(TransactionOption::SizeLimit(10_000_001).validate().is_err());
+
+        // rejected before reaching the C API
+        let err = unsafe { TransactionOption::Timeout(-1).apply(std::ptr::null_mut()) }
+            .expect_err("invalid timeout");
+        assert!(matches!(
+            err,
+            OptionError::InvalidValue(InvalidOptionValue {
+                option: "TransactionOption::Timeout",
+                ..
+            })
+        ));
+        assert_eq!(FdbError::from(err).code(), 2006);
+    }
+
+    #[test]
+    fn test_validate_param_types() {
+        // options without documented constraints are always valid
+        assert_eq!(TransactionOption::CausalWriteRisky.validate(), Ok(()));
+        assert_eq!(DatabaseOption::LocationCacheSize(-1).validate(), Ok(()));
+        assert_eq!(TransactionOption::Tag("tag".to_string()).validate(), Ok(()));
+        assert_eq!(
+            NetworkOption::TLSCertBytes(b"cert".to_vec()).validate(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_deprecated() {
+        assert!(TransactionOption::DurabilityDevNullIsWebScale.is_deprecated());
+        assert!(!TransactionOption::Timeout(1000).is_deprecated());
+    }
+
+    #[test]
+    fn test_hidden() {
+        assert_eq!(
+            TransactionOption::hidden_name(TransactionOption::CausalWriteRisky.code()),
+            None
+        );
+        // `external_client`
+        assert_eq!(
+            NetworkOption::hidden_name(1001),
+            Some("NetworkOption::ExternalClient")
+        );
+    }
+
+    #[test]
+    fn test_name() {
+        assert_eq!(
+            TransactionOption::Timeout(0).name(),
+            "TransactionOption::Timeout"
+        );
+        assert_eq!(
+            NetworkOption::DisableMultiVersionClientApi.name(),
+            "NetworkOption::DisableMultiVersionClientApi"
+        );
+    }
+}
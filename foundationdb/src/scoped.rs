@@ -0,0 +1,612 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! A view of a transaction restricted to a subspace.
+//!
+//! The keys given to a [`ScopedTransaction`] are relative to its subspace: they are appended to
+//! its prefix, and the keys it returns have the prefix stripped. Layer code written against
+//! [`ReadTransaction`] and [`MutateTransaction`] can then be given a scoped transaction and keep
+//! its keys within the subspace without being aware of it.
+//!
+//! The scope is the range of the subspace, see [`Subspace::range`]. Range reads, range clears
+//! and conflict ranges are clamped to it. Key selectors other than `first_greater_or_equal` and
+//! `first_greater_than` are resolved first, and must select a key within the scope.
+
+use std::borrow::Cow;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use futures::future::BoxFuture;
+use futures::{FutureExt, TryFutureExt};
+
+use crate::error::OutOfScope;
+use crate::options::{self, MutationType};
+use crate::traits::{read_as, read_key_as, read_range_as, KeyValue, KeyValues};
+use crate::tuple::Subspace;
+use crate::{
+    FdbBindingError, FdbError, FdbResult, KeySelector, MutateTransaction, RangeOption,
+    ReadTransaction, Transaction,
+};
+
+/// `key_outside_legal_range`, reported by the trait methods instead of [`OutOfScope`].
+const KEY_OUTSIDE_LEGAL_RANGE: i32 = 2004;
+
+impl Transaction {
+    /// Returns a view of this transaction restricted to `subspace`, see [`ScopedTransaction`].
+    pub fn scoped(&self, subspace: &Subspace) -> ScopedTransaction<'_> {
+        ScopedTransaction::new(self, subspace)
+    }
+}
+
+/// A view of a transaction restricted to a subspace, see the [module documentation](self).
+///
+/// The inherent methods report keys addressed outside of the subspace with an [`OutOfScope`]
+/// error. Through the [`ReadTransaction`] trait, including the snapshot reads of
+/// [`snapshot`](ReadTransaction::snapshot), reads fail with a `key_outside_legal_range`
+/// [`FdbError`] instead.
+///
+/// The writes of the [`MutateTransaction`] trait cannot fail: out of scope, they are dropped and
+/// the first one is recorded. Every read then fails, and [`check`](Self::check) reports it, to be
+/// called before committing the underlying transaction.
+#[derive(Debug)]
+pub struct ScopedTransaction<'a, T: ?Sized = Transaction> {
+    trx: &'a T,
+    subspace: Subspace,
+    begin: Vec<u8>,
+    end: Vec<u8>,
+    /// The first key written out of scope through the [`MutateTransaction`] trait
+    violation: Mutex<Option<OutOfScope>>,
+}
+
+impl<'a, T: ?Sized> ScopedTransaction<'a, T> {
+    /// Creates a view of `trx` restricted to `subspace`.
+    pub fn new(trx: &'a T, subspace: &Subspace) -> Self {
+        let (begin, end) = subspace.range();
+        ScopedTransaction {
+            trx,
+            subspace: subspace.clone(),
+            begin,
+            end,
+            violation: Mutex::new(None),
+        }
+    }
+
+    /// Returns the subspace of this view.
+    pub fn subspace(&self) -> &Subspace {
+        &self.subspace
+    }
+
+    /// Fails with the first key written outside of the subspace through the
+    /// [`MutateTransaction`] trait, whose write was dropped.
+    pub fn check(&self) -> Result<(), OutOfScope> {
+        match &*self.lock_violation() {
+            Some(err) => Err(err.clone()),
+            None => Ok(()),
+        }
+    }
+
+    fn lock_violation(&self) -> MutexGuard<'_, Option<OutOfScope>> {
+        self.violation
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Keeps the first violation of a write which cannot fail.
+    fn record(&self, result: Result<(), OutOfScope>) {
+        if let Err(err) = result {
+            self.lock_violation().get_or_insert(err);
+        }
+    }
+
+    /// Returns the key of the underlying transaction addressed by the relative `key`.
+    pub fn absolute_key(&self, key: &[u8]) -> Result<Vec<u8>, OutOfScope> {
+        let mut absolute = Vec::with_capacity(self.subspace.bytes().len() + key.len());
+        absolute.extend_from_slice(self.subspace.bytes());
+        absolute.extend_from_slice(key);
+        if self.contains(&absolute) {
+            Ok(absolute)
+        } else {
+            Err(OutOfScope { key: absolute })
+        }
+    }
+
+    /// Returns the relative key addressing `key` of the underlying transaction.
+    pub fn relative_key<'k>(&self, key: &'k [u8]) -> Result<&'k [u8], OutOfScope> {
+        if self.contains(key) {
+            Ok(&key[self.subspace.bytes().len()..])
+        } else {
+            Err(OutOfScope { key: key.to_vec() })
+        }
+    }
+
+    fn contains(&self, key: &[u8]) -> bool {
+        self.begin.as_slice() <= key && key < self.end.as_slice()
+    }
+
+    /// Appends the relative `key` to the prefix, clamped to the scope.
+    fn clamp(&self, key: &[u8]) -> Vec<u8> {
+        let mut absolute = self.subspace.bytes().to_vec();
+        absolute.extend_from_slice(key);
+        if absolute < self.begin {
+            self.begin.clone()
+        } else if absolute > self.end {
+            self.end.clone()
+        } else {
+            absolute
+        }
+    }
+
+    fn absolute_selector(&self, selector: &KeySelector) -> KeySelector<'static> {
+        let mut key = self.subspace.bytes().to_vec();
+        key.extend_from_slice(selector.key());
+        KeySelector::new(Cow::Owned(key), selector.or_equal(), selector.offset())
+    }
+
+    /// Maps a bound of a range read, `Err` if it must be resolved first.
+    fn range_bound(
+        &self,
+        selector: &KeySelector,
+    ) -> Result<KeySelector<'static>, KeySelector<'static>> {
+        if selector.offset() != 1 {
+            return Err(self.absolute_selector(selector));
+        }
+        let mut key = self.subspace.bytes().to_vec();
+        key.extend_from_slice(selector.key());
+        if key < self.begin {
+            Ok(KeySelector::first_greater_or_equal(self.begin.clone()))
+        } else if key > self.end {
+            Ok(KeySelector::first_greater_or_equal(self.end.clone()))
+        } else {
+            Ok(KeySelector::new(Cow::Owned(key), selector.or_equal(), 1))
+        }
+    }
+
+    fn relative_key_values(&self, values: KeyValues) -> Result<KeyValues, OutOfScope> {
+        let more = values.more();
+        let mut key_values = Vec::with_capacity(values.len());
+        for key_value in values {
+            let (key, value) = key_value.into_parts();
+            let key = self.relative_key(&key)?.to_vec();
+            key_values.push(KeyValue::new(key, value));
+        }
+        Ok(KeyValues::new(key_values, more))
+    }
+}
+
+impl<'a, T: ReadTransaction + ?Sized> ScopedTransaction<'a, T> {
+    /// Reads the value of the relative `key`, see [`Transaction::read`].
+    pub fn read(&self, key: &[u8]) -> BoxFuture<'_, Result<Option<Vec<u8>>, FdbBindingError>> {
+        self.read_as(key, false)
+    }
+
+    /// Resolves a key selector relative to the subspace, see [`Transaction::read_key`].
+    ///
+    /// Fails with [`OutOfScope`] if it selects a key outside of the subspace.
+    pub fn read_key(
+        &self,
+        selector: &KeySelector,
+    ) -> BoxFuture<'_, Result<Vec<u8>, FdbBindingError>> {
+        self.read_key_as(selector, false)
+    }
+
+    /// Reads a batch of key-value pairs of a range relative to the subspace, clamped to it, see
+    /// [`Transaction::read_range`].
+    ///
+    /// Fails with [`OutOfScope`] if a key selector, other than `first_greater_or_equal` and
+    /// `first_greater_than`, selects a key outside of the subspace.
+    pub fn read_range(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+    ) -> BoxFuture<'_, Result<KeyValues, FdbBindingError>> {
+        self.read_range_as(opt, iteration, false)
+    }
+
+    fn read_as(
+        &self,
+        key: &[u8],
+        snapshot: bool,
+    ) -> BoxFuture<'_, Result<Option<Vec<u8>>, FdbBindingError>> {
+        if let Err(err) = self.check() {
+            return futures::future::err(err.into()).boxed();
+        }
+        match self.absolute_key(key) {
+            Ok(key) => read_as(self.trx, &key, snapshot).err_into().boxed(),
+            Err(err) => futures::future::err(err.into()).boxed(),
+        }
+    }
+
+    fn read_key_as(
+        &self,
+        selector: &KeySelector,
+        snapshot: bool,
+    ) -> BoxFuture<'_, Result<Vec<u8>, FdbBindingError>> {
+        if let Err(err) = self.check() {
+            return futures::future::err(err.into()).boxed();
+        }
+        let key = read_key_as(self.trx, &self.absolute_selector(selector), snapshot);
+        async move {
+            let key = key.await?;
+            Ok(self.relative_key(&key)?.to_vec())
+        }
+        .boxed()
+    }
+
+    fn read_range_as(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+        snapshot: bool,
+    ) -> BoxFuture<'_, Result<KeyValues, FdbBindingError>> {
+        if let Err(err) = self.check() {
+            return futures::future::err(err.into()).boxed();
+        }
+        let opt = opt.clone();
+        match (self.range_bound(&opt.begin), self.range_bound(&opt.end)) {
+            (Ok(begin), Ok(end)) => {
+                let values = read_range_as(
+                    self.trx,
+                    &RangeOption { begin, end, ..opt },
+                    iteration,
+                    snapshot,
+                );
+                async move { Ok(self.relative_key_values(values.await?)?) }.boxed()
+            }
+            (begin, end) => async move {
+                let begin = self.resolve_bound(begin, snapshot).await?;
+                let end = self.resolve_bound(end, snapshot).await?;
+                let values = read_range_as(
+                    self.trx,
+                    &RangeOption { begin, end, ..opt },
+                    iteration,
+                    snapshot,
+                )
+                .await?;
+                Ok(self.relative_key_values(values)?)
+            }
+            .boxed(),
+        }
+    }
+
+    async fn resolve_bound(
+        &self,
+        bound: Result<KeySelector<'static>, KeySelector<'static>>,
+        snapshot: bool,
+    ) -> Result<KeySelector<'static>, FdbBindingError> {
+        match bound {
+            Ok(selector) => Ok(selector),
+            Err(selector) => {
+                let key = read_key_as(self.trx, &selector, snapshot).await?;
+                // the end of the scope is a valid bound for the range
+                if key == self.end || self.contains(&key) {
+                    Ok(KeySelector::first_greater_or_equal(key))
+                } else {
+                    Err(OutOfScope { key }.into())
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T: MutateTransaction + ?Sized> ScopedTransaction<'a, T> {
+    /// Sets the value of the relative `key`, see [`Transaction::set`].
+    pub fn set(&self, key: &[u8], value: &[u8]) -> Result<(), OutOfScope> {
+        self.trx.set(&self.absolute_key(key)?, value);
+        Ok(())
+    }
+
+    /// Clears the relative `key`, see [`Transaction::clear`].
+    pub fn clear(&self, key: &[u8]) -> Result<(), OutOfScope> {
+        self.trx.clear(&self.absolute_key(key)?);
+        Ok(())
+    }
+
+    /// Clears a range relative to the subspace, clamped to it, see [`Transaction::clear_range`].
+    pub fn clear_range(&self, begin: &[u8], end: &[u8]) {
+        self.trx.clear_range(&self.clamp(begin), &self.clamp(end));
+    }
+
+    /// Clears every key of the subspace.
+    pub fn clear_all(&self) {
+        self.trx.clear_range(&self.begin, &self.end);
+    }
+
+    /// Performs an atomic operation on the relative `key`, see [`Transaction::atomic_op`].
+    ///
+    /// The offset of the versionstamp of a `SetVersionstampedKey` key is relative to the key
+    /// given, it is moved by the length of the prefix.
+    pub fn atomic_op(
+        &self,
+        key: &[u8],
+        param: &[u8],
+        op_type: MutationType,
+    ) -> Result<(), OutOfScope> {
+        if let MutationType::SetVersionstampedKey = op_type {
+            let (key, offset) = match key.len().checked_sub(4) {
+                Some(split) => key.split_at(split),
+                None => return Err(OutOfScope { key: key.to_vec() }),
+            };
+            let offset = u32::from_le_bytes(offset.try_into().expect("4 bytes"))
+                + self.subspace.bytes().len() as u32;
+            let mut key = self.absolute_key(key)?;
+            key.extend_from_slice(&offset.to_le_bytes());
+            self.trx.atomic_op(&key, param, op_type);
+        } else {
+            self.trx.atomic_op(&self.absolute_key(key)?, param, op_type);
+        }
+        Ok(())
+    }
+
+    /// Adds a conflict range relative to the subspace, clamped to it, see
+    /// [`Transaction::add_conflict_range`].
+    pub fn add_conflict_range(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+        ty: options::ConflictRangeType,
+    ) -> FdbResult<()> {
+        self.trx
+            .add_conflict_range(&self.clamp(begin), &self.clamp(end), ty)
+    }
+}
+
+/// The error reported by the trait methods, which can not return an [`OutOfScope`].
+fn trait_error(err: FdbBindingError) -> FdbError {
+    match err {
+        FdbBindingError::NonRetryableFdbError(err) => err,
+        _ => FdbError::from_code(KEY_OUTSIDE_LEGAL_RANGE),
+    }
+}
+
+impl<'a, T: ReadTransaction + ?Sized> ReadTransaction for ScopedTransaction<'a, T> {
+    fn read(&self, key: &[u8]) -> BoxFuture<'_, FdbResult<Option<Vec<u8>>>> {
+        self.read_as(key, false).map_err(trait_error).boxed()
+    }
+
+    fn read_key(&self, selector: &KeySelector) -> BoxFuture<'_, FdbResult<Vec<u8>>> {
+        self.read_key_as(selector, false)
+            .map_err(trait_error)
+            .boxed()
+    }
+
+    fn read_range(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+    ) -> BoxFuture<'_, FdbResult<KeyValues>> {
+        self.read_range_as(opt, iteration, false)
+            .map_err(trait_error)
+            .boxed()
+    }
+
+    fn snapshot_read(&self, key: &[u8]) -> BoxFuture<'_, FdbResult<Option<Vec<u8>>>> {
+        self.read_as(key, true).map_err(trait_error).boxed()
+    }
+
+    fn snapshot_read_key(&self, selector: &KeySelector) -> BoxFuture<'_, FdbResult<Vec<u8>>> {
+        self.read_key_as(selector, true)
+            .map_err(trait_error)
+            .boxed()
+    }
+
+    fn snapshot_read_range(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+    ) -> BoxFuture<'_, FdbResult<KeyValues>> {
+        self.read_range_as(opt, iteration, true)
+            .map_err(trait_error)
+            .boxed()
+    }
+
+    fn get_read_version(&self) -> BoxFuture<'_, FdbResult<i64>> {
+        self.trx.get_read_version()
+    }
+
+    #[foundationdb_macros::cfg_api_versions(min = 630)]
+    fn get_estimated_range_size_bytes(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+    ) -> BoxFuture<'_, FdbResult<i64>> {
+        self.trx
+            .get_estimated_range_size_bytes(&self.clamp(begin), &self.clamp(end))
+    }
+}
+
+impl<'a, T: MutateTransaction + ?Sized> MutateTransaction for ScopedTransaction<'a, T> {
+    /// Dropped if `key` is outside of the subspace, see [`check`](ScopedTransaction::check).
+    fn set(&self, key: &[u8], value: &[u8]) {
+        self.record(ScopedTransaction::set(self, key, value))
+    }
+
+    /// Dropped if `key` is outside of the subspace, see [`check`](ScopedTransaction::check).
+    fn clear(&self, key: &[u8]) {
+        self.record(ScopedTransaction::clear(self, key))
+    }
+
+    fn clear_range(&self, begin: &[u8], end: &[u8]) {
+        ScopedTransaction::clear_range(self, begin, end)
+    }
+
+    /// Dropped if `key` is outside of the subspace, see [`check`](ScopedTransaction::check).
+    fn atomic_op(&self, key: &[u8], param: &[u8], op_type: MutationType) {
+        self.record(ScopedTransaction::atomic_op(self, key, param, op_type))
+    }
+
+    fn add_conflict_range(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+        ty: options::ConflictRangeType,
+    ) -> FdbResult<()> {
+        ScopedTransaction::add_conflict_range(self, begin, end, ty)
+    }
+
+    fn set_option(&self, opt: options::TransactionOption) -> Result<(), options::OptionError> {
+        self.trx.set_option(opt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockDatabase;
+    use crate::tuple::pack;
+    use futures::executor::block_on;
+
+    fn keys(values: &KeyValues) -> Vec<Vec<u8>> {
+        values.iter().map(|kv| kv.key().to_vec()).collect()
+    }
+
+    #[test]
+    fn range_reads_are_clamped() {
+        block_on(async {
+            let db = MockDatabase::new();
+            let trx = db.create_trx()?;
+            // keys right before, in, and right after the subspace
+            trx.set(b"\x01", b"before");
+            trx.set(&pack(&("scope", 1)), b"1");
+            trx.set(&pack(&("scope", 2)), b"2");
+            trx.set(&pack(&("scope", 3)), b"3");
+            trx.set(b"\x03", b"after");
+
+            let scoped = ScopedTransaction::new(&trx, &Subspace::from("scope"));
+            let all =
+                ReadTransaction::read_range(&scoped, &(b"".to_vec()..b"\xff".to_vec()).into(), 1)
+                    .await?;
+            assert_eq!(keys(&all), vec![pack(&1), pack(&2), pack(&3)]);
+            assert_eq!(all[0].value(), b"1");
+
+            let from_two =
+                ReadTransaction::read_range(&scoped, &(pack(&2)..b"\xff\xff".to_vec()).into(), 1)
+                    .await?;
+            assert_eq!(keys(&from_two), vec![pack(&2), pack(&3)]);
+            let to_two =
+                ReadTransaction::read_range(&scoped, &(Vec::new()..pack(&2)).into(), 1).await?;
+            assert_eq!(keys(&to_two), vec![pack(&1)]);
+
+            // selectors are resolved within the scope
+            let last = RangeOption::from((
+                KeySelector::last_less_than(pack(&3)),
+                KeySelector::first_greater_or_equal(b"\xff".to_vec()),
+            ));
+            let last = ReadTransaction::read_range(&scoped, &last, 1).await?;
+            assert_eq!(keys(&last), vec![pack(&2), pack(&3)]);
+            assert_eq!(
+                scoped
+                    .read_key(&KeySelector::first_greater_than(pack(&1)))
+                    .await
+                    .unwrap(),
+                pack(&2)
+            );
+            Ok::<_, FdbError>(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn out_of_scope() {
+        block_on(async {
+            let db = MockDatabase::new();
+            let trx = db.create_trx()?;
+            trx.set(b"\x01", b"before");
+            trx.set(&pack(&("scope", 1)), b"1");
+            let scoped = ScopedTransaction::new(&trx, &Subspace::from("scope"));
+
+            // the selector steps out of the subspace
+            let before = KeySelector::last_less_than(pack(&1));
+            match scoped.read_key(&before).await {
+                Err(FdbBindingError::OutOfScope(err)) => assert_eq!(err.key, b"\x01"),
+                other => panic!("unexpected result {:?}", other),
+            }
+            let range =
+                RangeOption::from((before.clone(), KeySelector::first_greater_than(pack(&1))));
+            assert!(matches!(
+                scoped.read_range(&range, 1).await,
+                Err(FdbBindingError::OutOfScope(_))
+            ));
+            let err = ReadTransaction::read_key(&scoped, &before)
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), KEY_OUTSIDE_LEGAL_RANGE);
+            let err = scoped.snapshot().get_key(&before).await.unwrap_err();
+            assert_eq!(err.code(), KEY_OUTSIDE_LEGAL_RANGE);
+
+            // the prefix alone, and keys after the range of the subspace
+            assert!(scoped.set(b"", b"").is_err());
+            assert!(scoped.set(b"\xff", b"").is_err());
+            assert!(scoped.read(b"").await.is_err());
+            assert_eq!(trx.read(b"\x02scope\x00").await?, None);
+            Ok::<_, FdbError>(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn trait_writes_out_of_scope_are_recorded() {
+        block_on(async {
+            let db = MockDatabase::new();
+            let trx = db.create_trx()?;
+            let scoped = ScopedTransaction::new(&trx, &Subspace::from("scope"));
+            MutateTransaction::set(&scoped, &pack(&1), b"1");
+            assert_eq!(scoped.check(), Ok(()));
+
+            MutateTransaction::set(&scoped, b"\xff", b"");
+            MutateTransaction::clear(&scoped, b"");
+            // the first violation is kept, and the writes are dropped
+            let after = [scoped.subspace().bytes(), b"\xff"].concat();
+            assert_eq!(scoped.check(), Err(OutOfScope { key: after.clone() }));
+            assert_eq!(trx.read(&after).await?, None);
+            assert_eq!(
+                trx.read(&pack(&("scope", 1))).await?.as_deref(),
+                Some(&b"1"[..])
+            );
+
+            // then every read fails
+            assert!(matches!(
+                scoped.read(&pack(&1)).await,
+                Err(FdbBindingError::OutOfScope(_))
+            ));
+            let err = ReadTransaction::read(&scoped, &pack(&1)).await.unwrap_err();
+            assert_eq!(err.code(), KEY_OUTSIDE_LEGAL_RANGE);
+            let all = RangeOption::from((b"".to_vec(), b"\xff".to_vec()));
+            assert!(scoped.snapshot().get_range(&all, 1).await.is_err());
+            Ok::<_, FdbError>(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn clear_all_keeps_siblings() {
+        block_on(async {
+            let db = MockDatabase::new();
+            let trx = db.create_trx()?;
+            trx.set(&pack(&("app", 1)), b"1");
+            trx.set(&pack(&("app", 2)), b"2");
+            // a sibling sharing the first bytes of the prefix
+            trx.set(&pack(&("apple", 1)), b"1");
+            trx.set(&pack(&"app"), b"the key of the prefix");
+
+            let scoped = ScopedTransaction::new(&trx, &Subspace::from("app"));
+            MutateTransaction::set(&scoped, &pack(&3), b"3");
+            assert_eq!(
+                trx.read(&pack(&("app", 3))).await?.as_deref(),
+                Some(&b"3"[..])
+            );
+            scoped.clear_all();
+            // clamped to the scope too
+            MutateTransaction::clear_range(&scoped, b"", b"\xff\xff");
+
+            let left = trx
+                .read_range(&RangeOption::from((b"".to_vec(), b"\xff".to_vec())), 1)
+                .await?;
+            assert_eq!(keys(&left), vec![pack(&"app"), pack(&("apple", 1))]);
+            Ok::<_, FdbError>(())
+        })
+        .unwrap();
+    }
+}
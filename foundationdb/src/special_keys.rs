@@ -0,0 +1,308 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+// Copyright 2013-2018 Apple, Inc and the FoundationDB project authors.
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Introspection helpers over the system and special key spaces.
+//!
+//! Those keys are maintained by the cluster itself, reading them requires the transaction to
+//! have the `AccessSystemKeys` option set, the helpers leave the options of the transaction they
+//! are given untouched.
+//!
+//! Tag throttles have no management special keys in the supported API versions, so like the
+//! `throttle` command of `fdbcli`, the throttle helpers read and write the `\xff\x02/throttledTags/`
+//! system keys directly, in the format of the cluster.
+
+use crate::options::MutationType;
+use crate::{FdbError, FdbResult, KeySelector, RangeOption, Transaction};
+use futures::TryStreamExt;
+
+const TAG_THROTTLE_KEYS_PREFIX: &[u8] = b"\xff\x02/throttledTags/tag/";
+const TAG_THROTTLE_KEYS_END: &[u8] = b"\xff\x02/throttledTags/tag0";
+const TAG_THROTTLE_SIGNAL_KEY: &[u8] = b"\xff\x02/throttledTags/signal";
+const TAG_THROTTLE_COUNT_KEY: &[u8] = b"\xff\x02/throttledTags/manualThrottleCount";
+
+/// Protocol version `TagThrottleValue`s are written with, the first one storing a reason.
+const TAG_THROTTLE_VALUE_PROTOCOL_VERSION: u64 = 0x0FDB_00B0_6301_0000;
+
+/// `tag_too_long` error
+const TAG_TOO_LONG: i32 = 2110;
+
+/// Who asked for a tag to be throttled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TagThrottleType {
+    /// Throttled by an operator through `fdbcli` or [`crate::Database::set_manual_throttle`].
+    Manual,
+    /// Throttled automatically by the ratekeeper.
+    Auto,
+}
+
+/// Priority of the transactions a throttle applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TransactionPriority {
+    Batch,
+    Default,
+    Immediate,
+}
+
+/// Why a tag is being throttled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TagThrottleReason {
+    /// The reason was not recorded, throttles written by older clusters have no reason.
+    Unset,
+    Manual,
+    BusyRead,
+    BusyWrite,
+}
+
+/// A throttle currently applied by the cluster on a transaction tag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThrottledTag {
+    /// The throttled tag, as given to `TransactionOption::Tag`.
+    pub tag: Vec<u8>,
+    /// Maximum number of transactions per second allowed for this tag.
+    pub rate: f64,
+    /// Time, in seconds of the cluster clock, at which the throttle expires.
+    ///
+    /// A freshly created manual throttle reports `0.0` until the ratekeeper picks it up.
+    pub expiration: f64,
+    /// Duration, in seconds, the throttle was created for.
+    pub initial_duration: f64,
+    pub priority: TransactionPriority,
+    pub throttle_type: TagThrottleType,
+    pub reason: TagThrottleReason,
+}
+
+impl TransactionPriority {
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(TransactionPriority::Batch),
+            1 => Some(TransactionPriority::Default),
+            2 => Some(TransactionPriority::Immediate),
+            _ => None,
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            TransactionPriority::Batch => 0,
+            TransactionPriority::Default => 1,
+            TransactionPriority::Immediate => 2,
+        }
+    }
+}
+
+impl TagThrottleReason {
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(TagThrottleReason::Unset),
+            1 => Some(TagThrottleReason::Manual),
+            2 => Some(TagThrottleReason::BusyRead),
+            3 => Some(TagThrottleReason::BusyWrite),
+            _ => None,
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            TagThrottleReason::Unset => 0,
+            TagThrottleReason::Manual => 1,
+            TagThrottleReason::BusyRead => 2,
+            TagThrottleReason::BusyWrite => 3,
+        }
+    }
+}
+
+impl ThrottledTag {
+    /// Decode a throttle from its `\xff\x02/throttledTags/tag/` key and value.
+    ///
+    /// Returns `None` if the entry is not in a format known by this binding.
+    pub(crate) fn from_key_value(key: &[u8], value: &[u8]) -> Option<Self> {
+        let key = key.strip_prefix(TAG_THROTTLE_KEYS_PREFIX)?;
+        let (&throttle_type, key) = key.split_first()?;
+        let throttle_type = match throttle_type {
+            0 => TagThrottleType::Manual,
+            1 => TagThrottleType::Auto,
+            _ => return None,
+        };
+        let (&priority, key) = key.split_first()?;
+        let priority = TransactionPriority::from_code(priority)?;
+        // FoundationDB only supports a single tag per throttle
+        let (&tag_len, tag) = key.split_first()?;
+        if tag.len() != tag_len as usize {
+            return None;
+        }
+
+        // the value is prefixed by the protocol version it was written with
+        let value = value.get(8..)?;
+        let read_f64 = |offset: usize| -> Option<f64> {
+            let bytes = value.get(offset..offset + 8)?;
+            Some(f64::from_le_bytes(bytes.try_into().ok()?))
+        };
+        let reason = match value.get(24) {
+            Some(&reason) => TagThrottleReason::from_code(reason)?,
+            None => TagThrottleReason::Unset,
+        };
+
+        Some(ThrottledTag {
+            tag: tag.to_vec(),
+            rate: read_f64(0)?,
+            expiration: read_f64(8)?,
+            initial_duration: read_f64(16)?,
+            priority,
+            throttle_type,
+            reason,
+        })
+    }
+}
+
+/// Fails with a `tag_too_long` error if the tag does not fit in the 255 bytes of a throttle key.
+pub(crate) fn tag_throttle_key(
+    throttle_type: TagThrottleType,
+    priority: TransactionPriority,
+    tag: &[u8],
+) -> FdbResult<Vec<u8>> {
+    let tag_len: u8 = tag
+        .len()
+        .try_into()
+        .map_err(|_| FdbError::new(TAG_TOO_LONG))?;
+    let mut key = Vec::with_capacity(TAG_THROTTLE_KEYS_PREFIX.len() + 3 + tag.len());
+    key.extend_from_slice(TAG_THROTTLE_KEYS_PREFIX);
+    key.push(match throttle_type {
+        TagThrottleType::Manual => 0,
+        TagThrottleType::Auto => 1,
+    });
+    key.push(priority.code());
+    key.push(tag_len);
+    key.extend_from_slice(tag);
+    Ok(key)
+}
+
+pub(crate) fn tag_throttle_value(
+    rate: f64,
+    expiration: f64,
+    initial_duration: f64,
+    reason: TagThrottleReason,
+) -> Vec<u8> {
+    let mut value = Vec::with_capacity(33);
+    value.extend_from_slice(&TAG_THROTTLE_VALUE_PROTOCOL_VERSION.to_le_bytes());
+    value.extend_from_slice(&rate.to_le_bytes());
+    value.extend_from_slice(&expiration.to_le_bytes());
+    value.extend_from_slice(&initial_duration.to_le_bytes());
+    value.push(reason.code());
+    value
+}
+
+/// Notify the ratekeeper that the set of throttles changed.
+pub(crate) fn signal_throttle_change(trx: &Transaction) {
+    trx.atomic_op(
+        TAG_THROTTLE_SIGNAL_KEY,
+        b"XXXXXXXXXX\x00\x00\x00\x00",
+        MutationType::SetVersionstampedValue,
+    );
+}
+
+/// Add `delta` to the number of manual throttles, as tracked by `fdbcli`.
+pub(crate) async fn update_throttle_count(trx: &Transaction, delta: i64) -> FdbResult<()> {
+    let count = match trx.read(TAG_THROTTLE_COUNT_KEY).await? {
+        Some(value) => match value[..].try_into() {
+            Ok(bytes) => i64::from_le_bytes(bytes),
+            Err(_) => 0,
+        },
+        None => 0,
+    };
+    trx.set(TAG_THROTTLE_COUNT_KEY, &(count + delta).to_le_bytes());
+    Ok(())
+}
+
+/// A view over the keys maintained by the cluster, obtained with [`Transaction::special_keys`].
+pub struct SpecialKeys<'a> {
+    trx: &'a Transaction,
+}
+
+impl Transaction {
+    /// Access introspection helpers reading the system and special key spaces
+    /// with this transaction.
+    pub fn special_keys(&self) -> SpecialKeys<'_> {
+        SpecialKeys { trx: self }
+    }
+}
+
+impl<'a> SpecialKeys<'a> {
+    /// List the tags currently throttled by the cluster, either manually or by the ratekeeper.
+    ///
+    /// The transaction must have the `AccessSystemKeys` option set, see
+    /// [`crate::Database::throttled_tags`] to read them in a transaction of their own. Entries
+    /// written in a format unknown to this binding are skipped.
+    pub async fn throttled_tags(&self) -> FdbResult<Vec<ThrottledTag>> {
+        let range_option = RangeOption {
+            begin: KeySelector::first_greater_or_equal(TAG_THROTTLE_KEYS_PREFIX),
+            end: KeySelector::first_greater_or_equal(TAG_THROTTLE_KEYS_END),
+            ..Default::default()
+        };
+
+        self.trx
+            .get_ranges_keyvalues(range_option, false)
+            .try_filter_map(|fdb_value| async move {
+                Ok(ThrottledTag::from_key_value(
+                    fdb_value.key(),
+                    fdb_value.value(),
+                ))
+            })
+            .try_collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttled_tag_round_trip() {
+        let key = tag_throttle_key(
+            TagThrottleType::Manual,
+            TransactionPriority::Default,
+            b"my_tag",
+        )
+        .expect("valid tag");
+        assert_eq!(&key, b"\xff\x02/throttledTags/tag/\x00\x01\x06my_tag");
+
+        let value = tag_throttle_value(12.5, 0.0, 60.0, TagThrottleReason::Manual);
+        let throttle = ThrottledTag::from_key_value(&key, &value).expect("valid throttle");
+        assert_eq!(
+            throttle,
+            ThrottledTag {
+                tag: b"my_tag".to_vec(),
+                rate: 12.5,
+                expiration: 0.0,
+                initial_duration: 60.0,
+                priority: TransactionPriority::Default,
+                throttle_type: TagThrottleType::Manual,
+                reason: TagThrottleReason::Manual,
+            }
+        );
+
+        // values written before reasons were introduced
+        let throttle =
+            ThrottledTag::from_key_value(&key, &value[..value.len() - 1]).expect("valid throttle");
+        assert_eq!(throttle.reason, TagThrottleReason::Unset);
+
+        // truncated tag
+        assert_eq!(
+            ThrottledTag::from_key_value(&key[..key.len() - 1], &value),
+            None
+        );
+    }
+
+    #[test]
+    fn test_tag_too_long() {
+        let tag = [b'a'; 256];
+        let err = tag_throttle_key(TagThrottleType::Manual, TransactionPriority::Default, &tag)
+            .expect_err("tag too long");
+        assert_eq!(err.code(), TAG_TOO_LONG);
+    }
+}
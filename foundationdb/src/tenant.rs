@@ -23,8 +23,22 @@ use std::ptr::NonNull;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Instant;
 
+#[cfg(not(feature = "fdb-7_3"))]
 const TENANT_MAP_PREFIX: &[u8] = b"\xFF\xFF/management/tenant_map/";
+#[cfg(not(feature = "fdb-7_3"))]
 const TENANT_MAP_PREFIX_END: &[u8] = b"\xFF\xFF/management/tenant_map0";
+// the tenant management keys moved under `tenant/` in 7.2
+#[cfg(feature = "fdb-7_3")]
+const TENANT_MAP_PREFIX: &[u8] = b"\xFF\xFF/management/tenant/map/";
+#[cfg(feature = "fdb-7_3")]
+const TENANT_MAP_PREFIX_END: &[u8] = b"\xFF\xFF/management/tenant/map0";
+#[cfg(feature = "fdb-7_3")]
+const TENANT_RENAME_PREFIX: &[u8] = b"\xFF\xFF/management/tenant/rename/";
+
+/// `tenant_not_found` error
+const TENANT_NOT_FOUND: i32 = 2131;
+/// `tenant_already_exists` error
+const TENANT_ALREADY_EXISTS: i32 = 2132;
 
 /// A `FdbTenant` represents a named key-space within a database that can be interacted with transactionally.
 pub struct FdbTenant {
@@ -216,14 +230,17 @@ impl TryFrom<(&[u8], &[u8])> for TenantInfo {
         let key = k_v.0;
         let value = k_v.1;
         let tenant_name = key.split_at(TENANT_MAP_PREFIX.len()).1;
-        match serde_json::from_slice::<FDBTenantInfo>(value) {
-            Ok(tenant_info) => Ok(TenantInfo {
-                name: tenant_name.to_vec(),
-                id: tenant_info.id,
-                prefix: tenant_info.prefix,
-            }),
-            Err(err) => Err(err),
-        }
+        let tenant_info = serde_json::from_slice::<FDBTenantInfo>(value)?;
+        #[cfg(not(feature = "fdb-7_3"))]
+        let prefix = tenant_info.prefix;
+        // since 7.2, the prefix is the big endian id, only given in base64 in the tenant map
+        #[cfg(feature = "fdb-7_3")]
+        let prefix = tenant_info.id.to_be_bytes().to_vec();
+        Ok(TenantInfo {
+            name: tenant_name.to_vec(),
+            id: tenant_info.id,
+            prefix,
+        })
     }
 }
 
@@ -231,10 +248,50 @@ impl TryFrom<(&[u8], &[u8])> for TenantInfo {
 #[derive(Serialize, Deserialize, Debug)]
 struct FDBTenantInfo {
     id: i64,
+    #[cfg(not(feature = "fdb-7_3"))]
     #[serde(with = "serde_bytes")]
     prefix: Vec<u8>,
 }
 
+/// The error returned by [`TenantManagement::rename_tenant`]
+#[cfg(feature = "fdb-7_3")]
+#[derive(Debug, Clone, Copy)]
+pub enum RenameTenantError {
+    /// No tenant has the old name
+    SourceMissing,
+    /// A tenant already has the new name
+    DestinationExists,
+    /// The rename could not be committed
+    FdbError(FdbError),
+}
+
+#[cfg(feature = "fdb-7_3")]
+impl std::fmt::Display for RenameTenantError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenameTenantError::SourceMissing => write!(f, "the tenant to rename does not exist"),
+            RenameTenantError::DestinationExists => {
+                write!(f, "a tenant already exists with the new name")
+            }
+            RenameTenantError::FdbError(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "fdb-7_3")]
+impl std::error::Error for RenameTenantError {}
+
+#[cfg(feature = "fdb-7_3")]
+impl From<FdbError> for RenameTenantError {
+    fn from(err: FdbError) -> Self {
+        match err.code() {
+            TENANT_NOT_FOUND => RenameTenantError::SourceMissing,
+            TENANT_ALREADY_EXISTS => RenameTenantError::DestinationExists,
+            _ => RenameTenantError::FdbError(err),
+        }
+    }
+}
+
 /// The FoundationDB API includes function to manage the set of tenants in a cluster.
 // This is a port from https://github.com/apple/foundationdb/blob/87ee0a2963f615079b3f50afa332acd0ead5f1fe/bindings/java/src/main/com/apple/foundationdb/TenantManagement.java
 #[derive(Debug)]
@@ -262,7 +319,7 @@ impl TenantManagement {
                 trx.set(key_ref, &[]);
                 Ok(())
             } else {
-                let maybe_key = trx.get(key_ref, false).await?;
+                let maybe_key = trx.read(key_ref).await?;
 
                 checked_existence_ref.store(true, Ordering::SeqCst);
 
@@ -271,10 +328,7 @@ impl TenantManagement {
                         trx.set(key_ref, &[]);
                         Ok(())
                     }
-                    Some(_) => {
-                        // `tenant_already_exists` error
-                        Err(FdbBindingError::from(FdbError::new(2132)))
-                    }
+                    Some(_) => Err(FdbBindingError::from(FdbError::new(TENANT_ALREADY_EXISTS))),
                 }
             }
         })
@@ -298,7 +352,7 @@ impl TenantManagement {
                 trx.set_option(TransactionOption::ReadSystemKeys)?;
                 trx.set_option(TransactionOption::ReadLockAware)?;
 
-                Ok(trx.get(key_ref, false).await?)
+                Ok(trx.read(key_ref).await?)
             })
             .await
         {
@@ -330,15 +384,12 @@ impl TenantManagement {
                 trx.clear(key_ref);
                 Ok(())
             } else {
-                let maybe_key = trx.get(key_ref, false).await?;
+                let maybe_key = trx.read(key_ref).await?;
 
                 checked_existence_ref.store(true, Ordering::SeqCst);
 
                 match maybe_key {
-                    None => {
-                        // `tenant_not_found` error
-                        Err(FdbBindingError::from(FdbError::new(2131)))
-                    }
+                    None => Err(FdbBindingError::from(FdbError::new(TENANT_NOT_FOUND))),
                     Some(_) => {
                         trx.clear(key_ref);
                         Ok(())
@@ -351,6 +402,66 @@ impl TenantManagement {
         .map_err(|e| e.get_fdb_error().unwrap())
     }
 
+    /// Renames a tenant of the cluster using a transaction created on the specified `Database`,
+    /// without moving its data.
+    ///
+    /// This operation will first check that the tenant exists and that the new name is free, and
+    /// will otherwise return a [`RenameTenantError::SourceMissing`] or a
+    /// [`RenameTenantError::DestinationExists`] error. Then, it will attempt to rename the tenant
+    /// in a retry loop. If the tenant is renamed concurrently by another transaction, this
+    /// function may return one of those errors although the tenant was renamed.
+    ///
+    /// Tenants are looked up by name when opened: once renamed, a tenant opened on the old name
+    /// fails with a `tenant_not_found` error, it has to be opened on the new name instead.
+    #[cfg(feature = "fdb-7_3")]
+    pub async fn rename_tenant(
+        db: &Database,
+        old_name: &[u8],
+        new_name: &[u8],
+    ) -> Result<(), RenameTenantError> {
+        let checked_existence = AtomicBool::new(false);
+        let checked_existence_ref = &checked_existence;
+
+        let mut old_key: Vec<u8> = Vec::with_capacity(TENANT_MAP_PREFIX.len() + old_name.len());
+        old_key.extend_from_slice(TENANT_MAP_PREFIX);
+        old_key.extend_from_slice(old_name);
+
+        let mut new_key: Vec<u8> = Vec::with_capacity(TENANT_MAP_PREFIX.len() + new_name.len());
+        new_key.extend_from_slice(TENANT_MAP_PREFIX);
+        new_key.extend_from_slice(new_name);
+
+        let mut rename_key: Vec<u8> =
+            Vec::with_capacity(TENANT_RENAME_PREFIX.len() + old_name.len());
+        rename_key.extend_from_slice(TENANT_RENAME_PREFIX);
+        rename_key.extend_from_slice(old_name);
+
+        let (old_key_ref, new_key_ref, rename_key_ref) = (&old_key, &new_key, &rename_key);
+
+        db.run(|trx, _maybe_committed| async move {
+            trx.set_option(TransactionOption::SpecialKeySpaceEnableWrites)?;
+
+            if !checked_existence_ref.load(Ordering::SeqCst) {
+                let (source, destination) =
+                    futures::future::try_join(trx.read(old_key_ref), trx.read(new_key_ref)).await?;
+
+                checked_existence_ref.store(true, Ordering::SeqCst);
+
+                if source.is_none() {
+                    return Err(FdbBindingError::from(FdbError::new(TENANT_NOT_FOUND)));
+                }
+                if destination.is_some() {
+                    return Err(FdbBindingError::from(FdbError::new(TENANT_ALREADY_EXISTS)));
+                }
+            }
+
+            trx.set(rename_key_ref, new_name);
+            Ok(())
+        })
+        .await
+        // error can only be an fdb_error
+        .map_err(|e| RenameTenantError::from(e.get_fdb_error().unwrap()))
+    }
+
     /// Lists all tenants in between the range specified. The number of tenants listed can be restricted.
     /// This is a convenience method that generates the begin and end ranges by packing two Tuples.
     pub async fn list_tenant(
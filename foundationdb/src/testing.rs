@@ -0,0 +1,39 @@
+// Copyright 2018 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Integration tests running against a FoundationDB cluster.
+//!
+//! The [`test`] attribute lives in this module rather than at the root of the crate, so that
+//! `use foundationdb::*;` does not shadow the built-in `#[test]`.
+
+/// Turns an `async fn(db: Database, subspace: Subspace)` into a test.
+///
+/// The test runs against the database of the default cluster file, or of `FDB_CLUSTER_FILE`,
+/// for instance one provisioned for the tests, with a subspace of its own which is cleared
+/// before and after the test, even if it panics. The network is booted by the first test and
+/// keeps running until the test binary exits. The function returns `()` or `Result<(), E>`,
+/// the test failing on `Err`. Like [`main`](crate::main), `#[foundationdb::testing::test(tokio)]`
+/// runs it on a tokio runtime.
+///
+/// ```no_run
+/// use foundationdb::tuple::Subspace;
+/// use foundationdb::{Database, FdbResult};
+///
+/// #[foundationdb::testing::test]
+/// async fn set_and_get(db: Database, subspace: Subspace) -> FdbResult<()> {
+///     let trx = db.create_trx()?;
+///     trx.set(&subspace.pack(&"key"), b"value");
+///     assert!(trx.read(&subspace.pack(&"key")).await?.is_some());
+///     Ok(())
+/// }
+/// ```
+///
+/// ```compile_fail
+/// #[foundationdb::testing::test]
+/// async fn no_subspace(db: foundationdb::Database) {} // the subspace argument is missing
+/// ```
+pub use foundationdb_macros::test;
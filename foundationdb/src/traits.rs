@@ -0,0 +1,956 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Traits abstracting over transactions, to write layers working with any implementation.
+//!
+//! [`ReadTransaction`] holds the reads and [`MutateTransaction`] the writes of a transaction.
+//! They are implemented by [`Transaction`], [`RetryableTransaction`] and
+//! [`MockTransaction`](crate::mock::MockTransaction), and [`Snapshot`] implements
+//! `ReadTransaction` alone, to hand out a read-only view.
+//!
+//! Both traits are object safe, so layers can take a `&dyn MutateTransaction` without being
+//! generic, as the [`Directory`](crate::directory::Directory) trait does. This has a cost: the
+//! futures are boxed and the values are copied out of the FoundationDB futures, code on a hot
+//! path is better off with the inherent methods of `Transaction`.
+//!
+//! Like the inherent methods, reads are issued when called, not when the returned future is
+//! first polled, and the returned futures do not borrow the parameters.
+
+use std::ops::Deref;
+
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::{future, stream, FutureExt, StreamExt, TryFutureExt};
+
+use crate::error::RangeTooLarge;
+use crate::future::{FdbKeyValue, FdbValues};
+use crate::options;
+use crate::{
+    FdbBindingError, FdbResult, Key, KeySelector, RangeOption, RetryableTransaction, Transaction,
+    Value,
+};
+use foundationdb_macros::cfg_api_versions;
+
+/// A key-value pair, owned.
+///
+/// Its `Debug` output shows the key and the value like [`Key`] and [`Value`] do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyValue {
+    key: Key,
+    value: Value,
+}
+
+impl KeyValue {
+    /// Creates a new pair.
+    pub fn new(key: Vec<u8>, value: Vec<u8>) -> Self {
+        KeyValue {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Returns the key.
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Returns the value.
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+
+    /// Returns the key and the value.
+    pub fn into_parts(self) -> (Vec<u8>, Vec<u8>) {
+        (self.key.into_bytes(), self.value.into_bytes())
+    }
+
+    /// Returns the key and the value, as [`Key`] and [`Value`].
+    pub fn into_key_value(self) -> (Key, Value) {
+        (self.key, self.value)
+    }
+}
+
+impl From<&FdbKeyValue> for KeyValue {
+    fn from(kv: &FdbKeyValue) -> Self {
+        KeyValue::new(kv.key().to_vec(), kv.value().to_vec())
+    }
+}
+
+/// A batch of key-value pairs read by [`ReadTransaction::get_range`], owned.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyValues {
+    key_values: Vec<KeyValue>,
+    more: bool,
+}
+
+impl KeyValues {
+    /// Creates a new batch, `more` telling if the range holds more pairs.
+    pub fn new(key_values: Vec<KeyValue>, more: bool) -> Self {
+        KeyValues { key_values, more }
+    }
+
+    /// Returns if the range holds more pairs than returned in this batch.
+    pub fn more(&self) -> bool {
+        self.more
+    }
+}
+
+impl From<&FdbValues> for KeyValues {
+    fn from(values: &FdbValues) -> Self {
+        KeyValues::new(values.iter().map(KeyValue::from).collect(), values.more())
+    }
+}
+
+impl Deref for KeyValues {
+    type Target = [KeyValue];
+
+    fn deref(&self) -> &Self::Target {
+        &self.key_values
+    }
+}
+
+impl IntoIterator for KeyValues {
+    type Item = KeyValue;
+    type IntoIter = std::vec::IntoIter<KeyValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.key_values.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a KeyValues {
+    type Item = &'a KeyValue;
+    type IntoIter = std::slice::Iter<'a, KeyValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.key_values.iter()
+    }
+}
+
+/// The reads of a transaction, see [`Transaction`] for their documentation.
+///
+/// The `read` methods are serializable reads, the `snapshot_read` ones are snapshot reads, which
+/// are usually made through the read-only view returned by [`snapshot`](Self::snapshot).
+pub trait ReadTransaction: Send + Sync {
+    /// Reads a value, see [`Transaction::read`].
+    fn read(&self, key: &[u8]) -> BoxFuture<'_, FdbResult<Option<Vec<u8>>>>;
+
+    /// Resolves a key selector, see [`Transaction::read_key`].
+    fn read_key(&self, selector: &KeySelector) -> BoxFuture<'_, FdbResult<Vec<u8>>>;
+
+    /// Reads a batch of key-value pairs, see [`Transaction::read_range`].
+    fn read_range(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+    ) -> BoxFuture<'_, FdbResult<KeyValues>>;
+
+    /// Reads a value as a snapshot read, see [`Snapshot::get`].
+    fn snapshot_read(&self, key: &[u8]) -> BoxFuture<'_, FdbResult<Option<Vec<u8>>>>;
+
+    /// Resolves a key selector as a snapshot read, see [`Snapshot::get_key`].
+    fn snapshot_read_key(&self, selector: &KeySelector) -> BoxFuture<'_, FdbResult<Vec<u8>>>;
+
+    /// Reads a batch of key-value pairs as a snapshot read, see [`Snapshot::get_range`].
+    fn snapshot_read_range(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+    ) -> BoxFuture<'_, FdbResult<KeyValues>>;
+
+    /// Returns the read version of the transaction, see [`Transaction::get_read_version`].
+    fn get_read_version(&self) -> BoxFuture<'_, FdbResult<i64>>;
+
+    /// Estimates the byte size of a range, see [`Transaction::get_estimated_range_size_bytes`].
+    #[cfg_api_versions(min = 630)]
+    fn get_estimated_range_size_bytes(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+    ) -> BoxFuture<'_, FdbResult<i64>>;
+
+    /// Reads all the key-value pairs of a range, batch after batch, see
+    /// [`Transaction::read_ranges`].
+    fn read_ranges<'a>(&'a self, opt: RangeOption<'a>) -> BoxStream<'a, FdbResult<KeyValues>> {
+        read_ranges_as(self, opt, false)
+    }
+
+    /// Reads a value, see [`Transaction::get`].
+    #[deprecated(note = "use `read`, or `snapshot().get` for a snapshot read")]
+    fn get(&self, key: &[u8], snapshot: bool) -> BoxFuture<'_, FdbResult<Option<Vec<u8>>>> {
+        read_as(self, key, snapshot)
+    }
+
+    /// Resolves a key selector, see [`Transaction::get_key`].
+    #[deprecated(note = "use `read_key`, or `snapshot().get_key` for a snapshot read")]
+    fn get_key(&self, selector: &KeySelector, snapshot: bool) -> BoxFuture<'_, FdbResult<Vec<u8>>> {
+        read_key_as(self, selector, snapshot)
+    }
+
+    /// Reads a batch of key-value pairs, see [`Transaction::get_range`].
+    #[deprecated(note = "use `read_range`, or `snapshot().get_range` for a snapshot read")]
+    fn get_range(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+        snapshot: bool,
+    ) -> BoxFuture<'_, FdbResult<KeyValues>> {
+        read_range_as(self, opt, iteration, snapshot)
+    }
+
+    /// Reads all the key-value pairs of a range, batch after batch, see
+    /// [`Transaction::get_ranges`].
+    #[deprecated(note = "use `read_ranges`, or `snapshot().get_ranges` for a snapshot read")]
+    fn get_ranges<'a>(
+        &'a self,
+        opt: RangeOption<'a>,
+        snapshot: bool,
+    ) -> BoxStream<'a, FdbResult<KeyValues>> {
+        read_ranges_as(self, opt, snapshot)
+    }
+
+    /// Reads all the key-value pairs of a small range at once, see
+    /// [`Transaction::get_range_all`].
+    fn get_range_all(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+        max_rows: usize,
+        max_bytes: Option<usize>,
+        snapshot: bool,
+    ) -> BoxFuture<'_, Result<KeyValues, FdbBindingError>> {
+        // each batch stops around what is left of the byte cap, one byte more so that going over
+        // it is noticed, 0 being no cap
+        let target_bytes = move |read_bytes: usize| {
+            max_bytes.map_or(0, |max| max.saturating_sub(read_bytes).saturating_add(1))
+        };
+        let opt = RangeOption {
+            // the pair after the last one returned tells if the range is truncated
+            limit: Some(max_rows.saturating_add(1)),
+            target_bytes: target_bytes(0),
+            mode: options::StreamingMode::WantAll,
+            ..RangeOption::from((begin.to_vec(), end.to_vec()))
+        };
+        let first = read_range_as(self, &opt, 1, snapshot);
+        async move {
+            let mut key_values = Vec::new();
+            let mut read_bytes = 0;
+            let mut batch = first.await?;
+            let mut next = Some(opt);
+            for iteration in 2.. {
+                next = next.and_then(|opt| {
+                    opt.next_range_after(batch.more(), batch.len(), batch.last().map(KeyValue::key))
+                });
+                for key_value in batch {
+                    if key_values.len() == max_rows {
+                        return Ok(KeyValues::new(key_values, true));
+                    }
+                    read_bytes += key_value.key().len() + key_value.value().len();
+                    if let Some(max_bytes) = max_bytes.filter(|max| read_bytes > *max) {
+                        return Err(RangeTooLarge {
+                            max_bytes,
+                            read_bytes,
+                        }
+                        .into());
+                    }
+                    key_values.push(key_value);
+                }
+                match &mut next {
+                    Some(opt) => {
+                        opt.target_bytes = target_bytes(read_bytes);
+                        batch = read_range_as(self, opt, iteration, snapshot).await?;
+                    }
+                    None => break,
+                }
+            }
+            Ok(KeyValues::new(key_values, false))
+        }
+        .boxed()
+    }
+
+    /// Returns a read-only view of this transaction, whose reads are all snapshot reads.
+    fn snapshot(&self) -> Snapshot<'_, Self>
+    where
+        Self: Sized,
+    {
+        Snapshot::new(self)
+    }
+}
+
+/// Reads a value, as a snapshot read or not.
+pub(crate) fn read_as<'a, T: ReadTransaction + ?Sized>(
+    trx: &'a T,
+    key: &[u8],
+    snapshot: bool,
+) -> BoxFuture<'a, FdbResult<Option<Vec<u8>>>> {
+    if snapshot {
+        trx.snapshot_read(key)
+    } else {
+        trx.read(key)
+    }
+}
+
+/// Resolves a key selector, as a snapshot read or not.
+pub(crate) fn read_key_as<'a, T: ReadTransaction + ?Sized>(
+    trx: &'a T,
+    selector: &KeySelector,
+    snapshot: bool,
+) -> BoxFuture<'a, FdbResult<Vec<u8>>> {
+    if snapshot {
+        trx.snapshot_read_key(selector)
+    } else {
+        trx.read_key(selector)
+    }
+}
+
+/// Reads a batch of key-value pairs, as a snapshot read or not.
+pub(crate) fn read_range_as<'a, T: ReadTransaction + ?Sized>(
+    trx: &'a T,
+    opt: &RangeOption,
+    iteration: usize,
+    snapshot: bool,
+) -> BoxFuture<'a, FdbResult<KeyValues>> {
+    if snapshot {
+        trx.snapshot_read_range(opt, iteration)
+    } else {
+        trx.read_range(opt, iteration)
+    }
+}
+
+/// Reads all the key-value pairs of a range, batch after batch, as snapshot reads or not.
+fn read_ranges_as<'a, T: ReadTransaction + ?Sized>(
+    trx: &'a T,
+    opt: RangeOption<'a>,
+    snapshot: bool,
+) -> BoxStream<'a, FdbResult<KeyValues>> {
+    stream::unfold((1, Some(opt)), move |(iteration, maybe_opt)| {
+        if let Some(opt) = maybe_opt {
+            read_range_as(trx, &opt, iteration, snapshot)
+                .map(move |maybe_values| {
+                    let next_opt = match &maybe_values {
+                        Ok(values) => opt.next_range_after(
+                            values.more(),
+                            values.len(),
+                            values.last().map(KeyValue::key),
+                        ),
+                        Err(..) => None,
+                    };
+                    Some((maybe_values, (iteration + 1, next_opt)))
+                })
+                .left_future()
+        } else {
+            future::ready(None).right_future()
+        }
+    })
+    .boxed()
+}
+
+/// The writes of a transaction, see [`Transaction`] for their documentation.
+pub trait MutateTransaction: ReadTransaction {
+    /// Sets the value of a key, see [`Transaction::set`].
+    fn set(&self, key: &[u8], value: &[u8]);
+
+    /// Clears a key, see [`Transaction::clear`].
+    fn clear(&self, key: &[u8]);
+
+    /// Clears a range of keys, see [`Transaction::clear_range`].
+    fn clear_range(&self, begin: &[u8], end: &[u8]);
+
+    /// Performs an atomic operation, see [`Transaction::atomic_op`].
+    fn atomic_op(&self, key: &[u8], param: &[u8], op_type: options::MutationType);
+
+    /// Adds `delta` to the little-endian `i64` stored in `key`, with `MutationType::Add`.
+    ///
+    /// A missing key counts as 0, and the sum wraps around on overflow. Read it back with
+    /// [`counter::read_i64`](crate::counter::read_i64).
+    fn atomic_add_i64(&self, key: &[u8], delta: i64) {
+        self.atomic_op(key, &delta.to_le_bytes(), options::MutationType::Add)
+    }
+
+    /// Sets `key` to the smaller of its little-endian `u64` value and `value`, with
+    /// `MutationType::Min`. A missing key is set to `value`.
+    fn atomic_min_u64(&self, key: &[u8], value: u64) {
+        self.atomic_op(key, &value.to_le_bytes(), options::MutationType::Min)
+    }
+
+    /// Sets `key` to the larger of its little-endian `u64` value and `value`, with
+    /// `MutationType::Max`. A missing key is set to `value`.
+    fn atomic_max_u64(&self, key: &[u8], value: u64) {
+        self.atomic_op(key, &value.to_le_bytes(), options::MutationType::Max)
+    }
+
+    /// Bitwise ands the value of `key` with `mask`, with `MutationType::BitAnd`.
+    ///
+    /// The value is truncated or zero extended to the length of `mask` first, a missing key is
+    /// set to `mask`.
+    fn atomic_bit_and(&self, key: &[u8], mask: &[u8]) {
+        self.atomic_op(key, mask, options::MutationType::BitAnd)
+    }
+
+    /// Bitwise ors the value of `key` with `mask`, with `MutationType::BitOr`.
+    ///
+    /// The value is truncated or zero extended to the length of `mask` first.
+    fn atomic_bit_or(&self, key: &[u8], mask: &[u8]) {
+        self.atomic_op(key, mask, options::MutationType::BitOr)
+    }
+
+    /// Bitwise xors the value of `key` with `mask`, with `MutationType::BitXor`.
+    ///
+    /// The value is truncated or zero extended to the length of `mask` first.
+    fn atomic_bit_xor(&self, key: &[u8], mask: &[u8]) {
+        self.atomic_op(key, mask, options::MutationType::BitXor)
+    }
+
+    /// Appends `suffix` to the value of `key` if the result fits in a value, with
+    /// `MutationType::AppendIfFits`. Nothing is written otherwise.
+    fn atomic_append_if_fits(&self, key: &[u8], suffix: &[u8]) {
+        self.atomic_op(key, suffix, options::MutationType::AppendIfFits)
+    }
+
+    /// Clears `key` if its value is byte-for-byte `expected`, see
+    /// [`Transaction::compare_and_clear`].
+    #[cfg_api_versions(min = 610)]
+    fn atomic_compare_and_clear(&self, key: &[u8], expected: &[u8]) {
+        self.atomic_op(key, expected, options::MutationType::CompareAndClear)
+    }
+
+    /// Adds a conflict range, see [`Transaction::add_conflict_range`].
+    fn add_conflict_range(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+        ty: options::ConflictRangeType,
+    ) -> FdbResult<()>;
+
+    /// Adds a read conflict range covering only `key`, see
+    /// [`Transaction::add_read_conflict_key`].
+    fn add_read_conflict_key(&self, key: &[u8]) -> FdbResult<()> {
+        let end = crate::transaction::key_after(key);
+        self.add_conflict_range(key, &end, options::ConflictRangeType::Read)
+    }
+
+    /// Adds a write conflict range covering only `key`, see
+    /// [`Transaction::add_write_conflict_key`].
+    fn add_write_conflict_key(&self, key: &[u8]) -> FdbResult<()> {
+        let end = crate::transaction::key_after(key);
+        self.add_conflict_range(key, &end, options::ConflictRangeType::Write)
+    }
+
+    /// Sets an option, see [`Transaction::set_option`].
+    fn set_option(&self, opt: options::TransactionOption) -> Result<(), options::OptionError>;
+}
+
+impl ReadTransaction for Transaction {
+    fn read(&self, key: &[u8]) -> BoxFuture<'_, FdbResult<Option<Vec<u8>>>> {
+        Transaction::read(self, key)
+            .map_ok(|value| value.map(|value| value.to_vec()))
+            .boxed()
+    }
+
+    fn read_key(&self, selector: &KeySelector) -> BoxFuture<'_, FdbResult<Vec<u8>>> {
+        Transaction::read_key(self, selector)
+            .map_ok(|key| key.to_vec())
+            .boxed()
+    }
+
+    fn read_range(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+    ) -> BoxFuture<'_, FdbResult<KeyValues>> {
+        Transaction::read_range(self, opt, iteration)
+            .map_ok(|values| KeyValues::from(&values))
+            .boxed()
+    }
+
+    fn snapshot_read(&self, key: &[u8]) -> BoxFuture<'_, FdbResult<Option<Vec<u8>>>> {
+        self.fdb_get(key, true)
+            .map_ok(|value| value.map(|value| value.to_vec()))
+            .boxed()
+    }
+
+    fn snapshot_read_key(&self, selector: &KeySelector) -> BoxFuture<'_, FdbResult<Vec<u8>>> {
+        self.fdb_get_key(selector, true)
+            .map_ok(|key| key.to_vec())
+            .boxed()
+    }
+
+    fn snapshot_read_range(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+    ) -> BoxFuture<'_, FdbResult<KeyValues>> {
+        self.fdb_get_range(opt, iteration, true)
+            .map_ok(|values| KeyValues::from(&values))
+            .boxed()
+    }
+
+    fn get_read_version(&self) -> BoxFuture<'_, FdbResult<i64>> {
+        Transaction::get_read_version(self).boxed()
+    }
+
+    #[cfg_api_versions(min = 630)]
+    fn get_estimated_range_size_bytes(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+    ) -> BoxFuture<'_, FdbResult<i64>> {
+        Transaction::get_estimated_range_size_bytes(self, begin, end).boxed()
+    }
+}
+
+impl MutateTransaction for Transaction {
+    fn set(&self, key: &[u8], value: &[u8]) {
+        Transaction::set(self, key, value)
+    }
+
+    fn clear(&self, key: &[u8]) {
+        Transaction::clear(self, key)
+    }
+
+    fn clear_range(&self, begin: &[u8], end: &[u8]) {
+        Transaction::clear_range(self, begin, end)
+    }
+
+    fn atomic_op(&self, key: &[u8], param: &[u8], op_type: options::MutationType) {
+        Transaction::atomic_op(self, key, param, op_type)
+    }
+
+    fn add_conflict_range(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+        ty: options::ConflictRangeType,
+    ) -> FdbResult<()> {
+        Transaction::add_conflict_range(self, begin, end, ty)
+    }
+
+    fn set_option(&self, opt: options::TransactionOption) -> Result<(), options::OptionError> {
+        Transaction::set_option(self, opt)
+    }
+}
+
+impl ReadTransaction for RetryableTransaction {
+    fn read(&self, key: &[u8]) -> BoxFuture<'_, FdbResult<Option<Vec<u8>>>> {
+        ReadTransaction::read(&**self, key)
+    }
+
+    fn read_key(&self, selector: &KeySelector) -> BoxFuture<'_, FdbResult<Vec<u8>>> {
+        ReadTransaction::read_key(&**self, selector)
+    }
+
+    fn read_range(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+    ) -> BoxFuture<'_, FdbResult<KeyValues>> {
+        ReadTransaction::read_range(&**self, opt, iteration)
+    }
+
+    fn snapshot_read(&self, key: &[u8]) -> BoxFuture<'_, FdbResult<Option<Vec<u8>>>> {
+        ReadTransaction::snapshot_read(&**self, key)
+    }
+
+    fn snapshot_read_key(&self, selector: &KeySelector) -> BoxFuture<'_, FdbResult<Vec<u8>>> {
+        ReadTransaction::snapshot_read_key(&**self, selector)
+    }
+
+    fn snapshot_read_range(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+    ) -> BoxFuture<'_, FdbResult<KeyValues>> {
+        ReadTransaction::snapshot_read_range(&**self, opt, iteration)
+    }
+
+    fn get_read_version(&self) -> BoxFuture<'_, FdbResult<i64>> {
+        ReadTransaction::get_read_version(&**self)
+    }
+
+    #[cfg_api_versions(min = 630)]
+    fn get_estimated_range_size_bytes(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+    ) -> BoxFuture<'_, FdbResult<i64>> {
+        ReadTransaction::get_estimated_range_size_bytes(&**self, begin, end)
+    }
+}
+
+impl MutateTransaction for RetryableTransaction {
+    fn set(&self, key: &[u8], value: &[u8]) {
+        Transaction::set(self, key, value)
+    }
+
+    fn clear(&self, key: &[u8]) {
+        Transaction::clear(self, key)
+    }
+
+    fn clear_range(&self, begin: &[u8], end: &[u8]) {
+        Transaction::clear_range(self, begin, end)
+    }
+
+    fn atomic_op(&self, key: &[u8], param: &[u8], op_type: options::MutationType) {
+        Transaction::atomic_op(self, key, param, op_type)
+    }
+
+    fn add_conflict_range(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+        ty: options::ConflictRangeType,
+    ) -> FdbResult<()> {
+        Transaction::add_conflict_range(self, begin, end, ty)
+    }
+
+    fn set_option(&self, opt: options::TransactionOption) -> Result<(), options::OptionError> {
+        Transaction::set_option(self, opt)
+    }
+}
+
+/// A read-only view of a transaction, whose reads are all snapshot reads.
+///
+/// Created with [`Transaction::snapshot`], [`ReadTransaction::snapshot`], or [`Snapshot::new`]
+/// for trait objects. Its `ReadTransaction` reads are snapshot reads too, including the
+/// `read` ones. Snapshot reads add no read conflict: the transaction does not fail to commit
+/// when the keys it read this way are changed concurrently.
+#[derive(Debug)]
+pub struct Snapshot<'a, T: ?Sized> {
+    trx: &'a T,
+}
+
+impl<'a, T: ?Sized> Clone for Snapshot<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: ?Sized> Copy for Snapshot<'a, T> {}
+
+impl<'a, T: ReadTransaction + ?Sized> Snapshot<'a, T> {
+    /// Creates a read-only view of `trx`.
+    pub fn new(trx: &'a T) -> Self {
+        Snapshot { trx }
+    }
+
+    /// Reads a value as a snapshot read, see [`Transaction::read`].
+    pub fn get(&self, key: &[u8]) -> BoxFuture<'a, FdbResult<Option<Vec<u8>>>> {
+        self.trx.snapshot_read(key)
+    }
+
+    /// Resolves a key selector as a snapshot read, see [`Transaction::read_key`].
+    pub fn get_key(&self, selector: &KeySelector) -> BoxFuture<'a, FdbResult<Vec<u8>>> {
+        self.trx.snapshot_read_key(selector)
+    }
+
+    /// Reads a batch of key-value pairs as a snapshot read, see [`Transaction::read_range`].
+    pub fn get_range(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+    ) -> BoxFuture<'a, FdbResult<KeyValues>> {
+        self.trx.snapshot_read_range(opt, iteration)
+    }
+
+    /// Reads all the key-value pairs of a range as snapshot reads, see
+    /// [`Transaction::read_ranges`].
+    pub fn get_ranges<'b>(&self, opt: RangeOption<'b>) -> BoxStream<'b, FdbResult<KeyValues>>
+    where
+        'a: 'b,
+    {
+        read_ranges_as(self.trx, opt, true)
+    }
+}
+
+impl<'a, T: ReadTransaction + ?Sized> ReadTransaction for Snapshot<'a, T> {
+    fn read(&self, key: &[u8]) -> BoxFuture<'_, FdbResult<Option<Vec<u8>>>> {
+        self.trx.snapshot_read(key)
+    }
+
+    fn read_key(&self, selector: &KeySelector) -> BoxFuture<'_, FdbResult<Vec<u8>>> {
+        self.trx.snapshot_read_key(selector)
+    }
+
+    fn read_range(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+    ) -> BoxFuture<'_, FdbResult<KeyValues>> {
+        self.trx.snapshot_read_range(opt, iteration)
+    }
+
+    fn snapshot_read(&self, key: &[u8]) -> BoxFuture<'_, FdbResult<Option<Vec<u8>>>> {
+        self.trx.snapshot_read(key)
+    }
+
+    fn snapshot_read_key(&self, selector: &KeySelector) -> BoxFuture<'_, FdbResult<Vec<u8>>> {
+        self.trx.snapshot_read_key(selector)
+    }
+
+    fn snapshot_read_range(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+    ) -> BoxFuture<'_, FdbResult<KeyValues>> {
+        self.trx.snapshot_read_range(opt, iteration)
+    }
+
+    fn get_read_version(&self) -> BoxFuture<'_, FdbResult<i64>> {
+        self.trx.get_read_version()
+    }
+
+    #[cfg_api_versions(min = 630)]
+    fn get_estimated_range_size_bytes(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+    ) -> BoxFuture<'_, FdbResult<i64>> {
+        self.trx.get_estimated_range_size_bytes(begin, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockDatabase, MockTransaction};
+    use futures::executor::block_on;
+    use futures::TryStreamExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Returns at most `batch_size` pairs per read, like a cluster would for a large range.
+    struct Batched {
+        trx: MockTransaction,
+        batch_size: usize,
+        reads: AtomicUsize,
+    }
+
+    impl Batched {
+        fn batch<'o>(&self, opt: &RangeOption<'o>) -> RangeOption<'o> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            RangeOption {
+                limit: Some(
+                    opt.limit
+                        .map_or(self.batch_size, |l| l.min(self.batch_size)),
+                ),
+                ..opt.clone()
+            }
+        }
+    }
+
+    impl ReadTransaction for Batched {
+        fn read(&self, key: &[u8]) -> BoxFuture<'_, FdbResult<Option<Vec<u8>>>> {
+            self.trx.read(key)
+        }
+
+        fn read_key(&self, selector: &KeySelector) -> BoxFuture<'_, FdbResult<Vec<u8>>> {
+            self.trx.read_key(selector)
+        }
+
+        fn read_range(
+            &self,
+            opt: &RangeOption,
+            iteration: usize,
+        ) -> BoxFuture<'_, FdbResult<KeyValues>> {
+            self.trx.read_range(&self.batch(opt), iteration)
+        }
+
+        fn snapshot_read(&self, key: &[u8]) -> BoxFuture<'_, FdbResult<Option<Vec<u8>>>> {
+            self.trx.snapshot_read(key)
+        }
+
+        fn snapshot_read_key(&self, selector: &KeySelector) -> BoxFuture<'_, FdbResult<Vec<u8>>> {
+            self.trx.snapshot_read_key(selector)
+        }
+
+        fn snapshot_read_range(
+            &self,
+            opt: &RangeOption,
+            iteration: usize,
+        ) -> BoxFuture<'_, FdbResult<KeyValues>> {
+            self.trx.snapshot_read_range(&self.batch(opt), iteration)
+        }
+
+        fn get_read_version(&self) -> BoxFuture<'_, FdbResult<i64>> {
+            self.trx.get_read_version()
+        }
+
+        #[cfg_api_versions(min = 630)]
+        fn get_estimated_range_size_bytes(
+            &self,
+            begin: &[u8],
+            end: &[u8],
+        ) -> BoxFuture<'_, FdbResult<i64>> {
+            self.trx.get_estimated_range_size_bytes(begin, end)
+        }
+    }
+
+    fn batched(rows: u8, batch_size: usize) -> Batched {
+        let db = MockDatabase::new();
+        let trx = db.create_trx().unwrap();
+        for i in 0..rows {
+            trx.set(&[b'k', i], &[i; 10]);
+        }
+        Batched {
+            trx,
+            batch_size,
+            reads: AtomicUsize::new(0),
+        }
+    }
+
+    #[test]
+    fn get_range_all_assembles_batches() {
+        let trx = batched(25, 10);
+        let values = block_on(trx.get_range_all(b"k", b"l", 100, None, false)).unwrap();
+        assert_eq!(values.len(), 25);
+        assert!(!values.more());
+        assert_eq!(trx.reads.load(Ordering::SeqCst), 3);
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(value.key(), &[b'k', i as u8]);
+        }
+
+        // exactly `max_rows` pairs are not truncated
+        let values = block_on(trx.get_range_all(b"k", b"l", 25, None, false)).unwrap();
+        assert_eq!(values.len(), 25);
+        assert!(!values.more());
+
+        let values = block_on(trx.get_range_all(b"m", b"n", 25, None, false)).unwrap();
+        assert!(values.is_empty());
+        assert!(!values.more());
+    }
+
+    #[test]
+    fn get_range_all_truncates() {
+        let trx = batched(25, 10);
+        for max_rows in [0, 1, 10, 24] {
+            let values = block_on(trx.get_range_all(b"k", b"l", max_rows, None, false)).unwrap();
+            assert_eq!(values.len(), max_rows);
+            assert!(values.more());
+            if let Some(last) = values.last() {
+                assert_eq!(last.key(), &[b'k', max_rows as u8 - 1]);
+            }
+        }
+    }
+
+    #[test]
+    fn get_range_all_byte_cap() {
+        let trx = batched(25, 10);
+        // each pair holds 12 bytes
+        let values = block_on(trx.get_range_all(b"k", b"l", 100, Some(300), false)).unwrap();
+        assert_eq!(values.len(), 25);
+
+        match block_on(trx.get_range_all(b"k", b"l", 100, Some(299), false)) {
+            Err(FdbBindingError::RangeTooLarge(err)) => assert_eq!(
+                err,
+                RangeTooLarge {
+                    max_bytes: 299,
+                    read_bytes: 300
+                }
+            ),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // the byte cap applies to the pairs returned only
+        block_on(trx.get_range_all(b"k", b"l", 10, Some(120), false)).unwrap();
+    }
+
+    #[test]
+    fn typed_atomic_ops() {
+        let db = MockDatabase::new();
+        let trx = db.create_trx().unwrap();
+        let value = |key: &[u8]| block_on(trx.read(key)).unwrap();
+
+        trx.atomic_add_i64(b"add", 5);
+        trx.atomic_add_i64(b"add", -7);
+        assert_eq!(value(b"add"), Some((-2i64).to_le_bytes().to_vec()));
+        // shorter values are zero extended
+        trx.set(b"short", &[0x01]);
+        trx.atomic_add_i64(b"short", 0x100);
+        assert_eq!(value(b"short"), Some(0x101i64.to_le_bytes().to_vec()));
+        assert_eq!(
+            block_on(crate::counter::read_i64(&trx, b"add")).unwrap(),
+            -2
+        );
+        trx.set(b"byte", &[0x02]);
+        assert_eq!(
+            block_on(crate::counter::read_i64(&trx, b"byte")).unwrap(),
+            2
+        );
+        assert_eq!(
+            block_on(crate::counter::read_i64(&trx, b"none")).unwrap(),
+            0
+        );
+
+        // compared as little-endian integers, not as bytes
+        trx.atomic_max_u64(b"max", 0x1ff);
+        trx.atomic_max_u64(b"max", 0x200);
+        trx.atomic_max_u64(b"max", 0xff);
+        assert_eq!(value(b"max"), Some(0x200u64.to_le_bytes().to_vec()));
+        trx.atomic_min_u64(b"min", 0x200);
+        trx.atomic_min_u64(b"min", 0x1ff);
+        trx.atomic_min_u64(b"min", u64::MAX);
+        assert_eq!(value(b"min"), Some(0x1ffu64.to_le_bytes().to_vec()));
+
+        trx.set(b"bits", &[0b1100, 0b1100]);
+        trx.atomic_bit_and(b"bits", &[0b1010, 0b1111]);
+        assert_eq!(value(b"bits"), Some(vec![0b1000, 0b1100]));
+        trx.atomic_bit_or(b"bits", &[0b0001, 0b0001]);
+        assert_eq!(value(b"bits"), Some(vec![0b1001, 0b1101]));
+        trx.atomic_bit_xor(b"bits", &[0b1111]);
+        assert_eq!(value(b"bits"), Some(vec![0b0110]));
+
+        #[cfg(any(
+            feature = "fdb-7_3",
+            feature = "fdb-7_1",
+            feature = "fdb-7_0",
+            feature = "fdb-6_3",
+            feature = "fdb-6_2",
+            feature = "fdb-6_1"
+        ))]
+        {
+            trx.atomic_compare_and_clear(b"bits", &[0b0111]);
+            assert_eq!(value(b"bits"), Some(vec![0b0110]));
+            trx.atomic_compare_and_clear(b"bits", &[0b0110]);
+            assert_eq!(value(b"bits"), None);
+        }
+    }
+
+    #[test]
+    fn snapshot_reads_do_not_conflict() {
+        block_on(async {
+            for snapshot in [true, false] {
+                let db = MockDatabase::new();
+                let reader = db.create_trx().unwrap();
+                let writer = db.create_trx().unwrap();
+                reader.get_read_version().await.unwrap();
+                if snapshot {
+                    assert_eq!(reader.snapshot().get(b"key").await.unwrap(), None);
+                    let values: Vec<KeyValues> = reader
+                        .snapshot()
+                        .get_ranges(RangeOption::from(&b"k"[..]..&b"l"[..]))
+                        .try_collect()
+                        .await
+                        .unwrap();
+                    assert!(values.iter().all(|values| values.is_empty()));
+                } else {
+                    assert_eq!(reader.read(b"key").await.unwrap(), None);
+                }
+
+                writer.set(b"key", b"value");
+                writer.commit().await.unwrap();
+
+                reader.set(b"other", b"value");
+                match reader.commit().await {
+                    Ok(()) => assert!(snapshot),
+                    Err(err) => {
+                        assert!(!snapshot);
+                        assert_eq!(err.code(), 1020);
+                    }
+                }
+            }
+        });
+    }
+}
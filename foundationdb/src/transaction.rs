@@ -11,16 +11,21 @@
 //! <https://apple.github.io/foundationdb/api-c.html#transaction>
 
 use foundationdb_sys as fdb_sys;
+use std::collections::HashMap;
 use std::fmt;
 use std::ops::{Deref, Range, RangeInclusive};
 use std::ptr::NonNull;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
 
 use crate::future::*;
 use crate::keyselector::*;
 use crate::options;
+use crate::options::OptionError;
 
-use crate::{error, FdbError, FdbResult};
+use crate::database::{millis, saturating_i32};
+use crate::{error, FdbError, FdbResult, KeyValues, ReadTransaction, Snapshot};
 use foundationdb_macros::cfg_api_versions;
 
 use crate::error::FdbBindingError;
@@ -60,6 +65,13 @@ impl TransactionCommitted {
         Ok(version)
     }
 
+    /// Returns the cost units the cluster charged to the committed transaction, see
+    /// [`Transaction::get_total_cost`].
+    #[cfg_api_versions(min = 730)]
+    pub fn get_total_cost(&self) -> impl Future<Output = FdbResult<i64>> + Send + Sync + Unpin {
+        self.tr.get_total_cost()
+    }
+
     /// Reset the transaction to its initial state.
     ///
     /// This will not affect previously committed data.
@@ -78,7 +90,8 @@ impl From<TransactionCommitted> for Transaction {
 
 /// A failed to commit transaction.
 pub struct TransactionCommitError {
-    tr: Transaction,
+    // boxed to keep the `Result` of a commit small
+    tr: Box<Transaction>,
     err: FdbError,
 }
 
@@ -96,7 +109,17 @@ impl TransactionCommitError {
         FdbFuture::<()>::new(unsafe {
             fdb_sys::fdb_transaction_on_error(self.tr.inner.as_ptr(), self.err.code())
         })
-        .map_ok(|()| self.tr)
+        .map(|r| {
+            r?;
+            self.tr.read_cache.clear();
+            self.tr.set_default_options()?;
+            Ok(*self.tr)
+        })
+    }
+
+    /// Returns the tags of the transaction which failed to commit, see [`Transaction::tags`].
+    pub fn tags(&self) -> Vec<String> {
+        self.tr.tags()
     }
 
     /// Reset the transaction to its initial state.
@@ -104,7 +127,7 @@ impl TransactionCommitError {
     /// This is similar to dropping the transaction and creating a new one.
     pub fn reset(mut self) -> Transaction {
         self.tr.reset();
-        self.tr
+        *self.tr
     }
 }
 
@@ -171,10 +194,96 @@ pub struct Transaction {
     // Order of fields should not be changed, because Rust drops field top-to-bottom, and
     // transaction should be dropped before cluster.
     inner: NonNull<fdb_sys::FDBTransaction>,
+    read_cache: ReadCache,
+    // set again whenever the transaction is reset, see `Database::set_default_transaction_options`
+    default_options: Vec<options::TransactionOption>,
+    // the tags given to `set_option`, reported when the transaction is throttled
+    tags: Mutex<Vec<String>>,
 }
 unsafe impl Send for Transaction {}
 unsafe impl Sync for Transaction {}
 
+/// Values read by `Transaction::get_cached`, kept until the key is written or the transaction is
+/// reset.
+///
+/// Nothing is allocated and writes do not take the lock until `get_cached` is first called, so
+/// transactions not using the cache do not pay for it. Writes invalidate the cache once issued, so
+/// a concurrent read is either not cached or sees the write.
+#[derive(Debug, Default)]
+struct ReadCache {
+    used: AtomicBool,
+    state: Mutex<ReadCacheState>,
+}
+
+#[derive(Debug, Default)]
+struct ReadCacheState {
+    values: HashMap<Vec<u8>, Option<Vec<u8>>>,
+    // bumped on every invalidation, a read racing with a write must not be cached
+    generation: u64,
+}
+
+impl ReadCache {
+    fn lock(&self) -> MutexGuard<'_, ReadCacheState> {
+        // the state is always consistent, even if a panic occurred while holding the lock
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    async fn get_or_fetch<F, Fut>(&self, key: &[u8], fetch: F) -> FdbResult<Option<Vec<u8>>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = FdbResult<Option<Vec<u8>>>>,
+    {
+        // set before the fetch is issued, so writes issued after it invalidate the cache
+        self.used.store(true, Ordering::SeqCst);
+        let generation = {
+            let state = self.lock();
+            if let Some(value) = state.values.get(key) {
+                return Ok(value.clone());
+            }
+            state.generation
+        };
+        let value = fetch().await?;
+        let mut state = self.lock();
+        if state.generation == generation {
+            state.values.insert(key.to_vec(), value.clone());
+        }
+        Ok(value)
+    }
+
+    fn is_used(&self) -> bool {
+        self.used.load(Ordering::SeqCst)
+    }
+
+    fn invalidate(&self, key: &[u8]) {
+        if !self.is_used() {
+            return;
+        }
+        let mut state = self.lock();
+        state.generation += 1;
+        state.values.remove(key);
+    }
+
+    fn invalidate_range(&self, begin: &[u8], end: &[u8]) {
+        if !self.is_used() {
+            return;
+        }
+        let mut state = self.lock();
+        state.generation += 1;
+        state
+            .values
+            .retain(|key, _| key.as_slice() < begin || key.as_slice() >= end);
+    }
+
+    fn clear(&self) {
+        if !self.is_used() {
+            return;
+        }
+        let mut state = self.lock();
+        state.generation += 1;
+        state.values.clear();
+    }
+}
+
 /// Converts Rust `bool` into `fdb_sys::fdb_bool_t`
 #[inline]
 fn fdb_bool(v: bool) -> fdb_sys::fdb_bool_t {
@@ -224,6 +333,20 @@ fn fdb_limit(v: usize) -> std::os::raw::c_int {
 ///     ..RangeOption::from((b"begin".as_ref(), b"end".as_ref()))
 /// };
 /// ```
+///
+/// Or from a subspace, then set its other parameters one after another:
+///
+/// ```
+/// use foundationdb::options::StreamingMode;
+/// use foundationdb::tuple::Subspace;
+/// use foundationdb::RangeOption;
+///
+/// let subspace = Subspace::from_bytes(b"prefix");
+/// let opt = RangeOption::from(&subspace)
+///     .limit(10)
+///     .mode(StreamingMode::WantAll)
+///     .rev();
+/// ```
 #[derive(Debug, Clone)]
 pub struct RangeOption<'a> {
     /// The beginning of the range.
@@ -252,16 +375,44 @@ impl<'a> RangeOption<'a> {
         self
     }
 
-    pub fn next_range(mut self, kvs: &FdbValues) -> Option<Self> {
-        if !kvs.more() {
+    /// Returns at most `limit` key-value pairs.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Asks for batches of about `target_bytes` bytes of keys and values.
+    pub fn target_bytes(mut self, target_bytes: usize) -> Self {
+        self.target_bytes = target_bytes;
+        self
+    }
+
+    /// Sets how eagerly the range is read, see [`options::StreamingMode`].
+    pub fn mode(mut self, mode: options::StreamingMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn next_range(self, kvs: &FdbValues) -> Option<Self> {
+        self.next_range_after(kvs.more(), kvs.len(), kvs.last().map(|last| last.key()))
+    }
+
+    /// Same as `next_range`, given the `more` flag, length and last key of the batch returned,
+    /// e.g. for the [`KeyValues`] of the [`ReadTransaction`] reads.
+    pub fn next_range_after(
+        mut self,
+        more: bool,
+        len: usize,
+        last_key: Option<&[u8]>,
+    ) -> Option<Self> {
+        if !more {
             return None;
         }
 
-        let last = kvs.last()?;
-        let last_key = last.key();
+        let last_key = last_key?;
 
         if let Some(limit) = self.limit.as_mut() {
-            *limit = limit.saturating_sub(kvs.len());
+            *limit = limit.saturating_sub(len);
             if *limit == 0 {
                 return None;
             }
@@ -375,12 +526,61 @@ impl From<std::ops::RangeInclusive<std::vec::Vec<u8>>> for RangeOption<'static>
 
 impl Transaction {
     pub(crate) fn new(inner: NonNull<fdb_sys::FDBTransaction>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            read_cache: ReadCache::default(),
+            default_options: Vec::new(),
+            tags: Mutex::default(),
+        }
+    }
+
+    /// Sets `default_options` now and each time the transaction is reset, like on a new
+    /// transaction.
+    pub(crate) fn with_default_options(
+        mut self,
+        default_options: Vec<options::TransactionOption>,
+    ) -> FdbResult<Self> {
+        self.default_options = default_options;
+        self.set_default_options()?;
+        Ok(self)
+    }
+
+    fn set_default_options(&self) -> FdbResult<()> {
+        // the tags are reset along with the other options
+        self.lock_tags().clear();
+        for opt in &self.default_options {
+            self.set_option(opt.clone())?;
+        }
+        Ok(())
     }
 
     /// Called to set an option on an FDBTransaction.
-    pub fn set_option(&self, opt: options::TransactionOption) -> FdbResult<()> {
-        unsafe { opt.apply(self.inner.as_ptr()) }
+    pub fn set_option(&self, opt: options::TransactionOption) -> Result<(), OptionError> {
+        unsafe { opt.apply(self.inner.as_ptr())? };
+        #[cfg(any(
+            feature = "fdb-6_3",
+            feature = "fdb-7_0",
+            feature = "fdb-7_1",
+            feature = "fdb-7_3"
+        ))]
+        if let options::TransactionOption::Tag(ref tag)
+        | options::TransactionOption::AutoThrottleTag(ref tag) = opt
+        {
+            self.lock_tags().push(tag.clone());
+        }
+        Ok(())
+    }
+
+    /// Returns the tags set on the transaction with
+    /// [`TransactionOption::Tag`](options::TransactionOption::Tag) or
+    /// [`TransactionOption::AutoThrottleTag`](options::TransactionOption::AutoThrottleTag),
+    /// including the default ones, in the order they were set.
+    pub fn tags(&self) -> Vec<String> {
+        self.lock_tags().clone()
+    }
+
+    fn lock_tags(&self) -> MutexGuard<'_, Vec<String>> {
+        self.tags.lock().unwrap_or_else(PoisonError::into_inner)
     }
 
     /// Pass through an option given a code and raw data. Useful when creating a passthrough layer
@@ -392,6 +592,10 @@ impl Transaction {
         code: fdb_sys::FDBTransactionOption,
         data: Option<Vec<u8>>,
     ) -> FdbResult<()> {
+        #[cfg(feature = "tracing")]
+        if let Some(option) = options::TransactionOption::hidden_name(code) {
+            tracing::warn!(option, "setting a hidden option");
+        }
         let (data_ptr, size) = data
             .as_ref()
             .map(|data| {
@@ -411,6 +615,50 @@ impl Transaction {
         }
     }
 
+    /// Cancels the transaction after `timeout`, retries included, see
+    /// [`TransactionOption::Timeout`](options::TransactionOption::Timeout).
+    ///
+    /// A zero duration disables the timeout, others are rounded up to the millisecond. It is
+    /// then reported as a `transaction_timed_out` error by the pending and future operations of
+    /// the transaction.
+    pub fn set_timeout(&self, timeout: Duration) -> Result<(), OptionError> {
+        self.set_option(options::TransactionOption::Timeout(millis(timeout)))
+    }
+
+    /// Limits the number of retries of `on_error`, see
+    /// [`TransactionOption::RetryLimit`](options::TransactionOption::RetryLimit).
+    ///
+    /// `-1` removes the limit, e.g. one set by default on the database, other negative values
+    /// are rejected.
+    pub fn set_retry_limit(&self, retry_limit: i32) -> Result<(), OptionError> {
+        self.set_option(options::TransactionOption::RetryLimit(retry_limit))
+    }
+
+    /// Caps the delay of `on_error` between retries, see
+    /// [`TransactionOption::MaxRetryDelay`](options::TransactionOption::MaxRetryDelay).
+    pub fn set_max_retry_delay(&self, max_retry_delay: Duration) -> Result<(), OptionError> {
+        self.set_option(options::TransactionOption::MaxRetryDelay(millis(
+            max_retry_delay,
+        )))
+    }
+
+    /// Limits the size in bytes of the transaction, see
+    /// [`TransactionOption::SizeLimit`](options::TransactionOption::SizeLimit).
+    #[cfg_api_versions(min = 620)]
+    pub fn set_size_limit(&self, size_limit: u32) -> Result<(), OptionError> {
+        self.set_option(options::TransactionOption::SizeLimit(saturating_i32(
+            size_limit,
+        )))
+    }
+
+    /// Reads no longer see the writes of the transaction, see
+    /// [`TransactionOption::ReadYourWritesDisable`](options::TransactionOption::ReadYourWritesDisable).
+    ///
+    /// It must be set before any read or write.
+    pub fn set_read_your_writes_disable(&self) -> Result<(), OptionError> {
+        self.set_option(options::TransactionOption::ReadYourWritesDisable)
+    }
+
     /// Modify the database snapshot represented by transaction to change the given
     /// key to have the given value.
     ///
@@ -431,7 +679,8 @@ impl Transaction {
                 value.as_ptr(),
                 fdb_len(value.len(), "value"),
             )
-        }
+        };
+        self.read_cache.invalidate(key);
     }
 
     /// Modify the database snapshot represented by transaction to remove the given key from the
@@ -451,7 +700,25 @@ impl Transaction {
                 key.as_ptr(),
                 fdb_len(key.len(), "key"),
             )
-        }
+        };
+        self.read_cache.invalidate(key);
+    }
+
+    /// Reads a value from the database snapshot represented by transaction.
+    ///
+    /// Returns an FDBFuture which will be set to the value of key in the database if there is any.
+    ///
+    /// This is a serializable read, snapshot reads are made through
+    /// [`snapshot`](Transaction::snapshot).
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - the name of the key to be looked up in the database
+    pub fn read(
+        &self,
+        key: &[u8],
+    ) -> impl Future<Output = FdbResult<Option<FdbSlice>>> + Send + Sync + Unpin {
+        self.fdb_get(key, false)
     }
 
     /// Reads a value from the database snapshot represented by transaction.
@@ -462,10 +729,19 @@ impl Transaction {
     ///
     /// * `key` - the name of the key to be looked up in the database
     /// * `snapshot` - `true` if this is a [snapshot read](https://apple.github.io/foundationdb/api-c.html#snapshots)
+    #[deprecated(note = "use `read`, or `snapshot().get` for a snapshot read")]
     pub fn get(
         &self,
         key: &[u8],
         snapshot: bool,
+    ) -> impl Future<Output = FdbResult<Option<FdbSlice>>> + Send + Sync + Unpin {
+        self.fdb_get(key, snapshot)
+    }
+
+    pub(crate) fn fdb_get(
+        &self,
+        key: &[u8],
+        snapshot: bool,
     ) -> impl Future<Output = FdbResult<Option<FdbSlice>>> + Send + Sync + Unpin {
         FdbFuture::new(unsafe {
             fdb_sys::fdb_transaction_get(
@@ -477,6 +753,38 @@ impl Transaction {
         })
     }
 
+    /// Returns a read-only view of this transaction, whose reads are all
+    /// [snapshot reads](https://apple.github.io/foundationdb/api-c.html#snapshots).
+    ///
+    /// Snapshot reads add no read conflict: the transaction does not fail to commit when the keys
+    /// it read this way are changed concurrently.
+    pub fn snapshot(&self) -> Snapshot<'_, Transaction> {
+        Snapshot::new(self)
+    }
+
+    /// Reads a value from the database snapshot represented by transaction, caching it for the
+    /// lifetime of the transaction.
+    ///
+    /// The first call for a given key is a regular `get`, later calls are answered from the cache
+    /// without any round-trip until the key is written by this transaction with `set`, `clear`,
+    /// `clear_range` or `atomic_op`. The cache is dropped when the transaction is reset, including
+    /// by `on_error`.
+    ///
+    /// This is meant for keys read repeatedly within a long transaction, like configuration keys.
+    /// Values are copied into the cache, large values are better read with `get`.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - the name of the key to be looked up in the database
+    pub async fn get_cached(&self, key: &[u8]) -> FdbResult<Option<Vec<u8>>> {
+        self.read_cache
+            .get_or_fetch(key, || {
+                self.read(key)
+                    .map_ok(|value| value.map(|value| value.to_vec()))
+            })
+            .await
+    }
+
     /// Modify the database snapshot represented by transaction to perform the operation indicated
     /// by operationType with operand param to the value stored by the given key.
     ///
@@ -511,7 +819,77 @@ impl Transaction {
                 fdb_len(param.len(), "param"),
                 op_type.code(),
             )
-        }
+        };
+        self.read_cache.invalidate(key);
+    }
+
+    /// Clears the given key if its current value is equal to `expected`, with a
+    /// `MutationType::CompareAndClear` atomic operation.
+    ///
+    /// The comparison is byte-exact, including the length: `b"1"` does not match `b"1\0"`. Like
+    /// any atomic operation, it does not read the key and thus does not add a read conflict.
+    ///
+    /// This is ideal to consume idempotency tokens, the consuming transaction checks the token
+    /// is present and clears it in one go:
+    ///
+    /// ```no_run
+    /// use foundationdb::{tuple::Subspace, FdbBindingError, RetryableTransaction};
+    ///
+    /// async fn consume_token(
+    ///     trx: &RetryableTransaction,
+    ///     tokens: &Subspace,
+    ///     token_id: u64,
+    /// ) -> Result<bool, FdbBindingError> {
+    ///     let key = tokens.pack(&token_id);
+    ///     match trx.read(&key).await? {
+    ///         Some(token) => {
+    ///             trx.compare_and_clear(&key, &token);
+    ///             Ok(true)
+    ///         }
+    ///         // already consumed
+    ///         None => Ok(false),
+    ///     }
+    /// }
+    /// ```
+    #[cfg_api_versions(min = 610)]
+    pub fn compare_and_clear(&self, key: &[u8], expected: &[u8]) {
+        self.atomic_op(key, expected, options::MutationType::CompareAndClear)
+    }
+
+    /// Clears the given key if it holds the little-endian `i64` `expected`, which is how counters
+    /// updated with `MutationType::Add` and an `i64` operand are stored.
+    ///
+    /// Decrementing a reference count and clearing it once it reaches zero can thus be done
+    /// without reading the key:
+    ///
+    /// ```no_run
+    /// # fn release(trx: &foundationdb::Transaction, key: &[u8]) {
+    /// trx.atomic_op(key, &(-1i64).to_le_bytes(), foundationdb::options::MutationType::Add);
+    /// trx.compare_and_clear_i64(key, 0);
+    /// # }
+    /// ```
+    #[cfg_api_versions(min = 610)]
+    pub fn compare_and_clear_i64(&self, key: &[u8], expected: i64) {
+        self.compare_and_clear(key, &expected.to_le_bytes())
+    }
+
+    /// Resolves a key selector against the keys in the database snapshot represented by
+    /// transaction.
+    ///
+    /// Returns an FDBFuture which will be set to the key in the database matching the key
+    /// selector.
+    ///
+    /// This is a serializable read, snapshot reads are made through
+    /// [`snapshot`](Transaction::snapshot).
+    ///
+    /// # Arguments
+    ///
+    /// * `selector`: the key selector
+    pub fn read_key(
+        &self,
+        selector: &KeySelector,
+    ) -> impl Future<Output = FdbResult<FdbSlice>> + Send + Sync + Unpin {
+        self.fdb_get_key(selector, false)
     }
 
     /// Resolves a key selector against the keys in the database snapshot represented by
@@ -524,10 +902,19 @@ impl Transaction {
     ///
     /// * `selector`: the key selector
     /// * `snapshot`: `true` if this is a [snapshot read](https://apple.github.io/foundationdb/api-c.html#snapshots)
+    #[deprecated(note = "use `read_key`, or `snapshot().get_key` for a snapshot read")]
     pub fn get_key(
         &self,
         selector: &KeySelector,
         snapshot: bool,
+    ) -> impl Future<Output = FdbResult<FdbSlice>> + Send + Sync + Unpin {
+        self.fdb_get_key(selector, snapshot)
+    }
+
+    pub(crate) fn fdb_get_key(
+        &self,
+        selector: &KeySelector,
+        snapshot: bool,
     ) -> impl Future<Output = FdbResult<FdbSlice>> + Send + Sync + Unpin {
         let key = selector.key();
         FdbFuture::new(unsafe {
@@ -547,29 +934,30 @@ impl Transaction {
     /// equal to the key resolved by the begin key selector and lexicographically less than the key
     /// resolved by the end key selector.
     ///
-    /// Returns a stream of KeyValue slices.
-    ///
-    /// This method is a little more efficient than `get_ranges_keyvalues` but a little harder to
-    /// use.
+    /// Returns a stream of the batches returned by the database, as [`RangeBatch`]es. Each batch
+    /// is sized by `target_bytes` and the streaming mode, and keeps the keyvalues in the buffer of
+    /// its future, so it can be handed off as is, e.g. to compression or IO.
     ///
     /// # Arguments
     ///
     /// * `opt`: the range, limit, target_bytes and mode
     /// * `snapshot`: `true` if this is a [snapshot read](https://apple.github.io/foundationdb/api-c.html#snapshots)
-    pub fn get_ranges<'a>(
+    pub fn get_ranges_batched<'a>(
         &'a self,
         opt: RangeOption<'a>,
         snapshot: bool,
-    ) -> impl Stream<Item = FdbResult<FdbValues>> + Send + Sync + Unpin + 'a {
+    ) -> impl Stream<Item = FdbResult<RangeBatch>> + Send + Sync + Unpin + 'a {
         stream::unfold((1, Some(opt)), move |(iteration, maybe_opt)| {
             if let Some(opt) = maybe_opt {
-                Either::Left(self.get_range(&opt, iteration as usize, snapshot).map(
+                Either::Left(self.fdb_get_range(&opt, iteration, snapshot).map(
                     move |maybe_values| {
                         let next_opt = match &maybe_values {
                             Ok(values) => opt.next_range(values),
                             Err(..) => None,
                         };
-                        Some((maybe_values, (iteration + 1, next_opt)))
+                        let maybe_batch =
+                            maybe_values.map(|values| RangeBatch::new(values, iteration - 1));
+                        Some((maybe_batch, (iteration + 1, next_opt)))
                     },
                 ))
             } else {
@@ -578,6 +966,51 @@ impl Transaction {
         })
     }
 
+    /// Reads all key-value pairs in the database snapshot represented by transaction (potentially
+    /// limited by limit, target_bytes, or mode) which have a key lexicographically greater than or
+    /// equal to the key resolved by the begin key selector and lexicographically less than the key
+    /// resolved by the end key selector.
+    ///
+    /// Returns a stream of KeyValue slices.
+    ///
+    /// These are serializable reads, snapshot reads are made through
+    /// [`snapshot`](Transaction::snapshot).
+    ///
+    /// # Arguments
+    ///
+    /// * `opt`: the range, limit, target_bytes and mode
+    pub fn read_ranges<'a>(
+        &'a self,
+        opt: RangeOption<'a>,
+    ) -> impl Stream<Item = FdbResult<FdbValues>> + Send + Sync + Unpin + 'a {
+        self.get_ranges_batched(opt, false)
+            .map_ok(RangeBatch::into_values)
+    }
+
+    /// Reads all key-value pairs in the database snapshot represented by transaction (potentially
+    /// limited by limit, target_bytes, or mode) which have a key lexicographically greater than or
+    /// equal to the key resolved by the begin key selector and lexicographically less than the key
+    /// resolved by the end key selector.
+    ///
+    /// Returns a stream of KeyValue slices.
+    ///
+    /// This method is a little more efficient than `get_ranges_keyvalues` but a little harder to
+    /// use.
+    ///
+    /// # Arguments
+    ///
+    /// * `opt`: the range, limit, target_bytes and mode
+    /// * `snapshot`: `true` if this is a [snapshot read](https://apple.github.io/foundationdb/api-c.html#snapshots)
+    #[deprecated(note = "use `read_ranges`, or `snapshot().get_ranges` for a snapshot read")]
+    pub fn get_ranges<'a>(
+        &'a self,
+        opt: RangeOption<'a>,
+        snapshot: bool,
+    ) -> impl Stream<Item = FdbResult<FdbValues>> + Send + Sync + Unpin + 'a {
+        self.get_ranges_batched(opt, snapshot)
+            .map_ok(RangeBatch::into_values)
+    }
+
     /// Reads all key-value pairs in the database snapshot represented by transaction (potentially
     /// limited by limit, target_bytes, or mode) which have a key lexicographically greater than or
     /// equal to the key resolved by the begin key selector and lexicographically less than the key
@@ -594,11 +1027,60 @@ impl Transaction {
         opt: RangeOption<'a>,
         snapshot: bool,
     ) -> impl Stream<Item = FdbResult<FdbValue>> + Unpin + 'a {
-        self.get_ranges(opt, snapshot)
-            .map_ok(|values| stream::iter(values.into_iter().map(Ok)))
+        self.get_ranges_batched(opt, snapshot)
+            .map_ok(|batch| stream::iter(batch.into_iter().map(Ok)))
             .try_flatten()
     }
 
+    /// Reads all the key-value pairs of a small range, like configuration or metadata, at once.
+    ///
+    /// The range is read with [`StreamingMode::WantAll`](options::StreamingMode::WantAll), batch
+    /// after batch, until its end or `max_rows` pairs. The `more` flag of the returned
+    /// [`KeyValues`] tells if the range held more pairs than that. To bound the memory used, the
+    /// batches are requested with a `target_bytes` of what is left of `max_bytes`, if set, and
+    /// the read fails with [`FdbBindingError::RangeTooLarge`] as soon as the keys and values read
+    /// exceed it. Larger ranges are better read batch by batch, with
+    /// [`read_ranges`](Transaction::read_ranges).
+    ///
+    /// # Arguments
+    ///
+    /// * `begin`: the first key of the range
+    /// * `end`: the key after the range, excluded
+    /// * `max_rows`: the maximum number of pairs returned
+    /// * `max_bytes`: the maximum combined size of the keys and values returned
+    /// * `snapshot`: `true` if this is a [snapshot read](https://apple.github.io/foundationdb/api-c.html#snapshots)
+    pub fn get_range_all(
+        &self,
+        begin: &[u8],
+        end: &[u8],
+        max_rows: usize,
+        max_bytes: Option<usize>,
+        snapshot: bool,
+    ) -> impl Future<Output = Result<KeyValues, FdbBindingError>> + Send + '_ {
+        ReadTransaction::get_range_all(self, begin, end, max_rows, max_bytes, snapshot)
+    }
+
+    /// Reads all key-value pairs in the database snapshot represented by transaction (potentially
+    /// limited by limit, target_bytes, or mode) which have a key lexicographically greater than or
+    /// equal to the key resolved by the begin key selector and lexicographically less than the key
+    /// resolved by the end key selector.
+    ///
+    /// This is a serializable read, snapshot reads are made through
+    /// [`snapshot`](Transaction::snapshot).
+    ///
+    /// # Arguments
+    ///
+    /// * `opt`: the range, limit, target_bytes and mode
+    /// * `iteration`: If opt.mode is Iterator, this parameter should start at 1 and be incremented
+    ///   by 1 for each successive call while reading this range. In all other cases it is ignored.
+    pub fn read_range(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+    ) -> impl Future<Output = FdbResult<FdbValues>> + Send + Sync + Unpin {
+        self.fdb_get_range(opt, iteration, false)
+    }
+
     /// Reads all key-value pairs in the database snapshot represented by transaction (potentially
     /// limited by limit, target_bytes, or mode) which have a key lexicographically greater than or
     /// equal to the key resolved by the begin key selector and lexicographically less than the key
@@ -610,11 +1092,21 @@ impl Transaction {
     /// * `iteration`: If opt.mode is Iterator, this parameter should start at 1 and be incremented
     ///   by 1 for each successive call while reading this range. In all other cases it is ignored.
     /// * `snapshot`: `true` if this is a [snapshot read](https://apple.github.io/foundationdb/api-c.html#snapshots)
+    #[deprecated(note = "use `read_range`, or `snapshot().get_range` for a snapshot read")]
     pub fn get_range(
         &self,
         opt: &RangeOption,
         iteration: usize,
         snapshot: bool,
+    ) -> impl Future<Output = FdbResult<FdbValues>> + Send + Sync + Unpin {
+        self.fdb_get_range(opt, iteration, snapshot)
+    }
+
+    pub(crate) fn fdb_get_range(
+        &self,
+        opt: &RangeOption,
+        iteration: usize,
+        snapshot: bool,
     ) -> impl Future<Output = FdbResult<FdbValues>> + Send + Sync + Unpin {
         let begin = &opt.begin;
         let end = &opt.end;
@@ -758,7 +1250,8 @@ impl Transaction {
                 end.as_ptr(),
                 fdb_len(end.len(), "end"),
             )
-        }
+        };
+        self.read_cache.invalidate_range(begin, end);
     }
 
     /// Get the estimated byte size of the key range based on the byte sample collected by FDB
@@ -806,7 +1299,10 @@ impl Transaction {
         FdbFuture::<()>::new(unsafe { fdb_sys::fdb_transaction_commit(self.inner.as_ptr()) }).map(
             move |r| match r {
                 Ok(()) => Ok(TransactionCommitted { tr: self }),
-                Err(err) => Err(TransactionCommitError { tr: self, err }),
+                Err(err) => Err(TransactionCommitError {
+                    tr: Box::new(self),
+                    err,
+                }),
             },
         )
     }
@@ -830,7 +1326,12 @@ impl Transaction {
         FdbFuture::<()>::new(unsafe {
             fdb_sys::fdb_transaction_on_error(self.inner.as_ptr(), err.code())
         })
-        .map_ok(|()| self)
+        .map(|r| {
+            r?;
+            self.read_cache.clear();
+            self.set_default_options()?;
+            Ok(self)
+        })
     }
 
     /// Cancels the transaction. All pending or future uses of the transaction will return a
@@ -904,6 +1405,18 @@ impl Transaction {
         })
     }
 
+    /// Returns an FDBFuture which will be set to the cost units the cluster charged to the
+    /// transaction so far, for its reads and writes.
+    ///
+    /// This can be called multiple times before the transaction is committed, and on the
+    /// [`TransactionCommitted`] once it is. The future fails with an error for which
+    /// [`FdbError::is_unsupported_operation`] is true when the client talks to the cluster
+    /// through a client library older than 7.3.
+    #[cfg_api_versions(min = 730)]
+    pub fn get_total_cost(&self) -> impl Future<Output = FdbResult<i64>> + Send + Sync + Unpin {
+        FdbFuture::new(unsafe { fdb_sys::fdb_transaction_get_total_cost(self.inner.as_ptr()) })
+    }
+
     /// Gets a list of keys that can split the given range into (roughly) equally sized chunks based on chunk_size.
     /// Note: the returned split points contain the start key and end key of the given range.
     #[cfg_api_versions(min = 700)]
@@ -965,7 +1478,7 @@ impl Transaction {
     /// To retrieve the metadataVersion, you need to set `TransactionOption::ReadSystemKeys`
     #[cfg_api_versions(min = 610)]
     pub async fn get_metadata_version(&self, snapshot: bool) -> FdbResult<Option<i64>> {
-        match self.get(METADATA_VERSION_KEY, snapshot).await {
+        match self.fdb_get(METADATA_VERSION_KEY, snapshot).await {
             Ok(Some(fdb_slice)) => {
                 let value = fdb_slice.deref();
                 // as we cannot write the metadata-key directly(we must mutate with an atomic_op),
@@ -1015,7 +1528,15 @@ impl Transaction {
     /// It is not necessary to call `reset()` when handling an error with `on_error()` since the
     /// transaction has already been reset.
     pub fn reset(&mut self) {
+        self.read_cache.clear();
         unsafe { fdb_sys::fdb_transaction_reset(self.inner.as_ptr()) }
+        // they were valid when the transaction was created
+        let result = self.set_default_options();
+        debug_assert!(
+            result.is_ok(),
+            "the default options were rejected after a reset: {:?}",
+            result
+        );
     }
 
     /// Adds a conflict range to a transaction without performing the associated read or write.
@@ -1041,6 +1562,27 @@ impl Transaction {
             )
         })
     }
+
+    /// Adds a read conflict range covering only `key`, as if it was read.
+    ///
+    /// Useful after a snapshot read of `key`, to make the transaction conflict with concurrent
+    /// writes of it again.
+    pub fn add_read_conflict_key(&self, key: &[u8]) -> FdbResult<()> {
+        self.add_conflict_range(key, &key_after(key), options::ConflictRangeType::Read)
+    }
+
+    /// Adds a write conflict range covering only `key`, as if it was written.
+    pub fn add_write_conflict_key(&self, key: &[u8]) -> FdbResult<()> {
+        self.add_conflict_range(key, &key_after(key), options::ConflictRangeType::Write)
+    }
+}
+
+/// Returns the first key after `key`, which ends the range containing only `key`.
+pub(crate) fn key_after(key: &[u8]) -> Vec<u8> {
+    let mut after = Vec::with_capacity(key.len() + 1);
+    after.extend_from_slice(key);
+    after.push(0x00);
+    after
 }
 
 impl Drop for Transaction {
@@ -1094,3 +1636,62 @@ impl RetryableTransaction {
         Ok(self.take()?.commit().await)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn read_cache() {
+        let cache = ReadCache::default();
+        let fetches = Cell::new(0);
+        let get = |key: &'static [u8]| {
+            futures::executor::block_on(cache.get_or_fetch(key, || {
+                fetches.set(fetches.get() + 1);
+                future::ready(Ok(Some(key.to_vec())))
+            }))
+            .unwrap()
+        };
+
+        assert_eq!(get(b"a"), Some(b"a".to_vec()));
+        assert_eq!(get(b"a"), Some(b"a".to_vec()));
+        assert_eq!(fetches.get(), 1);
+
+        cache.invalidate(b"a");
+        get(b"a");
+        get(b"b");
+        assert_eq!(fetches.get(), 3);
+
+        cache.invalidate_range(b"a", b"b");
+        get(b"a");
+        get(b"b");
+        assert_eq!(fetches.get(), 4);
+
+        cache.clear();
+        get(b"b");
+        assert_eq!(fetches.get(), 5);
+    }
+
+    #[test]
+    fn read_cache_unused() {
+        let cache = ReadCache::default();
+        cache.invalidate(b"a");
+        cache.invalidate_range(b"a", b"b");
+        cache.clear();
+        assert_eq!(cache.lock().generation, 0);
+    }
+
+    #[test]
+    fn read_cache_racing_write() {
+        let cache = ReadCache::default();
+        let value = futures::executor::block_on(cache.get_or_fetch(b"a", || {
+            // a write issued while the read is in flight
+            cache.invalidate(b"a");
+            future::ready(Ok(None))
+        }))
+        .unwrap();
+        assert_eq!(value, None);
+        assert!(cache.lock().values.is_empty());
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright 2018 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+// Copyright 2013-2018 Apple, Inc and the FoundationDB project authors.
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use super::*;
+
+const PACK_ERR_MSG: &str = "pack io error on Vec, data size didn't fit in `u32`?";
+
+/// Builds a packed tuple one element at a time
+///
+/// Useful when the shape of a key is only known at runtime. Each added element is packed as if
+/// it was an element of a Rust tuple, so the result is the same as packing the equivalent tuple.
+///
+/// ```
+/// use foundationdb::tuple::{pack, TupleBuilder};
+///
+/// let key = TupleBuilder::new()
+///     .add_str("user")
+///     .add_int(42)
+///     .add_bytes(b"\x00\xff")
+///     .pack();
+/// assert_eq!(key, pack(&("user", 42, foundationdb::tuple::Bytes::from(&b"\x00\xff"[..]))));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TupleBuilder {
+    bytes: Vec<u8>,
+    versionstamp_offset: VersionstampOffset,
+}
+
+impl Default for TupleBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TupleBuilder {
+    /// Returns an empty builder.
+    pub fn new() -> Self {
+        Self::with_prefix(&[])
+    }
+
+    /// Returns a builder whose packed tuple will be prefixed by the given bytes, usually the
+    /// prefix of a [`Subspace`].
+    pub fn with_prefix(prefix: &[u8]) -> Self {
+        Self {
+            bytes: prefix.to_vec(),
+            versionstamp_offset: VersionstampOffset::None {
+                size: u32::try_from(prefix.len()).expect(PACK_ERR_MSG),
+            },
+        }
+    }
+
+    /// Adds any tuple encodable as the next element, tuples are added as nested tuples.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add<T: TuplePack>(mut self, v: &T) -> Self {
+        self.versionstamp_offset += v
+            .pack(&mut self.bytes, TupleDepth::new().increment())
+            .expect(PACK_ERR_MSG);
+        self
+    }
+
+    /// Adds a nil element.
+    pub fn add_nil(self) -> Self {
+        self.add(&Element::Nil)
+    }
+
+    /// Adds a boolean element.
+    pub fn add_bool(self, v: bool) -> Self {
+        self.add(&v)
+    }
+
+    /// Adds an integer element.
+    pub fn add_int(self, v: i64) -> Self {
+        self.add(&v)
+    }
+
+    /// Adds a double element.
+    pub fn add_double(self, v: f64) -> Self {
+        self.add(&v)
+    }
+
+    /// Adds a unicode string element.
+    pub fn add_str(self, v: &str) -> Self {
+        self.add(&v)
+    }
+
+    /// Adds a byte string element.
+    pub fn add_bytes(self, v: &[u8]) -> Self {
+        self.add(&Bytes::from(v))
+    }
+
+    /// Adds a versionstamp element.
+    pub fn add_versionstamp(self, v: &Versionstamp) -> Self {
+        self.add(v)
+    }
+
+    /// Adds an uuid element.
+    #[cfg(feature = "uuid")]
+    pub fn add_uuid(self, v: &uuid::Uuid) -> Self {
+        self.add(v)
+    }
+
+    /// Returns `true` if no element nor prefix was added.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Returns the packed tuple.
+    pub fn pack(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Returns the packed tuple, followed by the offset of its incomplete versionstamp if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is multiple versionstamp present.
+    pub fn pack_with_versionstamp(mut self) -> Vec<u8> {
+        match self.versionstamp_offset {
+            VersionstampOffset::OneIncomplete { offset } => {
+                self.bytes.extend_from_slice(&offset.to_le_bytes());
+            }
+            VersionstampOffset::MultipleIncomplete => {
+                panic!("pack_with_versionstamp does not allow multiple versionstamps");
+            }
+            VersionstampOffset::None { .. } => {}
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder() {
+        let key = TupleBuilder::new()
+            .add_str("foo")
+            .add_int(-42)
+            .add_bytes(b"\x00bar")
+            .add_nil()
+            .add_bool(true)
+            .add_double(1.5)
+            .add(&("nested", None::<i64>))
+            .pack();
+        let expected = pack(&(
+            "foo",
+            -42,
+            Bytes::from(&b"\x00bar"[..]),
+            None::<i64>,
+            true,
+            1.5f64,
+            ("nested", None::<i64>),
+        ));
+        assert_eq!(Bytes::from(key), Bytes::from(expected));
+
+        assert!(TupleBuilder::new().is_empty());
+        assert_eq!(TupleBuilder::new().pack(), pack(&()));
+    }
+
+    #[test]
+    fn builder_with_prefix() {
+        let subspace = Subspace::from("prefix");
+        let key = TupleBuilder::with_prefix(subspace.bytes())
+            .add_int(1)
+            .add_versionstamp(&Versionstamp::incomplete(2))
+            .pack_with_versionstamp();
+        assert_eq!(
+            key,
+            subspace.pack_with_versionstamp(&(1, Versionstamp::incomplete(2)))
+        );
+    }
+}
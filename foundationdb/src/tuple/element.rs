@@ -1,11 +1,16 @@
 use super::pack::{f32_to_u32_be_bytes, f64_to_u64_be_bytes};
-use super::{Bytes, Versionstamp};
-use std::{borrow::Cow, cmp};
+use super::{Bytes, PackResult, TupleDepth, TupleUnpack, Versionstamp};
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use core::hash::{Hash, Hasher};
+use core::iter::FusedIterator;
+use core::{cmp, fmt};
 
+#[cfg(any(feature = "num-bigint", feature = "uuid"))]
+use core::convert::TryFrom;
 #[cfg(feature = "num-bigint")]
 use num_bigint::Sign;
-#[cfg(feature = "num-bigint")]
-use std::convert::TryFrom;
 
 #[derive(Clone, Debug)]
 pub enum Element<'a> {
@@ -97,6 +102,13 @@ impl<'a> Ord for Element<'a> {
     }
 }
 
+/// Hashes the packed element, so elements comparing equal hash the same
+impl<'a> Hash for Element<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        super::pack(self).hash(state)
+    }
+}
+
 impl<'a> Element<'a> {
     fn code(&self) -> u8 {
         match self {
@@ -126,7 +138,7 @@ impl<'a> Element<'a> {
     fn cmp_values(&self) -> &[Self] {
         match self {
             Element::Tuple(v) => v.as_slice(),
-            v => std::slice::from_ref(v),
+            v => core::slice::from_ref(v),
         }
     }
 
@@ -161,6 +173,10 @@ impl<'a> Element<'a> {
         }
     }
 
+    fn as_byte_slice(&self) -> Option<&[u8]> {
+        self.as_bytes().map(|v| v.as_ref())
+    }
+
     pub fn as_str(&self) -> Option<&str> {
         match self {
             Element::String(v) => Some(v),
@@ -228,3 +244,257 @@ impl<'a> Element<'a> {
         }
     }
 }
+
+/// Renders the element like a literal of the Python binding, UUIDs in their hyphenated form
+///
+/// `Element::Nil` is rendered as `None` and tuples are parenthesized, with a trailing comma when
+/// they hold a single element.
+impl<'a> fmt::Display for Element<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Element::Nil => f.write_str("None"),
+            Element::Bytes(v) => fmt::Display::fmt(v, f),
+            Element::String(v) => fmt::Debug::fmt(v, f),
+            Element::Tuple(v) => {
+                f.write_str("(")?;
+                for (i, element) in v.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    fmt::Display::fmt(element, f)?;
+                }
+                if v.len() == 1 {
+                    f.write_str(",")?;
+                }
+                f.write_str(")")
+            }
+            Element::Int(v) => fmt::Display::fmt(v, f),
+            #[cfg(feature = "num-bigint")]
+            Element::BigInt(v) => fmt::Display::fmt(v, f),
+            Element::Float(v) => fmt::Debug::fmt(v, f),
+            Element::Double(v) => fmt::Debug::fmt(v, f),
+            Element::Bool(true) => f.write_str("True"),
+            Element::Bool(false) => f.write_str("False"),
+            #[cfg(feature = "uuid")]
+            Element::Uuid(v) => write!(f, "UUID('{}')", v.hyphenated()),
+            Element::Versionstamp(v) => write!(f, "Versionstamp({:?})", v),
+        }
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl<'a> From<uuid::Uuid> for Element<'a> {
+    fn from(uuid: uuid::Uuid) -> Self {
+        Element::Uuid(uuid)
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl<'a> TryFrom<Element<'a>> for uuid::Uuid {
+    type Error = super::PackError;
+
+    fn try_from(element: Element<'a>) -> Result<Self, Self::Error> {
+        match element {
+            Element::Uuid(v) => Ok(v),
+            other => Err(super::PackError::BadCode {
+                found: other.code(),
+                expected: Some(super::UUID),
+            }),
+        }
+    }
+}
+
+/// Implements `TryFrom<&Element>` with the `as_*` accessor, failing with
+/// [`PackError::BadCode`](super::PackError::BadCode) on other element types
+macro_rules! impl_try_from_ref {
+    ($($ty:ty => $as:ident, $code:expr;)*) => {
+        $(
+            impl<'a, 'b> TryFrom<&'b Element<'a>> for $ty {
+                type Error = super::PackError;
+
+                fn try_from(element: &'b Element<'a>) -> Result<Self, Self::Error> {
+                    element.$as().ok_or(super::PackError::BadCode {
+                        found: element.code(),
+                        expected: Some($code),
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_ref! {
+    f32 => as_f32, super::FLOAT;
+    f64 => as_f64, super::DOUBLE;
+    bool => as_bool, super::TRUE;
+    &'b str => as_str, super::STRING;
+    &'b [u8] => as_byte_slice, super::BYTES;
+    &'b [Element<'a>] => as_tuple, super::NESTED;
+    &'b Versionstamp => as_versionstamp, super::VERSIONSTAMP;
+}
+
+/// Big integers out of the `i64` range fail with
+/// [`PackError::IntegerOverflow`](super::PackError::IntegerOverflow)
+impl<'a, 'b> TryFrom<&'b Element<'a>> for i64 {
+    type Error = super::PackError;
+
+    fn try_from(element: &'b Element<'a>) -> Result<Self, Self::Error> {
+        match element {
+            Element::Int(v) => Ok(*v),
+            #[cfg(feature = "num-bigint")]
+            Element::BigInt(v) => i64::try_from(v).map_err(|_| super::PackError::IntegerOverflow),
+            other => Err(super::PackError::BadCode {
+                found: other.code(),
+                expected: Some(super::INTZERO),
+            }),
+        }
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($ty:ty),*) => {
+        $(
+            impl<'a> From<$ty> for Element<'a> {
+                fn from(v: $ty) -> Self {
+                    Element::Int(i64::from(v))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(i8, i16, i32, i64, u8, u16, u32);
+
+impl<'a> From<f32> for Element<'a> {
+    fn from(v: f32) -> Self {
+        Element::Float(v)
+    }
+}
+
+impl<'a> From<f64> for Element<'a> {
+    fn from(v: f64) -> Self {
+        Element::Double(v)
+    }
+}
+
+impl<'a> From<bool> for Element<'a> {
+    fn from(v: bool) -> Self {
+        Element::Bool(v)
+    }
+}
+
+impl<'a> From<&'a str> for Element<'a> {
+    fn from(v: &'a str) -> Self {
+        Element::String(Cow::Borrowed(v))
+    }
+}
+
+impl<'a> From<String> for Element<'a> {
+    fn from(v: String) -> Self {
+        Element::String(Cow::Owned(v))
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for Element<'a> {
+    fn from(v: Cow<'a, str>) -> Self {
+        Element::String(v)
+    }
+}
+
+impl<'a> From<&'a [u8]> for Element<'a> {
+    fn from(v: &'a [u8]) -> Self {
+        Element::Bytes(Bytes::from(v))
+    }
+}
+
+impl<'a> From<Vec<u8>> for Element<'a> {
+    fn from(v: Vec<u8>) -> Self {
+        Element::Bytes(Bytes::from(v))
+    }
+}
+
+impl<'a> From<Bytes<'a>> for Element<'a> {
+    fn from(v: Bytes<'a>) -> Self {
+        Element::Bytes(v)
+    }
+}
+
+impl<'a> From<Versionstamp> for Element<'a> {
+    fn from(v: Versionstamp) -> Self {
+        Element::Versionstamp(v)
+    }
+}
+
+impl<'a> From<Vec<Element<'a>>> for Element<'a> {
+    fn from(v: Vec<Element<'a>>) -> Self {
+        Element::Tuple(v)
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl<'a> From<num_bigint::BigInt> for Element<'a> {
+    fn from(v: num_bigint::BigInt) -> Self {
+        Element::BigInt(v)
+    }
+}
+
+/// `None` is `Element::Nil`
+impl<'a, T: Into<Element<'a>>> From<Option<T>> for Element<'a> {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Element::Nil, Into::into)
+    }
+}
+
+/// Collects elements into an `Element::Tuple`
+impl<'a> FromIterator<Element<'a>> for Element<'a> {
+    fn from_iter<I: IntoIterator<Item = Element<'a>>>(iter: I) -> Self {
+        Element::Tuple(iter.into_iter().collect())
+    }
+}
+
+/// An iterator over the elements of a packed tuple, unpacking them one at a time
+///
+/// Created by [`unpack_elements`](super::unpack_elements). It borrows the input and stops after
+/// yielding the first error.
+#[derive(Clone, Debug)]
+pub struct Elements<'a> {
+    input: &'a [u8],
+    failed: bool,
+}
+
+impl<'a> Elements<'a> {
+    pub(super) fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            failed: false,
+        }
+    }
+
+    /// Returns the part of the input not unpacked yet
+    pub fn remaining(&self) -> &'a [u8] {
+        self.input
+    }
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = PackResult<Element<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.input.is_empty() {
+            return None;
+        }
+        let result: PackResult<_> = Element::unpack(self.input, TupleDepth::new().increment());
+        match result {
+            Ok((input, element)) => {
+                self.input = input;
+                Some(Ok(element))
+            }
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<'a> FusedIterator for Elements<'a> {}
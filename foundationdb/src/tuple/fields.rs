@@ -0,0 +1,141 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use super::pack::parse_code;
+use super::*;
+
+/// A type packed as the elements of a tuple, like the fields of a struct
+///
+/// It is implemented by `#[derive(TuplePack)]` on structs, whose fields are packed in declaration
+/// order, as the elements of a tuple of their own:
+///
+/// ```
+/// use foundationdb::tuple::{pack, unpack, TuplePack, TupleUnpack};
+///
+/// #[derive(Debug, PartialEq, TuplePack, TupleUnpack)]
+/// struct UserIndexKey {
+///     tenant: String,
+///     user_id: u64,
+///     shard: u16,
+/// }
+///
+/// let key = UserIndexKey { tenant: "acme".into(), user_id: 42, shard: 3 };
+/// assert_eq!(pack(&key), pack(&("acme", 42u64, 3u16)));
+/// assert_eq!(unpack::<UserIndexKey>(&pack(&key)).unwrap(), key);
+/// ```
+///
+/// The fields can be annotated with:
+///
+/// - `#[fdb(skip)]`: the field is not packed, and is set to its `Default` when unpacked.
+/// - `#[fdb(flatten)]`: the fields of the field, a struct deriving `TuplePack` as well, are packed
+///   in place of the field, instead of as a nested tuple.
+///
+/// Enums with unit variants only can derive `TuplePack` and `TupleUnpack` too, they are packed
+/// as the `i64` value of their variant. Unpacking a value matching none of the variants fails with
+/// [`PackError::Message`].
+///
+/// Misuses are reported at compile time:
+///
+/// ```compile_fail
+/// use foundationdb::tuple::TuplePack;
+///
+/// #[derive(TuplePack)]
+/// struct Key {
+///     #[fdb(nested)] // only `skip` and `flatten` are known
+///     id: u64,
+/// }
+/// ```
+///
+/// ```compile_fail,E0277
+/// use foundationdb::tuple::TuplePack;
+///
+/// #[derive(TuplePack)]
+/// struct Key {
+///     #[fdb(flatten)] // `u64` is not a struct deriving `TuplePack`
+///     id: u64,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use foundationdb::tuple::TupleUnpack;
+///
+/// #[derive(TupleUnpack)]
+/// enum Event {
+///     Created { id: u64 }, // a variant with fields
+///     Deleted,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use foundationdb::tuple::TuplePack;
+///
+/// #[derive(TuplePack)]
+/// union Bits {
+///     int: u64,
+///     float: f64,
+/// }
+/// ```
+pub trait TuplePackFields {
+    /// Packs the fields, as elements of a tuple at `tuple_depth`
+    fn pack_fields<W: io::Write>(
+        &self,
+        w: &mut W,
+        tuple_depth: TupleDepth,
+    ) -> io::Result<VersionstampOffset>;
+
+    /// Packs the fields as a tuple, nested unless `tuple_depth` is the root
+    fn pack_tuple<W: io::Write>(
+        &self,
+        w: &mut W,
+        tuple_depth: TupleDepth,
+    ) -> io::Result<VersionstampOffset> {
+        let mut offset = VersionstampOffset::None { size: 0 };
+        if tuple_depth.depth() > 0 {
+            w.write_all(&[NESTED])?;
+            offset += 1;
+        }
+        offset += self.pack_fields(w, tuple_depth.increment())?;
+        if tuple_depth.depth() > 0 {
+            w.write_all(&[NIL])?;
+            offset += 1;
+        }
+        Ok(offset)
+    }
+
+    /// An upper bound of the bytes packed by [`pack_fields`](Self::pack_fields), `0` meaning no hint
+    fn fields_size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+        0
+    }
+
+    /// An upper bound of the bytes packed by [`pack_tuple`](Self::pack_tuple)
+    fn tuple_size_hint(&self, tuple_depth: TupleDepth) -> usize {
+        let markers = if tuple_depth.depth() > 0 { 2 } else { 0 };
+        markers + self.fields_size_hint(tuple_depth.increment())
+    }
+}
+
+/// A type unpacked from the elements of a tuple, see [`TuplePackFields`]
+pub trait TupleUnpackFields<'de>: Sized {
+    /// Unpacks the fields, from elements of a tuple at `tuple_depth`
+    fn unpack_fields(input: &'de [u8], tuple_depth: TupleDepth) -> PackResult<(&'de [u8], Self)>;
+
+    /// Unpacks the fields from a tuple, nested unless `tuple_depth` is the root
+    fn unpack_tuple(input: &'de [u8], tuple_depth: TupleDepth) -> PackResult<(&'de [u8], Self)> {
+        let input = if tuple_depth.depth() > 0 {
+            parse_code(input, NESTED)?
+        } else {
+            input
+        };
+        let (input, v) = Self::unpack_fields(input, tuple_depth.increment())?;
+        let input = if tuple_depth.depth() > 0 {
+            parse_code(input, NIL)?
+        } else {
+            input
+        };
+        Ok((input, v))
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright 2018 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+// Copyright 2013-2018 Apple, Inc and the FoundationDB project authors.
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use super::*;
+
+/// A [`Subspace`] spreading its keys across a fixed number of hash buckets
+///
+/// Keys packed by a `HashedSubspace` are prefixed by the subspace prefix followed by the bucket,
+/// encoded as a tuple integer, derived from a hash of the packed tuple. Writes to a hot subspace
+/// are thus spread across several shards instead of a single one.
+///
+/// The hash is stable across processes and releases, so a key always lands in the same bucket
+/// as long as the number of buckets is not changed. Scanning the whole subspace in key order is
+/// no longer possible, scans have to fan out across [`HashedSubspace::range_for_bucket`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct HashedSubspace {
+    subspace: Subspace,
+    buckets: u16,
+}
+
+impl HashedSubspace {
+    /// Returns a new `HashedSubspace` spreading keys of `subspace` across `buckets` buckets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buckets` is 0.
+    pub fn new(subspace: Subspace, buckets: u16) -> Self {
+        assert!(buckets > 0, "a HashedSubspace needs at least one bucket");
+        Self { subspace, buckets }
+    }
+
+    /// Returns the underlying Subspace.
+    pub fn subspace(&self) -> &Subspace {
+        &self.subspace
+    }
+
+    /// Returns the number of buckets keys are spread across.
+    pub fn buckets(&self) -> u16 {
+        self.buckets
+    }
+
+    /// Returns the bucket the given tuple encodable is stored in.
+    pub fn bucket_of<T: TuplePack>(&self, t: &T) -> u16 {
+        (fnv1a(&pack(t)) % u32::from(self.buckets)) as u16
+    }
+
+    /// Returns the Subspace holding every key of the given bucket.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bucket` is not lower than the number of buckets.
+    pub fn bucket_subspace(&self, bucket: u16) -> Subspace {
+        assert!(bucket < self.buckets, "bucket out of range");
+        self.subspace.subspace(&bucket)
+    }
+
+    /// Returns the key encoding the specified Tuple with the prefix of this Subspace and the
+    /// bucket of the Tuple prepended.
+    pub fn pack<T: TuplePack>(&self, t: &T) -> Vec<u8> {
+        let bucket = self.bucket_of(t);
+        let mut out = self.subspace.pack(&bucket);
+        pack_into(t, &mut out);
+        out
+    }
+
+    /// `unpack` returns the Tuple encoded by the given key with the prefix of this Subspace and
+    /// the bucket removed. `unpack` will return an error if the key is not in this Subspace or
+    /// does not encode a well-formed Tuple.
+    pub fn unpack<'de, T: TupleUnpack<'de>>(&self, key: &'de [u8]) -> PackResult<T> {
+        if !self.subspace.is_start_of(key) {
+            return Err(PackError::BadPrefix);
+        }
+        let key = &key[self.subspace.bytes().len()..];
+        let (key, bucket) = u16::unpack(key, TupleDepth::new().increment())?;
+        if bucket >= self.buckets {
+            return Err(PackError::BadPrefix);
+        }
+        unpack(key)
+    }
+
+    /// `is_start_of` returns true if the provided key starts with the prefix of this Subspace.
+    pub fn is_start_of(&self, key: &[u8]) -> bool {
+        self.subspace.is_start_of(key)
+    }
+
+    /// `range_for_bucket` returns first and last key of the given bucket.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bucket` is not lower than the number of buckets.
+    pub fn range_for_bucket(&self, bucket: u16) -> (Vec<u8>, Vec<u8>) {
+        self.bucket_subspace(bucket).range()
+    }
+
+    /// Returns the ranges of every bucket, in bucket order.
+    pub fn ranges(&self) -> impl Iterator<Item = (Vec<u8>, Vec<u8>)> + '_ {
+        (0..self.buckets).map(move |bucket| self.range_for_bucket(bucket))
+    }
+}
+
+/// 32 bits FNV-1a, simple and stable hash function.
+fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, &byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn pack_unpack() {
+        let hashed = HashedSubspace::new(Subspace::from("hot"), 16);
+        let mut buckets = HashSet::new();
+
+        for i in 0..100i64 {
+            let tup = ("user", i);
+            let packed = hashed.pack(&tup);
+            let bucket = hashed.bucket_of(&tup);
+            buckets.insert(bucket);
+
+            assert!(hashed.is_start_of(&packed));
+            assert!(hashed.bucket_subspace(bucket).is_start_of(&packed));
+            let (begin, end) = hashed.range_for_bucket(bucket);
+            assert!(packed >= begin && packed <= end);
+
+            let unpacked: (String, i64) = hashed.unpack(&packed).unwrap();
+            assert_eq!(unpacked, ("user".to_string(), i));
+        }
+
+        assert!(buckets.len() > 1, "keys should be spread across buckets");
+        assert!(buckets.iter().all(|&bucket| bucket < 16));
+    }
+
+    #[test]
+    fn stable_hash() {
+        let hashed = HashedSubspace::new(Subspace::all(), 1024);
+        assert_eq!(fnv1a(b""), 0x811c_9dc5);
+        assert_eq!(fnv1a(b"a"), 0xe40c_292c);
+        assert_eq!(hashed.bucket_of(&"a"), (fnv1a(&pack(&"a")) % 1024) as u16);
+    }
+
+    #[test]
+    fn unpack_errors() {
+        let hashed = HashedSubspace::new(Subspace::from(1), 4);
+        assert!(matches!(
+            hashed.unpack::<i64>(&pack(&(2, 0, 42))),
+            Err(PackError::BadPrefix)
+        ));
+        assert!(matches!(
+            hashed.unpack::<i64>(&pack(&(1, 4, 42))),
+            Err(PackError::BadPrefix)
+        ));
+        assert_eq!(hashed.ranges().count(), 4);
+    }
+}
@@ -63,6 +63,12 @@ impl From<FdbError> for HcaError {
         Self::FdbError(err)
     }
 }
+// the allocator only sets options without parameters, which cannot be invalid
+impl From<options::OptionError> for HcaError {
+    fn from(err: options::OptionError) -> Self {
+        Self::FdbError(err.into())
+    }
+}
 impl From<PackError> for HcaError {
     fn from(err: PackError) -> Self {
         Self::PackError(err)
@@ -110,7 +116,7 @@ impl HighContentionAllocator {
     /// Returns a byte string that
     ///   1) has never and will never be returned by another call to this method on the same subspace
     ///   2) is nearly as short as possible given the above
-    pub async fn allocate(&self, trx: &Transaction) -> Result<i64, HcaError> {
+    pub async fn allocate<T: MutateTransaction + ?Sized>(&self, trx: &T) -> Result<i64, HcaError> {
         let (begin, end) = self.counters.range();
         let begin = KeySelector::first_greater_or_equal(begin);
         let end = KeySelector::first_greater_than(end);
@@ -124,7 +130,7 @@ impl HighContentionAllocator {
         let mut rng = SmallRng::from_rng(&mut rand::thread_rng())?;
 
         loop {
-            let kvs = trx.get_range(&counters_range, 1, true).await?;
+            let kvs = Snapshot::new(trx).get_range(&counters_range, 1).await?;
 
             let mut start: i64 = if let Some(first) = kvs.first() {
                 self.counters.unpack(first.key())?
@@ -147,7 +153,7 @@ impl HighContentionAllocator {
 
                     // Increment the allocation count for the current window
                     trx.atomic_op(counters_start.bytes(), ONE_BYTES, MutationType::Add);
-                    trx.get(counters_start.bytes(), true)
+                    Snapshot::new(trx).get(counters_start.bytes())
                 };
 
                 let count_value = count_future.await?;
@@ -181,8 +187,8 @@ impl HighContentionAllocator {
 
                 let (latest_counter, candidate_value) = {
                     let _mutex_guard = self.allocation_mutex.lock()?;
-                    let latest_counter = trx.get_range(&counters_range, 1, true);
-                    let candidate_value = trx.get(recent_candidate.bytes(), false);
+                    let latest_counter = Snapshot::new(trx).get_range(&counters_range, 1);
+                    let candidate_value = trx.read(recent_candidate.bytes());
                     trx.set_option(TransactionOption::NextWriteNoWriteConflictRange)?;
                     trx.set(recent_candidate.bytes(), &[]);
                     (latest_counter, candidate_value)
@@ -0,0 +1,101 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The writer [`TuplePack`](super::TuplePack) packs into.
+//!
+//! With the `std` feature, this is `std::io`. Without it, a minimal replacement only providing
+//! what the tuple encoding needs is used instead.
+
+#[cfg(feature = "std")]
+pub use std::io::{Error, ErrorKind, Result, Write};
+
+#[cfg(not(feature = "std"))]
+mod no_std {
+    use alloc::vec::Vec;
+    use core::fmt;
+
+    /// A list specifying the categories of [`Error`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[non_exhaustive]
+    pub enum ErrorKind {
+        /// A parameter was incorrect.
+        InvalidInput,
+        /// Data not valid for the operation were encountered.
+        InvalidData,
+        /// The writer could not accept all the bytes.
+        WriteZero,
+        /// Any other error.
+        Other,
+    }
+
+    /// The error type of [`Write`].
+    #[derive(Debug)]
+    pub struct Error {
+        kind: ErrorKind,
+    }
+
+    impl Error {
+        /// Creates an error of the given kind, the payload is dropped without `std`.
+        pub fn new<E>(kind: ErrorKind, _error: E) -> Self {
+            Error { kind }
+        }
+
+        /// Returns the kind of this error.
+        pub fn kind(&self) -> ErrorKind {
+            self.kind
+        }
+    }
+
+    impl From<ErrorKind> for Error {
+        fn from(kind: ErrorKind) -> Self {
+            Error { kind }
+        }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{:?}", self.kind)
+        }
+    }
+
+    /// Alias for `Result<..., io::Error>`
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// A byte sink, the subset of `std::io::Write` used by the tuple encoding.
+    pub trait Write {
+        /// Writes the whole buffer.
+        fn write_all(&mut self, buf: &[u8]) -> Result<()>;
+    }
+
+    impl Write for Vec<u8> {
+        fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+            self.extend_from_slice(buf);
+            Ok(())
+        }
+    }
+
+    impl Write for &mut [u8] {
+        fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+            if buf.len() > self.len() {
+                return Err(ErrorKind::WriteZero.into());
+            }
+            let (head, tail) = core::mem::take(self).split_at_mut(buf.len());
+            head.copy_from_slice(buf);
+            *self = tail;
+            Ok(())
+        }
+    }
+
+    impl<W: Write + ?Sized> Write for &mut W {
+        fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+            (**self).write_all(buf)
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub use no_std::*;
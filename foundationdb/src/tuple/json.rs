@@ -0,0 +1,228 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Conversion of tuple elements to and from `serde_json::Value`, for dynamic tooling.
+
+use super::{Bytes, Element, PackError, Versionstamp};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{Map, Number, Value};
+use std::borrow::Cow;
+
+impl<'a> Element<'a> {
+    /// Converts this element into a JSON value, meant for key inspectors and other tooling.
+    ///
+    /// Nil becomes `null`, booleans, integers and floating point numbers become JSON booleans
+    /// and numbers, strings become strings and nested tuples become arrays. Other types become
+    /// an object with a single field telling the type:
+    ///
+    /// - byte strings: `{"bytes": "<base64>"}`
+    /// - integers not fitting in a JSON number: `{"bigint": "<decimal>"}`
+    /// - uuids: `{"uuid": "<hyphenated>"}`
+    /// - versionstamps: `{"versionstamp": "<hex>"}`
+    ///
+    /// The conversion is lossy, NaN and infinite numbers become `null` and single precision
+    /// floats are not distinguished from doubles.
+    pub fn to_json(&self) -> Value {
+        match self {
+            Element::Nil => Value::Null,
+            Element::Bytes(v) => tagged("bytes", BASE64.encode(v)),
+            Element::String(v) => Value::String(v.to_string()),
+            Element::Tuple(v) => Value::Array(v.iter().map(Element::to_json).collect()),
+            Element::Int(v) => Value::Number((*v).into()),
+            #[cfg(feature = "num-bigint")]
+            Element::BigInt(v) => match u64::try_from(v) {
+                Ok(v) => Value::Number(v.into()),
+                Err(_) => tagged("bigint", v.to_string()),
+            },
+            Element::Float(v) => float_to_json(f64::from(*v)),
+            Element::Double(v) => float_to_json(*v),
+            Element::Bool(v) => Value::Bool(*v),
+            #[cfg(feature = "uuid")]
+            Element::Uuid(v) => tagged("uuid", v.hyphenated().to_string()),
+            Element::Versionstamp(v) => tagged(
+                "versionstamp",
+                v.as_bytes().iter().map(|b| format!("{:02x}", b)).collect(),
+            ),
+        }
+    }
+
+    /// Converts a JSON value, as produced by [`Element::to_json`], back into an element.
+    ///
+    /// Numbers become integers when they are integral and doubles otherwise, arrays become
+    /// nested tuples. Integers above `i64::MAX` require the `num-bigint` feature, they fail with
+    /// [`PackError::IntegerOverflow`] otherwise, rather than losing precision as a double.
+    /// Objects and strings that are not a valid encoding of a tagged type fail with a
+    /// [`PackError::Message`].
+    pub fn from_json(value: &Value) -> Result<Element<'static>, PackError> {
+        Ok(match value {
+            Value::Null => Element::Nil,
+            Value::Bool(v) => Element::Bool(*v),
+            Value::Number(v) => {
+                if let Some(v) = v.as_i64() {
+                    Element::Int(v)
+                } else if let Some(v) = v.as_u64() {
+                    u64_to_element(v)?
+                } else {
+                    Element::Double(v.as_f64().ok_or_else(|| invalid("number", value))?)
+                }
+            }
+            Value::String(v) => Element::String(Cow::Owned(v.clone())),
+            Value::Array(v) => {
+                Element::Tuple(v.iter().map(Element::from_json).collect::<Result<_, _>>()?)
+            }
+            Value::Object(v) => {
+                let (tag, tagged) = match v.iter().next() {
+                    Some(field) if v.len() == 1 => field,
+                    _ => return Err(invalid("object", value)),
+                };
+                let s = tagged.as_str().ok_or_else(|| invalid(tag, tagged))?;
+                match tag.as_str() {
+                    "bytes" => Element::Bytes(Bytes::from(
+                        BASE64.decode(s).map_err(|_| invalid(tag, tagged))?,
+                    )),
+                    #[cfg(feature = "num-bigint")]
+                    "bigint" => Element::BigInt(s.parse().map_err(|_| invalid(tag, tagged))?),
+                    #[cfg(feature = "uuid")]
+                    "uuid" => Element::Uuid(s.parse().map_err(|_| invalid(tag, tagged))?),
+                    "versionstamp" => {
+                        if s.len() != 24 || !s.is_ascii() {
+                            return Err(invalid(tag, tagged));
+                        }
+                        let mut bytes = [0; 12];
+                        for (i, byte) in bytes.iter_mut().enumerate() {
+                            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16)
+                                .map_err(|_| invalid(tag, tagged))?;
+                        }
+                        Element::Versionstamp(Versionstamp::from(bytes))
+                    }
+                    _ => return Err(invalid("object", value)),
+                }
+            }
+        })
+    }
+}
+
+fn tagged(tag: &str, value: String) -> Value {
+    let mut map = Map::new();
+    map.insert(tag.to_string(), Value::String(value));
+    Value::Object(map)
+}
+
+#[cfg(feature = "num-bigint")]
+fn u64_to_element(v: u64) -> Result<Element<'static>, PackError> {
+    Ok(Element::BigInt(v.into()))
+}
+
+/// Without bigints, integers above `i64::MAX` have no element to be held in
+#[cfg(not(feature = "num-bigint"))]
+fn u64_to_element(_v: u64) -> Result<Element<'static>, PackError> {
+    Err(PackError::IntegerOverflow)
+}
+
+fn invalid(what: &str, value: &Value) -> PackError {
+    PackError::Message(format!("invalid {} in tuple JSON: {}", what, value).into())
+}
+
+fn float_to_json(v: f64) -> Value {
+    Number::from_f64(v)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_json() {
+        let tuple = Element::Tuple(vec![
+            Element::Nil,
+            Element::Bool(true),
+            Element::Int(-42),
+            Element::Double(1.5),
+            Element::String(Cow::Borrowed("user")),
+            Element::Bytes(Bytes::from(&b"\x00\xff"[..])),
+            Element::Tuple(vec![Element::Int(1), Element::Nil]),
+            Element::Versionstamp(Versionstamp::complete([1; 10], 2)),
+        ]);
+        let value = tuple.to_json();
+        assert_eq!(
+            value,
+            json!([
+                null,
+                true,
+                -42,
+                1.5,
+                "user",
+                {"bytes": "AP8="},
+                [1, null],
+                {"versionstamp": "010101010101010101010002"},
+            ])
+        );
+        assert_eq!(Element::from_json(&value).unwrap(), tuple);
+
+        assert_eq!(Element::Double(f64::NAN).to_json(), Value::Null);
+        assert!(Element::from_json(&json!({"bytes": "A"})).is_err());
+        assert!(Element::from_json(&json!({"unknown": "A"})).is_err());
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_uuid_to_json() {
+        let uuid = uuid::Uuid::from_u128(0x0011_2233_4455_6677_8899_aabb_ccdd_eeff);
+        let value = Element::Uuid(uuid).to_json();
+        assert_eq!(
+            value,
+            json!({"uuid": "00112233-4455-6677-8899-aabbccddeeff"})
+        );
+        assert_eq!(Element::from_json(&value).unwrap(), Element::Uuid(uuid));
+    }
+
+    #[cfg(feature = "num-bigint")]
+    #[test]
+    fn test_bigint_to_json() {
+        let big = num_bigint::BigInt::from(u64::MAX) * 2u32;
+        let value = Element::BigInt(big.clone()).to_json();
+        assert_eq!(value, json!({"bigint": "36893488147419103230"}));
+        assert_eq!(Element::from_json(&value).unwrap(), Element::BigInt(big));
+        assert_eq!(
+            Element::from_json(&json!(u64::MAX)).unwrap(),
+            Element::BigInt(u64::MAX.into())
+        );
+    }
+
+    #[cfg(not(feature = "num-bigint"))]
+    #[test]
+    fn test_u64_from_json() {
+        assert!(matches!(
+            Element::from_json(&json!(u64::MAX)),
+            Err(PackError::IntegerOverflow)
+        ));
+    }
+
+    #[test]
+    fn test_bytes_to_json() {
+        for (raw, encoded) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"\xff\xfe\xfd", "//79"),
+        ] {
+            let value = json!({ "bytes": encoded });
+            assert_eq!(Element::Bytes(Bytes::from(raw)).to_json(), value);
+            assert_eq!(
+                Element::from_json(&value).unwrap(),
+                Element::Bytes(Bytes::from(raw))
+            );
+        }
+        assert!(Element::from_json(&json!({"bytes": "Zg=a"})).is_err());
+        assert!(Element::from_json(&json!({"bytes": "Zg==Zg=="})).is_err());
+    }
+}
@@ -2,25 +2,69 @@
 //!
 //! The official specification can be found [here](https://github.com/apple/foundationdb/blob/master/design/tuple.md).
 
+mod builder;
 mod element;
+mod fields;
+mod hashed_subspace;
+#[cfg(feature = "client")]
 pub mod hca;
+pub mod io;
+#[cfg(feature = "serde")]
+mod json;
+mod ordered;
 mod pack;
+pub(crate) mod printable;
+#[cfg(feature = "serde")]
+mod serialize;
 mod subspace;
+mod timestamp;
 mod versionstamp;
 
-use std::borrow::Cow;
-use std::fmt::{self, Display};
-use std::io;
-use std::ops::Deref;
-use std::result;
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+use core::fmt::{self, Display};
+use core::ops::Deref;
+use core::result;
 
 #[cfg(feature = "uuid")]
 pub use uuid::Uuid;
 
-pub use element::Element;
-pub use pack::{TuplePack, TupleUnpack, VersionstampOffset};
+pub use builder::TupleBuilder;
+pub use element::{Element, Elements};
+pub use fields::{TuplePackFields, TupleUnpackFields};
+/// Only the enums with unit variants are supported, see [`TuplePackFields`]:
+///
+/// ```compile_fail
+/// use foundationdb::tuple::TuplePack;
+///
+/// #[derive(TuplePack)]
+/// enum Id {
+///     Numeric(u64), // a variant with fields
+///     Named(String),
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use foundationdb::tuple::TuplePack;
+///
+/// #[derive(TuplePack)]
+/// enum Level<const N: usize> {
+///     // generic, even without fields
+///     Low,
+///     High,
+/// }
+/// ```
+pub use foundationdb_macros::TuplePack;
+pub use foundationdb_macros::TupleUnpack;
+pub use hashed_subspace::HashedSubspace;
+pub use ordered::{OrderedF32, OrderedF64};
+pub use pack::{LenientBool, TuplePack, TupleUnpack, VersionstampOffset};
+#[cfg(feature = "serde")]
+pub use serialize::{from_bytes, to_bytes};
 pub use subspace::Subspace;
-pub use versionstamp::Versionstamp;
+pub use timestamp::Timestamp;
+pub use versionstamp::{Versionstamp, VersionstampAllocator};
 
 const NIL: u8 = 0x00;
 const BYTES: u8 = 0x01;
@@ -41,23 +85,43 @@ const VERSIONSTAMP: u8 = 0x33;
 
 const ESCAPE: u8 = 0xff;
 
+/// How deep tuples may be nested when unpacking, to bound the recursion on untrusted input
+pub const MAX_NESTED_DEPTH: usize = 256;
+
 /// Tracks the depth of a Tuple decoding chain
 #[derive(Copy, Clone)]
-pub struct TupleDepth(usize);
+pub struct TupleDepth {
+    depth: usize,
+    /// Whether the errors of tuple elements are wrapped in [`PackError::Located`]
+    located: bool,
+}
 
 impl TupleDepth {
     fn new() -> Self {
-        TupleDepth(0)
+        TupleDepth {
+            depth: 0,
+            located: false,
+        }
+    }
+
+    fn located() -> Self {
+        TupleDepth {
+            depth: 0,
+            located: true,
+        }
     }
 
     /// Increment the depth by one, this be called when calling into `Tuple::{encode, decode}` of tuple-like datastructures
     pub fn increment(self) -> Self {
-        TupleDepth(self.0 + 1)
+        TupleDepth {
+            depth: self.depth + 1,
+            ..self
+        }
     }
 
     /// Returns the current depth in any recursive tuple processing, 0 representing there having been no recursion
     pub fn depth(self) -> usize {
-        self.0
+        self.depth
     }
 }
 
@@ -67,6 +131,7 @@ pub enum PackError {
     Message(Box<str>),
     IoError(io::Error),
     TrailingBytes,
+    /// The input ended before the value, or a nested tuple it opened, was complete
     MissingBytes,
     BadStringFormat,
     BadCode {
@@ -76,7 +141,33 @@ pub enum PackError {
     BadPrefix,
     #[cfg(feature = "uuid")]
     BadUuid,
+    /// Not returned anymore, integers not fitting the type decoded give
+    /// [`PackError::IntegerOverflow`]
     UnsupportedIntLength,
+    /// The integer decoded does not fit the requested type
+    IntegerOverflow,
+    /// A fixed-size value was decoded from an element of another length
+    BadLength {
+        expected: usize,
+        found: usize,
+    },
+    /// Tuples are nested deeper than [`MAX_NESTED_DEPTH`]
+    NestedTooDeep,
+    /// A versionstamped key was requested from a value holding no incomplete versionstamp
+    NoIncompleteVersionstamp,
+    /// A versionstamped key was requested from a value holding several incomplete versionstamps
+    MultipleIncompleteVersionstamps,
+    /// Where unpacking failed, only returned by [`unpack_located`]
+    Located {
+        /// The offset in the input of the element which could not be unpacked, the innermost one
+        /// when tuples are nested
+        offset: usize,
+        /// The index of the top-level element holding it
+        element: usize,
+        /// The type code of the element, `None` if the input ended before it
+        code: Option<u8>,
+        source: Box<PackError>,
+    },
 }
 
 impl From<io::Error> for PackError {
@@ -98,11 +189,44 @@ impl Display for PackError {
             #[cfg(feature = "uuid")]
             PackError::BadUuid => write!(f, "bad uuid"),
             PackError::UnsupportedIntLength => write!(f, "integer length was to large"),
+            PackError::IntegerOverflow => write!(f, "integer overflow"),
+            PackError::BadLength { expected, found } => {
+                write!(
+                    f,
+                    "bad length, expected {} bytes, found {}",
+                    expected, found
+                )
+            }
+            PackError::NestedTooDeep => write!(f, "tuples nested too deep"),
+            PackError::NoIncompleteVersionstamp => write!(f, "no incomplete versionstamp"),
+            PackError::MultipleIncompleteVersionstamps => {
+                write!(f, "multiple incomplete versionstamps")
+            }
+            PackError::Located {
+                offset,
+                element,
+                code,
+                source,
+            } => {
+                write!(f, "{} at offset {} (element {}", source, offset, element)?;
+                match code {
+                    Some(code) => write!(f, ", code {:#04x})", code),
+                    None => write!(f, ")"),
+                }
+            }
         }
     }
 }
 
-impl std::error::Error for PackError {}
+#[cfg(feature = "std")]
+impl std::error::Error for PackError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PackError::Located { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
 
 /// Alias for `Result<..., tuple::Error>`
 pub type PackResult<T> = result::Result<T, PackError>;
@@ -214,11 +338,94 @@ pub fn pack_into_with_versionstamp<T: TuplePack>(v: &T, output: &mut Vec<u8>) {
     }
 }
 
+/// Pack value and returns the packed buffer, followed by the offset of its incomplete
+/// versionstamp, as expected by `MutationType::SetVersionstampedKey`
+///
+/// Unlike [`pack_with_versionstamp`], it fails if the value does not hold exactly one incomplete
+/// versionstamp, instead of giving a key the cluster would reject at commit time.
+///
+/// # Panics
+///
+/// Panics if the encoded data size doesn't fit in `u32`.
+pub fn try_pack_with_versionstamp<T: TuplePack>(v: &T) -> PackResult<Vec<u8>> {
+    let mut output = Vec::new();
+    match v.pack_into_vec_with_versionstamp(&mut output) {
+        VersionstampOffset::OneIncomplete { .. } => Ok(output),
+        VersionstampOffset::None { .. } => Err(PackError::NoIncompleteVersionstamp),
+        VersionstampOffset::MultipleIncomplete => Err(PackError::MultipleIncompleteVersionstamps),
+    }
+}
+
 /// Unpack input
 pub fn unpack<'de, T: TupleUnpack<'de>>(input: &'de [u8]) -> PackResult<T> {
     T::unpack_root(input)
 }
 
+/// Unpack input, ignoring any trailing `0x00` padding bytes after the value
+///
+/// Some external producers pad keys with zeros to a fixed width, `unpack` would reject those keys
+/// with [`PackError::TrailingBytes`]. Only use this mode to accommodate such producers.
+///
+/// Note that a top-level `0x00` is also the encoding of a nil element, value types that accept
+/// any number of elements, like `Vec<Element>`, will decode the padding as trailing nils.
+pub fn unpack_ignore_trailing_zeros<'de, T: TupleUnpack<'de>>(input: &'de [u8]) -> PackResult<T> {
+    let (input, this) = T::unpack(input, TupleDepth::new())?;
+    if input.iter().any(|&byte| byte != NIL) {
+        return Err(PackError::TrailingBytes);
+    }
+    Ok(this)
+}
+
+/// Unpack input, failing with [`PackError::Located`] to tell which element could not be unpacked
+///
+/// Trailing bytes after the value fail with [`PackError::TrailingBytes`], like with [`unpack`].
+pub fn unpack_located<'de, T: TupleUnpack<'de>>(input: &'de [u8]) -> PackResult<T> {
+    match T::unpack(input, TupleDepth::located()) {
+        Ok((rem, this)) => {
+            if !rem.is_empty() {
+                return Err(PackError::TrailingBytes);
+            }
+            Ok(this)
+        }
+        // the offsets were recorded as the length of the input left
+        Err(PackError::Located {
+            offset,
+            element,
+            code,
+            source,
+        }) => Err(PackError::Located {
+            offset: input.len() - offset,
+            element,
+            code,
+            source,
+        }),
+        Err(err) => Err(PackError::Located {
+            offset: 0,
+            element: 0,
+            code: input.first().copied(),
+            source: Box::new(err),
+        }),
+    }
+}
+
+/// Iterate over the elements of input, unpacking them lazily
+///
+/// Unlike `unpack::<Vec<Element>>`, nothing is allocated up front and the iteration can stop
+/// early, after the first elements of a long key.
+pub fn unpack_elements(input: &[u8]) -> Elements<'_> {
+    Elements::new(input)
+}
+
+/// Unpack the value at the start of input, and the number of bytes it took
+///
+/// Unlike [`unpack`], the input may go on after the value, so a tuple type unpacks as many
+/// elements of a longer key as it holds. Types holding any number of elements, like
+/// `Vec<Element>`, still unpack all of them.
+pub fn unpack_prefix<'de, T: TupleUnpack<'de>>(input: &'de [u8]) -> PackResult<(T, usize)> {
+    let (rem, this) = T::unpack(input, TupleDepth::new())?;
+    Ok((this, input.len() - rem.len()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -351,7 +558,7 @@ mod tests {
         test_serde(9252427359321063944i128, b"\x1c\x80g9\xa9np\x02\x08");
         assert!(matches!(
             unpack::<i64>(b"\x1c\x80g9\xa9np\x02\x08").unwrap_err(),
-            PackError::UnsupportedIntLength
+            PackError::IntegerOverflow
         ));
 
         test_serde(
@@ -360,7 +567,7 @@ mod tests {
         );
         assert!(matches!(
             unpack::<i64>(b"\x0c\x7f\x98\xc6V\x91\x8f\xfd\xf7").unwrap_err(),
-            PackError::UnsupportedIntLength
+            PackError::IntegerOverflow
         ));
 
         test_serde(
@@ -369,7 +576,7 @@ mod tests {
         );
         assert!(matches!(
             unpack::<i64>(b"\x1c\xff\xff\xff\xff\xff\xff\xff\xff").unwrap_err(),
-            PackError::UnsupportedIntLength
+            PackError::IntegerOverflow
         ));
 
         test_serde(
@@ -378,7 +585,7 @@ mod tests {
         );
         assert!(matches!(
             unpack::<i64>(b"\x0c\x00\x00\x00\x00\x00\x00\x00\x00").unwrap_err(),
-            PackError::UnsupportedIntLength
+            PackError::IntegerOverflow
         ));
 
         test_serde(
@@ -387,7 +594,7 @@ mod tests {
         );
         assert!(matches!(
             unpack::<i64>(b"\x1c\x80\x00\x00\x00\x00\x00\x00\x00").unwrap_err(),
-            PackError::UnsupportedIntLength
+            PackError::IntegerOverflow
         ));
 
         test_serde(
@@ -396,7 +603,136 @@ mod tests {
         );
         assert!(matches!(
             unpack::<i64>(b"\x0c\x7f\xff\xff\xff\xff\xff\xff\xfe").unwrap_err(),
-            PackError::UnsupportedIntLength
+            PackError::IntegerOverflow
+        ));
+    }
+
+    #[test]
+    fn test_integer_overflow() {
+        fn overflows<'de, T: TupleUnpack<'de> + fmt::Debug>(buf: &'de [u8]) {
+            assert!(
+                matches!(unpack::<T>(buf), Err(PackError::IntegerOverflow)),
+                "{:?}",
+                unpack::<T>(buf)
+            );
+        }
+
+        overflows::<u16>(&pack(&65536u32));
+        overflows::<i16>(&pack(&32768u32));
+        overflows::<i16>(&pack(&-32769i32));
+        overflows::<u64>(&pack(&-1i64));
+        overflows::<u32>(&pack(&i64::MIN));
+        overflows::<u64>(&pack(&(u64::MAX as u128 + 1)));
+        overflows::<i64>(&pack(&(u64::MAX as u128 + 1)));
+        overflows::<i64>(&pack(&(-(u64::MAX as i128) - 1)));
+        overflows::<i128>(&pack(&u128::MAX));
+
+        assert_eq!(unpack::<u16>(&pack(&65535u32)).unwrap(), 65535);
+        assert_eq!(unpack::<i16>(&pack(&-32768i32)).unwrap(), -32768);
+        assert_eq!(unpack::<u64>(&pack(&(u64::MAX as i128))).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn test_integer_ordering() {
+        let boundaries = [
+            -(u64::MAX as i128) * 2,
+            -(u64::MAX as i128) - 1,
+            -(u64::MAX as i128),
+            i64::MIN as i128 - 1,
+            i64::MIN as i128,
+            i64::MIN as i128 + 1,
+            -256,
+            -255,
+            -1,
+            0,
+            1,
+            255,
+            256,
+            i64::MAX as i128,
+            i64::MAX as i128 + 1,
+            u64::MAX as i128,
+            u64::MAX as i128 + 1,
+            u64::MAX as i128 * 2,
+        ];
+        for a in boundaries {
+            for b in boundaries {
+                assert_eq!(a.cmp(&b), pack(&a).cmp(&pack(&b)), "{} {}", a, b);
+            }
+            if let Ok(v) = i64::try_from(a) {
+                assert_eq!(pack(&v), pack(&a));
+                assert_eq!(unpack::<i64>(&pack(&a)).unwrap(), v);
+            }
+            if let Ok(v) = u64::try_from(a) {
+                assert_eq!(pack(&v), pack(&a));
+                assert_eq!(unpack::<u64>(&pack(&a)).unwrap(), v);
+            }
+        }
+    }
+
+    #[test]
+    fn test_integer_widths() {
+        for n in 1..=8u32 {
+            // the largest magnitude taking n bytes, and the smallest one taking n + 1
+            let max = if n == 8 {
+                u64::MAX
+            } else {
+                (1u64 << (8 * n)) - 1
+            };
+            let code = INTZERO + n as u8;
+            let mut expected = vec![code];
+            expected.extend_from_slice(&max.to_be_bytes()[8 - n as usize..]);
+            test_serde(max, &expected);
+            test_serde(max as i128, &expected);
+
+            // negative integers are the one's complement of their magnitude
+            let code = INTZERO - n as u8;
+            let mut expected = vec![code];
+            expected.extend_from_slice(&[0; 8][8 - n as usize..]);
+            test_serde(-(max as i128), &expected);
+            if let Ok(min) = i64::try_from(-(max as i128)) {
+                test_serde(min, &expected);
+            }
+
+            if n < 8 {
+                assert_eq!(pack(&(max + 1))[0], INTZERO + n as u8 + 1);
+                assert_eq!(pack(&-(max as i128 + 1))[0], INTZERO - n as u8 - 1);
+            }
+
+            // truncated input fails instead of reading past its end
+            let packed = pack(&max);
+            for len in 1..packed.len() {
+                assert!(matches!(
+                    unpack::<u64>(&packed[..len]),
+                    Err(PackError::MissingBytes)
+                ));
+                assert!(matches!(
+                    unpack::<i128>(&packed[..len]),
+                    Err(PackError::MissingBytes)
+                ));
+            }
+            let packed = pack(&-(max as i128));
+            for len in 1..packed.len() {
+                assert!(matches!(
+                    unpack::<i128>(&packed[..len]),
+                    Err(PackError::MissingBytes)
+                ));
+            }
+        }
+
+        test_serde(-1i64, b"\x13\xfe");
+        test_serde(i64::MAX, b"\x1c\x7f\xff\xff\xff\xff\xff\xff\xff");
+        test_serde(i64::MIN, b"\x0c\x7f\xff\xff\xff\xff\xff\xff\xff");
+        assert!(matches!(
+            unpack::<i64>(b"\x1d\x09\x01"),
+            Err(PackError::IntegerOverflow)
+        ));
+        assert!(matches!(
+            unpack::<i128>(b"\x1d\x09\x01"),
+            Err(PackError::MissingBytes)
+        ));
+        assert!(matches!(
+            unpack::<i128>(b"\x0b"),
+            Err(PackError::MissingBytes)
         ));
     }
 
@@ -554,6 +890,161 @@ mod tests {
             ),
             b"\x30\xba\xff\xff\xff\xff\x5e\xba\x11\x00\x00\x00\x00\x5c\xa1\xab\x1e",
         );
+
+        // packed by the Java binding with
+        // Tuple.from(UUID.fromString("123e4567-e89b-12d3-a456-426614174000")).pack()
+        // UUIDs are in the RFC 4122 big-endian order, not the mixed-endian order of Windows GUIDs
+        let uuid: Uuid = "123e4567-e89b-12d3-a456-426614174000".parse().unwrap();
+        let java = b"\x30\x12\x3e\x45\x67\xe8\x9b\x12\xd3\xa4\x56\x42\x66\x14\x17\x40\x00";
+        test_serde(uuid, java);
+        test_serde(Element::from(uuid), java);
+        assert_ne!(&java[1..], &uuid.to_bytes_le()[..]);
+
+        assert_eq!(Uuid::try_from(Element::from(uuid)).unwrap(), uuid);
+        assert!(matches!(
+            Uuid::try_from(Element::Int(1)),
+            Err(PackError::BadCode {
+                found: INTZERO,
+                expected: Some(UUID)
+            })
+        ));
+
+        // in subspaces and typed tuples
+        let subspace = Subspace::from("users").subspace(&uuid);
+        assert_eq!(
+            subspace.bytes(),
+            &[&b"\x02users\x00"[..], java].concat()[..]
+        );
+        let key = subspace.pack(&("name", uuid));
+        assert_eq!(
+            Subspace::from("users")
+                .unpack::<(Uuid, String, Uuid)>(&key)
+                .unwrap(),
+            (uuid, "name".to_string(), uuid)
+        );
+
+        assert_eq!(
+            Element::Tuple(vec![Element::from(uuid)]).to_string(),
+            "(UUID('123e4567-e89b-12d3-a456-426614174000'),)"
+        );
+    }
+
+    #[test]
+    fn test_element_from() {
+        let element: Element = vec![
+            Element::from(42i64),
+            Element::from(-1i8),
+            Element::from(7u32),
+            Element::from("hi"),
+            Element::from(String::from("owned")),
+            Element::from(vec![1u8, 0]),
+            Element::from(&b"\xff"[..]),
+            Element::from(true),
+            Element::from(1.5f32),
+            Element::from(-2.5f64),
+            Element::from(None::<i64>),
+            Element::from(Some("some")),
+            Element::from(Versionstamp::complete([1; 10], 2)),
+            ["a", "b"].into_iter().map(Element::from).collect(),
+        ]
+        .into();
+
+        let mut expected = pack(&(
+            42i64,
+            -1i64,
+            7i64,
+            "hi",
+            "owned",
+            Bytes::from(&b"\x01\x00"[..]),
+            Bytes::from(&b"\xff"[..]),
+        ));
+        expected.extend(pack(&(
+            true,
+            1.5f32,
+            -2.5f64,
+            None::<i64>,
+            "some",
+            Versionstamp::complete([1; 10], 2),
+            ("a", "b"),
+        )));
+        assert_eq!(pack(&element), expected);
+
+        // a dynamic number of elements
+        let collected: Element = (0..3).map(Element::from).collect();
+        assert_eq!(
+            collected,
+            Element::Tuple(vec![0.into(), 1.into(), 2.into()])
+        );
+        assert_eq!(pack(&collected), pack(&(0, 1, 2)));
+    }
+
+    #[test]
+    fn test_element_access() {
+        let packed = pack(&("user", 42, -1.5f64, true, b"\x00".as_ref(), (1, 2)));
+        let elements = unpack::<Vec<Element>>(&packed).unwrap();
+
+        assert_eq!(elements.len(), 6);
+        assert_eq!(elements.get(1), Some(&Element::Int(42)));
+        assert_eq!(elements.get(6), None);
+        assert_eq!(elements[0], Element::String("user".into()));
+        assert_eq!(elements.iter().filter(|e| e.as_i64().is_some()).count(), 1);
+
+        assert_eq!(<&str>::try_from(&elements[0]).unwrap(), "user");
+        assert_eq!(i64::try_from(&elements[1]).unwrap(), 42);
+        assert_eq!(f64::try_from(&elements[2]).unwrap(), -1.5);
+        assert!(bool::try_from(&elements[3]).unwrap());
+        assert_eq!(<&[u8]>::try_from(&elements[4]).unwrap(), b"\x00");
+        let nested = <&[Element]>::try_from(&elements[5]).unwrap();
+        assert_eq!(i64::try_from(&nested[1]).unwrap(), 2);
+
+        // type mismatches
+        assert!(matches!(
+            i64::try_from(&elements[0]),
+            Err(PackError::BadCode {
+                found: STRING,
+                expected: Some(INTZERO)
+            })
+        ));
+        assert!(matches!(
+            <&str>::try_from(&elements[4]),
+            Err(PackError::BadCode {
+                found: BYTES,
+                expected: Some(STRING)
+            })
+        ));
+        assert!(matches!(
+            f32::try_from(&elements[2]),
+            Err(PackError::BadCode {
+                found: DOUBLE,
+                expected: Some(FLOAT)
+            })
+        ));
+        #[cfg(feature = "num-bigint")]
+        assert!(matches!(
+            i64::try_from(&Element::BigInt(num_bigint::BigInt::from(u64::MAX))),
+            Err(PackError::IntegerOverflow)
+        ));
+    }
+
+    #[test]
+    fn test_element_display() {
+        let element = Element::Tuple(vec![
+            Element::Nil,
+            Element::Bytes(Bytes::from(&b"a\x00"[..])),
+            Element::String(Cow::Borrowed("it's \"quoted\"")),
+            Element::Int(-42),
+            Element::Float(1.5),
+            Element::Double(-0.0),
+            Element::Bool(true),
+            Element::Tuple(vec![]),
+            Element::Tuple(vec![Element::Bool(false)]),
+            Element::Versionstamp(Versionstamp::incomplete(1)),
+        ]);
+        assert_eq!(
+            element.to_string(),
+            "(None, b\"a\\x00\", \"it's \\\"quoted\\\"\", -42, 1.5, -0.0, True, (), (False,), \
+             Versionstamp(b\"\\xff\\xff\\xff\\xff\\xff\\xff\\xff\\xff\\xff\\xff\\x00\\x01\"))"
+        );
     }
 
     #[test]
@@ -608,6 +1099,155 @@ mod tests {
         );
     }
 
+    /// A xorshift generator, so every run checks the same elements
+    struct Xorshift(u64);
+
+    impl Xorshift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+
+        /// Few distinct bytes, so values often share prefixes or hold escaped zeros
+        fn bytes(&mut self) -> Vec<u8> {
+            let len = self.below(4);
+            (0..len)
+                .map(|_| [0x00, 0x01, b'a', 0xfe, 0xff][self.below(5) as usize])
+                .collect()
+        }
+
+        fn element(&mut self, depth: usize) -> Element<'static> {
+            match self.below(if depth < 3 { 12 } else { 11 }) {
+                0 => Element::Nil,
+                1 => Element::Bytes(self.bytes().into()),
+                2 => Element::String(Cow::Owned(
+                    (0..self.below(4))
+                        .map(|_| ["\0", "a", "b", "é", "\u{10000}"][self.below(5) as usize])
+                        .collect(),
+                )),
+                3 => {
+                    // integers of every width, and their neighbours
+                    let v = self.next() >> self.below(64);
+                    let v = (v as i64).wrapping_add(self.below(3) as i64 - 1);
+                    Element::Int(if self.below(2) == 0 {
+                        v
+                    } else {
+                        v.wrapping_neg()
+                    })
+                }
+                4 => Element::Int(
+                    [
+                        i64::MIN,
+                        i64::MIN + 1,
+                        -256,
+                        -255,
+                        -1,
+                        0,
+                        1,
+                        255,
+                        256,
+                        i64::MAX,
+                    ][self.below(10) as usize],
+                ),
+                #[cfg(feature = "num-bigint")]
+                5 => {
+                    let v = (self.next() as i128) << self.below(64);
+                    Element::BigInt(if self.below(2) == 0 { v } else { -v }.into())
+                }
+                #[cfg(not(feature = "num-bigint"))]
+                5 => Element::Int(self.next() as i64),
+                6 => Element::Float(match self.below(4) {
+                    0 => [0.0, -0.0, f32::INFINITY, f32::NEG_INFINITY, f32::NAN]
+                        [self.below(5) as usize],
+                    _ => f32::from_bits(self.next() as u32),
+                }),
+                7 => Element::Double(match self.below(4) {
+                    0 => [0.0, -0.0, f64::INFINITY, f64::NEG_INFINITY, f64::NAN]
+                        [self.below(5) as usize],
+                    _ => f64::from_bits(self.next()),
+                }),
+                8 => Element::Bool(self.below(2) == 0),
+                #[cfg(feature = "uuid")]
+                9 => Element::Uuid(uuid::Uuid::from_u128(
+                    u128::from(self.below(3)) << (self.below(2) * 120),
+                )),
+                #[cfg(not(feature = "uuid"))]
+                9 => Element::Bool(true),
+                10 => {
+                    let mut tr_version = [0; 10];
+                    tr_version[self.below(10) as usize] = self.below(256) as u8;
+                    Element::Versionstamp(match self.below(4) {
+                        0 => Versionstamp::incomplete(self.below(3) as u16),
+                        _ => Versionstamp::complete(tr_version, self.below(3) as u16),
+                    })
+                }
+                _ => Element::Tuple(
+                    (0..self.below(4))
+                        .map(|_| self.element(depth + 1))
+                        .collect(),
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_element_ordering() {
+        let mut rng = Xorshift(0x2545_f491_4f6c_dd1d);
+        let elements: Vec<Element> = (0..600).map(|_| rng.element(0)).collect();
+        for a in &elements {
+            let packed_a = pack(a);
+            for b in &elements {
+                let packed_b = pack(b);
+                assert_eq!(
+                    a.cmp(b),
+                    packed_a.cmp(&packed_b),
+                    "{:?} {:?}, packed as {:?} {:?}",
+                    a,
+                    b,
+                    Bytes::from(packed_a.as_slice()),
+                    Bytes::from(packed_b.as_slice())
+                );
+            }
+        }
+
+        let mut sorted = elements.clone();
+        sorted.sort();
+        let mut packed: Vec<Vec<u8>> = elements.iter().map(pack).collect();
+        packed.sort();
+        assert_eq!(sorted.iter().map(pack).collect::<Vec<_>>(), packed);
+    }
+
+    #[test]
+    fn test_element_hash() {
+        use std::collections::HashSet;
+
+        let mut rng = Xorshift(0x9e37_79b9_7f4a_7c15);
+        let elements: Vec<Element> = (0..2000).map(|_| rng.element(0)).collect();
+        let distinct: HashSet<&Element> = elements.iter().collect();
+        let packed: HashSet<Vec<u8>> = elements.iter().map(pack).collect();
+        assert_eq!(distinct.len(), packed.len());
+
+        let set: HashSet<Element> = [
+            Element::Int(5),
+            #[cfg(feature = "num-bigint")]
+            Element::BigInt(5.into()),
+            Element::Tuple(vec![Element::Int(5)]),
+            Element::Double(0.0),
+            Element::Double(-0.0),
+            Element::Double(f64::NAN),
+            Element::Double(f64::NAN),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(set.len(), 4);
+    }
+
     #[test]
     fn test_element() {
         test_serde(Element::Bool(true), &[TRUE]);
@@ -649,6 +1289,499 @@ mod tests {
         test_serde(Element::Tuple(vec![]), &[]);
     }
 
+    #[test]
+    fn test_option() {
+        test_serde(Some(1i64), &[0x15, 1]);
+        test_serde((Some(1i64), None::<i64>), &[0x15, 1, NIL]);
+        test_serde(
+            ((None::<i64>, Some(1i64)),),
+            &[NESTED, NIL, ESCAPE, 0x15, 1, NIL],
+        );
+
+        // the nil element is None however deep the options are nested
+        assert_eq!(pack(&Some(None::<i64>)), pack(&None::<i64>));
+        assert_eq!(unpack::<Option<Option<i64>>>(&[NIL]).unwrap(), None);
+        test_serde(Some(Some(1i64)), &[0x15, 1]);
+
+        // Some(()) is an empty nested tuple, or nothing at all at the top level
+        test_serde((Some(()), None::<()>), &[NESTED, NIL, NIL]);
+        test_serde(Some(()), &[]);
+        test_serde(None::<()>, &[NIL]);
+
+        test_serde(
+            (Some("a".to_owned()), None::<i64>),
+            &[STRING, b'a', NIL, NIL],
+        );
+        assert_eq!(
+            unpack::<Vec<Element>>(&[STRING, b'a', NIL, NIL]).unwrap(),
+            vec![Element::String(Cow::Borrowed("a")), Element::Nil]
+        );
+        // a leading zero byte is escaped, and not mistaken for the nil element
+        test_serde(
+            (Some(Bytes::from(&b"\x00a"[..])), None::<i64>),
+            &[BYTES, NIL, ESCAPE, b'a', NIL, NIL],
+        );
+        test_serde(
+            ((Bytes::from(&b"\x00"[..]), None::<i64>),),
+            &[NESTED, BYTES, NIL, ESCAPE, NIL, NIL, ESCAPE, NIL],
+        );
+    }
+
+    #[test]
+    fn test_bool() {
+        // fdb.tuple.pack(("flag", True, 1))
+        let fixture = b"\x02flag\x00\x27\x15\x01";
+        test_serde(("flag".to_owned(), true, 1), fixture);
+        assert_eq!(
+            unpack::<Vec<Element>>(fixture).unwrap(),
+            vec![
+                Element::String(Cow::Borrowed("flag")),
+                Element::Bool(true),
+                Element::Int(1),
+            ]
+        );
+        assert!(pack(&false) < pack(&true));
+
+        // booleans stored as integers by older layers
+        assert!(unpack::<(String, bool, bool)>(fixture).is_err());
+        let (_, a, b) = unpack::<(String, LenientBool, LenientBool)>(fixture).unwrap();
+        assert_eq!((a, b), (LenientBool(true), LenientBool(true)));
+        test_serde(LenientBool(false), &[FALSE]);
+        assert_eq!(
+            unpack::<LenientBool>(&pack(&0)).unwrap(),
+            LenientBool(false)
+        );
+        assert!(unpack::<LenientBool>(&pack(&2)).is_err());
+        assert!(unpack::<LenientBool>(&pack(&-1)).is_err());
+        assert!(unpack::<LenientBool>(&pack(&65536)).is_err());
+        assert!(unpack::<LenientBool>(&pack(&"true")).is_err());
+    }
+
+    #[test]
+    fn test_escaping() {
+        for (bytes, packed) in [
+            (&b""[..], &b"\x01\x00"[..]),
+            (b"\x00", b"\x01\x00\xff\x00"),
+            (b"a\x00b", b"\x01a\x00\xffb\x00"),
+            (b"\x00\x00", b"\x01\x00\xff\x00\xff\x00"),
+            (b"ab\x00", b"\x01ab\x00\xff\x00"),
+            (b"\xff\x00\xff", b"\x01\xff\x00\xff\xff\x00"),
+        ] {
+            test_serde(bytes.to_vec(), packed);
+        }
+        test_serde("ab\x00".to_owned(), b"\x02ab\x00\xff\x00");
+        test_serde("\x00".to_owned(), b"\x02\x00\xff\x00");
+
+        // a lone 0x00 terminates the string, even before an escaped one
+        assert!(matches!(
+            unpack::<Vec<u8>>(b"\x01a\x00\x00\xff\x00"),
+            Err(PackError::TrailingBytes)
+        ));
+        assert_eq!(
+            unpack_prefix::<Vec<u8>>(b"\x01a\x00\x00\xff\x00").unwrap(),
+            (b"a".to_vec(), 3)
+        );
+        // the terminator is required, also right after an escaped 0x00
+        assert!(matches!(
+            unpack::<Vec<u8>>(b"\x01a\x00\xff"),
+            Err(PackError::MissingBytes)
+        ));
+        assert!(matches!(
+            unpack::<String>(b"\x02\x00\xff"),
+            Err(PackError::MissingBytes)
+        ));
+    }
+
+    #[test]
+    fn test_borrowed() {
+        let packed = pack(&(&b"abc"[..], "def", &b"a\x00c"[..], "d\x00f"));
+        let (plain_bytes, plain_str, escaped_bytes, escaped_str) =
+            unpack::<(Cow<[u8]>, Cow<str>, Cow<[u8]>, Cow<str>)>(&packed).unwrap();
+        assert!(matches!(plain_bytes, Cow::Borrowed(b"abc")));
+        assert!(matches!(plain_str, Cow::Borrowed("def")));
+        assert!(matches!(escaped_bytes, Cow::Owned(ref v) if v == b"a\x00c"));
+        assert!(matches!(escaped_str, Cow::Owned(ref v) if v == "d\x00f"));
+        // the borrowed parts point into the input
+        assert_eq!(plain_bytes.as_ptr(), packed[1..].as_ptr());
+
+        test_serde(Cow::Borrowed(&b"a\x00"[..]), b"\x01a\x00\xff\x00");
+        test_serde(Cow::Borrowed("a\x00"), b"\x02a\x00\xff\x00");
+        assert_eq!(
+            unpack::<Vec<Cow<[u8]>>>(&pack(&vec![Bytes::from("x"), Bytes::from("\x00")])).unwrap(),
+            vec![Cow::Borrowed(&b"x"[..]), Cow::Owned(vec![0])]
+        );
+    }
+
+    #[test]
+    fn test_borrowed_pack() {
+        let name = String::from("user");
+        let id = b"\x00id".to_vec();
+        let hash: [u8; 4] = *b"hash";
+        let owned = pack(&(name.clone(), Bytes::from(id.clone()), hash.to_vec(), 42i64));
+
+        assert_eq!(pack(&("user", &id[..], &hash[..], 42i64)), owned);
+        assert_eq!(pack(&(&name, &Bytes::from(&id[..]), hash, &42i64)), owned);
+        assert_eq!(pack(&&&(name.as_str(), id.as_slice(), &hash, 42i64)), owned);
+        assert_eq!(pack(&("user",)), pack(&(&name,)));
+
+        let subspace = Subspace::from_bytes(b"prefix");
+        let mut key = b"prefix".to_vec();
+        key.extend_from_slice(&owned);
+        assert_eq!(subspace.pack(&("user", &id[..], hash, 42i64)), key);
+        assert_eq!(subspace.pack(&(&name, id.as_slice(), &hash, &42i64)), key);
+    }
+
+    #[test]
+    fn test_byte_arrays() {
+        test_serde([0u8; 0], &[BYTES, NIL]);
+        let hash: [u8; 16] = *b"0123456789abcdef";
+        test_serde(hash, b"\x010123456789abcdef\x00");
+        assert_eq!(pack(&&hash), pack(&hash));
+        assert_eq!(pack(&hash), pack(&hash.to_vec()));
+        test_serde(
+            (7i64, [0xde, 0xad, 0xbe, 0xefu8]),
+            b"\x15\x07\x01\xde\xad\xbe\xef\x00",
+        );
+
+        // escaped NILs take two bytes on the wire, but count as one
+        test_serde([0u8; 4], b"\x01\x00\xff\x00\xff\x00\xff\x00\xff\x00");
+        test_serde([1, 0, 2u8], b"\x01\x01\x00\xff\x02\x00");
+
+        assert!(matches!(
+            unpack::<[u8; 4]>(b"\x01\x00\xff\x00\xff\x00\xff\x00"),
+            Err(PackError::BadLength {
+                expected: 4,
+                found: 3
+            })
+        ));
+        assert!(matches!(
+            unpack::<[u8; 2]>(&pack(&hash)),
+            Err(PackError::BadLength {
+                expected: 2,
+                found: 16
+            })
+        ));
+        assert!(matches!(
+            unpack::<[u8; 2]>(&pack(&"ab")),
+            Err(PackError::BadCode {
+                found: STRING,
+                expected: Some(BYTES)
+            })
+        ));
+    }
+
+    #[test]
+    fn test_unpack_located() {
+        fn located<'de, T: TupleUnpack<'de> + fmt::Debug>(
+            input: &'de [u8],
+        ) -> (usize, usize, Option<u8>, PackError) {
+            match unpack_located::<T>(input).unwrap_err() {
+                PackError::Located {
+                    offset,
+                    element,
+                    code,
+                    source,
+                } => (offset, element, code, *source),
+                err => panic!("not located: {:?}", err),
+            }
+        }
+
+        // the string starts at offset 4, and is not an integer
+        let packed = pack(&(1, 2, "three"));
+        let (offset, element, code, source) = located::<(i64, i64, i64)>(&packed);
+        assert_eq!((offset, element, code), (4, 2, Some(STRING)));
+        assert!(matches!(source, PackError::BadCode { found: STRING, .. }));
+
+        // the innermost element gives the offset, the top-level one the index
+        let packed = pack(&(1, (2, (3, "four")), 5));
+        let (offset, element, code, _) = located::<(i64, (i64, (i64, i64)), i64)>(&packed);
+        assert_eq!((offset, element, code), (8, 1, Some(STRING)));
+
+        let (offset, element, code, source) = located::<(i64, i64)>(&pack(&(1,)));
+        assert_eq!((offset, element, code), (2, 1, None));
+        assert!(matches!(source, PackError::MissingBytes));
+        let (offset, element, code, _) = located::<Vec<Element>>(&[INTZERO, 0x15]);
+        assert_eq!((offset, element, code), (1, 1, Some(0x15)));
+        // an unknown type code in the middle of the tuple
+        let (offset, element, code, source) =
+            located::<Vec<Element>>(&[INTZERO, 0x15, 0x07, 0x42, INTZERO]);
+        assert_eq!((offset, element, code), (3, 2, Some(0x42)));
+        assert!(matches!(source, PackError::BadCode { found: 0x42, .. }));
+        let (offset, element, code, _) = located::<i64>(&pack(&"one"));
+        assert_eq!((offset, element, code), (0, 0, Some(STRING)));
+
+        assert!(matches!(
+            unpack_located::<(i64,)>(&pack(&(1, 2))),
+            Err(PackError::TrailingBytes)
+        ));
+        assert_eq!(
+            unpack_located::<(i64, (i64, (i64, String)), i64)>(&packed).unwrap(),
+            (1, (2, (3, "four".to_owned())), 5)
+        );
+        // unpack keeps reporting the error itself
+        assert!(matches!(
+            unpack::<(i64, i64, i64)>(&pack(&(1, 2, "three"))),
+            Err(PackError::BadCode { found: STRING, .. })
+        ));
+    }
+
+    #[test]
+    fn test_unpack_prefix() {
+        let packed = pack(&("user", 42, "name", true));
+        assert_eq!(
+            unpack_prefix::<(String, i64)>(&packed).unwrap(),
+            (("user".to_owned(), 42), 8)
+        );
+        assert_eq!(
+            unpack::<(String, bool)>(&packed[8..]).unwrap(),
+            ("name".to_owned(), true)
+        );
+        assert_eq!(
+            unpack_prefix::<(String, i64, String, bool)>(&packed).unwrap(),
+            (
+                ("user".to_owned(), 42, "name".to_owned(), true),
+                packed.len()
+            )
+        );
+        assert_eq!(
+            unpack_prefix::<Vec<Element>>(&packed).unwrap().1,
+            packed.len()
+        );
+        assert!(matches!(
+            unpack_prefix::<(String, String)>(&packed),
+            Err(PackError::BadCode { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unpack_partial() {
+        let packed = pack(&("user", 42, 1.5f64));
+        let ((name, id), rest) = <(Cow<str>, i64)>::unpack_partial(&packed).unwrap();
+        assert_eq!((name.as_ref(), id), ("user", 42));
+        assert_eq!(rest, &pack(&1.5f64)[..]);
+        assert_eq!(f64::unpack_root(rest).unwrap(), 1.5);
+
+        let (all, rest) = <(String, i64, f64)>::unpack_partial(&packed).unwrap();
+        assert_eq!(all, ("user".to_owned(), 42, 1.5));
+        assert!(rest.is_empty());
+        // the strict unpack still rejects the rest
+        assert!(matches!(
+            <(String, i64)>::unpack_root(&packed),
+            Err(PackError::TrailingBytes)
+        ));
+    }
+
+    #[test]
+    fn test_size_hint() {
+        fn hint_and_len<T: TuplePack>(v: T) -> (usize, usize) {
+            (v.size_hint_root(), pack(&v).len())
+        }
+        fn assert_exact<T: TuplePack>(v: T) {
+            let (hint, len) = hint_and_len(v);
+            assert_eq!(hint, len);
+        }
+
+        for i in [0i64, 1, -1, 255, -255, 256, i64::MAX, i64::MIN] {
+            assert_exact(i);
+        }
+        for u in [0u64, 1, 255, 256, u64::MAX] {
+            assert_exact(u);
+        }
+        assert_exact(i128::MIN);
+        assert_exact(u128::MAX);
+        assert_exact(1.5f32);
+        assert_exact(-1.5f64);
+        assert_exact(true);
+        assert_exact(());
+        assert_exact(Option::<i64>::None);
+        assert_exact(Versionstamp::incomplete(7));
+        assert_exact(Versionstamp::complete([1; 10], 7));
+        #[cfg(feature = "uuid")]
+        assert_exact(uuid::Uuid::from_u128(42));
+        assert_exact((1i64, (None::<i64>, ()), vec![(), ()], ("a", -2i64)));
+        assert_exact(Element::Tuple(vec![
+            Element::Nil,
+            Element::Int(-3),
+            Element::String("s".into()),
+        ]));
+
+        // escaped byte strings
+        for bytes in [&b""[..], b"\x00", b"a\x00b\x00\x00", b"\xff"] {
+            let (hint, len) = hint_and_len(bytes);
+            assert!(hint >= len);
+            let (hint, len) = hint_and_len(String::from_utf8_lossy(bytes));
+            assert!(hint >= len);
+        }
+
+        // the packing buffer is pre-sized
+        let key = ("user", 42u64, b"\x00\x01".as_ref());
+        let mut out = Vec::new();
+        key.pack_into_vec(&mut out);
+        assert!(out.capacity() >= key.size_hint_root());
+    }
+
+    #[test]
+    fn test_unpack_elements() {
+        let packed = pack(&(
+            "user",
+            42,
+            (1.5f64, None::<i64>),
+            Versionstamp::incomplete(1),
+        ));
+
+        let all: Vec<Element> = unpack_elements(&packed).collect::<PackResult<_>>().unwrap();
+        assert_eq!(all, unpack::<Vec<Element>>(&packed).unwrap());
+        assert!(unpack_elements(&[]).next().is_none());
+
+        // stopping early
+        let mut elements = unpack_elements(&packed);
+        assert_eq!(
+            elements.next().unwrap().unwrap(),
+            Element::String("user".into())
+        );
+        assert_eq!(
+            elements.remaining(),
+            &pack(&(42, (1.5f64, None::<i64>), Versionstamp::incomplete(1)))[..]
+        );
+        assert_eq!(elements.next().unwrap().unwrap(), Element::Int(42));
+
+        // a malformed tail yields one error
+        let mut malformed = pack(&("user", 42));
+        malformed.extend_from_slice(&[NESTED, INTZERO]);
+        let mut elements = unpack_elements(&malformed);
+        assert!(elements.next().unwrap().is_ok());
+        assert!(elements.next().unwrap().is_ok());
+        assert!(matches!(
+            elements.next(),
+            Some(Err(PackError::MissingBytes))
+        ));
+        assert!(elements.next().is_none());
+        assert!(elements.next().is_none());
+    }
+
+    #[test]
+    fn test_unterminated_nested() {
+        const UNTERMINATED: &[u8] = &[NESTED, INTZERO, NESTED];
+
+        assert!(matches!(
+            unpack::<Element>(UNTERMINATED),
+            Err(PackError::MissingBytes)
+        ));
+        assert!(matches!(
+            unpack::<Vec<Element>>(UNTERMINATED),
+            Err(PackError::MissingBytes)
+        ));
+        assert!(matches!(
+            unpack::<((i64, Vec<Element>),)>(UNTERMINATED),
+            Err(PackError::MissingBytes)
+        ));
+        // the outer nested tuple is never closed either
+        assert!(matches!(
+            unpack::<Element>(&[NESTED, INTZERO, NESTED, NIL]),
+            Err(PackError::MissingBytes)
+        ));
+        // an escaped nil does not close a nested tuple
+        assert!(matches!(
+            unpack::<Element>(&[NESTED, NIL, ESCAPE]),
+            Err(PackError::MissingBytes)
+        ));
+    }
+
+    #[test]
+    fn test_nested_depth() {
+        let mut deepest = vec![NESTED; MAX_NESTED_DEPTH];
+        deepest.extend_from_slice(&[NIL; MAX_NESTED_DEPTH]);
+        let mut element = unpack::<Element>(&deepest).unwrap();
+        let mut depth = 0;
+        while let Element::Tuple(mut items) = element {
+            element = match items.pop() {
+                Some(item) => item,
+                None => break,
+            };
+            depth += 1;
+        }
+        assert_eq!(depth, MAX_NESTED_DEPTH);
+
+        let mut deeper = vec![NESTED; MAX_NESTED_DEPTH + 1];
+        deeper.extend_from_slice(&[NIL; MAX_NESTED_DEPTH + 1]);
+        assert!(matches!(
+            unpack::<Element>(&deeper),
+            Err(PackError::NestedTooDeep)
+        ));
+        // pathological input fails without overflowing the stack
+        assert!(matches!(
+            unpack::<Element>(&[NESTED; 100_000]),
+            Err(PackError::NestedTooDeep)
+        ));
+        assert!(matches!(
+            unpack::<Vec<Element>>(&[NESTED; 100_000]),
+            Err(PackError::NestedTooDeep)
+        ));
+    }
+
+    #[test]
+    fn test_unpack_ignore_trailing_zeros() {
+        let mut padded = pack(&(42, "foo"));
+        padded.extend_from_slice(&[NIL; 5]);
+
+        assert!(matches!(
+            unpack::<(i64, String)>(&padded),
+            Err(PackError::TrailingBytes)
+        ));
+        assert_eq!(
+            unpack_ignore_trailing_zeros::<(i64, String)>(&padded).unwrap(),
+            (42, "foo".to_string())
+        );
+        assert_eq!(
+            unpack_ignore_trailing_zeros::<(i64, String)>(&pack(&(42, "foo"))).unwrap(),
+            (42, "foo".to_string())
+        );
+
+        padded.push(INTZERO);
+        assert!(matches!(
+            unpack_ignore_trailing_zeros::<(i64, String)>(&padded),
+            Err(PackError::TrailingBytes)
+        ));
+    }
+
+    #[test]
+    fn test_try_pack_with_versionstamp() {
+        assert_eq!(
+            try_pack_with_versionstamp(&("foo", Versionstamp::incomplete(0))).unwrap(),
+            pack_with_versionstamp(&("foo", Versionstamp::incomplete(0)))
+        );
+        // the offset follows the versionstamp into nested tuples
+        assert_eq!(
+            try_pack_with_versionstamp(&(1, ("foo", Versionstamp::incomplete(7)))).unwrap(),
+            b"\x15\x01\x05\x02foo\x00\x33\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x00\x07\x00\x09\x00\x00\x00"
+        );
+        assert!(matches!(
+            try_pack_with_versionstamp(&("foo", Versionstamp::complete([1; 10], 0))),
+            Err(PackError::NoIncompleteVersionstamp)
+        ));
+        assert!(matches!(
+            try_pack_with_versionstamp(&(Versionstamp::incomplete(0), Versionstamp::incomplete(1))),
+            Err(PackError::MultipleIncompleteVersionstamps)
+        ));
+    }
+
+    #[test]
+    fn test_versionstamp_user_version_order() {
+        let versionstamps = [
+            Versionstamp::complete([1; 10], 0),
+            Versionstamp::complete([1; 10], 1),
+            Versionstamp::complete([1; 10], 0xff00),
+            Versionstamp::complete([2; 10], 0),
+        ];
+        let packed: Vec<Vec<u8>> = versionstamps.iter().map(|v| pack(&("idx", v))).collect();
+        assert!(packed.windows(2).all(|pair| pair[0] < pair[1]));
+        for (versionstamp, packed) in versionstamps.iter().zip(&packed) {
+            let (_, unpacked): (String, Versionstamp) = unpack(packed).unwrap();
+            assert_eq!(&unpacked, versionstamp);
+            assert_eq!(unpacked.user_version(), versionstamp.user_version());
+        }
+    }
+
     #[test]
     fn test_verstionstamp() {
         assert_eq!(
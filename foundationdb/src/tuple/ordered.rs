@@ -0,0 +1,225 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use super::pack::{f32_to_u32_be_bytes, f64_to_u64_be_bytes};
+use super::{io, PackResult, TupleDepth, TuplePack, TupleUnpack, VersionstampOffset};
+use core::cmp;
+use core::hash::{Hash, Hasher};
+
+macro_rules! impl_ordered {
+    ($name: ident, $fx: ident, $fx_to_ux_be_bytes: ident) => {
+        #[doc = concat!("An `", stringify!($fx), "` ordered like its tuple encoding")]
+        ///
+        /// Unlike the float itself, this implements `Eq`, `Ord` and `Hash`, so it can be used in
+        /// keys of maps and sets. Values are compared by their packed bytes: in increasing order
+        /// negative NaNs, negative infinity, negative numbers, `-0.0`, `0.0`, positive numbers,
+        /// positive infinity and positive NaNs. So `-0.0` and `0.0` differ, and NaNs are equal
+        /// to themselves but differ from NaNs with another payload.
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct $name(pub $fx);
+
+        impl $name {
+            /// Returns the inner float.
+            pub fn into_inner(self) -> $fx {
+                self.0
+            }
+        }
+
+        impl From<$fx> for $name {
+            fn from(v: $fx) -> Self {
+                $name(v)
+            }
+        }
+
+        impl From<$name> for $fx {
+            fn from(v: $name) -> Self {
+                v.0
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.0.to_bits() == other.0.to_bits()
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> cmp::Ordering {
+                $fx_to_ux_be_bytes(self.0).cmp(&$fx_to_ux_be_bytes(other.0))
+            }
+        }
+
+        impl Hash for $name {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.to_bits().hash(state)
+            }
+        }
+
+        impl TuplePack for $name {
+            fn pack<W: io::Write>(
+                &self,
+                w: &mut W,
+                tuple_depth: TupleDepth,
+            ) -> io::Result<VersionstampOffset> {
+                self.0.pack(w, tuple_depth)
+            }
+
+            fn size_hint(&self, tuple_depth: TupleDepth) -> usize {
+                self.0.size_hint(tuple_depth)
+            }
+        }
+
+        impl<'de> TupleUnpack<'de> for $name {
+            fn unpack(input: &'de [u8], tuple_depth: TupleDepth) -> PackResult<(&'de [u8], Self)> {
+                let (input, v) = $fx::unpack(input, tuple_depth)?;
+                Ok((input, $name(v)))
+            }
+        }
+    };
+}
+
+impl_ordered!(OrderedF32, f32, f32_to_u32_be_bytes);
+impl_ordered!(OrderedF64, f64, f64_to_u64_be_bytes);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuple::{pack, unpack};
+    use std::collections::{BTreeSet, HashSet};
+
+    /// xorshift64, random bit patterns cover NaNs, infinities, zeros and subnormals
+    struct Bits(u64);
+
+    impl Iterator for Bits {
+        type Item = u64;
+
+        fn next(&mut self) -> Option<u64> {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            Some(self.0)
+        }
+    }
+
+    fn specials_f64() -> Vec<f64> {
+        vec![
+            0.0,
+            -0.0,
+            1.0,
+            -1.0,
+            f64::MIN_POSITIVE,
+            -f64::MIN_POSITIVE,
+            f64::from_bits(1),
+            f64::MAX,
+            f64::MIN,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NAN,
+            -f64::NAN,
+            f64::from_bits(0x7ff0_0000_0000_0001),
+            f64::from_bits(0xfff0_0000_0000_0001),
+        ]
+    }
+
+    #[test]
+    fn ordering_matches_encoding() {
+        let mut values: Vec<f64> = specials_f64();
+        // mostly random exponents, then random small magnitudes with a biased exponent
+        values.extend(Bits(0x2545_f491_4f6c_dd1d).take(500).map(f64::from_bits));
+        values.extend(
+            Bits(0x9e37_79b9_7f4a_7c15)
+                .take(500)
+                .map(|bits| f64::from_bits(bits & 0x800f_ffff_ffff_ffff | 0x3ff0_0000_0000_0000)),
+        );
+
+        for a in &values {
+            for b in &values {
+                let (a, b) = (OrderedF64(*a), OrderedF64(*b));
+                assert_eq!(a.cmp(&b), pack(&a).cmp(&pack(&b)), "{:?} {:?}", a, b);
+                assert_eq!(a == b, pack(&a) == pack(&b));
+            }
+        }
+
+        let floats: Vec<f32> = values
+            .iter()
+            .map(|v| *v as f32)
+            .chain(
+                Bits(0xdead_beef)
+                    .take(500)
+                    .map(|bits| f32::from_bits(bits as u32)),
+            )
+            .chain([-0.0, f32::NAN, -f32::NAN, f32::from_bits(0x7f80_0001)])
+            .collect();
+        for a in &floats {
+            for b in &floats {
+                let (a, b) = (OrderedF32(*a), OrderedF32(*b));
+                assert_eq!(a.cmp(&b), pack(&a).cmp(&pack(&b)), "{:?} {:?}", a, b);
+                assert_eq!(a == b, pack(&a) == pack(&b));
+            }
+        }
+    }
+
+    #[test]
+    fn special_values() {
+        let sorted: Vec<f64> = specials_f64()
+            .into_iter()
+            .map(OrderedF64)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(f64::from)
+            .collect();
+        let bits: Vec<u64> = sorted.iter().map(|v| v.to_bits()).collect();
+        let expected: Vec<u64> = [
+            -f64::NAN,
+            f64::from_bits(0xfff0_0000_0000_0001),
+            f64::NEG_INFINITY,
+            f64::MIN,
+            -1.0,
+            -f64::MIN_POSITIVE,
+            -0.0,
+            0.0,
+            f64::from_bits(1),
+            f64::MIN_POSITIVE,
+            1.0,
+            f64::MAX,
+            f64::INFINITY,
+            f64::from_bits(0x7ff0_0000_0000_0001),
+            f64::NAN,
+        ]
+        .iter()
+        .map(|v| v.to_bits())
+        .collect();
+        assert_eq!(bits, expected);
+
+        let set: HashSet<OrderedF64> = [0.0, -0.0, f64::NAN, f64::NAN]
+            .into_iter()
+            .map(OrderedF64)
+            .collect();
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn round_trip() {
+        for v in specials_f64() {
+            let packed = pack(&OrderedF64(v));
+            assert_eq!(packed, pack(&v));
+            assert_eq!(unpack::<OrderedF64>(&packed).unwrap(), OrderedF64(v));
+
+            let packed = pack(&OrderedF32(v as f32));
+            assert_eq!(packed, pack(&(v as f32)));
+            assert_eq!(unpack::<OrderedF32>(&packed).unwrap(), OrderedF32(v as f32));
+        }
+    }
+}
@@ -1,8 +1,7 @@
 use super::*;
+use core::convert::TryFrom;
+use core::mem;
 use memchr::memchr_iter;
-use std::convert::TryFrom;
-use std::io;
-use std::mem;
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub enum VersionstampOffset {
@@ -10,14 +9,14 @@ pub enum VersionstampOffset {
     OneIncomplete { offset: u32 },
     MultipleIncomplete,
 }
-impl std::ops::AddAssign<u32> for VersionstampOffset {
+impl core::ops::AddAssign<u32> for VersionstampOffset {
     fn add_assign(&mut self, r: u32) {
         if let VersionstampOffset::None { size } = self {
             *size += r;
         }
     }
 }
-impl std::ops::AddAssign for VersionstampOffset {
+impl core::ops::AddAssign for VersionstampOffset {
     fn add_assign(&mut self, rhs: Self) {
         match (&mut *self, rhs) {
             (VersionstampOffset::None { size }, VersionstampOffset::None { size: r }) => {
@@ -55,6 +54,19 @@ pub trait TuplePack {
         self.pack(w, TupleDepth::new())
     }
 
+    /// An upper bound of the number of bytes packed at `tuple_depth`, to size buffers
+    ///
+    /// It is exact for the types of this crate, but for dates which are only bounded and big
+    /// integers which are not hinted. The default returns `0`, meaning no hint.
+    fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+        0
+    }
+
+    /// An upper bound of the number of bytes packed by [`pack_root`](Self::pack_root)
+    fn size_hint_root(&self) -> usize {
+        self.size_hint(TupleDepth::new())
+    }
+
     /// Pack value and returns the packed buffer
     ///
     /// # Panics
@@ -86,6 +98,7 @@ pub trait TuplePack {
     ///
     /// Panics if the encoded data size doesn't fit in `u32`.
     fn pack_into_vec(&self, output: &mut Vec<u8>) {
+        output.reserve(self.size_hint_root());
         self.pack_root(output).expect(PACK_ERR_MSG);
     }
 
@@ -95,6 +108,7 @@ pub trait TuplePack {
     ///
     /// Panics if the encoded data size doesn't fit in `u32`.
     fn pack_into_vec_with_versionstamp(&self, output: &mut Vec<u8>) -> VersionstampOffset {
+        output.reserve(self.size_hint_root());
         let mut offset = VersionstampOffset::None {
             size: u32::try_from(output.len()).expect(PACK_ERR_MSG),
         };
@@ -117,6 +131,15 @@ pub trait TupleUnpack<'de>: Sized {
         }
         Ok(this)
     }
+
+    /// Unpacks the value at the start of `input`, and gives the bytes following it
+    ///
+    /// Unlike [`TupleUnpack::unpack_root`], the input may go on after the value, see
+    /// [`unpack_prefix`](super::unpack_prefix).
+    fn unpack_partial(input: &'de [u8]) -> PackResult<(Self, &'de [u8])> {
+        let (input, this) = Self::unpack(input, TupleDepth::new())?;
+        Ok((this, input))
+    }
 }
 
 impl<'a, T> TuplePack for &'a T
@@ -130,6 +153,10 @@ where
     ) -> io::Result<VersionstampOffset> {
         (*self).pack(w, tuple_depth)
     }
+
+    fn size_hint(&self, tuple_depth: TupleDepth) -> usize {
+        (*self).size_hint(tuple_depth)
+    }
 }
 
 #[inline]
@@ -150,7 +177,37 @@ fn parse_byte(input: &[u8]) -> PackResult<(&[u8], u8)> {
     }
 }
 
-fn parse_code(input: &[u8], expected: u8) -> PackResult<&[u8]> {
+/// Wraps the error of the element `element` of a tuple, starting at `input`, when unpacking with
+/// [`unpack_located`](super::unpack_located)
+///
+/// The offset is the length of the input left, turned into an offset by `unpack_located`. The
+/// innermost element keeps its offset, and the outermost tuple gives the index.
+fn locate(err: PackError, input: &[u8], element: usize, tuple_depth: TupleDepth) -> PackError {
+    if !tuple_depth.located {
+        return err;
+    }
+    match err {
+        PackError::Located {
+            offset,
+            code,
+            source,
+            ..
+        } => PackError::Located {
+            offset,
+            element,
+            code,
+            source,
+        },
+        err => PackError::Located {
+            offset: input.len(),
+            element,
+            code: input.first().copied(),
+            source: Box::new(err),
+        },
+    }
+}
+
+pub(super) fn parse_code(input: &[u8], expected: u8) -> PackResult<&[u8]> {
     let (input, found) = parse_byte(input)?;
     if found == expected {
         Ok(input)
@@ -209,7 +266,7 @@ fn parse_string(input: &[u8]) -> PackResult<(&[u8], Cow<str>)> {
         input,
         match slice {
             Cow::Borrowed(slice) => {
-                Cow::Borrowed(std::str::from_utf8(slice).map_err(|_| PackError::BadStringFormat)?)
+                Cow::Borrowed(core::str::from_utf8(slice).map_err(|_| PackError::BadStringFormat)?)
             }
             Cow::Owned(vec) => {
                 Cow::Owned(String::from_utf8(vec).map_err(|_| PackError::BadStringFormat)?)
@@ -231,6 +288,10 @@ impl TuplePack for () {
             Ok(VersionstampOffset::None { size: 0 })
         }
     }
+
+    fn size_hint(&self, tuple_depth: TupleDepth) -> usize {
+        nested_size(tuple_depth)
+    }
 }
 
 impl<'de> TupleUnpack<'de> for () {
@@ -267,6 +328,10 @@ macro_rules! tuple_impls {
                     }
                     Ok(offset)
                 }
+
+                fn size_hint(&self, tuple_depth: TupleDepth) -> usize {
+                    nested_size(tuple_depth) $(+ self.$n.size_hint(tuple_depth.increment()))*
+                }
             }
 
             impl<'de, $($name),+> TupleUnpack<'de> for ($($name,)+)
@@ -277,7 +342,8 @@ macro_rules! tuple_impls {
                     let input = if tuple_depth.depth() > 0 { parse_code(input, NESTED)? } else { input };
 
                     $(
-                        let (input, $v) = $name::unpack(input, tuple_depth.increment())?;
+                        let (input, $v) = $name::unpack(input, tuple_depth.increment())
+                            .map_err(|err| locate(err, input, $n, tuple_depth))?;
                     )*
 
                     let input = if tuple_depth.depth() > 0 { parse_code(input, NIL)? } else { input };
@@ -307,6 +373,30 @@ tuple_impls! {
 
 const MAX_SZ: usize = 8;
 
+/// The size of the markers of a tuple packed at `tuple_depth`
+fn nested_size(tuple_depth: TupleDepth) -> usize {
+    if tuple_depth.depth() > 0 {
+        2
+    } else {
+        0
+    }
+}
+
+/// The packed size of an integer of `sz` bytes, with `leading_zeros` in its absolute value
+fn int_size(sz: usize, leading_zeros: u32) -> usize {
+    let n = sz - leading_zeros as usize / 8;
+    if n <= MAX_SZ {
+        1 + n
+    } else {
+        2 + n
+    }
+}
+
+/// The packed size of a byte string or a string, escaped zeros included
+fn bytes_size(v: &[u8]) -> usize {
+    2 + v.len() + memchr_iter(NIL, v).count()
+}
+
 macro_rules! sign_bit {
     ($type:ident) => {
         (1 << (mem::size_of::<$type>() * 8 - 1))
@@ -316,8 +406,8 @@ macro_rules! sign_bit {
 macro_rules! unpack_ux {
     ($ux: ident, $input: expr, $n: expr) => {{
         let (input, bytes) = parse_bytes($input, $n)?;
-        let mut arr = [0u8; ::std::mem::size_of::<$ux>()];
-        (&mut arr[(::std::mem::size_of::<$ux>() - $n)..]).copy_from_slice(bytes);
+        let mut arr = [0u8; ::core::mem::size_of::<$ux>()];
+        (&mut arr[(::core::mem::size_of::<$ux>() - $n)..]).copy_from_slice(bytes);
         (input, $ux::from_be_bytes(arr))
     }};
 }
@@ -325,11 +415,11 @@ macro_rules! unpack_ux {
 macro_rules! unpack_px {
     ($ix: ident, $ux: ident, $input: expr, $n: expr) => {{
         let (input, bytes) = parse_bytes($input, $n)?;
-        let mut arr = [0u8; ::std::mem::size_of::<$ux>()];
-        (&mut arr[(::std::mem::size_of::<$ux>() - $n)..]).copy_from_slice(bytes);
+        let mut arr = [0u8; ::core::mem::size_of::<$ux>()];
+        (&mut arr[(::core::mem::size_of::<$ux>() - $n)..]).copy_from_slice(bytes);
         let x = $ix::from_be_bytes(arr);
         if x < 0 {
-            Err(PackError::UnsupportedIntLength)
+            Err(PackError::IntegerOverflow)
         } else {
             Ok((input, x))
         }
@@ -338,11 +428,11 @@ macro_rules! unpack_px {
 macro_rules! unpack_nx {
     ($ix: ident, $ux: ident, $input: expr, $n: expr) => {{
         let (input, bytes) = parse_bytes($input, $n)?;
-        let mut arr = [0xffu8; ::std::mem::size_of::<$ix>()];
-        (&mut arr[(::std::mem::size_of::<$ix>() - $n)..]).copy_from_slice(bytes);
+        let mut arr = [0xffu8; ::core::mem::size_of::<$ix>()];
+        (&mut arr[(::core::mem::size_of::<$ix>() - $n)..]).copy_from_slice(bytes);
         let x = $ix::from_be_bytes(arr).wrapping_add(1);
         if x > 0 {
-            Err(PackError::UnsupportedIntLength)
+            Err(PackError::IntegerOverflow)
         } else {
             Ok((input, x))
         }
@@ -374,6 +464,10 @@ macro_rules! impl_ux {
 
                 Ok(offset)
             }
+
+            fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+                int_size(mem::size_of::<$ux>(), self.leading_zeros())
+            }
         }
 
         impl<'de> TupleUnpack<'de> for $ux {
@@ -383,11 +477,14 @@ macro_rules! impl_ux {
                 if INTZERO <= found && found <= INTZERO + $max_sz as u8 {
                     let n = (found - INTZERO) as usize;
                     Ok(unpack_ux!($ux, input, n))
+                } else if (NEGINTSTART..POSINTEND).contains(&found) {
+                    // negative, or too large
+                    Err(PackError::IntegerOverflow)
                 } else if found == POSINTEND {
                     let (input, raw_length) = parse_byte(input)?;
                     let n: usize = usize::from(raw_length);
                     if n > SZ {
-                        return Err(PackError::UnsupportedIntLength);
+                        return Err(PackError::IntegerOverflow);
                     }
                     Ok(unpack_ux!($ux, input, n))
                 } else {
@@ -438,6 +535,13 @@ macro_rules! impl_ix {
 
                 Ok(offset)
             }
+
+            fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+                int_size(
+                    mem::size_of::<$ix>(),
+                    (self.wrapping_abs() as $ux).leading_zeros(),
+                )
+            }
         }
 
         impl<'de> TupleUnpack<'de> for $ix {
@@ -450,18 +554,21 @@ macro_rules! impl_ix {
                 } else if INTZERO - $max_sz as u8 <= found && found < INTZERO {
                     let n = (INTZERO - found) as usize;
                     unpack_nx!($ix, $ux, input, n)
+                } else if NEGINTSTART < found && found < POSINTEND {
+                    // too large
+                    Err(PackError::IntegerOverflow)
                 } else if found == NEGINTSTART {
                     let (input, raw_length) = parse_byte(input)?;
                     let n = usize::from(raw_length ^ 0xff);
                     if n > SZ {
-                        return Err(PackError::UnsupportedIntLength);
+                        return Err(PackError::IntegerOverflow);
                     }
                     unpack_nx!($ix, $ux, input, n)
                 } else if found == POSINTEND {
                     let (input, raw_length) = parse_byte(input)?;
                     let n: usize = usize::from(raw_length);
                     if n > SZ {
-                        return Err(PackError::UnsupportedIntLength);
+                        return Err(PackError::IntegerOverflow);
                     }
                     unpack_px!($ix, $ux, input, n)
                 } else {
@@ -480,7 +587,7 @@ macro_rules! impl_fx {
         #[inline]
         pub(super) fn $fx_to_ux_be_bytes(f: $fx) -> [u8; $ux_width] {
             let u = if f.is_sign_negative() {
-                f.to_bits() ^ ::std::$ux::MAX
+                f.to_bits() ^ ::core::$ux::MAX
             } else {
                 f.to_bits() ^ sign_bit!($ux)
             };
@@ -496,9 +603,13 @@ macro_rules! impl_fx {
                 w.write_all(&[$code])?;
                 w.write_all(&bytes)?;
                 Ok(VersionstampOffset::None {
-                    size: std::mem::size_of::<$fx>() as u32 + 1,
+                    size: core::mem::size_of::<$fx>() as u32 + 1,
                 })
             }
+
+            fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+                1 + core::mem::size_of::<$fx>()
+            }
         }
 
         fn $parse_ux(input: &[u8]) -> PackResult<(&[u8], $ux)> {
@@ -514,7 +625,7 @@ macro_rules! impl_fx {
                 Ok((
                     input,
                     $fx::from_bits(if (u & sign_bit!($ux)) == 0 {
-                        u ^ ::std::$ux::MAX
+                        u ^ ::core::$ux::MAX
                     } else {
                         u ^ sign_bit!($ux)
                     }),
@@ -544,8 +655,8 @@ impl_fx!(f64, f64_to_u64_be_bytes, 8, parse_u64, u64, DOUBLE);
 #[cfg(feature = "num-bigint")]
 mod bigint {
     use super::*;
+    use core::convert::TryFrom;
     use num_bigint::{BigInt, BigUint, Sign};
-    use std::convert::TryFrom;
 
     fn invert(bytes: &mut [u8]) {
         // The ones' complement of a binary number is defined as the value
@@ -565,8 +676,8 @@ mod bigint {
 
     fn bigint_n(n: usize) -> io::Result<u8> {
         u8::try_from(n).map_err(|_| {
-            std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
                 "BigUint requires more than 255 bytes to be represented",
             )
         })
@@ -699,6 +810,10 @@ impl TuplePack for bool {
         w.write_all(&[if *self { TRUE } else { FALSE }])?;
         Ok(VersionstampOffset::None { size: 1 })
     }
+
+    fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+        1
+    }
 }
 
 impl<'de> TupleUnpack<'de> for bool {
@@ -714,6 +829,61 @@ impl<'de> TupleUnpack<'de> for bool {
     }
 }
 
+/// A `bool` also unpacked from the integers `0` and `1`
+///
+/// Older layers stored booleans as integers, before the tuple encoding had booleans of its own.
+/// This unpacks both representations, and packs as a `bool`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LenientBool(pub bool);
+
+impl From<bool> for LenientBool {
+    fn from(v: bool) -> Self {
+        LenientBool(v)
+    }
+}
+
+impl From<LenientBool> for bool {
+    fn from(v: LenientBool) -> Self {
+        v.0
+    }
+}
+
+impl TuplePack for LenientBool {
+    fn pack<W: io::Write>(
+        &self,
+        w: &mut W,
+        tuple_depth: TupleDepth,
+    ) -> io::Result<VersionstampOffset> {
+        self.0.pack(w, tuple_depth)
+    }
+
+    fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+        1
+    }
+}
+
+impl<'de> TupleUnpack<'de> for LenientBool {
+    fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+        match input.first() {
+            Some(&FALSE) | Some(&TRUE) => {
+                let (input, v) = bool::unpack(input, tuple_depth)?;
+                Ok((input, LenientBool(v)))
+            }
+            _ => match u16::unpack(input, tuple_depth) {
+                Ok((input, 0)) => Ok((input, LenientBool(false))),
+                Ok((input, 1)) => Ok((input, LenientBool(true))),
+                Ok((_, v)) => Err(PackError::Message(
+                    format!("{} is not a valid bool value", v).into_boxed_str(),
+                )),
+                Err(PackError::IntegerOverflow) => Err(PackError::Message(
+                    "integer is not a valid bool value".into(),
+                )),
+                Err(err) => Err(err),
+            },
+        }
+    }
+}
+
 impl<'a, T> TuplePack for &'a [T]
 where
     T: TuplePack,
@@ -740,6 +910,14 @@ where
         }
         Ok(offset)
     }
+
+    fn size_hint(&self, tuple_depth: TupleDepth) -> usize {
+        let elements: usize = self
+            .iter()
+            .map(|v| v.size_hint(tuple_depth.increment()))
+            .sum();
+        nested_size(tuple_depth) + elements
+    }
 }
 
 impl<T> TuplePack for Vec<T>
@@ -753,9 +931,13 @@ where
     ) -> io::Result<VersionstampOffset> {
         self.as_slice().pack(w, tuple_depth)
     }
+
+    fn size_hint(&self, tuple_depth: TupleDepth) -> usize {
+        self.as_slice().size_hint(tuple_depth)
+    }
 }
 
-fn is_end_of_tuple(input: &[u8], nested: bool) -> bool {
+pub(super) fn is_end_of_tuple(input: &[u8], nested: bool) -> bool {
     match input.first() {
         None => true,
         _ if !nested => false,
@@ -772,12 +954,16 @@ where
         let nested = tuple_depth.depth() > 0;
         if nested {
             input = parse_code(input, NESTED)?;
+            if tuple_depth.depth() > MAX_NESTED_DEPTH {
+                return Err(PackError::NestedTooDeep);
+            }
         }
 
         let mut vec = Vec::new();
 
         while !is_end_of_tuple(input, nested) {
-            let (rem, v) = T::unpack(input, tuple_depth.increment())?;
+            let (rem, v) = T::unpack(input, tuple_depth.increment())
+                .map_err(|err| locate(err, input, vec.len(), tuple_depth))?;
             input = rem;
             vec.push(v);
         }
@@ -799,6 +985,10 @@ impl<'a> TuplePack for Bytes<'a> {
         w.write_all(&[BYTES])?;
         write_bytes(w, self.as_ref())
     }
+
+    fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+        bytes_size(self.as_ref())
+    }
 }
 
 impl<'de> TupleUnpack<'de> for Bytes<'de> {
@@ -817,6 +1007,10 @@ impl<'a> TuplePack for &'a [u8] {
     ) -> io::Result<VersionstampOffset> {
         Bytes::from(*self).pack(w, tuple_depth)
     }
+
+    fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+        bytes_size(self)
+    }
 }
 
 impl TuplePack for Vec<u8> {
@@ -827,6 +1021,10 @@ impl TuplePack for Vec<u8> {
     ) -> io::Result<VersionstampOffset> {
         Bytes::from(self.as_slice()).pack(w, tuple_depth)
     }
+
+    fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+        bytes_size(self)
+    }
 }
 
 impl<'de> TupleUnpack<'de> for Vec<u8> {
@@ -836,6 +1034,57 @@ impl<'de> TupleUnpack<'de> for Vec<u8> {
     }
 }
 
+impl<'a> TuplePack for Cow<'a, [u8]> {
+    fn pack<W: io::Write>(
+        &self,
+        w: &mut W,
+        tuple_depth: TupleDepth,
+    ) -> io::Result<VersionstampOffset> {
+        Bytes::from(self.as_ref()).pack(w, tuple_depth)
+    }
+
+    fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+        bytes_size(self)
+    }
+}
+
+/// Borrows the bytes from the input, unless they hold escaped zeros.
+impl<'de> TupleUnpack<'de> for Cow<'de, [u8]> {
+    fn unpack(input: &'de [u8], tuple_depth: TupleDepth) -> PackResult<(&'de [u8], Self)> {
+        let (input, bytes) = Bytes::unpack(input, tuple_depth)?;
+        Ok((input, bytes.0))
+    }
+}
+
+impl<const N: usize> TuplePack for [u8; N] {
+    fn pack<W: io::Write>(
+        &self,
+        w: &mut W,
+        tuple_depth: TupleDepth,
+    ) -> io::Result<VersionstampOffset> {
+        Bytes::from(&self[..]).pack(w, tuple_depth)
+    }
+
+    fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+        bytes_size(self)
+    }
+}
+
+/// Decodes a byte string of exactly `N` bytes, once unescaped.
+impl<'de, const N: usize> TupleUnpack<'de> for [u8; N] {
+    fn unpack(input: &'de [u8], tuple_depth: TupleDepth) -> PackResult<(&'de [u8], Self)> {
+        let (input, bytes) = Bytes::unpack(input, tuple_depth)?;
+        let array = bytes
+            .as_ref()
+            .try_into()
+            .map_err(|_| PackError::BadLength {
+                expected: N,
+                found: bytes.len(),
+            })?;
+        Ok((input, array))
+    }
+}
+
 impl<'a> TuplePack for &'a str {
     fn pack<W: io::Write>(
         &self,
@@ -845,6 +1094,10 @@ impl<'a> TuplePack for &'a str {
         w.write_all(&[STRING])?;
         write_bytes(w, self.as_bytes())
     }
+
+    fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+        bytes_size(self.as_bytes())
+    }
 }
 
 impl TuplePack for String {
@@ -855,6 +1108,10 @@ impl TuplePack for String {
     ) -> io::Result<VersionstampOffset> {
         self.as_str().pack(w, tuple_depth)
     }
+
+    fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+        bytes_size(self.as_bytes())
+    }
 }
 
 impl<'a> TuplePack for Cow<'a, str> {
@@ -865,8 +1122,13 @@ impl<'a> TuplePack for Cow<'a, str> {
     ) -> io::Result<VersionstampOffset> {
         self.as_ref().pack(w, tuple_depth)
     }
+
+    fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+        bytes_size(self.as_bytes())
+    }
 }
 
+/// Borrows the string from the input, unless it holds escaped zeros.
 impl<'de> TupleUnpack<'de> for Cow<'de, str> {
     fn unpack(input: &'de [u8], _tuple_depth: TupleDepth) -> PackResult<(&'de [u8], Self)> {
         let input = parse_code(input, STRING)?;
@@ -883,6 +1145,11 @@ impl<'de> TupleUnpack<'de> for String {
     }
 }
 
+/// `None` packs as the nil element, and `Some(v)` as `v` itself.
+///
+/// The nil element is what `None` unpacks from, so an `Option<Option<T>>` does not round-trip
+/// `Some(None)`, which unpacks as `None`. `Some(())` packs as an empty nested tuple when nested,
+/// and as nothing at all at the top level, so it is told apart from `None` in both cases.
 impl<T> TuplePack for Option<T>
 where
     T: TuplePack,
@@ -908,6 +1175,14 @@ where
             Some(v) => v.pack(w, tuple_depth),
         }
     }
+
+    fn size_hint(&self, tuple_depth: TupleDepth) -> usize {
+        match self {
+            None if tuple_depth.depth() > 1 => 2,
+            None => 1,
+            Some(v) => v.size_hint(tuple_depth),
+        }
+    }
 }
 
 impl<'de, T> TupleUnpack<'de> for Option<T>
@@ -950,6 +1225,24 @@ impl<'a> TuplePack for Element<'a> {
             Element::BigInt(v) => v.pack(w, tuple_depth),
         }
     }
+
+    fn size_hint(&self, tuple_depth: TupleDepth) -> usize {
+        match self {
+            Element::Nil => Option::<()>::None.size_hint(tuple_depth),
+            Element::Bool(b) => b.size_hint(tuple_depth),
+            Element::Int(i) => i.size_hint(tuple_depth),
+            Element::Float(f) => f.size_hint(tuple_depth),
+            Element::Double(f) => f.size_hint(tuple_depth),
+            Element::String(ref c) => c.size_hint(tuple_depth),
+            Element::Bytes(ref b) => b.size_hint(tuple_depth),
+            Element::Versionstamp(ref b) => b.size_hint(tuple_depth),
+            Element::Tuple(ref v) => v.size_hint(tuple_depth),
+            #[cfg(feature = "uuid")]
+            Element::Uuid(v) => v.size_hint(tuple_depth),
+            #[cfg(feature = "num-bigint")]
+            Element::BigInt(v) => v.size_hint(tuple_depth),
+        }
+    }
 }
 
 impl<'de> TupleUnpack<'de> for Element<'de> {
@@ -983,7 +1276,7 @@ impl<'de> TupleUnpack<'de> for Element<'de> {
             INTMIN..=INTMAX => match i64::unpack(input, tuple_depth) {
                 Ok((input, v)) => (input, Element::Int(v)),
                 #[cfg(feature = "num-bigint")]
-                Err(PackError::UnsupportedIntLength) => {
+                Err(PackError::IntegerOverflow) => {
                     let (input, v) = num_bigint::BigInt::unpack(input, tuple_depth)?;
                     (input, Element::BigInt(v))
                 }
@@ -1066,6 +1359,10 @@ impl TuplePack for Versionstamp {
             Ok(VersionstampOffset::OneIncomplete { offset: 1 })
         }
     }
+
+    fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+        1 + 12
+    }
 }
 
 impl<'de> TupleUnpack<'de> for Versionstamp {
@@ -1093,6 +1390,10 @@ mod pack_uuid {
             w.write_all(self.as_bytes())?;
             Ok(VersionstampOffset::None { size: 1 + 16 })
         }
+
+        fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+            1 + 16
+        }
     }
 
     impl<'de> TupleUnpack<'de> for Uuid {
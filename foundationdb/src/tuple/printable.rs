@@ -0,0 +1,142 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The printable escape format of keys, as in `fdbcli`: printable ascii characters are kept
+//! as is, `\` is doubled and every other byte becomes `\xNN`.
+
+use super::*;
+
+/// Displays bytes in the printable escape format.
+pub(crate) struct Printable<'a>(pub &'a [u8]);
+
+impl<'a> fmt::Display for Printable<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &byte in self.0 {
+            match byte {
+                b'\\' => f.write_str(r"\\")?,
+                b' '..=b'~' => fmt::Write::write_char(f, byte as char)?,
+                _ => write!(f, "\\x{:02x}", byte)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses the printable escape format back, rejecting anything `Printable` could not produce
+/// but hexadecimal escapes of printable characters.
+#[cfg(feature = "serde")]
+pub(crate) fn parse_printable(s: &str) -> result::Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut input = s.as_bytes().iter().enumerate();
+    while let Some((pos, &byte)) = input.next() {
+        match byte {
+            b'\\' => match input.next() {
+                Some((_, b'\\')) => bytes.push(b'\\'),
+                Some((_, b'x')) => {
+                    let hex = |digit: Option<(usize, &u8)>| {
+                        digit.and_then(|(_, &digit)| (digit as char).to_digit(16))
+                    };
+                    match (hex(input.next()), hex(input.next())) {
+                        (Some(high), Some(low)) => bytes.push((high << 4 | low) as u8),
+                        _ => return Err(format!("invalid hexadecimal escape at offset {}", pos)),
+                    }
+                }
+                _ => return Err(format!("invalid escape at offset {}", pos)),
+            },
+            b' '..=b'~' => bytes.push(byte),
+            _ => {
+                return Err(format!(
+                    "unescaped non printable character at offset {}",
+                    pos
+                ))
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(feature = "serde")]
+pub(crate) mod serde_printable {
+    //! Bytes as a printable string for human readable formats, raw bytes otherwise.
+
+    use super::*;
+    use serde::{de, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(&Printable(bytes))
+        } else {
+            serializer.serialize_bytes(bytes)
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(BytesVisitor)
+        } else {
+            deserializer.deserialize_byte_buf(BytesVisitor)
+        }
+    }
+
+    struct BytesVisitor;
+
+    impl<'de> de::Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("bytes or a string in the printable escape format")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Vec<u8>, E> {
+            parse_printable(v).map_err(E::custom)
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+            Ok(v.to_vec())
+        }
+
+        fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+            Ok(v)
+        }
+
+        fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+            let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(byte) = seq.next_element()? {
+                bytes.push(byte);
+            }
+            Ok(bytes)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let all: Vec<u8> = (0..=255).collect();
+        for bytes in [&b""[..], b"hello world", b"a\\b\"c", b"\x00\xff\x01", &all] {
+            let printable = Printable(bytes).to_string();
+            assert_eq!(parse_printable(&printable).unwrap(), bytes);
+        }
+        assert_eq!(
+            Printable(b"\x02app\x00\xffkey\\\n").to_string(),
+            r"\x02app\x00\xffkey\\\x0a"
+        );
+        assert_eq!(parse_printable(r"\x41\x4a").unwrap(), b"AJ");
+    }
+
+    #[test]
+    fn malformed() {
+        for s in [r"\", r"\x", r"\x4", r"\xg0", r"\n", "\n", "é"] {
+            assert!(parse_printable(s).is_err(), "{:?}", s);
+        }
+    }
+}
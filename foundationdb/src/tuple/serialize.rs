@@ -0,0 +1,518 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! A serde `Serializer` and `Deserializer` over the tuple encoding.
+//!
+//! The serde data model maps onto the tuple elements as [`TuplePack`] does for the same types:
+//!
+//! - booleans, integers, floats, strings and byte strings are packed as themselves, `char`s as
+//!   one-character strings;
+//! - `None` is the nil element, and `Some(v)` is `v` itself;
+//! - `()` and unit structs are empty tuples, newtype structs are their value;
+//! - sequences, tuples and structs are tuples, their elements or fields being packed in order,
+//!   as `#[derive(TuplePack)]` would.
+//!
+//! Maps and enums are not supported, as the tuple encoding has no way to tell keys or variants
+//! apart, and fail with [`PackError::Message`].
+
+use super::pack::{is_end_of_tuple, parse_code};
+use super::*;
+use serde::de::{self, DeserializeSeed, SeqAccess, Visitor};
+use serde::ser::{self, Serialize};
+
+impl ser::Error for PackError {
+    fn custom<T: Display>(msg: T) -> Self {
+        PackError::Message(msg.to_string().into_boxed_str())
+    }
+}
+
+impl de::Error for PackError {
+    fn custom<T: Display>(msg: T) -> Self {
+        PackError::Message(msg.to_string().into_boxed_str())
+    }
+}
+
+fn unsupported(what: &str) -> PackError {
+    PackError::Message(format!("{} are not supported by the tuple encoding", what).into())
+}
+
+/// Serializes `value` into a packed tuple, see the [module documentation](self)
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> PackResult<Vec<u8>> {
+    let mut output = Vec::new();
+    value.serialize(Serializer {
+        output: &mut output,
+        tuple_depth: TupleDepth::new(),
+    })?;
+    Ok(output)
+}
+
+/// Deserializes a value from a packed tuple, see the [module documentation](self)
+///
+/// Like [`unpack`], it fails with [`PackError::TrailingBytes`] if the input goes on after the
+/// value.
+pub fn from_bytes<'de, T: serde::Deserialize<'de>>(input: &'de [u8]) -> PackResult<T> {
+    let mut deserializer = Deserializer {
+        input,
+        tuple_depth: TupleDepth::new(),
+    };
+    let value = T::deserialize(&mut deserializer)?;
+    if !deserializer.input.is_empty() {
+        return Err(PackError::TrailingBytes);
+    }
+    Ok(value)
+}
+
+struct Serializer<'a> {
+    output: &'a mut Vec<u8>,
+    tuple_depth: TupleDepth,
+}
+
+impl<'a> Serializer<'a> {
+    fn pack<T: TuplePack + ?Sized>(self, v: &T) -> PackResult<()> {
+        v.pack(self.output, self.tuple_depth)?;
+        Ok(())
+    }
+
+    fn tuple(self) -> PackResult<Compound<'a>> {
+        if self.tuple_depth.depth() > 0 {
+            self.output.push(NESTED);
+        }
+        Ok(Compound {
+            output: self.output,
+            tuple_depth: self.tuple_depth,
+        })
+    }
+}
+
+/// The elements of a tuple being serialized
+struct Compound<'a> {
+    output: &'a mut Vec<u8>,
+    tuple_depth: TupleDepth,
+}
+
+impl<'a> Compound<'a> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> PackResult<()> {
+        value.serialize(Serializer {
+            output: self.output,
+            tuple_depth: self.tuple_depth.increment(),
+        })
+    }
+
+    fn finish(self) -> PackResult<()> {
+        if self.tuple_depth.depth() > 0 {
+            self.output.push(NIL);
+        }
+        Ok(())
+    }
+}
+
+macro_rules! serialize_as {
+    ($($method:ident($ty:ty),)*) => {
+        $(
+            fn $method(self, v: $ty) -> PackResult<()> {
+                self.pack(&v)
+            }
+        )*
+    };
+}
+
+impl<'a> ser::Serializer for Serializer<'a> {
+    type Ok = ();
+    type Error = PackError;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = ser::Impossible<(), PackError>;
+    type SerializeMap = ser::Impossible<(), PackError>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = ser::Impossible<(), PackError>;
+
+    serialize_as! {
+        serialize_bool(bool),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+    }
+
+    fn serialize_i8(self, v: i8) -> PackResult<()> {
+        self.pack(&i16::from(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> PackResult<()> {
+        self.pack(&u16::from(v))
+    }
+
+    fn serialize_char(self, v: char) -> PackResult<()> {
+        self.pack(&(v.encode_utf8(&mut [0; 4]) as &str))
+    }
+
+    fn serialize_none(self) -> PackResult<()> {
+        self.pack(&None::<()>)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> PackResult<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> PackResult<()> {
+        self.pack(&())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> PackResult<()> {
+        self.pack(&())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> PackResult<()> {
+        Err(unsupported("enums"))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> PackResult<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> PackResult<()> {
+        Err(unsupported("enums"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> PackResult<Compound<'a>> {
+        self.tuple()
+    }
+
+    fn serialize_tuple(self, _len: usize) -> PackResult<Compound<'a>> {
+        self.tuple()
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> PackResult<Compound<'a>> {
+        self.tuple()
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> PackResult<Self::SerializeTupleVariant> {
+        Err(unsupported("enums"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> PackResult<Self::SerializeMap> {
+        Err(unsupported("maps"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> PackResult<Compound<'a>> {
+        self.tuple()
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> PackResult<Self::SerializeStructVariant> {
+        Err(unsupported("enums"))
+    }
+}
+
+impl<'a> ser::SerializeSeq for Compound<'a> {
+    type Ok = ();
+    type Error = PackError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> PackResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> PackResult<()> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTuple for Compound<'a> {
+    type Ok = ();
+    type Error = PackError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> PackResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> PackResult<()> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for Compound<'a> {
+    type Ok = ();
+    type Error = PackError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> PackResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> PackResult<()> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStruct for Compound<'a> {
+    type Ok = ();
+    type Error = PackError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> PackResult<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> PackResult<()> {
+        self.finish()
+    }
+}
+
+struct Deserializer<'de> {
+    input: &'de [u8],
+    tuple_depth: TupleDepth,
+}
+
+impl<'de> Deserializer<'de> {
+    fn unpack<T: TupleUnpack<'de>>(&mut self) -> PackResult<T> {
+        let (input, v) = T::unpack(self.input, self.tuple_depth)?;
+        self.input = input;
+        Ok(v)
+    }
+
+    fn tuple<V: Visitor<'de>>(&mut self, visitor: V) -> PackResult<V::Value> {
+        let nested = self.tuple_depth.depth() > 0;
+        if nested {
+            self.input = parse_code(self.input, NESTED)?;
+        }
+        let mut elements = Deserializer {
+            input: self.input,
+            tuple_depth: self.tuple_depth.increment(),
+        };
+        let value = visitor.visit_seq(Elements {
+            deserializer: &mut elements,
+            nested,
+        })?;
+        self.input = elements.input;
+        if nested {
+            self.input = parse_code(self.input, NIL)?;
+        }
+        Ok(value)
+    }
+}
+
+/// The elements of a tuple being deserialized
+struct Elements<'a, 'de> {
+    deserializer: &'a mut Deserializer<'de>,
+    nested: bool,
+}
+
+impl<'a, 'de> SeqAccess<'de> for Elements<'a, 'de> {
+    type Error = PackError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> PackResult<Option<T::Value>> {
+        if is_end_of_tuple(self.deserializer.input, self.nested) {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.deserializer).map(Some)
+    }
+}
+
+macro_rules! deserialize_as {
+    ($($method:ident($ty:ty) => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> PackResult<V::Value> {
+                visitor.$visit(self.unpack::<$ty>()?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = PackError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> PackResult<V::Value> {
+        match self.input.first().copied() {
+            Some(NIL) => self.deserialize_option(visitor),
+            Some(BYTES) => self.deserialize_bytes(visitor),
+            Some(STRING) => self.deserialize_str(visitor),
+            Some(NESTED) => self.tuple(visitor),
+            Some(code) if (NEGINTSTART..INTZERO).contains(&code) => self.deserialize_i64(visitor),
+            Some(code) if (INTZERO..=POSINTEND).contains(&code) => self.deserialize_u64(visitor),
+            Some(FLOAT) => self.deserialize_f32(visitor),
+            Some(DOUBLE) => self.deserialize_f64(visitor),
+            Some(FALSE) | Some(TRUE) => self.deserialize_bool(visitor),
+            _ => match self.unpack::<Element>()? {
+                #[cfg(feature = "uuid")]
+                Element::Uuid(v) => visitor.visit_bytes(v.as_bytes()),
+                Element::Versionstamp(v) => visitor.visit_bytes(v.as_bytes()),
+                element => Err(PackError::Message(
+                    format!("unexpected element {:?}", element).into(),
+                )),
+            },
+        }
+    }
+
+    deserialize_as! {
+        deserialize_bool(bool) => visit_bool,
+        deserialize_i16(i16) => visit_i16,
+        deserialize_i32(i32) => visit_i32,
+        deserialize_i64(i64) => visit_i64,
+        deserialize_i128(i128) => visit_i128,
+        deserialize_u16(u16) => visit_u16,
+        deserialize_u32(u32) => visit_u32,
+        deserialize_u64(u64) => visit_u64,
+        deserialize_u128(u128) => visit_u128,
+        deserialize_f32(f32) => visit_f32,
+        deserialize_f64(f64) => visit_f64,
+        deserialize_string(String) => visit_string,
+        deserialize_byte_buf(Vec<u8>) => visit_byte_buf,
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> PackResult<V::Value> {
+        let v = self.unpack::<i16>()?;
+        visitor.visit_i8(i8::try_from(v).map_err(|_| PackError::IntegerOverflow)?)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> PackResult<V::Value> {
+        let v = self.unpack::<u16>()?;
+        visitor.visit_u8(u8::try_from(v).map_err(|_| PackError::IntegerOverflow)?)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> PackResult<V::Value> {
+        let v = self.unpack::<Cow<str>>()?;
+        let mut chars = v.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(de::Error::invalid_length(
+                v.chars().count(),
+                &"one character",
+            )),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> PackResult<V::Value> {
+        match self.unpack::<Cow<str>>()? {
+            Cow::Borrowed(v) => visitor.visit_borrowed_str(v),
+            Cow::Owned(v) => visitor.visit_string(v),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> PackResult<V::Value> {
+        match self.unpack::<Cow<[u8]>>()? {
+            Cow::Borrowed(v) => visitor.visit_borrowed_bytes(v),
+            Cow::Owned(v) => visitor.visit_byte_buf(v),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> PackResult<V::Value> {
+        if self.input.first() == Some(&NIL) {
+            self.unpack::<Option<()>>()?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> PackResult<V::Value> {
+        self.unpack::<()>()?;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> PackResult<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> PackResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> PackResult<V::Value> {
+        self.tuple(visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> PackResult<V::Value> {
+        self.tuple(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> PackResult<V::Value> {
+        self.tuple(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, _visitor: V) -> PackResult<V::Value> {
+        Err(unsupported("maps"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> PackResult<V::Value> {
+        self.tuple(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        _visitor: V,
+    ) -> PackResult<V::Value> {
+        Err(unsupported("enums"))
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, _visitor: V) -> PackResult<V::Value> {
+        Err(unsupported("identifiers"))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> PackResult<V::Value> {
+        self.deserialize_any(visitor)
+    }
+}
@@ -7,9 +7,13 @@
 // copied, modified, or distributed except according to those terms.
 
 use super::*;
-use crate::{KeySelector, RangeOption, Transaction};
-use std::borrow::Cow;
-use std::hash::Hash;
+#[cfg(feature = "client")]
+use crate::{options, FdbBindingError, KeySelector, RangeOption, Transaction};
+#[cfg(feature = "client")]
+use alloc::borrow::Cow;
+use core::hash::Hash;
+#[cfg(feature = "client")]
+use futures::TryStreamExt;
 
 /// Represents a well-defined region of keyspace in a FoundationDB database
 ///
@@ -71,6 +75,18 @@ impl Subspace {
         out
     }
 
+    /// Returns the key encoding the specified Tuple with the prefix of this Subspace
+    /// prepended, followed by the offset of its incomplete versionstamp.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is multiple versionstamp present.
+    pub fn pack_with_versionstamp<T: TuplePack>(&self, t: &T) -> Vec<u8> {
+        let mut out = self.prefix.clone();
+        pack_into_with_versionstamp(t, &mut out);
+        out
+    }
+
     /// `unpack` returns the Tuple encoded by the given key with the prefix of this Subspace
     /// removed.  `unpack` will return an error if the key is not in this Subspace or does not
     /// encode a well-formed Tuple.
@@ -100,8 +116,15 @@ impl Subspace {
 
         (begin, end)
     }
+
+    /// `range_with_tuple` returns first and last key of the keys of this Subspace starting with
+    /// the given tuple encodable.
+    pub fn range_with_tuple<T: TuplePack>(&self, t: &T) -> (Vec<u8>, Vec<u8>) {
+        self.subspace(t).range()
+    }
 }
 
+#[cfg(feature = "client")]
 impl<'a> From<&'a Subspace> for RangeOption<'static> {
     fn from(subspace: &Subspace) -> Self {
         let (begin, end) = subspace.range();
@@ -114,11 +137,103 @@ impl<'a> From<&'a Subspace> for RangeOption<'static> {
     }
 }
 
+#[cfg(feature = "client")]
 impl Transaction {
     pub fn clear_subspace_range(&self, subspace: &Subspace) {
         let (begin, end) = subspace.range();
         self.clear_range(&begin, &end)
     }
+
+    /// Reads the key-value pairs of the given Subspace, decoding each key, with the prefix of the
+    /// Subspace removed, into `K` and each value into `V`.
+    ///
+    /// Values are expected to be packed tuples. Pairs are returned in key order, or in reverse
+    /// order if `opt.reverse` is set. The begin and end of `opt` are replaced by the range of the
+    /// Subspace, its other fields (limit, target bytes, mode) are honored.
+    ///
+    /// Fails with a `PackError` if a key or a value can not be decoded.
+    ///
+    /// # Arguments
+    ///
+    /// * `subspace`: the Subspace to read
+    /// * `opt`: the limit, target_bytes, mode and direction of the read
+    /// * `snapshot`: `true` if this is a [snapshot read](https://apple.github.io/foundationdb/api-c.html#snapshots)
+    pub async fn scan_typed<K, V>(
+        &self,
+        subspace: &Subspace,
+        opt: RangeOption<'_>,
+        snapshot: bool,
+    ) -> Result<Vec<(K, V)>, FdbBindingError>
+    where
+        K: for<'de> TupleUnpack<'de>,
+        V: for<'de> TupleUnpack<'de>,
+    {
+        let range = RangeOption::from(subspace);
+        let opt = RangeOption {
+            begin: range.begin,
+            end: range.end,
+            ..opt
+        };
+        let mut values = self.get_ranges_keyvalues(opt, snapshot);
+        let mut decoded = Vec::new();
+        while let Some(value) = values.try_next().await? {
+            decoded.push((subspace.unpack(value.key())?, unpack(value.value())?));
+        }
+        Ok(decoded)
+    }
+
+    /// Sets the key encoding the specified Tuple in the given Subspace to `value`, the single
+    /// incomplete versionstamp of the Tuple being replaced by the versionstamp of this transaction
+    /// at commit time.
+    ///
+    /// The user version of the incomplete versionstamp is replaced by `user_version`, keys written
+    /// within the same transaction must use distinct user versions to stay ordered and unique,
+    /// see [`VersionstampAllocator`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Tuple does not contain exactly one incomplete versionstamp.
+    pub fn set_versionstamped_key_with_user_version<T: TuplePack>(
+        &self,
+        subspace: &Subspace,
+        t: &T,
+        value: &[u8],
+        user_version: u16,
+    ) {
+        let mut key = subspace.bytes().to_vec();
+        match t.pack_into_vec_with_versionstamp(&mut key) {
+            VersionstampOffset::OneIncomplete { offset } => {
+                // the user version follows the 10 bytes of the transaction version
+                let offset = offset as usize + 10;
+                key[offset..offset + 2].copy_from_slice(&user_version.to_be_bytes());
+            }
+            VersionstampOffset::None { .. } => {
+                panic!(
+                    "set_versionstamped_key_with_user_version requires an incomplete versionstamp"
+                )
+            }
+            VersionstampOffset::MultipleIncomplete => {
+                panic!("set_versionstamped_key_with_user_version does not allow multiple versionstamps")
+            }
+        }
+        self.atomic_op(&key, value, options::MutationType::SetVersionstampedKey)
+    }
+}
+
+/// Serializes the prefix in the printable escape format (`\x02app\x00`) for human readable
+/// formats such as JSON, and as raw bytes otherwise.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Subspace {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        printable::serde_printable::serialize(&self.prefix, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Subspace {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        printable::serde_printable::deserialize(deserializer).map(Subspace::from_bytes)
+    }
 }
 
 #[cfg(test)]
@@ -136,6 +251,29 @@ mod tests {
         assert_eq!(ss1.bytes(), ss2.bytes());
     }
 
+    #[test]
+    fn nested() {
+        let root = Subspace::from_bytes(b"app");
+        let user = root.subspace(&("users",)).subspace(&(42,));
+        assert_eq!(user, root.subspace(&("users", 42)));
+
+        let key = user.pack(&("email", 1));
+        assert_eq!(key, root.pack(&("users", 42, "email", 1)));
+        assert_eq!(
+            user.unpack::<(String, i64)>(&key).unwrap(),
+            ("email".into(), 1)
+        );
+        assert_eq!(
+            root.unpack::<(String, i64, String, i64)>(&key).unwrap(),
+            ("users".into(), 42, "email".into(), 1)
+        );
+
+        let (begin, end) = user.range();
+        assert_eq!((begin, end), root.range_with_tuple(&("users", 42)));
+        assert!(user.is_start_of(&key));
+        assert!(!user.is_start_of(&root.pack(&("users", 43, "email", 1))));
+    }
+
     #[test]
     fn pack_unpack() {
         let ss0: Subspace = 1.into();
@@ -149,6 +287,27 @@ mod tests {
         assert_eq!(tup, tup_unpack);
 
         assert!(ss0.unpack::<(i64, i64, i64)>(&packed).is_err());
+
+        let foreign = Subspace::from(2).pack(&tup);
+        assert!(matches!(
+            ss0.unpack::<(i64, i64)>(&foreign),
+            Err(PackError::BadPrefix)
+        ));
+        // keys shorter than the prefix
+        let ss1: Subspace = ("users", 1).into();
+        for len in 0..ss1.bytes().len() {
+            assert!(matches!(
+                ss1.unpack::<(i64, i64)>(&ss1.bytes()[..len]),
+                Err(PackError::BadPrefix)
+            ));
+        }
+        assert_eq!(ss1.unpack::<()>(ss1.bytes()).unwrap(), ());
+        // truncated after the prefix
+        let key = ss1.pack(&("alice", 42));
+        assert!(matches!(
+            ss1.unpack::<(String, i64)>(&key[..key.len() - 1]),
+            Err(PackError::MissingBytes)
+        ));
     }
 
     #[test]
@@ -174,6 +333,72 @@ mod tests {
 
         let (begin, end) = ss.range();
         assert!(packed >= begin && packed <= end);
+        assert_eq!(
+            (begin.as_slice(), end.as_slice()),
+            (&b"\x15\x01\x00"[..], &b"\x15\x01\xff"[..])
+        );
+
+        let sibling = Subspace::from(2).pack(&tup);
+        assert!(sibling >= end);
+
+        // the end stays above the keys of the subspace when its prefix ends with 0xff, as no
+        // packed element starts with 0xff
+        let ss = Subspace::from_bytes(&b"a\xff\xff"[..]);
+        let (begin, end) = ss.range();
+        for key in [
+            ss.pack(&(u64::MAX,)),
+            ss.pack(&(Bytes::from(&b"\xff"[..]),)),
+        ] {
+            assert!(key >= begin && key < end);
+        }
+        for outside in [b"a\xff".to_vec(), b"a\xff\xff".to_vec(), b"b".to_vec()] {
+            assert!(outside < begin || outside >= end);
+        }
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn range_option() {
+        let ss: Subspace = ("users", 1).into();
+        let (begin, end) = ss.range();
+        let opt = RangeOption::from(&ss);
+        for (selector, key) in [(&opt.begin, begin), (&opt.end, end)] {
+            assert_eq!(selector.key(), key);
+            assert!(!selector.or_equal());
+            assert_eq!(selector.offset(), 1);
+        }
+    }
+
+    #[test]
+    fn range_with_tuple() {
+        let ss: Subspace = "users".into();
+        let (begin, end) = ss.range_with_tuple(&("team", 1));
+        assert_eq!(
+            (begin.clone(), end.clone()),
+            ss.subspace(&("team", 1)).range()
+        );
+
+        let inside = ss.pack(&("team", 1, "alice"));
+        assert!(inside > begin && inside < end);
+        for outside in [
+            ss.pack(&("team", 2, "alice")),
+            ss.pack(&("team",)),
+            ss.pack(&("teams", 1)),
+            Subspace::from("other").pack(&("team", 1, "alice")),
+        ] {
+            assert!(outside < begin || outside >= end);
+        }
+    }
+
+    #[test]
+    fn pack_with_versionstamp() {
+        let ss: Subspace = 1.into();
+        let packed = ss.pack_with_versionstamp(&(Versionstamp::incomplete(3), 2));
+        let mut expected = pack(&1);
+        pack_into_with_versionstamp(&(Versionstamp::incomplete(3), 2), &mut expected);
+        assert_eq!(expected, packed);
+        // versionstamp offset, right after the prefix and the versionstamp code
+        assert_eq!(&packed[packed.len() - 4..], &3u32.to_le_bytes());
     }
 
     #[test]
@@ -195,4 +420,21 @@ mod tests {
         assert_eq!(map.get(&Subspace::all().subspace(&"test")).unwrap(), &1);
         assert_eq!(map.get(&Subspace::all().subspace(&"test2")).unwrap(), &2);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_json() {
+        let subspace = Subspace::from(("app", 1)).subspace(&b"\\\xff".to_vec());
+        let json = serde_json::to_string(&subspace).unwrap();
+        assert_eq!(json, r#""\\x02app\\x00\\x15\\x01\\x01\\\\\\xff\\x00""#);
+        assert_eq!(serde_json::from_str::<Subspace>(&json).unwrap(), subspace);
+
+        let err = serde_json::from_str::<Subspace>(r#""\\x0""#).unwrap_err();
+        assert!(
+            err.to_string().contains("invalid hexadecimal escape"),
+            "{}",
+            err
+        );
+        assert!(serde_json::from_str::<Subspace>("12").is_err());
+    }
 }
@@ -0,0 +1,310 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use super::{io, PackResult, TupleDepth, TuplePack, TupleUnpack, VersionstampOffset};
+
+/// A point in time, as nanoseconds since the unix epoch
+///
+/// This is the canonical encoding of timestamps in tuples: a tuple integer holding the signed
+/// number of nanoseconds since 1970-01-01T00:00:00Z, so keys sort in chronological order. It
+/// covers 1677-09-21T00:12:43.145224192Z to 2262-04-11T23:47:16.854775807Z.
+///
+/// With the `chrono` and `time` features, `chrono::DateTime<Utc>` and `time::OffsetDateTime` are
+/// packed the same way. Packing one outside of the range above fails with an
+/// `io::ErrorKind::InvalidInput` error rather than truncating it, see
+/// [`TuplePack::pack_root`] to handle it instead of panicking.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    /// The earliest timestamp, 1677-09-21T00:12:43.145224192Z.
+    pub const MIN: Timestamp = Timestamp(i64::MIN);
+    /// The latest timestamp, 2262-04-11T23:47:16.854775807Z.
+    pub const MAX: Timestamp = Timestamp(i64::MAX);
+    /// 1970-01-01T00:00:00Z
+    pub const UNIX_EPOCH: Timestamp = Timestamp(0);
+
+    /// Creates a timestamp from nanoseconds since the unix epoch.
+    pub const fn from_unix_nanos(nanos: i64) -> Self {
+        Timestamp(nanos)
+    }
+
+    /// Returns the nanoseconds since the unix epoch.
+    pub const fn unix_nanos(self) -> i64 {
+        self.0
+    }
+
+    /// Returns the current time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the system clock is past 2262.
+    #[cfg(feature = "std")]
+    pub fn now() -> Self {
+        Self::from_system_time(std::time::SystemTime::now()).expect("timestamp out of range")
+    }
+
+    /// Converts a `SystemTime`, returns `None` if it is out of the range of timestamps.
+    #[cfg(feature = "std")]
+    pub fn from_system_time(time: std::time::SystemTime) -> Option<Self> {
+        let nanos = match time.duration_since(std::time::UNIX_EPOCH) {
+            Ok(after) => i64::try_from(after.as_nanos()).ok()?,
+            Err(before) => {
+                let before = i128::try_from(before.duration().as_nanos()).ok()?;
+                i64::try_from(-before).ok()?
+            }
+        };
+        Some(Timestamp(nanos))
+    }
+
+    /// Converts to a `SystemTime`.
+    #[cfg(feature = "std")]
+    pub fn to_system_time(self) -> std::time::SystemTime {
+        let duration = core::time::Duration::from_nanos(self.0.unsigned_abs());
+        if self.0 < 0 {
+            std::time::UNIX_EPOCH - duration
+        } else {
+            std::time::UNIX_EPOCH + duration
+        }
+    }
+}
+
+impl TuplePack for Timestamp {
+    fn pack<W: io::Write>(
+        &self,
+        w: &mut W,
+        tuple_depth: TupleDepth,
+    ) -> io::Result<VersionstampOffset> {
+        self.0.pack(w, tuple_depth)
+    }
+
+    fn size_hint(&self, tuple_depth: TupleDepth) -> usize {
+        self.0.size_hint(tuple_depth)
+    }
+}
+
+impl<'de> TupleUnpack<'de> for Timestamp {
+    fn unpack(input: &'de [u8], tuple_depth: TupleDepth) -> PackResult<(&'de [u8], Self)> {
+        let (input, nanos) = i64::unpack(input, tuple_depth)?;
+        Ok((input, Timestamp(nanos)))
+    }
+}
+
+#[cfg(any(feature = "chrono", feature = "time"))]
+fn out_of_range() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "timestamp out of the range of i64 nanoseconds since the unix epoch",
+    )
+}
+
+#[cfg(feature = "chrono")]
+mod pack_chrono {
+    use super::*;
+    use chrono::{DateTime, TimeZone, Utc};
+
+    impl From<Timestamp> for DateTime<Utc> {
+        fn from(timestamp: Timestamp) -> Self {
+            Utc.timestamp_nanos(timestamp.0)
+        }
+    }
+
+    impl TryFrom<DateTime<Utc>> for Timestamp {
+        type Error = io::Error;
+
+        fn try_from(time: DateTime<Utc>) -> io::Result<Self> {
+            time.timestamp_nanos_opt()
+                .map(Timestamp)
+                .ok_or_else(out_of_range)
+        }
+    }
+
+    impl TuplePack for DateTime<Utc> {
+        fn pack<W: io::Write>(
+            &self,
+            w: &mut W,
+            tuple_depth: TupleDepth,
+        ) -> io::Result<VersionstampOffset> {
+            Timestamp::try_from(*self)?.pack(w, tuple_depth)
+        }
+
+        fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+            // the largest packed i64
+            9
+        }
+    }
+
+    impl<'de> TupleUnpack<'de> for DateTime<Utc> {
+        fn unpack(input: &'de [u8], tuple_depth: TupleDepth) -> PackResult<(&'de [u8], Self)> {
+            let (input, timestamp) = Timestamp::unpack(input, tuple_depth)?;
+            Ok((input, timestamp.into()))
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+mod pack_time {
+    use super::*;
+    use time::OffsetDateTime;
+
+    /// The returned date is in UTC.
+    impl From<Timestamp> for OffsetDateTime {
+        fn from(timestamp: Timestamp) -> Self {
+            // time covers years -9999 to 9999, a superset of the range of timestamps
+            OffsetDateTime::from_unix_timestamp_nanos(i128::from(timestamp.0))
+                .expect("timestamp in the range of time")
+        }
+    }
+
+    impl TryFrom<OffsetDateTime> for Timestamp {
+        type Error = io::Error;
+
+        fn try_from(time: OffsetDateTime) -> io::Result<Self> {
+            i64::try_from(time.unix_timestamp_nanos())
+                .map(Timestamp)
+                .map_err(|_| out_of_range())
+        }
+    }
+
+    impl TuplePack for OffsetDateTime {
+        fn pack<W: io::Write>(
+            &self,
+            w: &mut W,
+            tuple_depth: TupleDepth,
+        ) -> io::Result<VersionstampOffset> {
+            Timestamp::try_from(*self)?.pack(w, tuple_depth)
+        }
+
+        fn size_hint(&self, _tuple_depth: TupleDepth) -> usize {
+            // the largest packed i64
+            9
+        }
+    }
+
+    impl<'de> TupleUnpack<'de> for OffsetDateTime {
+        fn unpack(input: &'de [u8], tuple_depth: TupleDepth) -> PackResult<(&'de [u8], Self)> {
+            let (input, timestamp) = Timestamp::unpack(input, tuple_depth)?;
+            Ok((input, timestamp.into()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuple::{pack, unpack};
+
+    // the boundaries, computed by hand from i64::MIN and i64::MAX nanoseconds
+    const MIN_SECS: i64 = -9_223_372_037;
+    const MIN_SUBSEC_NANOS: u32 = 145_224_192;
+    const MAX_SECS: i64 = 9_223_372_036;
+    const MAX_SUBSEC_NANOS: u32 = 854_775_807;
+
+    #[test]
+    fn round_trip() {
+        for timestamp in [
+            Timestamp::MIN,
+            Timestamp::from_unix_nanos(-1),
+            Timestamp::UNIX_EPOCH,
+            Timestamp::from_unix_nanos(1_700_000_000_123_456_789),
+            Timestamp::MAX,
+        ] {
+            let packed = pack(&timestamp);
+            assert_eq!(packed, pack(&timestamp.unix_nanos()));
+            assert_eq!(unpack::<Timestamp>(&packed).unwrap(), timestamp);
+        }
+        assert_eq!(pack(&Timestamp::UNIX_EPOCH), b"\x14");
+        assert_eq!(pack(&Timestamp::from_unix_nanos(-1)), b"\x13\xfe");
+    }
+
+    #[test]
+    fn ordering() {
+        let mut timestamps = [
+            Timestamp::MAX,
+            Timestamp::from_unix_nanos(1),
+            Timestamp::MIN,
+            Timestamp::from_unix_nanos(-255),
+            Timestamp::UNIX_EPOCH,
+            Timestamp::from_unix_nanos(-256),
+            Timestamp::from_unix_nanos(256),
+        ];
+        let mut packed: Vec<Vec<u8>> = timestamps.iter().map(pack).collect();
+        timestamps.sort();
+        packed.sort();
+        let decoded: Vec<Timestamp> = packed.iter().map(|key| unpack(key).unwrap()).collect();
+        assert_eq!(decoded, timestamps);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn system_time() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let min = UNIX_EPOCH - Duration::new(MIN_SECS.unsigned_abs(), 0)
+            + Duration::from_nanos(u64::from(MIN_SUBSEC_NANOS));
+        let max = UNIX_EPOCH + Duration::new(MAX_SECS as u64, MAX_SUBSEC_NANOS);
+        assert_eq!(Timestamp::from_system_time(min), Some(Timestamp::MIN));
+        assert_eq!(Timestamp::from_system_time(max), Some(Timestamp::MAX));
+        assert_eq!(Timestamp::MIN.to_system_time(), min);
+        assert_eq!(Timestamp::MAX.to_system_time(), max);
+
+        let nano = Duration::from_nanos(1);
+        assert_eq!(Timestamp::from_system_time(min - nano), None);
+        assert_eq!(Timestamp::from_system_time(max + nano), None);
+        assert_eq!(
+            Timestamp::from_system_time(UNIX_EPOCH - nano),
+            Some(Timestamp::from_unix_nanos(-1))
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_date_time() {
+        use chrono::{DateTime, TimeZone, Utc};
+
+        let min = Utc.timestamp_opt(MIN_SECS, MIN_SUBSEC_NANOS).unwrap();
+        let max = Utc.timestamp_opt(MAX_SECS, MAX_SUBSEC_NANOS).unwrap();
+        let date = |y, m, d, h, min, s, nanos| {
+            Utc.with_ymd_and_hms(y, m, d, h, min, s).unwrap() + chrono::Duration::nanoseconds(nanos)
+        };
+        assert_eq!(min, date(1677, 9, 21, 0, 12, 43, 145_224_192));
+        assert_eq!(max, date(2262, 4, 11, 23, 47, 16, 854_775_807));
+        for (time, timestamp) in [(min, Timestamp::MIN), (max, Timestamp::MAX)] {
+            assert_eq!(pack(&time), pack(&timestamp));
+            assert_eq!(unpack::<DateTime<Utc>>(&pack(&time)).unwrap(), time);
+        }
+
+        let nano = chrono::Duration::nanoseconds(1);
+        for time in [min - nano, max + nano] {
+            let err = time.pack_root(&mut Vec::new()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn time_offset_date_time() {
+        use time::{Duration, OffsetDateTime};
+
+        let min = OffsetDateTime::from_unix_timestamp(MIN_SECS).unwrap()
+            + Duration::nanoseconds(i64::from(MIN_SUBSEC_NANOS));
+        let max = OffsetDateTime::from_unix_timestamp(MAX_SECS).unwrap()
+            + Duration::nanoseconds(i64::from(MAX_SUBSEC_NANOS));
+        assert_eq!((min.year(), min.ordinal(), min.hour()), (1677, 264, 0));
+        assert_eq!((max.year(), max.ordinal(), max.hour()), (2262, 101, 23));
+        for (time, timestamp) in [(min, Timestamp::MIN), (max, Timestamp::MAX)] {
+            assert_eq!(pack(&time), pack(&timestamp));
+            assert_eq!(unpack::<OffsetDateTime>(&pack(&time)).unwrap(), time);
+        }
+
+        let nano = Duration::nanoseconds(1);
+        for time in [min - nano, max + nano] {
+            let err = time.pack_root(&mut Vec::new()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
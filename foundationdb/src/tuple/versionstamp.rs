@@ -1,5 +1,6 @@
 use super::{Bytes, Element};
-use std::fmt;
+use core::fmt;
+use core::sync::atomic::{AtomicU32, Ordering};
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Versionstamp {
@@ -51,6 +52,25 @@ impl From<[u8; 12]> for Versionstamp {
     }
 }
 
+/// Serialized like a [`Subspace`](super::Subspace) prefix, as 12 bytes.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Versionstamp {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        super::printable::serde_printable::serialize(&self.bytes, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Versionstamp {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = super::printable::serde_printable::deserialize(deserializer)?;
+        let bytes = <[u8; 12]>::try_from(bytes.as_slice()).map_err(|_| {
+            serde::de::Error::invalid_length(bytes.len(), &"a versionstamp of 12 bytes")
+        })?;
+        Ok(Versionstamp { bytes })
+    }
+}
+
 impl<'a> Element<'a> {
     pub fn count_incomplete_versionstamp(&self) -> usize {
         match self {
@@ -60,3 +80,75 @@ impl<'a> Element<'a> {
         }
     }
 }
+
+/// Assigns sequential user versions to the incomplete versionstamps written by a single transaction
+///
+/// Versionstamped keys written within the same transaction share the same transaction version,
+/// their user version keeps them ordered and unique. A new allocator must be used for each
+/// transaction attempt.
+#[derive(Debug, Default)]
+pub struct VersionstampAllocator {
+    next: AtomicU32,
+}
+
+impl VersionstampAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the next user version, or `None` once all the 65536 user versions were allocated.
+    pub fn next_user_version(&self) -> Option<u16> {
+        let next = self.next.fetch_add(1, Ordering::Relaxed);
+        if next > u32::from(u16::MAX) {
+            // do not wrap around on overflow of the counter itself
+            self.next.store(u32::from(u16::MAX) + 1, Ordering::Relaxed);
+            None
+        } else {
+            Some(next as u16)
+        }
+    }
+
+    /// Returns an incomplete versionstamp with the next user version, or `None` once all the
+    /// 65536 user versions were allocated.
+    pub fn next_incomplete(&self) -> Option<Versionstamp> {
+        self.next_user_version().map(Versionstamp::incomplete)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocator() {
+        let allocator = VersionstampAllocator::new();
+        assert_eq!(allocator.next_user_version(), Some(0));
+        assert_eq!(
+            allocator.next_incomplete(),
+            Some(Versionstamp::incomplete(1))
+        );
+        for user_version in 2..=u16::MAX {
+            assert_eq!(allocator.next_user_version(), Some(user_version));
+        }
+        assert_eq!(allocator.next_user_version(), None);
+        assert_eq!(allocator.next_incomplete(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_json() {
+        let versionstamp = Versionstamp::complete([0, 0, 0, 0, 0, 0, 0, 42, 0, 1], 7);
+        let json = serde_json::to_string(&versionstamp).unwrap();
+        assert_eq!(
+            json,
+            r#""\\x00\\x00\\x00\\x00\\x00\\x00\\x00*\\x00\\x01\\x00\\x07""#
+        );
+        assert_eq!(
+            serde_json::from_str::<Versionstamp>(&json).unwrap(),
+            versionstamp
+        );
+
+        let err = serde_json::from_str::<Versionstamp>(r#""\\x00""#).unwrap_err();
+        assert!(err.to_string().contains("invalid length 1"), "{}", err);
+    }
+}
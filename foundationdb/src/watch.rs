@@ -0,0 +1,277 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! A stream of the values of a key, built on watches.
+//!
+//! A watch only fires once, and a change made between the moment it fires and the moment the
+//! next one is registered would be missed. [`Database::watch_stream`] reads the key and
+//! registers the next watch in the same transaction, so the watch fires on any change made after
+//! the value it reports.
+//!
+//! Keys changing quickly are debounced: once a watch fires, the key is read again until it stays
+//! unchanged for [`Debounce::window`], or for at most [`Debounce::max_delay`], and only the latest
+//! value is reported.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{mpsc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use futures::channel::oneshot;
+use futures::prelude::*;
+
+use crate::{Database, FdbResult};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(10);
+const MAX_BACKOFF: Duration = Duration::from_secs(1);
+
+/// How [`Database::watch_stream`] coalesces successive changes of a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Debounce {
+    /// How long the key must stay unchanged before its value is reported
+    pub window: Duration,
+    /// How long a key changing continuously may go unreported
+    pub max_delay: Duration,
+}
+
+impl Default for Debounce {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl Database {
+    /// Returns a stream of the values of `key`, starting with its current value, see the
+    /// [module documentation](crate::watch).
+    ///
+    /// A value is only reported when it differs from the previous one, `None` meaning the key is
+    /// not set. Two reported values are at least [`Debounce::window`] apart, the changes made
+    /// meanwhile are coalesced into the latest one.
+    ///
+    /// The stream never ends. When reading the key or a watch fails, the error is reported, as
+    /// some changes may have been missed, and the key is read again after a backoff growing from
+    /// 10ms to 1s while the errors last.
+    ///
+    /// Each stream holds a watch, which counts in the limit of the database set by
+    /// [`DatabaseOption::MaxWatches`](crate::options::DatabaseOption::MaxWatches).
+    pub fn watch_stream<'a>(
+        &'a self,
+        key: impl Into<Vec<u8>>,
+        debounce: Debounce,
+    ) -> impl Stream<Item = FdbResult<Option<Vec<u8>>>> + Send + Unpin + 'a {
+        let state = WatchState {
+            db: self,
+            key: key.into(),
+            debounce,
+            last: None,
+            watch: None,
+            backoff: INITIAL_BACKOFF,
+            pending_backoff: None,
+        };
+        Box::pin(stream::unfold(state, |mut state| async move {
+            let item = state.next().await;
+            Some((item, state))
+        }))
+    }
+}
+
+type Watch = Pin<Box<dyn Future<Output = FdbResult<()>> + Send + Sync>>;
+
+struct WatchState<'a> {
+    db: &'a Database,
+    key: Vec<u8>,
+    debounce: Debounce,
+    /// The last value reported
+    last: Option<Option<Vec<u8>>>,
+    /// The watch registered with the last value read
+    watch: Option<Watch>,
+    backoff: Duration,
+    pending_backoff: Option<Duration>,
+}
+
+impl<'a> WatchState<'a> {
+    async fn next(&mut self) -> FdbResult<Option<Vec<u8>>> {
+        if let Some(backoff) = self.pending_backoff.take() {
+            delay(backoff).await;
+        }
+        loop {
+            match self.next_change().await {
+                Ok(Some(value)) => {
+                    self.backoff = INITIAL_BACKOFF;
+                    return Ok(value);
+                }
+                Ok(None) => continue,
+                Err(err) => {
+                    self.watch = None;
+                    self.pending_backoff = Some(self.backoff);
+                    self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    /// Waits for the key to change, gives `None` if it changed back to the last value reported
+    async fn next_change(&mut self) -> FdbResult<Option<Option<Vec<u8>>>> {
+        let value = match self.watch.take() {
+            // the first read, or the first one after an error, is reported right away
+            None => {
+                let (value, watch) = self.read_and_watch().await?;
+                self.watch = Some(watch);
+                value
+            }
+            Some(watch) => {
+                watch.await?;
+                self.settle().await?
+            }
+        };
+        if self.last.as_ref() == Some(&value) {
+            return Ok(None);
+        }
+        self.last = Some(value.clone());
+        Ok(Some(value))
+    }
+
+    /// Reads the key until it stays unchanged for a whole window, or until `max_delay` elapsed
+    async fn settle(&mut self) -> FdbResult<Option<Vec<u8>>> {
+        let deadline = Instant::now() + self.debounce.max_delay;
+        loop {
+            let (value, mut watch) = self.read_and_watch().await?;
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if !remaining.is_zero() {
+                delay(self.debounce.window.min(remaining)).await;
+                if let Some(fired) = (&mut watch).now_or_never() {
+                    // changed again during the window
+                    fired?;
+                    continue;
+                }
+            }
+            self.watch = Some(watch);
+            return Ok(value);
+        }
+    }
+
+    async fn read_and_watch(&self) -> FdbResult<(Option<Vec<u8>>, Watch)> {
+        let trx = self.db.create_trx()?;
+        let value = trx.read(&self.key).await?;
+        let watch: Watch = Box::pin(trx.watch(&self.key));
+        trx.commit().await?;
+        Ok((value.map(|value| value.to_vec()), watch))
+    }
+}
+
+/// Completes after `duration`
+///
+/// The crate does not depend on an executor providing timers, the delays of every stream are
+/// timed by a single thread instead, started by the first one.
+fn delay(duration: Duration) -> impl Future<Output = ()> + Send + Unpin {
+    let (sender, receiver) = oneshot::channel::<()>();
+    schedule(Timer {
+        deadline: Instant::now() + duration,
+        sender,
+    });
+    receiver.map(|_| ())
+}
+
+/// Sends `timer` to the timer thread, starting it if needed
+fn schedule(timer: Timer) {
+    let mut timers = TIMERS.lock().unwrap_or_else(PoisonError::into_inner);
+    let timer = match &*timers {
+        Some(sender) => match sender.send(timer) {
+            Ok(()) => return,
+            // the thread is gone
+            Err(mpsc::SendError(timer)) => timer,
+        },
+        None => timer,
+    };
+    let (sender, receiver) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("fdb-watch-timer".into())
+        .spawn(move || run_timers(receiver))
+        .expect("failed to spawn the timer thread");
+    sender.send(timer).expect("the timer thread to be running");
+    *timers = Some(sender);
+}
+
+/// The timers sent to the thread completing them, started by the first [`delay`]
+static TIMERS: Mutex<Option<mpsc::Sender<Timer>>> = Mutex::new(None);
+
+struct Timer {
+    deadline: Instant,
+    sender: oneshot::Sender<()>,
+}
+
+impl PartialEq for Timer {
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
+
+impl Eq for Timer {}
+
+impl PartialOrd for Timer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.deadline.cmp(&other.deadline)
+    }
+}
+
+/// Completes the timers once their deadline is reached, the earliest first
+fn run_timers(receiver: mpsc::Receiver<Timer>) {
+    let mut timers = BinaryHeap::<Reverse<Timer>>::new();
+    loop {
+        let now = Instant::now();
+        while let Some(Reverse(timer)) = timers.peek() {
+            if timer.deadline > now {
+                break;
+            }
+            if let Some(Reverse(timer)) = timers.pop() {
+                // the stream may be gone
+                let _ = timer.sender.send(());
+            }
+        }
+        let received = match timers.peek() {
+            Some(Reverse(timer)) => receiver.recv_timeout(timer.deadline - now),
+            None => receiver
+                .recv()
+                .map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(timer) => timers.push(Reverse(timer)),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delays_complete_in_order() {
+        let start = Instant::now();
+        let order = Mutex::new(Vec::new());
+        let delays = [30, 10, 20].map(|millis| {
+            let order = &order;
+            delay(Duration::from_millis(millis)).map(move |()| order.lock().unwrap().push(millis))
+        });
+        futures::executor::block_on(future::join_all(delays));
+        assert!(start.elapsed() >= Duration::from_millis(30));
+        assert_eq!(*order.lock().unwrap(), vec![10, 20, 30]);
+    }
+}
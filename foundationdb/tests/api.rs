@@ -2,9 +2,17 @@ use foundationdb as fdb;
 use foundationdb::{api::FdbApiBuilder, Database};
 use std::thread;
 
+mod common;
+
 #[test]
 #[should_panic(expected = "the fdb select api version can only be run once per process")]
 fn test_run() {
+    let cluster = common::cluster::shared();
+    let cluster_file = cluster
+        .as_ref()
+        .map(|cluster| cluster.cluster_file().to_string_lossy().into_owned())
+        .unwrap_or_else(|| fdb::default_config_path().to_string());
+
     let (runner, cond) = FdbApiBuilder::default()
         .build()
         .expect("could not initialize api")
@@ -18,18 +26,23 @@ fn test_run() {
 
     // network thread is running
 
-    #[cfg(not(any(feature = "fdb-5_1", feature = "fdb-5_2", feature = "fdb-6_0")))]
+    #[cfg(any(
+        feature = "fdb-6_1",
+        feature = "fdb-6_2",
+        feature = "fdb-6_3",
+        feature = "fdb-7_0",
+        feature = "fdb-7_1",
+        feature = "fdb-7_3"
+    ))]
     {
         assert!(Database::from_path("test".to_string().as_str()).is_err());
-        assert!(Database::from_path(fdb::default_config_path()).is_ok());
+        assert!(Database::from_path(&cluster_file).is_ok());
     }
     assert!(
         futures::executor::block_on(Database::new_compat(Some("test".to_string().as_str())))
             .is_err()
     );
-    assert!(
-        futures::executor::block_on(Database::new_compat(Some(fdb::default_config_path()))).is_ok()
-    );
+    assert!(futures::executor::block_on(Database::new_compat(Some(&cluster_file))).is_ok());
 
     stopper.stop().expect("failed to stop");
     net_thread.join().expect("failed to join net thread");
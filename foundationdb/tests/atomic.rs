@@ -5,15 +5,27 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 use byteorder::ByteOrder;
+use foundationdb::tuple::Subspace;
 use foundationdb::*;
+use foundationdb_macros::cfg_api_versions;
 use futures::future::*;
 
 mod common;
 
 #[test]
 fn test_atomic() {
-    let _guard = unsafe { foundationdb::boot() };
+    let _guard = common::boot();
     futures::executor::block_on(test_atomic_async()).expect("failed to run");
+    futures::executor::block_on(test_typed_atomic_async()).expect("failed to run");
+    #[cfg(any(
+        feature = "fdb-7_3",
+        feature = "fdb-7_1",
+        feature = "fdb-7_0",
+        feature = "fdb-6_3",
+        feature = "fdb-6_2",
+        feature = "fdb-6_1"
+    ))]
+    futures::executor::block_on(test_compare_and_clear_async()).expect("failed to run");
 }
 
 async fn atomic_add(db: &Database, key: &[u8], value: i64) -> FdbResult<()> {
@@ -56,7 +68,7 @@ async fn test_atomic_async() -> FdbResult<()> {
     println!("check!");
     {
         let trx = db.create_trx()?;
-        let value = trx.get(KEY, false).await?.expect("value should exists");
+        let value = trx.read(KEY).await?.expect("value should exists");
         let v: i64 = byteorder::LE::read_i64(&value);
         if v != 0 {
             panic!("expected 0, found {}", v);
@@ -64,3 +76,101 @@ async fn test_atomic_async() -> FdbResult<()> {
     }
     Ok(())
 }
+
+async fn test_typed_atomic_async() -> FdbResult<()> {
+    const COUNTER: &[u8] = b"test-typed-atomic-counter";
+    const MAX: &[u8] = b"test-typed-atomic-max";
+    const N: u64 = 1000;
+
+    let db = common::database().await?;
+    let trx = db.create_trx()?;
+    trx.clear(COUNTER);
+    trx.clear(MAX);
+    trx.commit().await?;
+
+    try_join_all((0..N).map(|i| {
+        let db = &db;
+        async move {
+            let trx = db.create_trx()?;
+            trx.atomic_add_i64(COUNTER, 2);
+            trx.atomic_add_i64(COUNTER, -1);
+            trx.atomic_max_u64(MAX, i);
+            trx.commit().await?;
+            FdbResult::Ok(())
+        }
+    }))
+    .await?;
+
+    let trx = db.create_trx()?;
+    assert_eq!(counter::read_i64(&trx, COUNTER).await?, N as i64);
+    let max = trx.read(MAX).await?.expect("value should exists");
+    assert_eq!(&*max, &(N - 1).to_le_bytes());
+    Ok(())
+}
+
+#[cfg_api_versions(min = 610)]
+async fn consume_token(
+    trx: &RetryableTransaction,
+    tokens: &Subspace,
+    token_id: u64,
+) -> Result<bool, FdbBindingError> {
+    let key = tokens.pack(&token_id);
+    match trx.read(&key).await? {
+        Some(token) => {
+            trx.compare_and_clear(&key, &token);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[cfg_api_versions(min = 610)]
+async fn test_compare_and_clear_async() -> FdbResult<()> {
+    const KEY: &[u8] = b"test-compare-and-clear";
+
+    let db = common::database().await?;
+
+    // the comparison is byte-exact, including the length
+    let trx = db.create_trx()?;
+    trx.set(KEY, b"1");
+    trx.compare_and_clear(KEY, b"1\0");
+    assert_eq!(trx.read(KEY).await?.as_deref(), Some(&b"1"[..]));
+    trx.compare_and_clear(KEY, b"1");
+    assert!(trx.read(KEY).await?.is_none());
+
+    // reference counting with typed operands
+    trx.atomic_op(KEY, &2i64.to_le_bytes(), options::MutationType::Add);
+    trx.commit().await?;
+    for expected in [Some(1i64), None] {
+        let trx = db.create_trx()?;
+        trx.atomic_op(KEY, &(-1i64).to_le_bytes(), options::MutationType::Add);
+        trx.compare_and_clear_i64(KEY, 0);
+        trx.commit().await?;
+
+        let trx = db.create_trx()?;
+        let value = trx.read(KEY).await?;
+        assert_eq!(value.map(|v| byteorder::LE::read_i64(&v)), expected);
+    }
+
+    // concurrent consumers of idempotency tokens
+    let tokens = Subspace::from("test-compare-and-clear-tokens");
+    let trx = db.create_trx()?;
+    trx.clear_subspace_range(&tokens);
+    trx.set(&tokens.pack(&1u64), b"token");
+    trx.commit().await?;
+
+    let consumed = try_join_all((0..10).map(|_| {
+        db.run(|trx, _maybe_committed| {
+            let tokens = tokens.clone();
+            async move { consume_token(&trx, &tokens, 1).await }
+        })
+    }))
+    .await
+    .expect("could not consume token");
+    assert_eq!(consumed.iter().filter(|&&consumed| consumed).count(), 1);
+
+    let trx = db.create_trx()?;
+    assert!(trx.read(&tokens.pack(&1u64)).await?.is_none());
+
+    Ok(())
+}
@@ -0,0 +1,33 @@
+// Copyright 2023 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+mod common;
+
+#[cfg(feature = "fdb-7_3")]
+#[foundationdb::testing::test]
+async fn test_client_status(
+    db: foundationdb::Database,
+    _subspace: foundationdb::tuple::Subspace,
+) -> Result<(), foundationdb::FdbBindingError> {
+    // the client connects to the cluster on its first request
+    db.create_trx()?.get_read_version().await?;
+    let status = db.client_status().await?;
+    assert!(status.healthy(), "{:#}", status.raw);
+
+    // nothing listens on the coordinator of this cluster file
+    let cluster_file = std::env::temp_dir().join(format!(
+        "fdb-client-status-{}.cluster",
+        common::random_str(10)
+    ));
+    std::fs::write(&cluster_file, "bogus:bogus@127.0.0.1:1").expect("could not write cluster file");
+    let bogus = foundationdb::Database::from_path(&cluster_file.to_string_lossy())?;
+    let status = bogus.client_status().await?;
+    std::fs::remove_file(&cluster_file).expect("could not remove cluster file");
+    assert!(!status.healthy(), "{:#}", status.raw);
+
+    Ok(())
+}
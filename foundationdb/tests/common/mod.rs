@@ -3,6 +3,9 @@ use rand::distributions::Alphanumeric;
 use rand::thread_rng;
 use rand::Rng;
 
+#[allow(unused)]
+pub use foundationdb::macro_support::cluster;
+
 /// generate random string. Foundationdb watch only fires when value changed, so updating with same
 /// value twice will not fire watches. To make examples work over multiple run, we use random
 /// string as a value.
@@ -15,7 +18,15 @@ pub fn random_str(len: usize) -> String {
         .collect()
 }
 
+/// Opens the database of the test cluster if one was provisioned, see [`cluster`], or the one of
+/// the default cluster file.
 #[allow(unused)]
 pub async fn database() -> fdb::FdbResult<fdb::Database> {
-    fdb::Database::new_compat(None).await
+    fdb::macro_support::database().await
+}
+
+/// Boots the network for tests running in a single `#[test]` function.
+#[allow(unused)]
+pub fn boot() -> fdb::api::NetworkAutoStop {
+    fdb::macro_support::boot()
 }
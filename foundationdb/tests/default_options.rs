@@ -0,0 +1,153 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use foundationdb::options::{DatabaseOption, OptionError, TransactionOption};
+use foundationdb::*;
+use std::thread::sleep;
+use std::time::Duration;
+
+mod common;
+
+const TIMED_OUT: i32 = 1031;
+const NOT_COMMITTED: i32 = 1020;
+
+#[test]
+fn test_default_options() {
+    let _guard = common::boot();
+    futures::executor::block_on(test_default_timeout_async()).expect("failed to run");
+    futures::executor::block_on(test_default_retry_limit_async()).expect("failed to run");
+    futures::executor::block_on(test_typed_options_async()).expect("failed to run");
+}
+
+async fn test_default_timeout_async() -> FdbResult<()> {
+    const KEY: &[u8] = b"test_default_timeout";
+    let db = common::database().await?;
+    db.set_default_transaction_options(TransactionOptions {
+        timeout: Some(Duration::from_millis(1)),
+        ..TransactionOptions::default()
+    })?;
+
+    let trx = db.create_trx()?;
+    sleep(Duration::from_millis(50));
+    let err = trx.read(KEY).await.err().expect("should time out");
+    assert_eq!(err.code(), TIMED_OUT);
+
+    // a per transaction timeout wins
+    let trx = db.create_trx()?;
+    trx.set_option(TransactionOption::Timeout(60_000))?;
+    sleep(Duration::from_millis(50));
+    trx.read(KEY).await?;
+
+    // the unset timeout is kept
+    db.set_default_transaction_options(TransactionOptions::default())?;
+    let trx = db.create_trx()?;
+    sleep(Duration::from_millis(50));
+    let err = trx.read(KEY).await.err().expect("should time out");
+    assert_eq!(err.code(), TIMED_OUT);
+
+    // a zero timeout disables it
+    db.set_default_transaction_options(TransactionOptions {
+        timeout: Some(Duration::ZERO),
+        ..TransactionOptions::default()
+    })?;
+    let trx = db.create_trx()?;
+    sleep(Duration::from_millis(50));
+    trx.read(KEY).await?;
+
+    // so is one set directly on the database
+    db.set_option(DatabaseOption::TransactionTimeout(1))?;
+    db.set_default_transaction_options(TransactionOptions {
+        retry_limit: Some(3),
+        ..TransactionOptions::default()
+    })?;
+    let trx = db.create_trx()?;
+    sleep(Duration::from_millis(50));
+    let err = trx.read(KEY).await.err().expect("should time out");
+    assert_eq!(err.code(), TIMED_OUT);
+    db.set_option(DatabaseOption::TransactionTimeout(0))?;
+    db.set_option(DatabaseOption::TransactionRetryLimit(-1))?;
+
+    // other databases are not affected
+    db.set_default_transaction_options(TransactionOptions {
+        timeout: Some(Duration::from_millis(1)),
+        ..TransactionOptions::default()
+    })?;
+    let other = common::database().await?;
+    let trx = other.create_trx()?;
+    sleep(Duration::from_millis(50));
+    trx.read(KEY).await?;
+
+    Ok(())
+}
+
+async fn test_default_retry_limit_async() -> FdbResult<()> {
+    let db = common::database().await?;
+    db.set_default_transaction_options(TransactionOptions {
+        retry_limit: Some(1),
+        ..TransactionOptions::default()
+    })?;
+
+    let trx = db.create_trx()?;
+    let trx = trx.on_error(FdbError::from_code(NOT_COMMITTED)).await?;
+    match trx.on_error(FdbError::from_code(NOT_COMMITTED)).await {
+        Err(err) => assert_eq!(err.code(), NOT_COMMITTED),
+        Ok(_) => panic!("the retry limit should be reached"),
+    }
+
+    // a per transaction retry limit wins
+    let mut trx = db.create_trx()?;
+    trx.set_option(TransactionOption::RetryLimit(3))?;
+    for _ in 0..3 {
+        trx = trx.on_error(FdbError::from_code(NOT_COMMITTED)).await?;
+    }
+
+    db.set_option(DatabaseOption::TransactionRetryLimit(-1))?;
+    Ok(())
+}
+
+async fn test_typed_options_async() -> FdbResult<()> {
+    const KEY: &[u8] = b"test_typed_options";
+    let db = common::database().await?;
+
+    let trx = db.create_trx()?;
+    trx.set_timeout(Duration::from_millis(1))?;
+    sleep(Duration::from_millis(50));
+    let err = trx.read(KEY).await.err().expect("should time out");
+    assert_eq!(err.code(), TIMED_OUT);
+
+    // zero disables the timeout
+    let trx = db.create_trx()?;
+    trx.set_timeout(Duration::from_millis(1))?;
+    trx.set_timeout(Duration::ZERO)?;
+    sleep(Duration::from_millis(50));
+    trx.read(KEY).await?;
+
+    // -1 lifts the limit, lower values are rejected
+    let trx = db.create_trx()?;
+    trx.set_retry_limit(-1)?;
+    assert!(matches!(
+        trx.set_retry_limit(-2),
+        Err(OptionError::InvalidValue(_))
+    ));
+
+    let trx = db.create_trx()?;
+    trx.set_retry_limit(1)?;
+    trx.set_max_retry_delay(Duration::from_millis(10))?;
+    let trx = trx.on_error(FdbError::from_code(NOT_COMMITTED)).await?;
+    match trx.on_error(FdbError::from_code(NOT_COMMITTED)).await {
+        Err(err) => assert_eq!(err.code(), NOT_COMMITTED),
+        Ok(_) => panic!("the retry limit should be reached"),
+    }
+
+    let trx = db.create_trx()?;
+    trx.set_read_your_writes_disable()?;
+    trx.clear(KEY);
+    trx.set(KEY, b"unseen");
+    assert!(trx.read(KEY).await?.is_none());
+
+    Ok(())
+}
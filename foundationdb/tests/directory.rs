@@ -8,6 +8,7 @@
 use foundationdb::directory::DirectoryLayer;
 
 use foundationdb::directory::Directory;
+use foundationdb::directory::DirectoryError;
 
 use foundationdb::*;
 
@@ -16,7 +17,7 @@ mod common;
 #[test]
 // testing basic features of the Directory, everything is tracked using with the BindingTester.
 fn test_directory() {
-    let _guard = unsafe { foundationdb::boot() };
+    let _guard = common::boot();
     let db = futures::executor::block_on(common::database()).expect("cannot open fdb");
 
     eprintln!("clearing all keys");
@@ -40,6 +41,137 @@ fn test_directory() {
         vec![String::from("1"), String::from("2")],
     ))
     .expect("failed to run");
+
+    futures::executor::block_on(test_directory_handle(&db)).expect("failed to run");
+    futures::executor::block_on(test_canonical_layout(&db, &directory)).expect("failed to run");
+}
+
+// The keys of the default directory layer, as written by the other bindings
+const ROOT_NODE: &[u8] = b"\xfe\x01\xfe\x00";
+const VERSION_KEY: &[u8] = b"\xfe\x01\xfe\x00\x01version\x00";
+const VERSION_VALUE: &[u8] = b"\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+
+/// The key holding the prefix of the top-level directory `name`
+fn entry_key(name: &str) -> Vec<u8> {
+    let mut key = ROOT_NODE.to_vec();
+    key.extend_from_slice(b"\x14\x02");
+    key.extend_from_slice(name.as_bytes());
+    key.push(0);
+    key
+}
+
+/// The key holding the layer of the directory of `prefix`, which must not hold NILs
+fn layer_key(prefix: &[u8]) -> Vec<u8> {
+    let mut key = b"\xfe\x01".to_vec();
+    key.extend_from_slice(prefix);
+    key.extend_from_slice(b"\x00\x01layer\x00");
+    key
+}
+
+async fn test_canonical_layout(
+    db: &Database,
+    directory: &DirectoryLayer,
+) -> Result<(), DirectoryError> {
+    let path = vec![String::from("layout")];
+
+    eprintln!("checking the keys written for {:?}", &path);
+    let trx = db.create_trx()?;
+    let created = directory
+        .create(&trx, &path, None, Some(b"fixture"))
+        .await?;
+    trx.commit().await.expect("cannot commit");
+    let prefix = created.bytes()?.to_vec();
+    assert!(!prefix.contains(&0));
+
+    let trx = db.create_trx()?;
+    assert_eq!(trx.read(VERSION_KEY).await?.as_deref(), Some(VERSION_VALUE));
+    assert_eq!(
+        trx.read(&entry_key("layout")).await?.as_deref(),
+        Some(prefix.as_slice())
+    );
+    assert_eq!(
+        trx.read(&layer_key(&prefix)).await?.as_deref(),
+        Some(&b"fixture"[..])
+    );
+
+    eprintln!("opening a directory written as the other bindings do");
+    let foreign = vec![String::from("foreign")];
+    trx.set(&entry_key("foreign"), b"foreign-prefix");
+    trx.set(&layer_key(b"foreign-prefix"), b"fixture");
+    trx.commit().await.expect("cannot commit");
+
+    let trx = db.create_trx()?;
+    let opened = directory.open(&trx, &foreign, Some(b"fixture")).await?;
+    assert_eq!(opened.bytes()?, b"foreign-prefix");
+    assert_eq!(opened.get_layer(), b"fixture");
+    assert!(directory
+        .list(&trx, &[])
+        .await?
+        .contains(&String::from("foreign")));
+    assert!(matches!(
+        directory.open(&trx, &foreign, Some(b"other")).await,
+        Err(DirectoryError::IncompatibleLayer)
+    ));
+
+    Ok(())
+}
+
+async fn test_directory_handle(db: &Database) -> Result<(), FdbBindingError> {
+    let path = vec![String::from("handle"), String::from("before")];
+    let new_path = vec![String::from("handle"), String::from("after")];
+
+    eprintln!("creating {:?} in a retry loop", &path);
+    let handle = db.directory().create_or_open(&path).await?;
+    assert_eq!(handle.get_path(), path.as_slice());
+    let opened = db.directory().open(&path).await?;
+    assert_eq!(
+        handle.directory_unchecked().bytes()?,
+        opened.directory_unchecked().bytes()?
+    );
+
+    let trx = db.create_trx()?;
+    let directory = handle.validate(&trx).await?;
+    trx.set(&directory.pack(&"key")?, b"value");
+    trx.commit().await.expect("cannot commit");
+
+    eprintln!("moving {:?} to {:?}", &path, &new_path);
+    let moved = db.directory().move_to(&path, &new_path).await?;
+    assert_eq!(moved.get_path(), new_path.as_slice());
+    assert!(!db.directory().exists(&path).await?);
+
+    let trx = db.create_trx()?;
+    assert!(matches!(
+        handle.validate(&trx).await,
+        Err(DirectoryError::DirectoryMovedOrRemoved)
+    ));
+    let directory = moved.validate(&trx).await?;
+    assert_eq!(handle.directory_unchecked().bytes()?, directory.bytes()?);
+    assert_eq!(
+        trx.read(&directory.pack(&"key")?).await?.as_deref(),
+        Some(&b"value"[..])
+    );
+
+    eprintln!("creating another directory at {:?}", &path);
+    db.directory().create(&path).await?;
+    let trx = db.create_trx()?;
+    assert!(matches!(
+        handle.validate(&trx).await,
+        Err(DirectoryError::DirectoryMovedOrRemoved)
+    ));
+
+    eprintln!("removing {:?}", &new_path);
+    assert!(db.directory().remove(&new_path).await?);
+    let trx = db.create_trx()?;
+    assert!(matches!(
+        moved.validate(&trx).await,
+        Err(DirectoryError::DirectoryMovedOrRemoved)
+    ));
+    assert_eq!(
+        db.directory().list(&path[..1]).await?,
+        vec![String::from("before")]
+    );
+
+    Ok(())
 }
 
 async fn test_create_then_open_then_delete(
@@ -0,0 +1,42 @@
+//! Loading libfdb_c at runtime, the other tests run with the `dlopen` feature too.
+#![cfg(feature = "dlopen")]
+
+use foundationdb::api::{self, LoadError};
+use std::env;
+use std::path::PathBuf;
+
+mod common;
+
+#[cfg(target_os = "linux")]
+const LIBRARY: &str = "libfdb_c.so";
+#[cfg(target_os = "macos")]
+const LIBRARY: &str = "libfdb_c.dylib";
+#[cfg(target_os = "windows")]
+const LIBRARY: &str = "fdb_c.dll";
+
+#[test]
+fn test_load_library() {
+    match api::load_library("/nonexistent/libfdb_c") {
+        Err(LoadError::Library(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    // the directory given to the build script, or the default locations of the dynamic linker
+    let path = match env::var_os("FDB_CLIENT_LIB_PATH") {
+        Some(dir) => PathBuf::from(dir).join(LIBRARY),
+        None => PathBuf::from(LIBRARY),
+    };
+    api::load_library(&path).expect("failed to load libfdb_c");
+    match api::load_library(&path) {
+        Err(LoadError::AlreadyLoaded) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    let _guard = common::boot();
+    futures::executor::block_on(async {
+        let db = common::database().await?;
+        let trx = db.create_trx()?;
+        trx.get_read_version().await
+    })
+    .expect("failed to use the loaded library");
+}
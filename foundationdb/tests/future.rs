@@ -1,3 +1,4 @@
+use foundationdb::tuple::Subspace;
 use foundationdb::*;
 use futures::prelude::*;
 use std::pin::Pin;
@@ -30,23 +31,18 @@ where
     }
 }
 
-#[test]
-fn test_future_discard() {
-    let _guard = unsafe { foundationdb::boot() };
-    futures::executor::block_on(test_future_discard_async()).expect("failed to run");
-}
-
-async fn test_future_discard_async() -> FdbResult<()> {
+#[foundationdb::testing::test]
+async fn test_future_discard(db: Database, subspace: Subspace) -> FdbResult<()> {
     // dropping a future while it's in the pending state should not crash
-    let db = common::database().await?;
+    let key = subspace.pack(&"key");
     let mut hit_pending = false;
     for _i in 0..=1000 {
         let hit_pending_step = db
             .transact_boxed_local(
-                (),
-                |trx, ()| {
+                key.as_slice(),
+                |trx, key| {
                     AbortingFuture {
-                        inner: trx.get(b"key", false),
+                        inner: trx.read(key),
                         polled: false,
                     }
                     .boxed_local()
@@ -15,21 +15,30 @@ mod common;
 
 #[test]
 fn test_get() {
-    let _guard = unsafe { foundationdb::boot() };
+    let _guard = common::boot();
     futures::executor::block_on(test_set_get_async()).expect("failed to run");
     futures::executor::block_on(test_get_multi_async()).expect("failed to run");
+    futures::executor::block_on(test_get_key_async()).expect("failed to run");
+    futures::executor::block_on(test_get_cached_async()).expect("failed to run");
     futures::executor::block_on(test_set_conflict_async()).expect("failed to run");
     futures::executor::block_on(test_set_conflict_snapshot_async()).expect("failed to run");
+    futures::executor::block_on(test_add_conflict_keys_async()).expect("failed to run");
     futures::executor::block_on(test_transact_async()).expect("failed to run");
+    futures::executor::block_on(test_transact_retry_once_async()).expect("failed to run");
+    futures::executor::block_on(test_transact_with_policy_async()).expect("failed to run");
     futures::executor::block_on(test_transact_limit()).expect("failed to run");
     futures::executor::block_on(test_transact_timeout()).expect("failed to run");
     futures::executor::block_on(test_versionstamp_async()).expect("failed to run");
+    futures::executor::block_on(test_committed_version_async()).expect("failed to run");
+    #[cfg(feature = "fdb-7_3")]
+    futures::executor::block_on(test_total_cost_async()).expect("failed to run");
     futures::executor::block_on(test_read_version_async()).expect("failed to run");
     futures::executor::block_on(test_set_read_version_async()).expect("failed to run");
     futures::executor::block_on(test_get_addresses_for_key_async()).expect("failed to run");
     futures::executor::block_on(test_set_raw_option_async()).expect("failed to run");
     futures::executor::block_on(test_fails_to_set_unknown_raw_option()).expect("failed to run");
     #[cfg(any(
+        feature = "fdb-7_3",
         feature = "fdb-7_1",
         feature = "fdb-7_0",
         feature = "fdb-6_3",
@@ -47,14 +56,14 @@ async fn test_set_get_async() -> FdbResult<()> {
     trx.commit().await?;
 
     let trx = db.create_trx()?;
-    let value = trx.get(b"hello", false).await?.unwrap();
+    let value = trx.read(b"hello").await?.unwrap();
     assert_eq!(value.deref(), b"world");
 
     trx.clear(b"hello");
     trx.commit().await?;
 
     let trx = db.create_trx()?;
-    assert!(trx.get(b"hello", false).await?.is_none());
+    assert!(trx.read(b"hello").await?.is_none());
 
     Ok(())
 }
@@ -64,7 +73,83 @@ async fn test_get_multi_async() -> FdbResult<()> {
 
     let trx = db.create_trx()?;
     let keys: &[&[u8]] = &[b"hello", b"world", b"foo", b"bar"];
-    let _results = try_join_all(keys.iter().map(|k| trx.get(k, false))).await?;
+    let _results = try_join_all(keys.iter().map(|k| trx.read(k))).await?;
+
+    Ok(())
+}
+
+async fn test_get_key_async() -> FdbResult<()> {
+    let db = common::database().await?;
+    let k = |i: usize| format!("test-get-key-{}", i).into_bytes();
+
+    let trx = db.create_trx()?;
+    trx.clear_range(b"test-get-key-", b"test-get-key.");
+    for i in 0..5 {
+        trx.set(&k(i), b"");
+    }
+
+    let resolve = |selector: KeySelector<'static>| {
+        let trx = &trx;
+        async move { Ok::<_, FdbError>(trx.read_key(&selector).await?.to_vec()) }
+    };
+
+    assert_eq!(
+        resolve(KeySelector::first_greater_or_equal(k(0))).await?,
+        k(0)
+    );
+    assert_eq!(resolve(KeySelector::first_greater_than(k(0))).await?, k(1));
+    assert_eq!(resolve(KeySelector::last_less_or_equal(k(2))).await?, k(2));
+    assert_eq!(resolve(KeySelector::last_less_than(k(2))).await?, k(1));
+
+    // offset arithmetic
+    assert_eq!(
+        resolve(KeySelector::first_greater_or_equal(k(0)) + 2).await?,
+        k(2)
+    );
+    assert_eq!(
+        resolve(KeySelector::last_less_or_equal(k(4)) - 1).await?,
+        k(3)
+    );
+    assert_eq!(
+        resolve(KeySelector::first_greater_than(k(1)) - 1).await?,
+        k(1)
+    );
+
+    // the boundaries resolve outside of the keys
+    assert!(resolve(KeySelector::last_less_than(k(0))).await? < k(0));
+    assert!(resolve(KeySelector::last_less_or_equal(k(0)) - 1).await? < k(0));
+    assert!(resolve(KeySelector::first_greater_than(k(4))).await? > k(4));
+    assert!(resolve(KeySelector::first_greater_or_equal(k(0)) + 5).await? > k(4));
+
+    Ok(())
+}
+
+async fn test_get_cached_async() -> FdbResult<()> {
+    let db = common::database().await?;
+
+    let trx = db.create_trx()?;
+    trx.set(b"cached", b"value");
+    trx.clear(b"cached-missing");
+    trx.commit().await?;
+
+    let trx = db.create_trx()?;
+    assert_eq!(trx.get_cached(b"cached").await?, Some(b"value".to_vec()));
+    assert_eq!(trx.get_cached(b"cached").await?, Some(b"value".to_vec()));
+    assert_eq!(trx.get_cached(b"cached-missing").await?, None);
+    assert_eq!(trx.get_cached(b"cached-missing").await?, None);
+
+    // writes of the transaction are visible to later cached reads
+    trx.set(b"cached", b"updated");
+    assert_eq!(trx.get_cached(b"cached").await?, Some(b"updated".to_vec()));
+    trx.clear(b"cached");
+    assert_eq!(trx.get_cached(b"cached").await?, None);
+    trx.set(b"cached-missing", b"now");
+    assert_eq!(
+        trx.get_cached(b"cached-missing").await?,
+        Some(b"now".to_vec())
+    );
+    trx.clear_range(b"cached", b"cached\xff");
+    assert_eq!(trx.get_cached(b"cached-missing").await?, None);
 
     Ok(())
 }
@@ -77,7 +162,7 @@ async fn test_set_conflict_async() -> FdbResult<()> {
     let trx2 = db.create_trx()?;
 
     // try to read value to set conflict range
-    let _ = trx2.get(key, false).await?;
+    let _ = trx2.read(key).await?;
 
     // commit first transaction to create conflict
     trx1.set(key, common::random_str(10).as_bytes());
@@ -113,7 +198,7 @@ async fn test_set_conflict_snapshot_async() -> FdbResult<()> {
 
     // snapshot read does not set conflict range, so both transaction will be
     // committed.
-    let _ = trx2.get(key, true).await?;
+    let _ = trx2.snapshot().get(key).await?;
 
     // commit first transaction
     trx1.set(key, common::random_str(10).as_bytes());
@@ -127,6 +212,42 @@ async fn test_set_conflict_snapshot_async() -> FdbResult<()> {
     Ok(())
 }
 
+async fn test_add_conflict_keys_async() -> FdbResult<()> {
+    let db = common::database().await?;
+
+    // disjoint writes conflict once they add overlapping write conflict ranges
+    let trx1 = db.create_trx()?;
+    let trx2 = db.create_trx()?;
+    let _ = trx2.read(b"test_add_conflict_keys_a").await?;
+    trx2.set(b"test_add_conflict_keys_b", b"2");
+    trx1.set(b"test_add_conflict_keys_c", b"1");
+    trx1.add_write_conflict_key(b"test_add_conflict_keys_a")?;
+    trx1.commit().await?;
+    let err = trx2.commit().await.unwrap_err();
+    assert!(err.is_retryable_not_committed());
+
+    // a read conflict key restores the conflict a snapshot read does not add
+    let key = b"test_add_conflict_keys_snapshot";
+    let trx1 = db.create_trx()?;
+    let trx2 = db.create_trx()?;
+    let _ = trx2.snapshot().get(key).await?;
+    trx2.add_read_conflict_key(key)?;
+    trx2.set(b"test_add_conflict_keys_d", b"2");
+    trx1.set(key, common::random_str(10).as_bytes());
+    trx1.commit().await?;
+    let err = trx2.commit().await.unwrap_err();
+    assert!(err.is_retryable_not_committed());
+
+    // inverted ranges are rejected
+    let trx = db.create_trx()?;
+    let err = trx
+        .add_conflict_range(b"b", b"a", options::ConflictRangeType::Read)
+        .unwrap_err();
+    assert_eq!(err.code(), 2005);
+
+    Ok(())
+}
+
 // Makes the key dirty. It will abort transactions which performs non-snapshot read on the `key`.
 async fn make_dirty(db: &Database, key: &[u8]) -> FdbResult<()> {
     let trx = db.create_trx()?;
@@ -151,7 +272,7 @@ async fn test_transact_async() -> FdbResult<()> {
             .expect("failed to set retry limit");
 
         // update conflict range
-        trx.get(KEY, false).await?;
+        trx.read(KEY).await?;
 
         // make current transaction invalid by making conflict
         make_dirty(db, KEY).await?;
@@ -180,6 +301,105 @@ async fn test_transact_async() -> FdbResult<()> {
     Ok(())
 }
 
+async fn test_transact_retry_once_async() -> FdbResult<()> {
+    const KEY: &[u8] = b"test_transact_retry_once";
+    const COUNTER: &[u8] = b"test_transact_retry_once_counter";
+    async fn async_body(
+        db: &Database,
+        trx: &Transaction,
+        try_count0: Arc<AtomicUsize>,
+    ) -> FdbResult<usize> {
+        let try_count = try_count0.fetch_add(1, Ordering::SeqCst) + 1;
+
+        // update conflict range
+        trx.read(KEY).await?;
+
+        // only the first try conflicts
+        if try_count == 1 {
+            make_dirty(db, KEY).await?;
+        }
+
+        trx.set(KEY, common::random_str(10).as_bytes());
+        trx.atomic_add_i64(COUNTER, 1);
+        Ok(try_count)
+    }
+
+    let db = common::database().await?;
+    let trx = db.create_trx()?;
+    trx.clear(COUNTER);
+    trx.commit().await?;
+
+    let try_count = Arc::new(AtomicUsize::new(0));
+    let committed_try = db
+        .transact_boxed(
+            &db,
+            |trx, db| async_body(db, trx, try_count.clone()).boxed(),
+            TransactOption::default(),
+        )
+        .await?;
+
+    // the closure ran twice, but only the second run was committed
+    assert_eq!(committed_try, 2);
+    assert_eq!(try_count.load(Ordering::SeqCst), 2);
+    let trx = db.create_trx()?;
+    assert_eq!(counter::read_i64(&trx, COUNTER).await?, 1);
+
+    Ok(())
+}
+
+async fn test_transact_with_policy_async() -> FdbResult<()> {
+    const KEY: &[u8] = b"test_transact_with_policy";
+    const NOT_COMMITTED: i32 = 1020;
+    async fn async_body(db: &Database, trx: &Transaction, conflicts: usize) -> FdbResult<()> {
+        trx.read(KEY).await?;
+        if conflicts > 0 {
+            make_dirty(db, KEY).await?;
+        }
+        trx.set(KEY, common::random_str(10).as_bytes());
+        Ok(())
+    }
+
+    let db = common::database().await?;
+    let backoffs = Arc::new(AtomicUsize::new(0));
+    let counted = backoffs.clone();
+    let policy = RetryPolicy::default().backoff(move |attempt, err: &FdbError| {
+        counted.fetch_add(1, Ordering::SeqCst);
+        assert_eq!(attempt, 1);
+        assert_eq!(err.code(), NOT_COMMITTED);
+        ready(())
+    });
+
+    // the first attempt conflicts, the backoff runs once before the second
+    let tries = Arc::new(AtomicUsize::new(0));
+    db.transact_with_policy(
+        &db,
+        |trx, db| {
+            let conflicts = 1usize.saturating_sub(tries.fetch_add(1, Ordering::SeqCst));
+            async_body(db, trx, conflicts).boxed()
+        },
+        policy,
+    )
+    .await
+    .map_err(|failure| failure.error)?;
+    assert_eq!(tries.load(Ordering::SeqCst), 2);
+    assert_eq!(backoffs.load(Ordering::SeqCst), 1);
+
+    // every attempt conflicts, the last error is reported
+    let failure = db
+        .transact_with_policy(
+            &db,
+            |trx, db| async_body(db, trx, 1).boxed(),
+            RetryPolicy::default().max_attempts(3),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(failure.error.code(), NOT_COMMITTED);
+    assert_eq!(failure.attempts, 3);
+    assert!(!failure.maybe_committed);
+
+    Ok(())
+}
+
 async fn test_transact_limit() -> FdbResult<()> {
     const KEY: &[u8] = b"test_transact_limit";
     async fn async_body(
@@ -191,7 +411,7 @@ async fn test_transact_limit() -> FdbResult<()> {
         try_count0.fetch_add(1, Ordering::SeqCst);
 
         // update conflict range
-        trx.get(KEY, false).await?;
+        trx.read(KEY).await?;
 
         // make current transaction invalid by making conflict
         make_dirty(db, KEY).await?;
@@ -232,7 +452,7 @@ async fn test_transact_timeout() -> FdbResult<()> {
         try_count0.fetch_add(1, Ordering::SeqCst);
 
         // update conflict range
-        trx.get(KEY, false).await?;
+        trx.read(KEY).await?;
 
         // make current transaction invalid by making conflict
         make_dirty(db, KEY).await?;
@@ -273,6 +493,72 @@ async fn test_versionstamp_async() -> FdbResult<()> {
     Ok(())
 }
 
+async fn test_committed_version_async() -> FdbResult<()> {
+    const KEY: &[u8] = b"test_committed_version";
+    let db = common::database().await?;
+
+    let mut versions = Vec::new();
+    for _ in 0..2 {
+        let trx = db.create_trx()?;
+        trx.set(KEY, common::random_str(10).as_bytes());
+        let f_versionstamp = trx.get_versionstamp();
+        let committed = trx.commit().await?;
+        let version = committed.committed_version()?;
+        let versionstamp = f_versionstamp.await?;
+
+        // the versionstamp starts with the big-endian commit version
+        assert_eq!(versionstamp.len(), 10);
+        assert_eq!(&versionstamp[..8], &version.to_be_bytes());
+        versions.push(version);
+    }
+    assert!(versions[0] < versions[1]);
+
+    // a transaction reading at the committed version sees the write
+    let trx = db.create_trx()?;
+    trx.set_read_version(versions[1]);
+    assert_eq!(trx.get_read_version().await?, versions[1]);
+    assert!(trx.read(KEY).await?.is_some());
+
+    Ok(())
+}
+
+#[cfg_api_versions(min = 730)]
+async fn test_total_cost_async() -> FdbResult<()> {
+    use futures::TryStreamExt;
+
+    const PREFIX: &[u8] = b"test_total_cost/";
+    let db = common::database().await?;
+
+    let key = |i: usize| [PREFIX, format!("{:04}", i).as_bytes()].concat();
+    let trx = db.create_trx()?;
+    for i in 0..1000 {
+        trx.set(&key(i), common::random_str(100).as_bytes());
+    }
+    let committed = trx.commit().await?;
+    let write_cost = match committed.get_total_cost().await {
+        Ok(cost) => cost,
+        // the cluster is reached through a client library older than 7.3
+        Err(err) if err.is_unsupported_operation() => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    assert!(write_cost > 0);
+
+    let trx = db.create_trx()?;
+    trx.read(&key(0)).await?;
+    let point_cost = trx.get_total_cost().await?;
+
+    let trx = db.create_trx()?;
+    let range: Vec<_> = trx
+        .get_ranges_keyvalues(RangeOption::from((key(0), key(1000))), false)
+        .try_collect()
+        .await?;
+    assert_eq!(range.len(), 1000);
+    let range_cost = trx.get_total_cost().await?;
+    assert!(range_cost > point_cost);
+
+    Ok(())
+}
+
 async fn test_read_version_async() -> FdbResult<()> {
     let db = common::database().await?;
 
@@ -288,7 +574,7 @@ async fn test_set_read_version_async() -> FdbResult<()> {
 
     let trx = db.create_trx()?;
     trx.set_read_version(0);
-    assert!(trx.get(KEY, false).await.is_err());
+    assert!(trx.read(KEY).await.is_err());
 
     Ok(())
 }
@@ -354,7 +640,7 @@ async fn test_set_raw_option_async() -> FdbResult<()> {
             .expect("failed to set retry limit");
 
         // update conflict range
-        trx.get(KEY, false).await?;
+        trx.read(KEY).await?;
 
         // make current transaction invalid by making conflict
         make_dirty(db, KEY).await?;
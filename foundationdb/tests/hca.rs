@@ -5,7 +5,10 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use foundationdb::tuple::{hca::HighContentionAllocator, Subspace};
+use foundationdb::tuple::{
+    hca::{HcaError, HighContentionAllocator},
+    Subspace,
+};
 use foundationdb::{FdbResult, TransactOption};
 use futures::prelude::*;
 use std::collections::HashSet;
@@ -15,10 +18,11 @@ mod common;
 
 #[test]
 fn test_hca_many_sequential_allocations() {
-    let _guard = unsafe { foundationdb::boot() };
+    let _guard = common::boot();
     futures::executor::block_on(test_hca_many_sequential_allocations_async())
         .expect("failed to run");
     futures::executor::block_on(test_hca_concurrent_allocations_async()).expect("failed to run");
+    futures::executor::block_on(test_hca_concurrent_batches_async()).expect("failed to run");
 }
 
 async fn test_hca_many_sequential_allocations_async() -> FdbResult<()> {
@@ -86,6 +90,47 @@ async fn test_hca_concurrent_allocations_async() -> FdbResult<()> {
     Ok(())
 }
 
+async fn test_hca_concurrent_batches_async() -> FdbResult<()> {
+    const N: usize = 100;
+    const M: usize = 10;
+    const KEY: &[u8] = b"test-hca-allocate-batches";
+
+    let db = common::database().await?;
+
+    {
+        let tx = db.create_trx()?;
+        tx.clear_subspace_range(&Subspace::from_bytes(KEY));
+        tx.commit().await?;
+    }
+
+    let hca = HighContentionAllocator::new(Subspace::from_bytes(KEY));
+
+    // each transaction allocates several values, all of them retried on conflicts
+    let batches: Vec<Vec<i64>> = future::try_join_all((0..N).map(|_| {
+        db.transact_boxed(
+            &hca,
+            move |tx, hca| {
+                async move {
+                    let mut batch = Vec::with_capacity(M);
+                    for _ in 0..M {
+                        batch.push(hca.allocate(tx).await?);
+                    }
+                    Ok::<_, HcaError>(batch)
+                }
+                .boxed()
+            },
+            TransactOption::default(),
+        )
+    }))
+    .await
+    .unwrap();
+    let all_ints: Vec<i64> = batches.into_iter().flatten().collect();
+    assert_eq!(all_ints.len(), N * M);
+    check_hca_result_uniqueness(&all_ints);
+
+    Ok(())
+}
+
 fn check_hca_result_uniqueness(results: &[i64]) {
     let result_set: HashSet<i64> = HashSet::from_iter(results.to_owned());
 
@@ -0,0 +1,52 @@
+// Copyright 2018 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use foundationdb::tuple::Subspace;
+use foundationdb::*;
+use std::net::SocketAddr;
+
+mod common;
+
+#[foundationdb::testing::test]
+async fn test_get_addresses_for_key(db: Database, subspace: Subspace) -> FdbResult<()> {
+    let key = subspace.pack(&"test-get-addresses-for-key");
+    let trx = db.create_trx()?;
+    trx.set(&key, b"value");
+    trx.commit().await?;
+
+    let trx = db.create_trx()?;
+    let addresses = locality::get_addresses_for_key(&trx, &key).await?;
+    assert!(!addresses.is_empty());
+    for address in addresses {
+        let address = address.trim_end_matches(":tls");
+        address
+            .parse::<SocketAddr>()
+            .unwrap_or_else(|_| panic!("{:?} to be a host:port pair", address));
+    }
+
+    Ok(())
+}
+
+#[foundationdb::testing::test]
+async fn test_get_boundary_keys(db: Database, _subspace: Subspace) -> FdbResult<()> {
+    // the first shard starts at the first key
+    let boundaries = locality::get_boundary_keys(&db, b"", b"\xff").await?;
+    assert_eq!(boundaries.first().map(Vec::as_slice), Some(&b""[..]));
+    assert!(boundaries.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(boundaries.iter().all(|key| key.as_slice() < b"\xff"));
+
+    // the boundaries of a range are the ones starting in it
+    if let Some(middle) = boundaries.get(boundaries.len() / 2) {
+        let after = locality::get_boundary_keys(&db, middle, b"\xff").await?;
+        assert_eq!(after, boundaries[boundaries.len() / 2..]);
+    }
+    assert!(locality::get_boundary_keys(&db, b"b", b"a")
+        .await?
+        .is_empty());
+
+    Ok(())
+}
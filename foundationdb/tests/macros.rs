@@ -0,0 +1,41 @@
+// Copyright 2018 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use foundationdb::tuple::Subspace;
+use foundationdb::{Database, FdbResult, RangeOption};
+
+#[foundationdb::testing::test]
+async fn test_isolated_subspace(db: Database, subspace: Subspace) -> FdbResult<()> {
+    // the subspace is reserved for this test, and starts empty
+    let trx = db.create_trx()?;
+    let values = trx.read_range(&RangeOption::from(&subspace), 1).await?;
+    assert!(values.is_empty());
+
+    trx.set(&subspace.pack(&"key"), b"value");
+    trx.commit().await?;
+
+    let trx = db.create_trx()?;
+    let value = trx.read(&subspace.pack(&"key")).await?;
+    assert_eq!(value.as_deref(), Some(&b"value"[..]));
+    Ok(())
+}
+
+#[foundationdb::testing::test(tokio)]
+async fn test_tokio_executor(db: Database, subspace: Subspace) {
+    let handle = tokio::runtime::Handle::current();
+    // the test body can spawn on the runtime it runs on
+    let key = subspace.pack(&"spawned");
+    let written = handle
+        .spawn(async move {
+            let trx = db.create_trx().expect("failed to create a transaction");
+            trx.set(&key, b"");
+            trx.commit().await.is_ok()
+        })
+        .await
+        .expect("the task panicked");
+    assert!(written);
+}
@@ -0,0 +1,219 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Runs the same scenarios against the mock database and a real cluster, to keep the mock honest.
+
+use foundationdb::directory::{Directory, DirectoryLayer};
+use foundationdb::mock::MockDatabase;
+use foundationdb::options::{MutationType, StreamingMode};
+use foundationdb::tuple::Subspace;
+use foundationdb::*;
+
+mod common;
+
+const NOT_COMMITTED: i32 = 1020;
+
+macro_rules! conformance {
+    ($name:ident, $database:ty) => {
+        async fn $name(db: &$database, subspace: &Subspace) -> Result<(), FdbBindingError> {
+            let key = subspace.pack(&"key");
+            let counter = subspace.pack(&"counter");
+            let range = subspace.subspace(&"range");
+
+            // writes are visible once committed, reads see the writes of their transaction
+            db.run(|trx, _maybe_committed| {
+                let (key, range) = (key.clone(), range.clone());
+                async move {
+                    trx.set(&key, b"value");
+                    for i in 0..5i64 {
+                        trx.set(&range.pack(&i), &i.to_le_bytes());
+                    }
+                    trx.clear(&range.pack(&2i64));
+                    assert_eq!(trx.read(&range.pack(&2i64)).await?.as_deref(), None);
+                    Ok(())
+                }
+            })
+            .await?;
+            let value = db
+                .run(|trx, _maybe_committed| {
+                    let key = key.clone();
+                    async move { Ok(trx.read(&key).await?.map(|value| value.to_vec())) }
+                })
+                .await?;
+            assert_eq!(value.as_deref(), Some(&b"value"[..]));
+
+            // ranges honor their limit and direction
+            let keys = db
+                .run(|trx, _maybe_committed| {
+                    let range = range.clone();
+                    async move {
+                        let forward = trx
+                            .read_range(
+                                &RangeOption {
+                                    mode: StreamingMode::WantAll,
+                                    ..RangeOption::from(&range)
+                                },
+                                1,
+                            )
+                            .await?;
+                        let backward = trx
+                            .read_range(
+                                &RangeOption {
+                                    mode: StreamingMode::WantAll,
+                                    limit: Some(2),
+                                    reverse: true,
+                                    ..RangeOption::from(&range)
+                                },
+                                1,
+                            )
+                            .await?;
+                        let unpack = |key: &[u8]| range.unpack::<i64>(key).expect("packed key");
+                        Ok((
+                            forward
+                                .iter()
+                                .map(|kv| unpack(kv.key()))
+                                .collect::<Vec<_>>(),
+                            backward
+                                .iter()
+                                .map(|kv| unpack(kv.key()))
+                                .collect::<Vec<_>>(),
+                        ))
+                    }
+                })
+                .await?;
+            assert_eq!(keys, (vec![0, 1, 3, 4], vec![4, 3]));
+
+            // atomic operations are applied at commit
+            for _ in 0..5 {
+                db.run(|trx, _maybe_committed| {
+                    let counter = counter.clone();
+                    async move {
+                        trx.atomic_op(&counter, &2i64.to_le_bytes(), MutationType::Add);
+                        Ok(())
+                    }
+                })
+                .await?;
+            }
+            let count = db
+                .run(|trx, _maybe_committed| {
+                    let counter = counter.clone();
+                    async move {
+                        let value = trx.read(&counter).await?.expect("counter");
+                        Ok(i64::from_le_bytes(value[..8].try_into().expect("8 bytes")))
+                    }
+                })
+                .await?;
+            assert_eq!(count, 10);
+
+            // a key read then written by a concurrent transaction conflicts
+            let trx1 = db.create_trx()?;
+            let trx2 = db.create_trx()?;
+            trx1.read(&key).await?;
+            trx1.set(&counter, b"trx1");
+            trx2.snapshot().get(&counter).await?;
+            trx2.set(&key, b"trx2");
+            trx2.commit().await.map_err(FdbError::from)?;
+            let err = trx1
+                .commit()
+                .await
+                .map(drop)
+                .map_err(FdbError::from)
+                .expect_err("trx1 read a key written by trx2");
+            assert_eq!(err.code(), NOT_COMMITTED);
+
+            // snapshot reads do not
+            let trx1 = db.create_trx()?;
+            let trx2 = db.create_trx()?;
+            trx1.snapshot().get(&key).await?;
+            trx1.set(&counter, b"trx1");
+            trx2.set(&key, b"trx2");
+            trx2.commit().await.map_err(FdbError::from)?;
+            trx1.commit().await.map_err(FdbError::from)?;
+
+            // the directory layer runs on any MutateTransaction
+            let directory = DirectoryLayer::new(
+                subspace.subspace(&"nodes"),
+                subspace.subspace(&"content"),
+                false,
+            );
+            let path = |names: &[&str]| {
+                names
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect::<Vec<_>>()
+            };
+            let trx = db.create_trx()?;
+            let app = directory
+                .create_or_open(&trx, &path(&["app"]), None, None)
+                .await
+                .expect("failed to create app");
+            for name in ["users", "orders"] {
+                directory
+                    .create_or_open(&trx, &path(&["app", name]), None, None)
+                    .await
+                    .expect("failed to create subdirectory");
+            }
+            trx.commit().await.map_err(FdbError::from)?;
+
+            let trx = db.create_trx()?;
+            let opened = directory
+                .open(&trx, &path(&["app"]), None)
+                .await
+                .expect("failed to open app");
+            assert_eq!(opened.bytes().ok(), app.bytes().ok());
+            assert_eq!(
+                directory
+                    .list(&trx, &path(&["app"]))
+                    .await
+                    .expect("failed to list"),
+                path(&["orders", "users"])
+            );
+            directory
+                .move_to(&trx, &path(&["app", "orders"]), &path(&["archive"]))
+                .await
+                .expect("failed to move");
+            assert!(directory
+                .remove(&trx, &path(&["app", "users"]))
+                .await
+                .expect("failed to remove"));
+            trx.commit().await.map_err(FdbError::from)?;
+
+            let trx = db.create_trx()?;
+            assert_eq!(
+                directory
+                    .list(&trx, &path(&["app"]))
+                    .await
+                    .expect("failed to list"),
+                Vec::<String>::new()
+            );
+            assert!(directory
+                .exists(&trx, &path(&["archive"]))
+                .await
+                .expect("failed to check"));
+
+            Ok(())
+        }
+    };
+}
+
+conformance!(mock_conformance, MockDatabase);
+conformance!(database_conformance, Database);
+
+#[test]
+fn test_mock_conformance() {
+    let subspace = Subspace::from("mock_conformance");
+    futures::executor::block_on(mock_conformance(&MockDatabase::new(), &subspace))
+        .expect("failed to run");
+}
+
+#[foundationdb::testing::test]
+async fn test_database_conformance(
+    db: Database,
+    subspace: Subspace,
+) -> Result<(), FdbBindingError> {
+    database_conformance(&db, &subspace).await
+}
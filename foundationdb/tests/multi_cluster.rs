@@ -0,0 +1,108 @@
+//! Copies a subspace between two clusters, from the same process.
+//!
+//! The second cluster is the one of `FDB_SECOND_CLUSTER_FILE`, or one started for this test when
+//! `FDB_BINARY_PATH` is set, see [`common::cluster`]. The test is skipped otherwise.
+
+use foundationdb::tuple::Subspace;
+use foundationdb::{Database, FdbBindingError, RangeOption};
+use futures::TryStreamExt;
+use std::env;
+use std::path::Path;
+
+mod common;
+
+const SECOND_CLUSTER_FILE_VAR: &str = "FDB_SECOND_CLUSTER_FILE";
+const KEY_COUNT: usize = 1000;
+const BATCH_SIZE: usize = 100;
+
+#[test]
+fn test_copy_between_clusters() {
+    // dropped after the network
+    let (_second_cluster, second_cluster_file) = match (
+        env::var(SECOND_CLUSTER_FILE_VAR),
+        env::var_os("FDB_BINARY_PATH"),
+    ) {
+        (Ok(cluster_file), _) => (None, cluster_file),
+        (Err(_), Some(bin_dir)) => {
+            let cluster = common::cluster::TestCluster::start(Path::new(&bin_dir));
+            let cluster_file = cluster.cluster_file().to_string_lossy().into_owned();
+            (Some(cluster), cluster_file)
+        }
+        (Err(_), None) => {
+            eprintln!(
+                "skipped: set {} or FDB_BINARY_PATH to provide a second cluster",
+                SECOND_CLUSTER_FILE_VAR
+            );
+            return;
+        }
+    };
+    let _guard = common::boot();
+    futures::executor::block_on(copy_between_clusters(&second_cluster_file))
+        .expect("failed to copy");
+}
+
+async fn copy_between_clusters(second_cluster_file: &str) -> Result<(), FdbBindingError> {
+    let source = common::database().await?;
+    let destination = Database::new_compat(Some(second_cluster_file)).await?;
+    assert_eq!(
+        source.cluster_file_path(),
+        common::cluster::cluster_file().as_deref()
+    );
+    assert_eq!(destination.cluster_file_path(), Some(second_cluster_file));
+
+    let subspace = Subspace::from(("multi_cluster", common::random_str(8)));
+    let subspace = &subspace;
+    for batch in (0..KEY_COUNT).collect::<Vec<_>>().chunks(BATCH_SIZE) {
+        source
+            .run(|trx, _| async move {
+                for i in batch {
+                    trx.set(&subspace.pack(i), &subspace.pack(&(i * 2)));
+                }
+                Ok(())
+            })
+            .await?;
+    }
+
+    // stream the source range and write each batch read to the destination
+    let trx = source.create_trx()?;
+    let mut batches = trx.snapshot().get_ranges(RangeOption::from(subspace));
+    while let Some(values) = batches.try_next().await? {
+        let values = values
+            .iter()
+            .map(|value| (value.key().to_vec(), value.value().to_vec()))
+            .collect::<Vec<_>>();
+        let values = &values;
+        destination
+            .run(|trx, _| async move {
+                for (key, value) in values {
+                    trx.set(key, value);
+                }
+                Ok(())
+            })
+            .await?;
+    }
+    drop(batches);
+    drop(trx);
+
+    let copied: Vec<(usize, usize)> = destination
+        .run(|trx, _| {
+            let subspace = &subspace;
+            async move {
+                trx.scan_typed(subspace, RangeOption::default(), false)
+                    .await
+            }
+        })
+        .await?;
+    assert_eq!(copied.len(), KEY_COUNT);
+    assert!(copied.iter().enumerate().all(|(i, kv)| *kv == (i, i * 2)));
+
+    for database in [&source, &destination] {
+        database
+            .run(|trx, _| async move {
+                trx.clear_subspace_range(subspace);
+                Ok(())
+            })
+            .await?;
+    }
+    Ok(())
+}
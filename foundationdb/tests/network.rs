@@ -0,0 +1,98 @@
+use foundationdb as fdb;
+use foundationdb::api::{self, BootError, NetworkPermanentlyStopped, WouldDeadlock};
+use futures::task::{waker, ArcWake};
+use std::future::Future;
+use std::sync::{mpsc, Arc, Mutex};
+use std::task::Context;
+use std::thread;
+use std::time::Duration;
+
+mod common;
+
+#[test]
+fn test_shared_network() {
+    // dropped last, once the network is stopped
+    let _cluster = common::cluster::shared();
+
+    // like tests running one after another, each booting then dropping its guard
+    for _ in 0..100 {
+        let guard = unsafe { fdb::boot() };
+        futures::executor::block_on(async {
+            let db = common::database()
+                .await
+                .expect("failed to open the database");
+            let trx = db.create_trx().expect("failed to create a transaction");
+            trx.get_read_version()
+                .await
+                .expect("failed to get a read version");
+        });
+        drop(guard);
+    }
+
+    let guards: Vec<_> = (0..8)
+        .map(|_| thread::spawn(|| unsafe { fdb::boot() }))
+        .map(|handle| handle.join().expect("failed to boot"))
+        .collect();
+    drop(guards);
+
+    futures::executor::block_on(wake_on_network_thread());
+
+    // stops the network even if guards are still alive
+    let guard = unsafe { fdb::boot() };
+    api::shutdown_forever();
+    assert!(matches!(
+        unsafe { fdb::try_boot() },
+        Err(BootError::Stopped(NetworkPermanentlyStopped))
+    ));
+    drop(guard);
+    api::shutdown_forever();
+    assert!(matches!(
+        unsafe { fdb::try_boot() },
+        Err(BootError::Stopped(NetworkPermanentlyStopped))
+    ));
+}
+
+/// A waker reporting what blocking would do on the thread it is woken on
+struct Offender(Mutex<mpsc::Sender<(bool, Result<(), WouldDeadlock>)>>);
+
+impl ArcWake for Offender {
+    fn wake_by_ref(offender: &Arc<Self>) {
+        let report = (api::is_network_thread(), api::check_not_network_thread());
+        let _ = offender.0.lock().unwrap().send(report);
+    }
+}
+
+async fn wake_on_network_thread() {
+    let db = common::database()
+        .await
+        .expect("failed to open the database");
+    let key = b"test-network-offender";
+    let trx = db.create_trx().expect("failed to create a transaction");
+    trx.set(key, b"0");
+    trx.commit().await.expect("failed to set the key");
+
+    let trx = db.create_trx().expect("failed to create a transaction");
+    let watch = trx.watch(key);
+    trx.commit().await.expect("failed to commit the watch");
+
+    // the watch fires from the network thread, where the offender tries to block
+    let (sender, receiver) = mpsc::channel();
+    let offender = waker(Arc::new(Offender(Mutex::new(sender))));
+    let mut watch = Box::pin(watch);
+    assert!(watch
+        .as_mut()
+        .poll(&mut Context::from_waker(&offender))
+        .is_pending());
+
+    let trx = db.create_trx().expect("failed to create a transaction");
+    trx.set(key, b"1");
+    trx.commit().await.expect("failed to change the key");
+
+    // rather than hanging if the watch never fires
+    let (on_network_thread, result) = receiver
+        .recv_timeout(Duration::from_secs(30))
+        .expect("the watch did not fire");
+    assert!(on_network_thread);
+    assert_eq!(result, Err(WouldDeadlock));
+    assert_eq!(api::check_not_network_thread(), Ok(()));
+}
@@ -5,9 +5,10 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-#[cfg_api_versions(min = 710)]
+#[cfg_api_versions(min = 700)]
 use crate::tuple::Subspace;
 
+use foundationdb::future::RangeBatch;
 use foundationdb::*;
 use foundationdb_macros::cfg_api_versions;
 use futures::future;
@@ -19,19 +20,27 @@ mod common;
 
 #[test]
 fn test_range() {
-    let _guard = unsafe { foundationdb::boot() };
+    let _guard = common::boot();
     futures::executor::block_on(test_get_range_async()).expect("failed to run");
     futures::executor::block_on(test_range_option_async()).expect("failed to run");
     futures::executor::block_on(test_get_ranges_async()).expect("failed to run");
-    #[cfg(any(feature = "fdb-6_3", feature = "fdb-7_0", feature = "fdb-7_1"))]
+    futures::executor::block_on(test_get_ranges_batched_async()).expect("failed to run");
+    futures::executor::block_on(test_get_ranges_keyvalues_async()).expect("failed to run");
+    #[cfg(any(
+        feature = "fdb-6_3",
+        feature = "fdb-7_0",
+        feature = "fdb-7_1",
+        feature = "fdb-7_3"
+    ))]
     {
         futures::executor::block_on(test_get_estimate_range()).expect("failed to run");
     }
-    #[cfg(any(feature = "fdb-7_0", feature = "fdb-7_1"))]
+    #[cfg(any(feature = "fdb-7_0", feature = "fdb-7_1", feature = "fdb-7_3"))]
     {
         futures::executor::block_on(test_get_range_split_points()).expect("failed to run");
+        futures::executor::block_on(test_sample_subspace_sizes()).expect("failed to run");
     }
-    #[cfg(feature = "fdb-7_1")]
+    #[cfg(any(feature = "fdb-7_1", feature = "fdb-7_3"))]
     {
         futures::executor::block_on(test_mapped_value()).expect("failed to run");
         futures::executor::block_on(test_mapped_values()).expect("failed to run");
@@ -63,7 +72,7 @@ async fn test_get_range_async() -> FdbResult<()> {
         let end = KeySelector::first_greater_than(Cow::Borrowed(key_end.as_bytes()));
         let opt = RangeOption::from((begin, end));
 
-        let range = trx.get_range(&opt, 1, false).await?;
+        let range = trx.read_range(&opt, 1).await?;
         assert!(range.len() > 0);
         assert!(range.more());
         let len = range.len();
@@ -82,7 +91,7 @@ async fn test_get_range_async() -> FdbResult<()> {
         );
 
         let owned_asc = trx
-            .get_range(&opt, 1, false)
+            .read_range(&opt, 1)
             .await?
             .into_iter()
             .collect::<Vec<_>>();
@@ -118,7 +127,7 @@ async fn test_get_ranges_async() -> FdbResult<()> {
         let opt = RangeOption::from((begin, end));
 
         let count = trx
-            .get_ranges(opt, false)
+            .read_ranges(opt)
             .try_fold(0usize, |count, kvs| future::ok(count + kvs.as_ref().len()))
             .await?;
 
@@ -129,6 +138,130 @@ async fn test_get_ranges_async() -> FdbResult<()> {
     Ok(())
 }
 
+async fn test_get_ranges_batched_async() -> FdbResult<()> {
+    const N: usize = 1000;
+    const TARGET_BYTES: usize = 4096;
+
+    let db = common::database().await?;
+    let trx = db.create_trx()?;
+    let key_begin = "test-ranges-batched-";
+    let key_end = "test-ranges-batched.";
+
+    trx.clear_range(key_begin.as_bytes(), key_end.as_bytes());
+    for i in 0..N {
+        let key = format!("{}-{:04}", key_begin, i);
+        trx.set(key.as_bytes(), common::random_str(100).as_bytes());
+    }
+
+    let opt = RangeOption {
+        target_bytes: TARGET_BYTES,
+        ..RangeOption::from((key_begin.as_bytes(), key_end.as_bytes()))
+    };
+    let batches: Vec<RangeBatch> = trx
+        .get_ranges_batched(opt.clone(), false)
+        .try_collect()
+        .await?;
+    assert!(batches.len() > 1, "the range was read in a single batch");
+
+    let mut batched_keys = Vec::new();
+    for (index, batch) in batches.iter().enumerate() {
+        assert_eq!(batch.index(), index);
+        assert_eq!(batch.more(), index + 1 < batches.len());
+        assert_eq!(batch.first_key(), batch.first().map(|kv| kv.key()));
+        assert_eq!(batch.last_key(), batch.last().map(|kv| kv.key()));
+        // target_bytes is only a hint to the server, the batches can be empty or go over it
+        // but stay around its size, far from the whole range
+        let bytes: usize = batch
+            .iter()
+            .map(|kv| kv.key().len() + kv.value().len())
+            .sum();
+        assert!(
+            bytes <= 4 * TARGET_BYTES,
+            "batch {} holds {} bytes",
+            index,
+            bytes
+        );
+        batched_keys.extend(batch.iter().map(|kv| kv.key().to_vec()));
+    }
+
+    let flat_keys: Vec<Vec<u8>> = trx
+        .get_ranges_keyvalues(opt, false)
+        .map_ok(|kv| kv.key().to_vec())
+        .try_collect()
+        .await?;
+    assert_eq!(flat_keys.len(), N);
+    assert_eq!(batched_keys, flat_keys);
+
+    Ok(())
+}
+
+async fn range_keys(trx: &Transaction, opt: RangeOption<'_>) -> FdbResult<Vec<Vec<u8>>> {
+    trx.get_ranges_keyvalues(opt, false)
+        .map_ok(|kv| kv.key().to_vec())
+        .try_collect()
+        .await
+}
+
+async fn test_get_ranges_keyvalues_async() -> FdbResult<()> {
+    const N: usize = 10000;
+
+    let db = common::database().await?;
+    let trx = db.create_trx()?;
+    let key_begin = "test-ranges-keyvalues-";
+    let key_end = "test-ranges-keyvalues.";
+
+    trx.clear_range(key_begin.as_bytes(), key_end.as_bytes());
+    let keys: Vec<Vec<u8>> = (0..N)
+        .map(|i| format!("{}-{:05}", key_begin, i).into_bytes())
+        .collect();
+    for key in keys.iter() {
+        trx.set(key, b"v");
+    }
+
+    // small batches, so that the stream goes through many iterations
+    let opt = RangeOption {
+        mode: options::StreamingMode::Small,
+        ..RangeOption::from((key_begin.as_bytes(), key_end.as_bytes()))
+    };
+    let forward = range_keys(&trx, opt.clone()).await?;
+    assert_eq!(forward, keys);
+
+    // the end selector moves in reverse
+    let reverse = range_keys(&trx, opt.clone().rev()).await?;
+    assert!(reverse.iter().eq(keys.iter().rev()));
+
+    // the limit spans the iterations
+    let limited = range_keys(
+        &trx,
+        RangeOption {
+            limit: Some(N / 2 + 1),
+            ..opt.clone()
+        },
+    )
+    .await?;
+    assert_eq!(limited, keys[..N / 2 + 1]);
+    let limited = range_keys(
+        &trx,
+        RangeOption {
+            limit: Some(N / 2 + 1),
+            ..opt.clone().rev()
+        },
+    )
+    .await?;
+    assert!(limited.iter().eq(keys[N / 2 - 1..].iter().rev()));
+
+    // dropping the stream stops the reads
+    let first: Vec<_> = trx
+        .get_ranges_keyvalues(opt, false)
+        .take(3)
+        .map_ok(|kv| kv.key().to_vec())
+        .try_collect()
+        .await?;
+    assert_eq!(first, keys[..3]);
+
+    Ok(())
+}
+
 async fn test_range_option_async() -> FdbResult<()> {
     let db = common::database().await?;
 
@@ -147,73 +280,70 @@ async fn test_range_option_async() -> FdbResult<()> {
             trx.set(k(i).as_bytes(), value.as_bytes());
         }
         assert_eq!(
-            trx.get_ranges(
+            trx.read_ranges(
                 (KeySelector::first_greater_or_equal(k(100).into_bytes())
                     ..KeySelector::first_greater_or_equal(k(5000).as_bytes()))
-                    .into(),
-                false
+                    .into()
             )
             .try_fold(0usize, |count, kvs| future::ok(count + kvs.as_ref().len()))
             .await?,
             4900
         );
         assert_eq!(
-            trx.get_ranges(
+            trx.read_ranges(
                 (
                     KeySelector::first_greater_or_equal(k(100).into_bytes()),
                     KeySelector::first_greater_or_equal(k(5000).as_bytes())
                 )
-                    .into(),
-                false
+                    .into()
             )
             .try_fold(0usize, |count, kvs| future::ok(count + kvs.as_ref().len()))
             .await?,
             4900
         );
         assert_eq!(
-            trx.get_ranges((k(100).into_bytes()..k(5000).into_bytes()).into(), false)
+            trx.read_ranges((k(100).into_bytes()..k(5000).into_bytes()).into())
                 .try_fold(0usize, |count, kvs| future::ok(count + kvs.as_ref().len()))
                 .await?,
             4900
         );
         assert_eq!(
-            trx.get_ranges((k(100).into_bytes(), k(5000).into_bytes()).into(), false)
+            trx.read_ranges((k(100).into_bytes(), k(5000).into_bytes()).into())
                 .try_fold(0usize, |count, kvs| future::ok(count + kvs.as_ref().len()))
                 .await?,
             4900
         );
         assert_eq!(
-            trx.get_ranges((k(100).as_bytes()..k(5000).as_bytes()).into(), false)
+            trx.read_ranges((k(100).as_bytes()..k(5000).as_bytes()).into())
                 .try_fold(0usize, |count, kvs| future::ok(count + kvs.as_ref().len()))
                 .await?,
             4900
         );
         assert_eq!(
-            trx.get_ranges((k(100).as_bytes(), k(5000).as_bytes()).into(), false)
+            trx.read_ranges((k(100).as_bytes(), k(5000).as_bytes()).into())
                 .try_fold(0usize, |count, kvs| future::ok(count + kvs.as_ref().len()))
                 .await?,
             4900
         );
 
         assert_eq!(
-            trx.get_ranges(
+            trx.read_ranges(
                 (KeySelector::first_greater_or_equal(k(100).into_bytes())
                     ..KeySelector::first_greater_than(k(5000).as_bytes()))
-                    .into(),
-                false
+                    .into()
             )
             .try_fold(0usize, |count, kvs| future::ok(count + kvs.as_ref().len()))
             .await?,
             4901
         );
         assert_eq!(
-            trx.get_ranges((k(100).into_bytes()..=k(5000).into_bytes()).into(), false)
+            trx.read_ranges((k(100).into_bytes()..=k(5000).into_bytes()).into())
                 .try_fold(0usize, |count, kvs| future::ok(count + kvs.as_ref().len()))
                 .await?,
             4901
         );
         assert_eq!(
-            trx.get_ranges((k(100).as_bytes()..=k(5000).as_bytes()).into(), false)
+            trx.read_ranges((k(100).as_bytes()..=k(5000).as_bytes()).into())
                 .try_fold(0usize, |count, kvs| future::ok(count + kvs.as_ref().len()))
                 .await?,
             4901
@@ -284,6 +414,51 @@ async fn test_get_range_split_points() -> FdbResult<()> {
     Ok(())
 }
 
+#[cfg_api_versions(min = 700)]
+async fn test_sample_subspace_sizes() -> FdbResult<()> {
+    const SMALL: usize = 10;
+    const LARGE: usize = 20_000;
+
+    let db = common::database().await?;
+    let small = Subspace::from("test-sample-small");
+    let large = Subspace::from("test-sample-large");
+
+    eprintln!("inserting...");
+    for (subspace, count) in [(&small, SMALL), (&large, LARGE)] {
+        let trx = db.create_trx()?;
+        trx.clear_subspace_range(subspace);
+        for i in 0..count {
+            trx.set(&subspace.pack(&i), common::random_str(20).as_bytes());
+        }
+        trx.commit().await?;
+    }
+
+    let stats = db.sample_subspace_sizes(&[small, large]).await?;
+    eprintln!("sampled {:?}", stats);
+    assert_eq!(stats.len(), 2);
+
+    assert!(stats[0].exact);
+    assert_eq!(stats[0].estimated_keys, SMALL as u64);
+    assert_eq!(stats[0].keys_seen, SMALL as u64);
+    assert_eq!(stats[0].relative_error, 0.0);
+
+    assert!(!stats[1].exact);
+    assert!(stats[1].keys_seen < LARGE as u64);
+    assert!(stats[1].relative_error > 0.0);
+    assert!(stats[1].estimated_bytes > stats[0].estimated_bytes);
+    assert!(stats[1].estimated_keys > stats[0].estimated_keys);
+    // the byte sample is only accurate to a few times
+    let keys = stats[1].estimated_keys as usize;
+    assert!(
+        keys > LARGE / 4 && keys < LARGE * 4,
+        "{} keys estimated instead of {}",
+        keys,
+        LARGE
+    );
+
+    Ok(())
+}
+
 #[cfg_api_versions(min = 710)]
 async fn test_mapped_value() -> FdbResult<()> {
     use foundationdb::tuple::{pack, Subspace};
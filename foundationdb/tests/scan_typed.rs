@@ -0,0 +1,129 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use foundationdb::tuple::{
+    pack, PackResult, Subspace, TupleDepth, TuplePack, TupleUnpack, VersionstampOffset,
+};
+use foundationdb::*;
+use std::io;
+
+mod common;
+
+#[derive(Debug, Clone, PartialEq)]
+struct UserKey {
+    team: String,
+    id: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct UserValue {
+    name: String,
+    age: i64,
+}
+
+impl TuplePack for UserKey {
+    fn pack<W: io::Write>(
+        &self,
+        w: &mut W,
+        tuple_depth: TupleDepth,
+    ) -> io::Result<VersionstampOffset> {
+        (&self.team, self.id).pack(w, tuple_depth)
+    }
+}
+
+impl<'de> TupleUnpack<'de> for UserKey {
+    fn unpack(input: &'de [u8], tuple_depth: TupleDepth) -> PackResult<(&'de [u8], Self)> {
+        let (input, (team, id)) = <(String, i64)>::unpack(input, tuple_depth)?;
+        Ok((input, UserKey { team, id }))
+    }
+}
+
+impl TuplePack for UserValue {
+    fn pack<W: io::Write>(
+        &self,
+        w: &mut W,
+        tuple_depth: TupleDepth,
+    ) -> io::Result<VersionstampOffset> {
+        (&self.name, self.age).pack(w, tuple_depth)
+    }
+}
+
+impl<'de> TupleUnpack<'de> for UserValue {
+    fn unpack(input: &'de [u8], tuple_depth: TupleDepth) -> PackResult<(&'de [u8], Self)> {
+        let (input, (name, age)) = <(String, i64)>::unpack(input, tuple_depth)?;
+        Ok((input, UserValue { name, age }))
+    }
+}
+
+fn user(team: &str, id: i64, name: &str, age: i64) -> (UserKey, UserValue) {
+    (
+        UserKey {
+            team: team.to_string(),
+            id,
+        },
+        UserValue {
+            name: name.to_string(),
+            age,
+        },
+    )
+}
+
+#[foundationdb::testing::test]
+async fn test_scan_typed(db: Database, subspace: Subspace) -> Result<(), FdbBindingError> {
+    let users = subspace.subspace(&"users");
+    let mut expected = vec![
+        user("ops", 2, "carol", 41),
+        user("dev", 7, "alice", 30),
+        user("dev", 3, "bob", 25),
+        user("ops", 1, "dave", 52),
+    ];
+
+    let trx = db.create_trx()?;
+    for (key, value) in &expected {
+        trx.set(&users.pack(key), &pack(value));
+    }
+    // next to the scanned subspace, must not be read
+    trx.set(&subspace.pack(&"usersx"), &pack(&("eve", 19)));
+    trx.commit().await.map_err(FdbError::from)?;
+
+    expected.sort_by(|(a, _), (b, _)| (&a.team, a.id).cmp(&(&b.team, b.id)));
+
+    let trx = db.create_trx()?;
+    let scanned: Vec<(UserKey, UserValue)> = trx
+        .scan_typed(&users, RangeOption::default(), false)
+        .await?;
+    assert_eq!(scanned, expected);
+
+    let reversed: Vec<(UserKey, UserValue)> = trx
+        .scan_typed(
+            &users,
+            RangeOption {
+                limit: Some(2),
+                reverse: true,
+                ..RangeOption::default()
+            },
+            false,
+        )
+        .await?;
+    assert_eq!(reversed, vec![expected[3].clone(), expected[2].clone()]);
+
+    // plain tuples decode as well
+    let raw: Vec<((String, i64), (String, i64))> =
+        trx.scan_typed(&users, RangeOption::default(), true).await?;
+    assert_eq!(raw.len(), expected.len());
+    assert_eq!(raw[0], (("dev".to_string(), 3), ("bob".to_string(), 25)));
+
+    // values which are not packed tuples fail to decode
+    trx.set(&users.pack(&("ops", 9)), b"not a tuple");
+    let err = trx
+        .scan_typed::<UserKey, UserValue>(&users, RangeOption::default(), false)
+        .await
+        .expect_err("the value is not a tuple");
+    assert!(matches!(err, FdbBindingError::PackError(_)), "{:?}", err);
+
+    Ok(())
+}
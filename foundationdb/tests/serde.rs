@@ -0,0 +1,146 @@
+#![cfg(feature = "serde")]
+
+use foundationdb::tuple::{Subspace, Versionstamp};
+
+#[test]
+fn test_bincode_round_trip() {
+    let subspace = Subspace::from(("app", 1, b"\x00\xff".to_vec()));
+    let encoded = bincode::serialize(&subspace).unwrap();
+    // raw bytes behind a u64 length, not the printable form
+    assert_eq!(
+        &encoded[..8],
+        &(subspace.bytes().len() as u64).to_le_bytes()
+    );
+    assert_eq!(&encoded[8..], subspace.bytes());
+    assert_eq!(
+        bincode::deserialize::<Subspace>(&encoded).unwrap(),
+        subspace
+    );
+
+    let versionstamp = Versionstamp::complete([1, 2, 3, 4, 5, 6, 7, 8, 9, 10], 11);
+    let encoded = bincode::serialize(&versionstamp).unwrap();
+    assert_eq!(&encoded[8..], versionstamp.as_bytes());
+    assert_eq!(
+        bincode::deserialize::<Versionstamp>(&encoded).unwrap(),
+        versionstamp
+    );
+    assert!(bincode::deserialize::<Versionstamp>(&encoded[..encoded.len() - 1]).is_err());
+    let short = bincode::serialize(&Subspace::from_bytes(vec![0; 11])).unwrap();
+    assert!(bincode::deserialize::<Versionstamp>(&short).is_err());
+}
+
+#[test]
+fn test_json_round_trip() {
+    let subspaces = vec![
+        Subspace::all(),
+        Subspace::from("users"),
+        Subspace::from_bytes((0..=255).collect::<Vec<u8>>()),
+    ];
+    let json = serde_json::to_string(&subspaces).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Vec<Subspace>>(&json).unwrap(),
+        subspaces
+    );
+    assert!(serde_json::from_str::<Subspace>(r#""café""#).is_err());
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Profile {
+    name: String,
+    tags: Vec<String>,
+    avatar: Option<serde_bytes::ByteBuf>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct User {
+    id: u64,
+    profile: Profile,
+    score: (i32, f64),
+    deleted: Option<bool>,
+}
+
+#[test]
+fn test_tuple_round_trip() {
+    use foundationdb::tuple::{from_bytes, pack, to_bytes, Bytes};
+
+    let user = User {
+        id: 42,
+        profile: Profile {
+            name: "alice".into(),
+            tags: vec!["admin".into(), "a\0b".into()],
+            avatar: Some(serde_bytes::ByteBuf::from(vec![0, 1, 2])),
+        },
+        score: (-7, 0.5),
+        deleted: None,
+    };
+    let encoded = to_bytes(&user).unwrap();
+    // fields are packed positionally, as the tuple they match
+    assert_eq!(
+        encoded,
+        pack(&(
+            42u64,
+            (
+                "alice",
+                vec!["admin", "a\0b"],
+                Some(Bytes::from(&b"\x00\x01\x02"[..]))
+            ),
+            (-7i32, 0.5f64),
+            None::<bool>,
+        ))
+    );
+    assert_eq!(from_bytes::<User>(&encoded).unwrap(), user);
+
+    assert_eq!(to_bytes(&'é').unwrap(), pack(&"é"));
+    assert_eq!(from_bytes::<char>(&pack(&"é")).unwrap(), 'é');
+    assert_eq!(to_bytes(&(1u8, ())).unwrap(), pack(&(1u16, ())));
+    assert_eq!(
+        from_bytes::<Vec<Option<String>>>(&pack(&vec![Some("a"), None])).unwrap(),
+        vec![Some("a".to_owned()), None]
+    );
+    let packed = pack(&("name", &b"data"[..]));
+    let borrowed: (&str, &[u8]) = from_bytes(&packed).unwrap();
+    assert_eq!(borrowed, ("name", &b"data"[..]));
+}
+
+#[test]
+fn test_tuple_errors() {
+    use foundationdb::tuple::{from_bytes, pack, to_bytes, PackError};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, serde::Serialize, serde::Deserialize)]
+    enum State {
+        Active,
+    }
+
+    let map = BTreeMap::from([("a", 1)]);
+    let err = to_bytes(&map).unwrap_err();
+    assert!(matches!(err, PackError::Message(_)));
+    assert_eq!(
+        err.to_string(),
+        "maps are not supported by the tuple encoding"
+    );
+    assert!(from_bytes::<BTreeMap<String, i64>>(&pack(&("a", 1))).is_err());
+    assert_eq!(
+        to_bytes(&State::Active).unwrap_err().to_string(),
+        "enums are not supported by the tuple encoding"
+    );
+    assert!(from_bytes::<State>(&pack(&0)).is_err());
+
+    // missing, extra and mistyped fields
+    assert!(matches!(
+        from_bytes::<(String, i64)>(&pack(&("a",))),
+        Err(PackError::Message(_))
+    ));
+    assert!(matches!(
+        from_bytes::<(String, i64)>(&pack(&("a", 1, 2))),
+        Err(PackError::TrailingBytes)
+    ));
+    assert!(matches!(
+        from_bytes::<u8>(&pack(&256)),
+        Err(PackError::IntegerOverflow)
+    ));
+    assert!(matches!(
+        from_bytes::<(i64, String)>(&pack(&("a", 1))),
+        Err(PackError::BadCode { .. })
+    ));
+}
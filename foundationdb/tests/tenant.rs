@@ -2,15 +2,23 @@ mod common;
 
 #[test]
 fn test_tenant() {
-    let _guard = unsafe { foundationdb::boot() };
-    #[cfg(all(feature = "fdb-7_1", feature = "tenant-experimental"))]
+    let _guard = common::boot();
+    #[cfg(all(
+        any(feature = "fdb-7_1", feature = "fdb-7_3"),
+        feature = "tenant-experimental"
+    ))]
     {
         futures::executor::block_on(test_tenant_management()).expect("failed to run");
         futures::executor::block_on(test_tenant_run()).expect("failed to run");
     }
+    #[cfg(all(feature = "fdb-7_3", feature = "tenant-experimental"))]
+    futures::executor::block_on(test_tenant_rename()).expect("failed to run");
 }
 
-#[cfg(all(feature = "fdb-7_1", feature = "tenant-experimental"))]
+#[cfg(all(
+    any(feature = "fdb-7_1", feature = "fdb-7_3"),
+    feature = "tenant-experimental"
+))]
 async fn test_tenant_management() -> foundationdb::FdbResult<()> {
     use foundationdb::tenant::TenantManagement;
 
@@ -56,7 +64,10 @@ async fn test_tenant_management() -> foundationdb::FdbResult<()> {
     Ok(())
 }
 
-#[cfg(all(feature = "fdb-7_1", feature = "tenant-experimental"))]
+#[cfg(all(
+    any(feature = "fdb-7_1", feature = "fdb-7_3"),
+    feature = "tenant-experimental"
+))]
 async fn test_tenant_run() -> foundationdb::FdbResult<()> {
     let now = std::time::SystemTime::now()
         .duration_since(std::time::SystemTime::UNIX_EPOCH)
@@ -98,7 +109,7 @@ async fn test_tenant_run() -> foundationdb::FdbResult<()> {
         let slice = tenant
             .run(|trx, _maybe_committed| async move {
                 Ok(trx
-                    .get("toto".as_bytes(), false)
+                    .read("toto".as_bytes())
                     .await?
                     .expect("could not find key 'toto'"))
             })
@@ -123,3 +134,95 @@ async fn test_tenant_run() -> foundationdb::FdbResult<()> {
 
     Ok(())
 }
+
+#[cfg(all(feature = "fdb-7_3", feature = "tenant-experimental"))]
+async fn test_tenant_rename() -> foundationdb::FdbResult<()> {
+    use foundationdb::tenant::{RenameTenantError, TenantManagement};
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let prefix = format!("tenant-rename-{}-", now);
+    let old_name = format!("{}old", prefix);
+    let new_name = format!("{}new", prefix);
+    let other_name = format!("{}other", prefix);
+    let db = common::database().await?;
+
+    TenantManagement::create_tenant(&db, old_name.as_bytes())
+        .await
+        .expect("could not create tenant");
+    TenantManagement::create_tenant(&db, other_name.as_bytes())
+        .await
+        .expect("could not create tenant");
+    db.open_tenant(old_name.as_bytes())?
+        .run(|trx, _maybe_committed| async move {
+            trx.set(b"toto", b"renamed");
+            Ok(())
+        })
+        .await
+        .expect("could not write key 'toto'");
+
+    let err = TenantManagement::rename_tenant(&db, old_name.as_bytes(), other_name.as_bytes())
+        .await
+        .expect_err("renamed the tenant onto an existing one");
+    assert!(
+        matches!(err, RenameTenantError::DestinationExists),
+        "{:?}",
+        err
+    );
+
+    TenantManagement::rename_tenant(&db, old_name.as_bytes(), new_name.as_bytes())
+        .await
+        .expect("could not rename tenant");
+
+    let err = TenantManagement::rename_tenant(&db, old_name.as_bytes(), new_name.as_bytes())
+        .await
+        .expect_err("renamed a missing tenant");
+    assert!(matches!(err, RenameTenantError::SourceMissing), "{:?}", err);
+
+    // the renamed tenant is only listed under its new name
+    let end = format!("{}\u{7f}", prefix);
+    let tenants = TenantManagement::list_tenant(&db, prefix.as_bytes(), end.as_bytes(), None)
+        .await?
+        .into_iter()
+        .map(|tenant| tenant.expect("tenant could not be deserialized").name)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tenants,
+        vec![new_name.as_bytes().to_vec(), other_name.as_bytes().to_vec()]
+    );
+    assert!(TenantManagement::get_tenant(&db, old_name.as_bytes())
+        .await?
+        .is_none());
+
+    // the old name does not open the tenant anymore
+    let read = db
+        .open_tenant(old_name.as_bytes())?
+        .create_trx()?
+        .read(b"toto")
+        .await;
+    assert!(
+        matches!(&read, Err(err) if err.code() == 2131),
+        "read from a renamed tenant through its old name"
+    );
+
+    // the data moved with the tenant
+    let tenant = db.open_tenant(new_name.as_bytes())?;
+    let value = tenant
+        .run(|trx, _maybe_committed| async move {
+            let value = trx.read(b"toto").await?;
+            trx.clear(b"toto");
+            Ok(value)
+        })
+        .await
+        .expect("could not read key 'toto'");
+    assert_eq!(value.as_deref(), Some(&b"renamed"[..]));
+
+    for name in [&new_name, &other_name] {
+        TenantManagement::delete_tenant(&db, name.as_bytes())
+            .await
+            .expect("could not delete tenant");
+    }
+    Ok(())
+}
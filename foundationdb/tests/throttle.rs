@@ -0,0 +1,98 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+mod common;
+
+// tag throttles are stored in the system keyspace, the test subspace is not used
+#[cfg(any(
+    feature = "fdb-6_3",
+    feature = "fdb-7_0",
+    feature = "fdb-7_1",
+    feature = "fdb-7_3"
+))]
+#[foundationdb::testing::test]
+async fn test_manual_throttle(
+    db: foundationdb::Database,
+    _subspace: foundationdb::tuple::Subspace,
+) -> foundationdb::FdbResult<()> {
+    use foundationdb::special_keys::{TagThrottleReason, TagThrottleType, TransactionPriority};
+    use std::time::Duration;
+
+    let tag = format!("test-throttle-{}", common::random_str(10));
+
+    db.set_manual_throttle(tag.as_bytes(), 42.0, Duration::from_secs(60))
+        .await?;
+
+    let throttles = db.throttled_tags().await?;
+    let throttle = throttles
+        .iter()
+        .find(|throttle| throttle.tag == tag.as_bytes())
+        .expect("manual throttle to be listed");
+    assert_eq!(throttle.rate, 42.0);
+    assert_eq!(throttle.initial_duration, 60.0);
+    assert_eq!(throttle.priority, TransactionPriority::Default);
+    assert_eq!(throttle.throttle_type, TagThrottleType::Manual);
+    assert_eq!(throttle.reason, TagThrottleReason::Manual);
+
+    assert!(db.remove_throttle(tag.as_bytes()).await?);
+    assert!(!db.remove_throttle(tag.as_bytes()).await?);
+
+    let trx = db.create_trx()?;
+    trx.set_option(foundationdb::options::TransactionOption::AccessSystemKeys)?;
+    let throttles = trx.special_keys().throttled_tags().await?;
+    assert!(throttles
+        .iter()
+        .all(|throttle| throttle.tag != tag.as_bytes()));
+
+    Ok(())
+}
+
+#[cfg(any(
+    feature = "fdb-6_3",
+    feature = "fdb-7_0",
+    feature = "fdb-7_1",
+    feature = "fdb-7_3"
+))]
+#[foundationdb::testing::test]
+async fn test_throttle_tag_too_long(
+    db: foundationdb::Database,
+    _subspace: foundationdb::tuple::Subspace,
+) -> foundationdb::FdbResult<()> {
+    use std::time::Duration;
+
+    let err = db
+        .set_manual_throttle(&[b'a'; 256], 42.0, Duration::from_secs(60))
+        .await
+        .expect_err("tags are limited to 255 bytes");
+    // `tag_too_long` error
+    assert_eq!(err.code(), 2110);
+    Ok(())
+}
+
+#[cfg(any(
+    feature = "fdb-6_3",
+    feature = "fdb-7_0",
+    feature = "fdb-7_1",
+    feature = "fdb-7_3"
+))]
+#[foundationdb::testing::test]
+async fn test_transaction_tags(
+    db: foundationdb::Database,
+    _subspace: foundationdb::tuple::Subspace,
+) -> foundationdb::FdbResult<()> {
+    use foundationdb::options::TransactionOption;
+
+    let trx = db.create_trx()?;
+    trx.set_option(TransactionOption::Tag("first".to_string()))?;
+    trx.set_option(TransactionOption::AutoThrottleTag("second".to_string()))?;
+    assert_eq!(trx.tags(), ["first", "second"]);
+
+    // the tags are reset along with the other options
+    let trx = trx.cancel().reset();
+    assert!(trx.tags().is_empty());
+    Ok(())
+}
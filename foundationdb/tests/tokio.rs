@@ -1,6 +1,5 @@
 extern crate core;
 
-use foundationdb::future::FdbValues;
 use foundationdb::options::ConflictRangeType;
 use foundationdb::tuple::{pack, Subspace};
 use foundationdb::*;
@@ -16,7 +15,7 @@ mod common;
 
 #[test]
 fn test_tokio_send() {
-    let _guard = unsafe { foundationdb::boot() };
+    let _guard = common::boot();
     let rt = Runtime::new().unwrap();
     rt.block_on(async {
         do_transact().await;
@@ -41,11 +40,7 @@ impl Display for CustomError {
 impl std::error::Error for CustomError {}
 
 async fn do_run_with_custom_error() {
-    let db = Arc::new(
-        foundationdb::Database::new_compat(None)
-            .await
-            .expect("failed to open fdb"),
-    );
+    let db = Arc::new(common::database().await.expect("failed to open fdb"));
 
     db.perform_no_op().await.expect("could not perform no_op");
 
@@ -70,11 +65,7 @@ async fn do_run_with_custom_error() {
 }
 
 async fn do_transact() {
-    let db = Arc::new(
-        foundationdb::Database::new_compat(None)
-            .await
-            .expect("failed to open fdb"),
-    );
+    let db = Arc::new(common::database().await.expect("failed to open fdb"));
 
     if (timeout(Duration::from_secs(1), db.perform_no_op()).await).is_err() {
         panic!("database is unavailable");
@@ -97,11 +88,7 @@ async fn do_transact() {
 }
 
 async fn do_trx() {
-    let db = Arc::new(
-        foundationdb::Database::new_compat(None)
-            .await
-            .expect("failed to open fdb"),
-    );
+    let db = Arc::new(common::database().await.expect("failed to open fdb"));
 
     let adb = db;
     tokio::spawn(async move {
@@ -115,11 +102,7 @@ async fn do_trx() {
 
 async fn do_run() {
     let limit_retry = 16;
-    let db = Arc::new(
-        foundationdb::Database::new_compat(None)
-            .await
-            .expect("failed to open fdb"),
-    );
+    let db = Arc::new(common::database().await.expect("failed to open fdb"));
 
     let counter = AtomicI16::new(0);
     let counter_ref = &counter;
@@ -139,8 +122,8 @@ async fn do_run() {
                 ConflictRangeType::Write,
             )?;
 
-            let _keys: Vec<FdbValues> = trx
-                .get_ranges(read_subspace.range().into(), false)
+            let _keys: Vec<KeyValues> = trx
+                .read_ranges(read_subspace.range().into())
                 .try_collect()
                 .await?;
 
@@ -166,11 +149,7 @@ async fn do_run() {
 
 async fn do_run_with_transaction_limits() {
     let limit_retry = 16;
-    let db = Arc::new(
-        foundationdb::Database::new_compat(None)
-            .await
-            .expect("failed to open fdb"),
-    );
+    let db = Arc::new(common::database().await.expect("failed to open fdb"));
 
     let db_arc = &db;
     let counter = AtomicI16::new(0);
@@ -185,8 +164,8 @@ async fn do_run_with_transaction_limits() {
             counter_ref.fetch_add(1, Ordering::SeqCst);
 
             // virtually reading some random data in the subspace "do_run"
-            let _keys: Vec<FdbValues> = trx
-                .get_ranges(read_subspace.range().into(), false)
+            let _keys: Vec<KeyValues> = trx
+                .read_ranges(read_subspace.range().into())
                 .try_collect()
                 .await?;
 
@@ -0,0 +1,175 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Round trips of the types deriving `TuplePack` and `TupleUnpack`.
+
+use foundationdb::tuple::{pack, unpack, PackError, Subspace, TuplePack, TupleUnpack};
+use std::borrow::Cow;
+use std::fmt::Debug;
+
+#[derive(Debug, PartialEq, TuplePack, TupleUnpack)]
+struct UserIndexKey {
+    tenant: String,
+    user_id: u64,
+    shard: u16,
+}
+
+#[derive(Debug, PartialEq, TuplePack, TupleUnpack)]
+struct Versioned {
+    #[fdb(flatten)]
+    key: UserIndexKey,
+    version: i64,
+    #[fdb(skip)]
+    cached: Option<String>,
+}
+
+#[derive(Debug, PartialEq, TuplePack, TupleUnpack)]
+struct Nested {
+    key: UserIndexKey,
+    state: State,
+}
+
+#[derive(Debug, PartialEq, TuplePack, TupleUnpack)]
+struct Borrowed<'a> {
+    name: Cow<'a, str>,
+    data: Cow<'a, [u8]>,
+}
+
+#[derive(Debug, PartialEq, TuplePack, TupleUnpack)]
+struct Pair<T>(T, T);
+
+#[derive(Debug, PartialEq, TuplePack, TupleUnpack)]
+struct Empty;
+
+#[derive(Debug, Clone, Copy, PartialEq, TuplePack, TupleUnpack)]
+enum State {
+    Active = 1,
+    Suspended,
+    Deleted = -7,
+}
+
+fn user() -> UserIndexKey {
+    UserIndexKey {
+        tenant: "acme".into(),
+        user_id: 42,
+        shard: 3,
+    }
+}
+
+fn round_trip<T>(value: T, expected: &[u8])
+where
+    T: TuplePack + for<'de> TupleUnpack<'de> + PartialEq + Debug,
+{
+    assert_eq!(pack(&value), expected);
+    assert_eq!(unpack::<T>(expected).unwrap(), value);
+}
+
+#[test]
+fn test_struct() {
+    let expected = pack(&("acme", 42u64, 3u16));
+    round_trip(user(), &expected);
+    round_trip(Pair(1i64, -1i64), &pack(&(1i64, -1i64)));
+    round_trip(Empty, &[]);
+
+    let subspace = Subspace::from("users");
+    assert_eq!(
+        subspace.pack(&user()),
+        subspace.pack(&("acme", 42u64, 3u16))
+    );
+    assert_eq!(
+        subspace
+            .unpack::<UserIndexKey>(&subspace.pack(&user()))
+            .unwrap(),
+        user()
+    );
+}
+
+#[test]
+fn test_attributes() {
+    let versioned = Versioned {
+        key: user(),
+        version: 9,
+        cached: Some("ignored".into()),
+    };
+    let packed = pack(&versioned);
+    assert_eq!(packed, pack(&("acme", 42u64, 3u16, 9i64)));
+    assert_eq!(versioned.size_hint_root(), packed.len());
+    assert_eq!(
+        unpack::<Versioned>(&packed).unwrap(),
+        Versioned {
+            cached: None,
+            ..versioned
+        }
+    );
+}
+
+#[test]
+fn test_nested() {
+    let nested = Nested {
+        key: user(),
+        state: State::Suspended,
+    };
+    assert_eq!(nested.size_hint_root(), pack(&nested).len());
+    round_trip(nested, &pack(&(("acme", 42u64, 3u16), 2i64)));
+
+    let packed = pack(&(user(), Pair("a", "b")));
+    assert_eq!(packed, pack(&(("acme", 42u64, 3u16), ("a", "b"))));
+    let (key, pair) = unpack::<(UserIndexKey, Pair<String>)>(&packed).unwrap();
+    assert_eq!(key, user());
+    assert_eq!(pair, Pair("a".into(), "b".into()));
+}
+
+#[test]
+fn test_borrowed() {
+    let packed = pack(&("name", b"\x01data".as_ref()));
+    let borrowed = unpack::<Borrowed>(&packed).unwrap();
+    assert!(matches!(borrowed.name, Cow::Borrowed("name")));
+    assert!(matches!(borrowed.data, Cow::Borrowed(b"\x01data")));
+    assert_eq!(pack(&borrowed), packed);
+}
+
+#[test]
+fn test_enum() {
+    round_trip(State::Active, &pack(&1i64));
+    round_trip(State::Suspended, &pack(&2i64));
+    round_trip(State::Deleted, &pack(&-7i64));
+
+    let err = unpack::<State>(&pack(&3i64)).unwrap_err();
+    assert!(matches!(err, PackError::Message(_)));
+    assert_eq!(err.to_string(), "not a variant of `State`");
+}
+
+#[test]
+fn test_mismatch() {
+    // missing fields
+    assert!(matches!(
+        unpack::<UserIndexKey>(&pack(&("acme", 42u64))),
+        Err(PackError::MissingBytes)
+    ));
+    // extra fields
+    assert!(matches!(
+        unpack::<UserIndexKey>(&pack(&("acme", 42u64, 3u16, 4u16))),
+        Err(PackError::TrailingBytes)
+    ));
+    assert!(matches!(
+        unpack::<Nested>(&pack(&(("acme", 42u64, 3u16, 4u16), 1i64))),
+        Err(PackError::BadCode { .. })
+    ));
+    // wrong types
+    assert!(matches!(
+        unpack::<UserIndexKey>(&pack(&(42u64, "acme", 3u16))),
+        Err(PackError::BadCode { .. })
+    ));
+    assert!(matches!(
+        unpack::<UserIndexKey>(&pack(&("acme", 42u64, 70_000u32))),
+        Err(PackError::IntegerOverflow)
+    ));
+    assert!(matches!(
+        unpack::<Nested>(&pack(&("acme", 42u64, 3u16, 1i64))),
+        Err(PackError::BadCode { .. })
+    ));
+}
@@ -0,0 +1,113 @@
+// Copyright 2022 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Checks the tuple layer against vectors packed by the official Python binding.
+//!
+//! The corpus is regenerated with `scripts/generate_tuple_vectors.py`, which also documents the
+//! format used to describe values.
+
+use foundationdb::tuple::{pack, unpack, Bytes, Element, Versionstamp};
+use serde_json::Value;
+use std::borrow::Cow;
+
+const VECTORS: &str = include_str!("data/tuple_vectors.json");
+
+fn from_hex(hex: &str) -> Vec<u8> {
+    assert!(hex.len() % 2 == 0, "odd hex length: {}", hex);
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("valid hex"))
+        .collect()
+}
+
+fn field<'a>(element: &'a Value, name: &str) -> &'a str {
+    element[name]
+        .as_str()
+        .unwrap_or_else(|| panic!("missing {} in {}", name, element))
+}
+
+/// Build the element described by `element`, `None` if its type is behind a disabled feature.
+fn to_element(element: &Value) -> Option<Element<'static>> {
+    Some(match field(element, "type") {
+        "nil" => Element::Nil,
+        "bytes" => Element::Bytes(Bytes::from(from_hex(field(element, "hex")))),
+        "string" => Element::String(Cow::Owned(field(element, "value").to_string())),
+        "int" => {
+            let value = field(element, "value");
+            match value.parse::<i64>() {
+                Ok(v) => Element::Int(v),
+                #[cfg(feature = "num-bigint")]
+                Err(_) => Element::BigInt(value.parse().expect("valid integer")),
+                #[cfg(not(feature = "num-bigint"))]
+                Err(_) => return None,
+            }
+        }
+        "float" => {
+            let bits = from_hex(field(element, "bits"));
+            Element::Float(f32::from_be_bytes(bits.try_into().expect("4 bytes")))
+        }
+        "double" => {
+            let bits = from_hex(field(element, "bits"));
+            Element::Double(f64::from_be_bytes(bits.try_into().expect("8 bytes")))
+        }
+        "bool" => Element::Bool(element["value"].as_bool().expect("boolean value")),
+        #[cfg(feature = "uuid")]
+        "uuid" => Element::Uuid(field(element, "value").parse().expect("valid uuid")),
+        #[cfg(not(feature = "uuid"))]
+        "uuid" => return None,
+        "versionstamp" => {
+            let raw: [u8; 12] = from_hex(field(element, "hex"))
+                .try_into()
+                .expect("12 bytes");
+            Element::Versionstamp(Versionstamp::from(raw))
+        }
+        "tuple" => Element::Tuple(
+            element["items"]
+                .as_array()
+                .expect("tuple items")
+                .iter()
+                .map(to_element)
+                .collect::<Option<_>>()?,
+        ),
+        other => panic!("unknown element type: {}", other),
+    })
+}
+
+#[test]
+fn test_tuple_golden_vectors() {
+    let corpus: Value = serde_json::from_str(VECTORS).expect("valid corpus");
+    let vectors = corpus["vectors"].as_array().expect("vectors");
+    assert!(!vectors.is_empty());
+
+    let mut checked = 0;
+    for vector in vectors {
+        let description = field(vector, "description");
+        let expected = from_hex(field(vector, "packed"));
+        let value: Option<Vec<Element>> = vector["value"]
+            .as_array()
+            .expect("value")
+            .iter()
+            .map(to_element)
+            .collect();
+        let value = match value {
+            Some(value) => value,
+            None => continue,
+        };
+
+        assert_eq!(
+            Bytes::from(pack(&value)),
+            Bytes::from(expected.as_slice()),
+            "encoding {}",
+            description
+        );
+        let decoded: Vec<Element> =
+            unpack(&expected).unwrap_or_else(|err| panic!("decoding {}: {:?}", description, err));
+        assert_eq!(decoded, value, "decoding {}", description);
+        checked += 1;
+    }
+    assert!(checked > 0);
+}
@@ -0,0 +1,49 @@
+// Copyright 2018 foundationdb-rs developers, https://github.com/Clikengo/foundationdb-rs/graphs/contributors
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use foundationdb::tuple::{Subspace, Versionstamp, VersionstampAllocator};
+use foundationdb::*;
+use futures::TryStreamExt;
+
+mod common;
+
+#[foundationdb::testing::test]
+async fn test_versionstamped_keys(db: Database, subspace: Subspace) -> FdbResult<()> {
+    let trx = db.create_trx()?;
+    let allocator = VersionstampAllocator::new();
+    for i in 0..100i64 {
+        let user_version = allocator.next_user_version().expect("user version");
+        trx.set_versionstamped_key_with_user_version(
+            &subspace,
+            &(Versionstamp::incomplete(0), i),
+            &i.to_le_bytes(),
+            user_version,
+        );
+    }
+    let trx_versionstamp = trx.get_versionstamp();
+    trx.commit().await?;
+    let trx_versionstamp = trx_versionstamp.await?;
+
+    let trx = db.create_trx()?;
+    let keyvalues: Vec<_> = trx
+        .get_ranges_keyvalues(RangeOption::from(&subspace), false)
+        .try_collect()
+        .await?;
+    assert_eq!(keyvalues.len(), 100);
+
+    for (i, keyvalue) in keyvalues.iter().enumerate() {
+        let (versionstamp, n): (Versionstamp, i64) =
+            subspace.unpack(keyvalue.key()).expect("valid key");
+        assert!(versionstamp.is_complete());
+        assert_eq!(versionstamp.transaction_version(), &trx_versionstamp[..]);
+        assert_eq!(versionstamp.user_version(), i as u16);
+        assert_eq!(n, i as i64);
+        assert_eq!(keyvalue.value(), &n.to_le_bytes());
+    }
+
+    Ok(())
+}
@@ -5,32 +5,29 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use foundationdb::tuple::Subspace;
+use foundationdb::watch::Debounce;
 use foundationdb::*;
+use futures::prelude::*;
+use std::time::{Duration, Instant};
 
 mod common;
 
-#[test]
-fn test_watch() {
-    let _guard = unsafe { foundationdb::boot() };
-    futures::executor::block_on(test_watch_async()).expect("failed to run");
-    futures::executor::block_on(test_watch_without_commit_async()).expect("failed to run");
-}
-
-async fn test_watch_async() -> FdbResult<()> {
-    const KEY: &[u8] = b"test-watch";
-
-    let db = common::database().await?;
+#[foundationdb::testing::test]
+async fn test_watch(db: Database, subspace: Subspace) -> FdbResult<()> {
+    let key = subspace.pack(&"test-watch");
+    let key: &[u8] = &key;
 
     eprintln!("setting watch");
     let trx = db.create_trx()?;
-    let watch = trx.watch(KEY);
+    let watch = trx.watch(key);
     trx.commit().await?;
     eprintln!("watch committed");
 
     eprintln!("writing value");
     let trx = db.create_trx()?;
     let value = common::random_str(10);
-    trx.set(KEY, value.as_bytes());
+    trx.set(key, value.as_bytes());
     trx.commit().await?;
     eprintln!("write committed");
 
@@ -39,17 +36,121 @@ async fn test_watch_async() -> FdbResult<()> {
     Ok(())
 }
 
-async fn test_watch_without_commit_async() -> FdbResult<()> {
-    const KEY: &[u8] = b"test-watch-2";
-
-    let db = common::database().await?;
+#[foundationdb::testing::test]
+async fn test_watch_without_commit(db: Database, subspace: Subspace) -> FdbResult<()> {
+    let key = subspace.pack(&"test-watch-2");
 
     eprintln!("setting watch");
     let trx = db.create_trx()?;
-    let watch = trx.watch(KEY);
+    let watch = trx.watch(&key);
 
     drop(trx);
     assert!(watch.await.is_err());
 
     Ok(())
 }
+
+#[foundationdb::testing::test]
+async fn test_watch_stream(db: Database, subspace: Subspace) -> FdbResult<()> {
+    let key = subspace.pack(&"test-watch-stream");
+    let debounce = Debounce {
+        window: Duration::from_millis(100),
+        max_delay: Duration::from_millis(500),
+    };
+    const WRITES: usize = 200;
+
+    let start = Instant::now();
+    let mut stream = db.watch_stream(key.clone(), debounce);
+    assert_eq!(stream.next().await.expect("a value")?, None);
+
+    let writer = async {
+        for i in 0..WRITES {
+            let trx = db.create_trx()?;
+            trx.set(&key, i.to_string().as_bytes());
+            trx.commit().await?;
+        }
+        FdbResult::Ok(())
+    };
+    let reader = async {
+        let mut values = Vec::new();
+        while let Some(value) = stream.next().await {
+            let value = value?.expect("the key to be set");
+            let done = value == (WRITES - 1).to_string().as_bytes();
+            values.push(value);
+            if done {
+                return FdbResult::Ok(values);
+            }
+        }
+        unreachable!("the stream never ends");
+    };
+    let ((), values) = futures::future::try_join(writer, reader).await?;
+    let elapsed = start.elapsed();
+    eprintln!("{} values reported in {:?}", values.len(), elapsed);
+
+    // every value but the first one waited for a whole window
+    let bound = 2 + (elapsed.as_millis() / debounce.window.as_millis()) as usize;
+    assert!(values.len() <= bound, "{} > {}", values.len(), bound);
+    assert!(values.len() < WRITES);
+    // values are reported in order, without duplicates
+    let values: Vec<usize> = values
+        .iter()
+        .map(|value| std::str::from_utf8(value).unwrap().parse().unwrap())
+        .collect();
+    assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+
+    Ok(())
+}
+
+/// Waits for `future` for at most `duration`, `None` meaning it timed out
+async fn timeout<F: Future + Unpin>(duration: Duration, future: F) -> Option<F::Output> {
+    let (sender, receiver) = futures::channel::oneshot::channel::<()>();
+    std::thread::spawn(move || {
+        std::thread::sleep(duration);
+        let _ = sender.send(());
+    });
+    match futures::future::select(future, receiver).await {
+        futures::future::Either::Left((output, _)) => Some(output),
+        futures::future::Either::Right(_) => None,
+    }
+}
+
+#[foundationdb::testing::test]
+async fn test_watch_timeout(db: Database, subspace: Subspace) -> FdbResult<()> {
+    let key = subspace.pack(&"test-watch-timeout");
+    let trx = db.create_trx()?;
+    trx.set(&key, b"initial");
+    trx.commit().await?;
+
+    let trx = db.create_trx()?;
+    let mut watch = trx.watch(&key);
+    trx.commit().await?;
+
+    // the watch outlives its transaction, and is not set before the value changes
+    assert!(timeout(Duration::from_millis(200), &mut watch)
+        .await
+        .is_none());
+    // setting the same value is not a change
+    let trx = db.create_trx()?;
+    trx.set(&key, b"initial");
+    trx.commit().await?;
+    assert!(timeout(Duration::from_millis(200), &mut watch)
+        .await
+        .is_none());
+
+    let trx = db.create_trx()?;
+    trx.set(&key, b"changed");
+    trx.commit().await?;
+    timeout(Duration::from_secs(5), watch)
+        .await
+        .expect("the watch to fire")?;
+
+    // dropped watches are cancelled, and do not hold on to their slot
+    for _ in 0..100 {
+        let trx = db.create_trx()?;
+        let watch = trx.watch(&key);
+        trx.commit().await?;
+        drop(watch);
+    }
+
+    Ok(())
+}
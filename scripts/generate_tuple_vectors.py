@@ -0,0 +1,199 @@
+#!/usr/bin/env python3
+"""Generate the tuple layer golden vectors checked by foundationdb/tests/tuple_golden.rs.
+
+The expected bytes are produced by the official Python binding, which must be importable:
+
+    pip install foundationdb
+    python3 scripts/generate_tuple_vectors.py > foundationdb/tests/data/tuple_vectors.json
+
+Values are described with a small JSON format understood by both this script and the Rust test,
+each element being an object with a "type" and its payload:
+
+    {"type": "nil"}
+    {"type": "bytes", "hex": "00ff"}
+    {"type": "string", "value": "foo"}
+    {"type": "int", "value": "-42"}            integers are strings to keep their precision
+    {"type": "float", "bits": "7fc00000"}      IEEE 754 big endian bits
+    {"type": "double", "bits": "7ff8000000000000"}
+    {"type": "bool", "value": true}
+    {"type": "uuid", "value": "00112233-4455-6677-8899-aabbccddeeff"}
+    {"type": "versionstamp", "hex": "<20 hex transaction version><4 hex user version>"}
+    {"type": "tuple", "items": [...]}
+
+To cover a new type, add a case to `to_python` and the matching case to the Rust test, then add
+vectors below and regenerate the corpus.
+"""
+
+import json
+import struct
+import sys
+import uuid
+
+import fdb.tuple
+
+
+def nil():
+    return {"type": "nil"}
+
+
+def byte_string(value):
+    return {"type": "bytes", "hex": value.hex()}
+
+
+def string(value):
+    return {"type": "string", "value": value}
+
+
+def integer(value):
+    return {"type": "int", "value": str(value)}
+
+
+def single(value):
+    return {"type": "float", "bits": struct.pack(">f", value).hex()}
+
+
+def double(value):
+    return {"type": "double", "bits": struct.pack(">d", value).hex()}
+
+
+def boolean(value):
+    return {"type": "bool", "value": value}
+
+
+def uuid_(value):
+    return {"type": "uuid", "value": value}
+
+
+def versionstamp(transaction_version, user_version):
+    return {
+        "type": "versionstamp",
+        "hex": transaction_version.hex() + struct.pack(">H", user_version).hex(),
+    }
+
+
+def tuple_(*items):
+    return {"type": "tuple", "items": list(items)}
+
+
+VECTORS = [
+    ("empty tuple", []),
+    ("nil", [nil()]),
+    ("empty byte string", [byte_string(b"")]),
+    ("byte string", [byte_string(b"foo\x01bar")]),
+    ("byte string of a single zero", [byte_string(b"\x00")]),
+    ("escape heavy byte string", [byte_string(b"\x00\x00\xff\x00\x01\x00")]),
+    ("byte string of 0xff", [byte_string(b"\xff\xff")]),
+    ("empty string", [string("")]),
+    ("ascii string", [string("hello world")]),
+    ("string with a zero", [string("a\x00b")]),
+    ("latin-1 string", [string("été")]),
+    ("cjk string", [string("日本語")]),
+    ("astral plane string", [string("\U0001f600")]),
+    ("zero", [integer(0)]),
+    ("one", [integer(1)]),
+    ("minus one", [integer(-1)]),
+    ("one byte positive boundary", [integer(255)]),
+    ("two bytes positive", [integer(256)]),
+    ("one byte negative boundary", [integer(-255)]),
+    ("two bytes negative", [integer(-256)]),
+    ("four bytes boundary", [integer(2**32 - 1)]),
+    ("five bytes", [integer(2**32)]),
+    ("minus five bytes", [integer(-(2**32))]),
+    ("i64 max", [integer(2**63 - 1)]),
+    ("i64 min", [integer(-(2**63))]),
+    ("above i64 max", [integer(2**63)]),
+    ("below i64 min", [integer(-(2**63) - 1)]),
+    ("2^64", [integer(2**64)]),
+    ("-2^64", [integer(-(2**64))]),
+    ("large positive", [integer(2**100 + 12345)]),
+    ("large negative", [integer(-(2**100) - 12345)]),
+    ("float zero", [single(0.0)]),
+    ("float negative zero", [single(-0.0)]),
+    ("float one", [single(1.0)]),
+    ("float minus one and a half", [single(-1.5)]),
+    ("float infinity", [single(float("inf"))]),
+    ("float negative infinity", [single(float("-inf"))]),
+    ("float nan", [{"type": "float", "bits": "7fc00000"}]),
+    ("float smallest subnormal", [{"type": "float", "bits": "00000001"}]),
+    ("float max", [{"type": "float", "bits": "7f7fffff"}]),
+    ("double zero", [double(0.0)]),
+    ("double negative zero", [double(-0.0)]),
+    ("double pi", [double(3.141592653589793)]),
+    ("double negative", [double(-42.125)]),
+    ("double infinity", [double(float("inf"))]),
+    ("double negative infinity", [double(float("-inf"))]),
+    ("double nan", [{"type": "double", "bits": "7ff8000000000000"}]),
+    ("double smallest subnormal", [{"type": "double", "bits": "0000000000000001"}]),
+    ("double max", [{"type": "double", "bits": "7fefffffffffffff"}]),
+    ("false", [boolean(False)]),
+    ("true", [boolean(True)]),
+    ("nil uuid", [uuid_("00000000-0000-0000-0000-000000000000")]),
+    ("uuid", [uuid_("00112233-4455-6677-8899-aabbccddeeff")]),
+    (
+        "versionstamp",
+        [versionstamp(b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09", 0x0a0b)],
+    ),
+    ("versionstamp with zeros", [versionstamp(b"\x00" * 10, 0)]),
+    ("empty nested tuple", [tuple_()]),
+    ("nested nil", [tuple_(nil())]),
+    (
+        "nested tuple",
+        [tuple_(integer(1), string("two"), nil(), byte_string(b"\x00"))],
+    ),
+    ("deeply nested tuple", [tuple_(tuple_(tuple_(nil(), integer(-1))), nil())]),
+    (
+        "mixed tuple",
+        [
+            string("user"),
+            integer(42),
+            nil(),
+            boolean(True),
+            double(1.5),
+            byte_string(b"\x00\xff"),
+            tuple_(string("nested"), nil()),
+        ],
+    ),
+]
+
+
+def to_python(element):
+    kind = element["type"]
+    if kind == "nil":
+        return None
+    if kind == "bytes":
+        return bytes.fromhex(element["hex"])
+    if kind == "string":
+        return element["value"]
+    if kind == "int":
+        return int(element["value"])
+    if kind == "float":
+        return fdb.tuple.SingleFloat(
+            struct.unpack(">f", bytes.fromhex(element["bits"]))[0]
+        )
+    if kind == "double":
+        return struct.unpack(">d", bytes.fromhex(element["bits"]))[0]
+    if kind == "bool":
+        return element["value"]
+    if kind == "uuid":
+        return uuid.UUID(element["value"])
+    if kind == "versionstamp":
+        raw = bytes.fromhex(element["hex"])
+        return fdb.tuple.Versionstamp(raw[:10], struct.unpack(">H", raw[10:])[0])
+    if kind == "tuple":
+        return tuple(to_python(item) for item in element["items"])
+    raise ValueError("unknown element type: {}".format(kind))
+
+
+def main():
+    vectors = []
+    for description, value in VECTORS:
+        packed = fdb.tuple.pack(tuple(to_python(element) for element in value))
+        vectors.append(
+            {"description": description, "value": value, "packed": packed.hex()}
+        )
+    json.dump({"vectors": vectors}, sys.stdout, indent=2, ensure_ascii=False)
+    sys.stdout.write("\n")
+
+
+if __name__ == "__main__":
+    main()